@@ -0,0 +1,68 @@
+//! "Parse, read a few fields, drop" on an array of records, comparing the owned
+//! tree, the borrowing parser and the buffer-backed `JsonDocument`.
+//!
+//! Run with `cargo bench --bench document`.
+
+use rust_json_parser::{JsonDocument, parse_json, parse_json_borrowed};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/*
+ * An array of small records with nested objects and arrays, compact.
+ */
+fn records() -> String {
+    let records: Vec<String> = (0..5000)
+        .map(|i| {
+            format!(
+                r#"{{"id":{},"name":"user{}","active":{},"score":{}.5,"tags":["a","b","c"],"address":{{"city":"Springfield","zip":"{:05}"}}}}"#,
+                i,
+                i,
+                i % 2 == 0,
+                i,
+                i
+            )
+        })
+        .collect();
+    format!("[{}]", records.join(","))
+}
+
+/*
+ * Runs `parse` repeatedly for about a second and prints the median time and
+ * throughput.
+ */
+fn bench(name: &str, input: &str, parse: impl Fn(&str) -> usize) {
+    let mut times = Vec::new();
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(1) || times.len() < 10 {
+        let start = Instant::now();
+        black_box(parse(black_box(input)));
+        times.push(start.elapsed());
+    }
+    times.sort();
+    let median = times[times.len() / 2];
+    let throughput = input.len() as f64 / median.as_secs_f64() / 1e6;
+    println!("{:<14} {:>10.3?}  {:>8.1} MB/s", name, median, throughput);
+}
+
+fn main() {
+    let input = records();
+    let last = |len: usize| len - 1;
+    bench("JsonValue", &input, |input| {
+        let value = parse_json(input).unwrap();
+        let rows = value.as_array().unwrap();
+        let row = &rows[last(rows.len())];
+        row.get("name").and_then(|n| n.as_str()).unwrap().len() + rows.len()
+    });
+    bench("JsonValueRef", &input, |input| {
+        let value = parse_json_borrowed(input).unwrap();
+        let rows = value.as_array().unwrap();
+        let row = &rows[last(rows.len())];
+        row.get("name").and_then(|n| n.as_str()).unwrap().len() + rows.len()
+    });
+    bench("JsonDocument", &input, |input| {
+        let document = JsonDocument::parse(input).unwrap();
+        let rows = document.root();
+        let row = rows.get_index(last(rows.len())).unwrap();
+        row.get("name").and_then(|n| n.as_str()).unwrap().len() + rows.len()
+    });
+}
@@ -0,0 +1,70 @@
+//! Parsing throughput on documents dominated by string contents and by
+//! indentation, the two byte-at-a-time scans of the tokenizer.
+//!
+//! Run with `cargo bench --bench tokenizer`.
+
+use rust_json_parser::{JsonValue, SerializeOptions, parse_json};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/*
+ * An array of records with long string values, compact.
+ */
+fn string_heavy() -> String {
+    let sentence = "The quick brown fox jumps over the lazy dog, café and naïve résumé. ";
+    let records: Vec<String> = (0..2000)
+        .map(|i| {
+            format!(
+                r#"{{"id":{},"title":"{}","body":"{}","note":"line\nbreak \"quoted\""}}"#,
+                i,
+                sentence.repeat(2),
+                sentence.repeat(12)
+            )
+        })
+        .collect();
+    format!("[{}]", records.join(","))
+}
+
+/*
+ * A deeply indented document with short values, as written by pretty-printers.
+ */
+fn whitespace_heavy() -> String {
+    let mut value = JsonValue::Array((0..8).map(|i| JsonValue::Number(i.into())).collect());
+    for depth in 0..6 {
+        let members = (0..4)
+            .map(|i| (format!("k{}{}", depth, i).as_str().into(), value.clone()))
+            .collect();
+        value = JsonValue::Object(members);
+    }
+    value.to_string_with(&SerializeOptions::pretty(4))
+}
+
+/*
+ * Parses `input` repeatedly for about a second and prints the median
+ * throughput.
+ */
+fn bench(name: &str, input: &str) {
+    parse_json(input).expect("benchmark input is valid");
+    let mut times = Vec::new();
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(1) || times.len() < 10 {
+        let start = Instant::now();
+        black_box(parse_json(black_box(input)).unwrap());
+        times.push(start.elapsed());
+    }
+    times.sort();
+    let median = times[times.len() / 2];
+    let throughput = input.len() as f64 / median.as_secs_f64() / 1e6;
+    println!(
+        "{:<18} {:>8.1} KB  {:>10.3?}  {:>8.1} MB/s",
+        name,
+        input.len() as f64 / 1e3,
+        median,
+        throughput
+    );
+}
+
+fn main() {
+    bench("string-heavy", &string_heavy());
+    bench("whitespace-heavy", &whitespace_heavy());
+}
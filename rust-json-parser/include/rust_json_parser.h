@@ -0,0 +1,94 @@
+/*
+ * C interface to rust-json-parser, available when the crate is built with the
+ * `capi` feature:
+ *
+ *     cargo build --release --features capi
+ *
+ * and linked against target/release/librust_json_parser.so (.dylib, .dll).
+ *
+ * rjp_parse returns a handle owning the document; release it with
+ * rjp_value_free. Every other RjpValue pointer borrows from a document and is
+ * valid until that document is freed. Strings passed in are UTF-8 buffers with
+ * an explicit length. Strings returned by accessors are not NUL-terminated,
+ * since JSON strings may contain NUL; serialized documents and error messages
+ * are.
+ */
+#ifndef RUST_JSON_PARSER_H
+#define RUST_JSON_PARSER_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RjpValue RjpValue;
+typedef struct RjpObjectIter RjpObjectIter;
+
+typedef enum RjpType {
+    RJP_NULL = 0,
+    RJP_BOOLEAN = 1,
+    RJP_NUMBER = 2,
+    RJP_STRING = 3,
+    RJP_ARRAY = 4,
+    RJP_OBJECT = 5,
+} RjpType;
+
+/*
+ * Why rjp_parse failed. position (a byte offset), line and column (1-based)
+ * are only meaningful if has_position is set. Release with rjp_error_free.
+ */
+typedef struct RjpError {
+    char *message;
+    size_t position;
+    size_t line;
+    size_t column;
+    bool has_position;
+} RjpError;
+
+/*
+ * Parses len bytes of UTF-8 (or UTF-16) at data. Returns NULL on failure,
+ * filling in error if it is not NULL; data may only be NULL if len is 0.
+ */
+RjpValue *rjp_parse(const char *data, size_t len, RjpError *error);
+void rjp_value_free(RjpValue *value);
+void rjp_error_free(RjpError *error);
+
+RjpType rjp_value_type(const RjpValue *value);
+/* false if value is not a boolean */
+bool rjp_value_as_bool(const RjpValue *value);
+/* 0 if value is not a number */
+double rjp_value_as_double(const RjpValue *value);
+/* false unless value is an integer that fits in int64_t */
+bool rjp_value_as_i64(const RjpValue *value, int64_t *out);
+/* NULL if value is not a string; the bytes are not NUL-terminated */
+const char *rjp_value_as_string(const RjpValue *value, size_t *len);
+
+/* Number of array elements or object members, 0 for other values */
+size_t rjp_value_len(const RjpValue *value);
+/* NULL if value is not an array or index is out of range */
+const RjpValue *rjp_value_index(const RjpValue *value, size_t index);
+/* NULL if value is not an object or has no member named by the key_len bytes at key */
+const RjpValue *rjp_value_get(const RjpValue *value, const char *key, size_t key_len);
+
+/* NULL if value is not an object; release with rjp_object_iter_free */
+RjpObjectIter *rjp_object_iter(const RjpValue *value);
+/* false once every member has been visited; the key is not NUL-terminated */
+bool rjp_object_iter_next(RjpObjectIter *iter, const char **key, size_t *key_len,
+                          const RjpValue **value);
+void rjp_object_iter_free(RjpObjectIter *iter);
+
+/*
+ * Serializes value with indent spaces per level, or on one line if indent is
+ * negative. len, if not NULL, receives the length. Release with rjp_string_free.
+ */
+char *rjp_serialize(const RjpValue *value, int32_t indent, size_t *len);
+void rjp_string_free(char *text);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUST_JSON_PARSER_H */
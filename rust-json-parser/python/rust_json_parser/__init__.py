@@ -1,13 +1,45 @@
 from rust_json_parser._rust_json_parser import (
+    JSONDecodeError,
+    JsonDocument,
+    JsonLinesIterator,
+    LazyJson,
     benchmark_performance,
+    dump,
     dumps,
+    iter_json_lines,
+    key_histogram,
+    load,
+    loads_lines,
     parse_json,
     parse_json_file,
+    parse_to_handle,
+    parse_validate_only,
+    pointer,
+    query,
+    tagged,
+    validate,
+    validate_json,
 )
 
 __all__ = [
     "parse_json",
     "parse_json_file",
+    "load",
+    "iter_json_lines",
+    "loads_lines",
+    "JsonLinesIterator",
+    "parse_validate_only",
+    "parse_to_handle",
+    "JsonDocument",
+    "LazyJson",
+    "JSONDecodeError",
+    "query",
+    "pointer",
+    "validate",
+    "validate_json",
+    "key_histogram",
+    "tagged",
     "dumps",
+    "dump",
     "benchmark_performance",
 ]
@@ -31,30 +31,46 @@ def _auto_rounds(size: int, requested: int) -> int:
     return requested
 
 
-def _comparison(label: str, other_time: float, rust_time: float) -> str:
+BASELINE_LABELS = {
+    "pure-rust": "Rust:",
+    "json": "Python json (C):",
+    "simplejson": "simplejson:",
+    "orjson": "orjson:",
+    "ujson": "ujson:",
+}
+
+
+def _timing(stats: dict) -> str:
+    return f"{stats['median']:.9f}s (p95 {stats['p95']:.9f}s, stddev {stats['stddev']:.9f}s)"
+
+
+def _comparison(label: str, other: dict, rust: dict) -> str:
+    other_time, rust_time = other["median"], rust["median"]
     if other_time >= rust_time:
         pct = (other_time / rust_time - 1) * 100
-        return f"  {label:<22} {other_time:.9f}s  (Rust with bindings is {pct:.0f}% faster)"
+        return f"    {label:<22} {_timing(other)}  (Rust with bindings is {pct:.0f}% faster)"
     pct = (rust_time / other_time - 1) * 100
-    return f"  {label:<22} {other_time:.9f}s  ({label.rstrip(':')} is {pct:.0f}% faster than Rust with Python bindings)"
+    return f"    {label:<22} {_timing(other)}  ({label.rstrip(':')} is {pct:.0f}% faster than Rust with Python bindings)"
 
 
-def _benchmark_file(path: str, rounds: int, warmup: int) -> None:
+def _benchmark_file(path: str, rounds: int, warmup: int, disable_gc: bool) -> None:
     raw = open(path).read()
     size = os.path.getsize(path)
     rounds = _auto_rounds(size, rounds)
     name = os.path.basename(path)
 
-    times = benchmark_performance(raw, rounds=rounds, warmup=warmup)
+    times = benchmark_performance(raw, rounds=rounds, warmup=warmup, disable_gc=disable_gc)
 
     print(f"\n{name} ({_human_size(size)}, {rounds} rounds):")
-    print(f"  {'Rust with bindings:':<22} {times['rust']:.9f}s")
-    print(_comparison("Rust:", times["pure-rust"], times["rust"]))
-    print(_comparison("Python json (C):", times["json"], times["rust"]))
-    print(_comparison("simplejson:", times["simplejson"], times["rust"]))
+    for operation, results in times.items():
+        print(f"  {operation}:")
+        print(f"    {'Rust with bindings:':<22} {_timing(results['rust'])}")
+        for key, label in BASELINE_LABELS.items():
+            if key in results:
+                print(_comparison(label, results[key], results["rust"]))
 
 
-def run_benchmark(test_data_dir: str, rounds: int, warmup: int) -> None:
+def run_benchmark(test_data_dir: str, rounds: int, warmup: int, disable_gc: bool) -> None:
     files = sorted(Path(test_data_dir).glob("*.json"))
     if not files:
         print(f"No JSON files found in {test_data_dir}", file=sys.stderr)
@@ -63,7 +79,7 @@ def run_benchmark(test_data_dir: str, rounds: int, warmup: int) -> None:
     print(f"Benchmarking {len(files)} files (including pure Rust implementation)...")
 
     for f in files:
-        _benchmark_file(str(f), rounds, warmup)
+        _benchmark_file(str(f), rounds, warmup, disable_gc)
 
     print()
 
@@ -88,7 +104,8 @@ def main():
         nargs="?",
         const="test-data",
         metavar="DIR",
-        help="run performance comparisons against json and simplejson (default dir: test-data)",
+        help="run performance comparisons against json and, when installed, simplejson, "
+        "orjson and ujson (default dir: test-data)",
     )
     parser.add_argument(
         "--rounds",
@@ -102,10 +119,15 @@ def main():
         default=WARMUP_ROUNDS,
         help=f"number of warmup iterations per parser (default: {WARMUP_ROUNDS})",
     )
+    parser.add_argument(
+        "--disable-gc",
+        action="store_true",
+        help="turn off Python's garbage collector while timing",
+    )
     args = parser.parse_args()
 
     if args.benchmark is not None:
-        run_benchmark(args.benchmark, args.rounds, args.warmup)
+        run_benchmark(args.benchmark, args.rounds, args.warmup, args.disable_gc)
         return
 
     if args.input is None:
@@ -0,0 +1,617 @@
+//! A read-only document stored in a few flat buffers instead of a tree of
+//! individually allocated nodes.
+//!
+//! [`JsonDocument`] keeps every string and object key in one text buffer, every
+//! array element in one node buffer and every object member in another, with
+//! containers referring to ranges of those buffers. Parsing therefore allocates
+//! a handful of growing buffers rather than a `Vec`, `HashMap` or `String` per
+//! node, and dropping the document frees them all at once. This suits
+//! "parse, read a few fields, throw away" workloads; use [`JsonValue`] when the
+//! tree needs to be modified.
+//!
+//! Values are read through [`JsonNode`], a copyable reference into the
+//! document.
+
+use std::fmt;
+
+use crate::error::{unexpected_end_of_input, unexpected_token_error};
+use crate::number::JsonNumber;
+use crate::options::ParserOptions;
+use crate::tokenizer::{RawToken, Tokenizer};
+use crate::value::{JsonMap, JsonValue};
+use crate::{JsonError, JsonResult};
+
+/*
+ * A byte range of the document's text buffer.
+ */
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+/*
+ * A value in the document. Containers refer to `len` consecutive entries of the
+ * element or member buffer, starting at `start`.
+ */
+#[derive(Debug, Clone)]
+enum Node {
+    Null,
+    Boolean(bool),
+    Number(JsonNumber),
+    String(Span),
+    Array { start: usize, len: usize },
+    Object { start: usize, len: usize },
+}
+
+/// A parsed JSON document whose nodes and strings live in a few shared buffers.
+///
+/// See the [module documentation](self) for how it differs from [`JsonValue`].
+/// Object members keep their input order, including repeated keys;
+/// [`JsonNode::get`] returns the last occurrence, like the default
+/// [`DuplicateKeyPolicy::KeepLast`](crate::DuplicateKeyPolicy::KeepLast).
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::JsonDocument;
+///
+/// let document = JsonDocument::parse(r#"{"user": {"id": 7, "name": "Ann"}, "tags": ["a", "b"]}"#)?;
+/// let user = document.root().get("user").unwrap();
+/// assert_eq!(user.get("id").and_then(|id| id.as_i64()), Some(7));
+/// assert_eq!(user.get("name").and_then(|name| name.as_str()), Some("Ann"));
+/// assert_eq!(document.root().get("tags").map(|tags| tags.len()), Some(2));
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonDocument {
+    text: String,
+    elements: Vec<Node>,
+    members: Vec<(Span, Node)>,
+    root: Node,
+}
+
+impl JsonDocument {
+    /// Parses `input` into a document with the default [`ParserOptions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`parse_json`](crate::parse_json).
+    pub fn parse(input: &str) -> JsonResult<Self> {
+        Self::parse_with_options(input, &ParserOptions::default())
+    }
+
+    /// Parses `input` into a document with the given [`ParserOptions`].
+    /// `options.duplicate_keys` is not applied: every member is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{JsonDocument, ParserOptions};
+    ///
+    /// let document = JsonDocument::parse_with_options("[1, 2,]", &ParserOptions::json5())?;
+    /// assert_eq!(document.root().len(), 2);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as
+    /// [`parse_json_with_options`](crate::parse_json_with_options), except
+    /// [`JsonError::DuplicateKey`].
+    pub fn parse_with_options(input: &str, options: &ParserOptions) -> JsonResult<Self> {
+        let mut tokenizer = Tokenizer::new(input);
+        tokenizer.configure(options);
+        tokenizer.skip_bom();
+        let mut builder = Builder {
+            tokenizer,
+            max_depth: options.max_depth,
+            allow_trailing_commas: options.allow_trailing_commas,
+            text: String::new(),
+            elements: Vec::new(),
+            members: Vec::new(),
+            pending_elements: Vec::new(),
+            pending_members: Vec::new(),
+        };
+        let token = builder.expect_token("valid JSON value")?;
+        let root = builder.parse_value(token)?;
+        match builder.tokenizer.next_raw_token() {
+            Ok(None) => {}
+            _ => {
+                return Err(JsonError::TrailingCharacters {
+                    position: builder.tokenizer.token_start(),
+                });
+            }
+        }
+        Ok(Self {
+            text: builder.text,
+            elements: builder.elements,
+            members: builder.members,
+            root,
+        })
+    }
+
+    /// Returns the top-level value of the document.
+    pub fn root(&self) -> JsonNode<'_> {
+        JsonNode {
+            document: self,
+            node: &self.root,
+        }
+    }
+
+    /*
+     * Returns the text of `span`.
+     */
+    fn text(&self, span: Span) -> &str {
+        &self.text[span.start..span.end]
+    }
+}
+
+/// A reference to a value inside a [`JsonDocument`].
+///
+/// It is `Copy` and as cheap to pass around as a pair of pointers; navigating
+/// with [`get`](JsonNode::get) or [`get_index`](JsonNode::get_index) never
+/// allocates.
+#[derive(Clone, Copy)]
+pub struct JsonNode<'doc> {
+    document: &'doc JsonDocument,
+    node: &'doc Node,
+}
+
+impl<'doc> JsonNode<'doc> {
+    fn with(self, node: &'doc Node) -> Self {
+        Self {
+            document: self.document,
+            node,
+        }
+    }
+
+    /// Returns `true` if this is the JSON `null` literal.
+    pub fn is_null(&self) -> bool {
+        matches!(self.node, Node::Null)
+    }
+
+    /// Returns `true` if this is an array.
+    pub fn is_array(&self) -> bool {
+        matches!(self.node, Node::Array { .. })
+    }
+
+    /// Returns `true` if this is an object.
+    pub fn is_object(&self) -> bool {
+        matches!(self.node, Node::Object { .. })
+    }
+
+    /// Returns the boolean if this is `true` or `false`, or `None` otherwise.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.node {
+            Node::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if this is a number, or `None` otherwise.
+    pub fn as_number(&self) -> Option<&'doc JsonNumber> {
+        match self.node {
+            Node::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as an `f64` if this is a number, or `None` otherwise.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().map(JsonNumber::as_f64)
+    }
+
+    /// Returns the number as an `i64` if this is a number that fits one exactly,
+    /// or `None` otherwise.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(JsonNumber::as_i64)
+    }
+
+    /// Returns the string if this is a string, or `None` otherwise. The slice
+    /// borrows from the document.
+    pub fn as_str(&self) -> Option<&'doc str> {
+        match self.node {
+            Node::String(span) => Some(self.document.text(*span)),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of elements of an array or members of an object, or 0
+    /// for any other value.
+    pub fn len(&self) -> usize {
+        match self.node {
+            Node::Array { len, .. } | Node::Object { len, .. } => *len,
+            _ => 0,
+        }
+    }
+
+    /// Returns `true` if [`len`](JsonNode::len) is 0.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Looks up a member by key if this is an object, returning the last member
+    /// with that key.
+    ///
+    /// Objects are searched linearly; for repeated lookups in a wide object,
+    /// collect [`members`](JsonNode::members) into a map first.
+    pub fn get(&self, key: &str) -> Option<JsonNode<'doc>> {
+        let Node::Object { start, len } = self.node else {
+            return None;
+        };
+        self.document.members[*start..start + len]
+            .iter()
+            .rev()
+            .find(|(name, _)| self.document.text(*name) == key)
+            .map(|(_, node)| self.with(node))
+    }
+
+    /// Returns the element at `index` if this is an array.
+    pub fn get_index(&self, index: usize) -> Option<JsonNode<'doc>> {
+        let Node::Array { start, len } = self.node else {
+            return None;
+        };
+        self.document.elements[*start..start + len]
+            .get(index)
+            .map(|node| self.with(node))
+    }
+
+    /// Returns the elements of an array in order, or nothing for any other
+    /// value.
+    pub fn elements(&self) -> impl ExactSizeIterator<Item = JsonNode<'doc>> + 'doc {
+        let elements = match self.node {
+            Node::Array { start, len } => &self.document.elements[*start..start + len],
+            _ => &[],
+        };
+        let this = *self;
+        elements.iter().map(move |node| this.with(node))
+    }
+
+    /// Returns the members of an object in input order, including repeated
+    /// keys, or nothing for any other value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::JsonDocument;
+    ///
+    /// let document = JsonDocument::parse(r#"{"b": 1, "a": 2}"#)?;
+    /// let keys: Vec<&str> = document.root().members().map(|(key, _)| key).collect();
+    /// assert_eq!(keys, ["b", "a"]);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn members(&self) -> impl ExactSizeIterator<Item = (&'doc str, JsonNode<'doc>)> + 'doc {
+        let members = match self.node {
+            Node::Object { start, len } => &self.document.members[*start..start + len],
+            _ => &[],
+        };
+        let this = *self;
+        members
+            .iter()
+            .map(move |(key, node)| (this.document.text(*key), this.with(node)))
+    }
+
+    /// Copies this value into a [`JsonValue`]. Repeated keys keep their last
+    /// value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, JsonDocument};
+    ///
+    /// let input = r#"{"list": [1, "two", null], "flag": true}"#;
+    /// let document = JsonDocument::parse(input)?;
+    /// assert_eq!(document.root().to_value(), parse_json(input)?);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn to_value(&self) -> JsonValue {
+        match self.node {
+            Node::Null => JsonValue::Null,
+            Node::Boolean(b) => JsonValue::Boolean(*b),
+            Node::Number(n) => JsonValue::Number(n.clone()),
+            Node::String(span) => JsonValue::String(self.document.text(*span).to_string()),
+            Node::Array { .. } => JsonValue::Array(self.elements().map(|e| e.to_value()).collect()),
+            Node::Object { len, .. } => {
+                let mut members = JsonMap::default();
+                #[cfg(not(feature = "btreemap"))]
+                members.reserve(*len);
+                #[cfg(feature = "btreemap")]
+                let _ = len;
+                for (key, node) in self.members() {
+                    members.insert(key.into(), node.to_value());
+                }
+                JsonValue::Object(members)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for JsonNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_value(), f)
+    }
+}
+
+/*
+ * Tracks a container being filled in. Members of open containers collect in
+ * the builder's pending buffers from the recorded index on, and move to the
+ * document's buffers in one piece when the container closes, so that every
+ * container's contents end up contiguous.
+ */
+enum Frame {
+    Array(usize),
+    Object(usize, Span),
+}
+
+/*
+ * Single-pass parser writing into the buffers of a `JsonDocument`. It mirrors
+ * the main parser's loop, so inputs are accepted and rejected the same way.
+ */
+struct Builder<'input> {
+    tokenizer: Tokenizer<'input>,
+    max_depth: usize,
+    allow_trailing_commas: bool,
+    text: String,
+    elements: Vec<Node>,
+    members: Vec<(Span, Node)>,
+    pending_elements: Vec<Node>,
+    pending_members: Vec<(Span, Node)>,
+}
+
+impl<'input> Builder<'input> {
+    /*
+     * Consumes the next token, failing with `UnexpectedEndOfInput` if there is none.
+     */
+    fn expect_token(&mut self, expected: &str) -> JsonResult<RawToken<'input>> {
+        self.tokenizer
+            .next_raw_token()?
+            .ok_or_else(|| unexpected_end_of_input(expected, self.tokenizer.offset()))
+    }
+
+    fn unexpected(&self, expected: &str, token: &RawToken) -> JsonError {
+        unexpected_token_error(
+            expected,
+            &format!("{:?}", token),
+            self.tokenizer.token_start(),
+        )
+    }
+
+    /*
+     * Appends `contents` to the text buffer and returns where it ended up.
+     */
+    fn push_text(&mut self, contents: &str) -> Span {
+        let start = self.text.len();
+        self.text.push_str(contents);
+        Span {
+            start,
+            end: self.text.len(),
+        }
+    }
+
+    /*
+     * Validates an object key token, stores it and consumes the colon that
+     * follows it.
+     */
+    fn expect_key(&mut self, token: RawToken<'input>) -> JsonResult<Span> {
+        let key = match token {
+            RawToken::String(key) => self.push_text(&key),
+            // Only produced when unquoted keys are allowed
+            RawToken::Identifier(name) => self.push_text(name),
+            other => return Err(self.unexpected("string", &other)),
+        };
+        match self.expect_token(":")? {
+            RawToken::Colon => Ok(key),
+            other => Err(self.unexpected(":", &other)),
+        }
+    }
+
+    fn check_depth(&self, stack: &[Frame]) -> JsonResult<()> {
+        if stack.len() >= self.max_depth {
+            return Err(JsonError::DepthLimitExceeded {
+                max_depth: self.max_depth,
+                position: self.tokenizer.token_start(),
+            });
+        }
+        Ok(())
+    }
+
+    /*
+     * Moves the elements of the array closing now into the document.
+     */
+    fn close_array(&mut self, mark: usize) -> Node {
+        let start = self.elements.len();
+        self.elements.extend(self.pending_elements.drain(mark..));
+        Node::Array {
+            start,
+            len: self.elements.len() - start,
+        }
+    }
+
+    /*
+     * Moves the members of the object closing now into the document.
+     */
+    fn close_object(&mut self, mark: usize) -> Node {
+        let start = self.members.len();
+        self.members.extend(self.pending_members.drain(mark..));
+        Node::Object {
+            start,
+            len: self.members.len() - start,
+        }
+    }
+
+    /*
+     * Parses the value that starts with the already consumed `token`.
+     */
+    fn parse_value(&mut self, mut token: RawToken<'input>) -> JsonResult<Node> {
+        let mut stack: Vec<Frame> = Vec::new();
+
+        loop {
+            self.tokenizer.count_value(self.tokenizer.token_start())?;
+            // Read a value, or open a container and continue with its first member
+            let mut node = match token {
+                RawToken::LeftBracket => {
+                    self.check_depth(&stack)?;
+                    token = self.expect_token("valid JSON value or closing bracket")?;
+                    if token != RawToken::RightBracket {
+                        stack.push(Frame::Array(self.pending_elements.len()));
+                        continue;
+                    }
+                    Node::Array {
+                        start: self.elements.len(),
+                        len: 0,
+                    }
+                }
+                RawToken::LeftBrace => {
+                    self.check_depth(&stack)?;
+                    token = self.expect_token("string or closing brace")?;
+                    if token != RawToken::RightBrace {
+                        let key = self.expect_key(token)?;
+                        stack.push(Frame::Object(self.pending_members.len(), key));
+                        token = self.expect_token("valid JSON value")?;
+                        continue;
+                    }
+                    Node::Object {
+                        start: self.members.len(),
+                        len: 0,
+                    }
+                }
+                RawToken::String(s) => Node::String(self.push_text(&s)),
+                RawToken::Number(n) => Node::Number(n),
+                RawToken::Boolean(b) => Node::Boolean(b),
+                RawToken::Null => Node::Null,
+                other => return Err(self.unexpected("valid JSON value", &other)),
+            };
+
+            // Store the value in its parent, closing every container that ends here
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(node),
+                    Some(Frame::Array(mark)) => {
+                        let mark = *mark;
+                        self.pending_elements.push(node);
+                        match self.expect_token(", or closing bracket")? {
+                            RawToken::Comma => {
+                                token = self.expect_token("valid JSON value")?;
+                                if !(self.allow_trailing_commas && token == RawToken::RightBracket)
+                                {
+                                    break;
+                                }
+                            }
+                            RawToken::RightBracket => {}
+                            other => return Err(self.unexpected(", or closing bracket", &other)),
+                        }
+                        stack.pop();
+                        node = self.close_array(mark);
+                    }
+                    Some(Frame::Object(mark, key)) => {
+                        let mark = *mark;
+                        self.pending_members.push((*key, node));
+                        match self.expect_token(", or closing brace")? {
+                            RawToken::Comma => {
+                                let key_token = self.expect_token("string")?;
+                                if !(self.allow_trailing_commas
+                                    && key_token == RawToken::RightBrace)
+                                {
+                                    *key = self.expect_key(key_token)?;
+                                    token = self.expect_token("valid JSON value")?;
+                                    break;
+                                }
+                            }
+                            RawToken::RightBrace => {}
+                            other => return Err(self.unexpected(", or closing brace", &other)),
+                        }
+                        stack.pop();
+                        node = self.close_object(mark);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn test_matches_owned_parser() {
+        let input = r#"{
+            "name": "café",
+            "list": [1, -2.5, "x\ny", [], {}, [[true, false]], null],
+            "nested": {"a": {"b": {"c": "deep"}}, "empty": ""}
+        }"#;
+        let document = JsonDocument::parse(input).unwrap();
+        assert_eq!(document.root().to_value(), parse_json(input).unwrap());
+    }
+
+    #[test]
+    fn test_navigation() {
+        let document =
+            JsonDocument::parse(r#"[{"id": 1, "tags": ["a", "b"]}, {"id": 2, "tags": []}]"#)
+                .unwrap();
+        let root = document.root();
+        assert!(root.is_array());
+        assert_eq!(root.len(), 2);
+        let ids: Vec<i64> = root
+            .elements()
+            .filter_map(|row| row.get("id").and_then(|id| id.as_i64()))
+            .collect();
+        assert_eq!(ids, [1, 2]);
+
+        let tags = root.get_index(0).and_then(|row| row.get("tags")).unwrap();
+        assert_eq!(tags.get_index(1).and_then(|tag| tag.as_str()), Some("b"));
+        assert!(tags.get_index(2).is_none());
+        assert!(root.get_index(1).unwrap().get("tags").unwrap().is_empty());
+        assert!(root.get("id").is_none());
+        assert_eq!(root.get_index(0).unwrap().get("id").unwrap().as_str(), None);
+    }
+
+    #[test]
+    fn test_repeated_keys_are_kept_and_last_wins() {
+        let document = JsonDocument::parse(r#"{"a": 1, "b": 2, "a": 3}"#).unwrap();
+        let root = document.root();
+        assert_eq!(root.len(), 3);
+        assert_eq!(root.get("a").and_then(|a| a.as_i64()), Some(3));
+        assert_eq!(root.to_value(), parse_json(r#"{"a": 3, "b": 2}"#).unwrap());
+    }
+
+    #[test]
+    fn test_byte_order_mark_is_skipped() {
+        let document = JsonDocument::parse("\u{FEFF}{\"a\": [1]}").unwrap();
+        assert_eq!(
+            document.root().to_value(),
+            parse_json(r#"{"a": [1]}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_errors_match_owned_parser() {
+        for input in ["", "[1, 2", r#"{"a" 1}"#, "[1,]", "1 2", r#"{"a": }"#] {
+            assert_eq!(
+                JsonDocument::parse(input).unwrap_err(),
+                parse_json(input).unwrap_err(),
+                "{}",
+                input
+            );
+        }
+        let options = ParserOptions {
+            max_depth: 2,
+            ..ParserOptions::default()
+        };
+        assert!(matches!(
+            JsonDocument::parse_with_options("[[[1]]]", &options),
+            Err(JsonError::DepthLimitExceeded { max_depth: 2, .. })
+        ));
+        let options = ParserOptions {
+            max_values: 3,
+            ..ParserOptions::default()
+        };
+        assert!(JsonDocument::parse_with_options(r#"{"a": [1]}"#, &options).is_ok());
+        assert!(matches!(
+            JsonDocument::parse_with_options(r#"{"a": [1, 2]}"#, &options),
+            Err(JsonError::ValueCountExceeded { position: 10, .. })
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+//! Asynchronous parsing on top of tokio (`async` feature).
+//!
+//! Input is read in chunks and handed to an [`IncrementalParser`], like
+//! [`parse_json_buf_reader`](crate::parse_json_buf_reader) does for blocking
+//! readers, so large files are loaded without blocking the runtime's worker
+//! threads. Once the root value is complete, it is parsed on tokio's blocking
+//! thread pool with [`spawn_blocking`](tokio::task::spawn_blocking), so large
+//! documents do not stall other tasks either.
+
+use std::io;
+use std::panic;
+use std::path::Path;
+
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+
+use crate::JsonResult;
+use crate::incremental::IncrementalParser;
+use crate::value::JsonValue;
+
+/// Reads JSON from any tokio [`AsyncRead`] source (files, sockets, pipes, ...)
+/// and parses it.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json_async_reader, JsonValue};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let input: &[u8] = br#"{"ok": true}"#;
+/// let value = parse_json_async_reader(input).await?;
+/// assert_eq!(value.get("ok"), Some(&JsonValue::Boolean(true)));
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// # }).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::Io`](crate::JsonError::Io) if reading fails or the
+/// runtime shuts down before the value is parsed,
+/// [`JsonError::InvalidUtf8`](crate::JsonError::InvalidUtf8) if the input is not
+/// valid UTF-8, or any other [`JsonError`](crate::JsonError) variant if the input
+/// is not valid JSON.
+pub async fn parse_json_async_reader<R: AsyncRead + Unpin>(reader: R) -> JsonResult<JsonValue> {
+    let mut reader = BufReader::new(reader);
+    let mut parser = IncrementalParser::deferred();
+    loop {
+        let chunk = reader.fill_buf().await?;
+        if chunk.is_empty() {
+            break;
+        }
+        let len = chunk.len();
+        parser.feed(chunk)?;
+        reader.consume(len);
+    }
+    match tokio::task::spawn_blocking(move || parser.finish()).await {
+        Ok(result) => result,
+        Err(err) if err.is_panic() => panic::resume_unwind(err.into_panic()),
+        // The runtime is shutting down
+        Err(err) => Err(io::Error::other(err).into()),
+    }
+}
+
+/// Reads a file at the given path without blocking the runtime and parses its
+/// contents as JSON.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_json_parser::parse_json_file_async;
+///
+/// # async fn load() -> Result<(), rust_json_parser::JsonError> {
+/// let value = parse_json_file_async("data.json").await?;
+/// println!("{}", value);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`parse_json_file`](crate::parse_json_file).
+pub async fn parse_json_file_async(path: impl AsRef<Path>) -> JsonResult<JsonValue> {
+    parse_json_async_reader(File::open(path).await?).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::files::tests::test_dir;
+    use crate::{JsonError, parse_json};
+
+    #[tokio::test]
+    async fn test_parse_json_async_reader() {
+        let input = br#"{"a": [1, 2, {"b": "\u00e9"}]}"#;
+        let value = parse_json_async_reader(&input[..]).await.unwrap();
+        assert_eq!(
+            value,
+            parse_json(std::str::from_utf8(input).unwrap()).unwrap()
+        );
+
+        let err = parse_json_async_reader(&b"[1, 2"[..]).await.unwrap_err();
+        assert!(matches!(err, JsonError::UnexpectedEndOfInput { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_parse_json_file_async() {
+        let dir = test_dir("async_file");
+        let path = dir.join("large.json");
+        // Larger than the reader's buffer, so the document arrives in several chunks
+        let document = format!("[{}]", vec!["\"0123456789\""; 2000].join(","));
+        std::fs::write(&path, &document).unwrap();
+
+        let value = parse_json_file_async(&path).await.unwrap();
+        assert_eq!(value.len(), 2000);
+        assert!(
+            parse_json_file_async(dir.join("missing.json"))
+                .await
+                .unwrap_err()
+                .is_io()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,400 @@
+//! Reports about input that is not read exactly as written.
+//!
+//! [`parse_json_audited`] parses a document like
+//! [`parse_json_with_options`](crate::parse_json_with_options) and also reports
+//! every number literal whose `f64` value denotes a different number, such as
+//! `9007199254740993` (read as `9007199254740992`) or `0.30000000000000000001`
+//! (read as `0.3`). Running it over samples of a feed shows whether that feed
+//! needs [`ParserOptions::preserve_number_text`] before the option is turned on
+//! everywhere.
+//!
+//! Literals are compared by value, so `0.1`, `1.50` and `1e2` are exact even
+//! though `0.1` has no finite binary expansion: the nearest `f64` reads back as
+//! the same decimal number.
+//!
+//! [`parse_json_with_escape_warnings`] lists the escape sequences JSON does not
+//! define that a lenient [`UnknownEscapePolicy`](crate::UnknownEscapePolicy) let through.
+
+use std::fmt;
+
+use crate::JsonResult;
+use crate::number::JsonNumber;
+use crate::options::ParserOptions;
+use crate::parser::parse_whole_input;
+use crate::pool::JsonValuePool;
+use crate::query::push_segment;
+use crate::tokenizer::Tokenizer;
+use crate::value::JsonValue;
+
+/// A number literal whose `f64` value is a different number, found by
+/// [`parse_json_audited`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LossyNumber {
+    /// JSON Pointer to the number (`""` for the root).
+    pub path: String,
+    /// The literal as written in the input.
+    pub literal: String,
+    /// The `f64` the literal is read as.
+    pub value: f64,
+}
+
+/// Formats the warning as `/path: literal is read as value`, writing the root
+/// as `(root)`.
+impl fmt::Display for LossyNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = match self.path.as_str() {
+            "" => "(root)",
+            path => path,
+        };
+        write!(f, "{}: {} is read as {}", path, self.literal, self.value)
+    }
+}
+
+/// Parses `input` with `options` and returns the value together with every
+/// number literal that loses precision as an `f64`, in document order.
+///
+/// Integers that fit in 64 bits are reported when they exceed the range in which
+/// `f64` is exact (±2^53), even though [`JsonNumber`] keeps them exactly: a
+/// consumer calling [`as_f64`](JsonNumber::as_f64), or a JavaScript client, still
+/// loses them. Literals too large for `f64` (such as `1e400`) are an error
+/// unless [`ParserOptions::preserve_number_text`] or
+/// [`ParserOptions::allow_nan`] lets them through; they are then reported as
+/// infinity. The words `NaN` and `Infinity` accepted by
+/// [`ParserOptions::allow_nan`] are not literals of a number and never reported.
+///
+/// The returned value is the same as without auditing; in particular, number
+/// text is only kept when `options.preserve_number_text` is set.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{ParserOptions, audit::parse_json_audited};
+///
+/// let input = r#"{"id": 12345678901234567890123, "price": 0.1, "ids": [9007199254740993]}"#;
+/// let (value, warnings) = parse_json_audited(input, &ParserOptions::default())?;
+///
+/// let mut paths: Vec<&str> = warnings.iter().map(|w| w.path.as_str()).collect();
+/// paths.sort_unstable();
+/// assert_eq!(paths, ["/id", "/ids/0"]);
+/// assert_eq!(value.get("price").and_then(|p| p.as_number()).and_then(|n| n.raw()), None);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`parse_json_with_options`](crate::parse_json_with_options).
+pub fn parse_json_audited(
+    input: &str,
+    options: &ParserOptions,
+) -> JsonResult<(JsonValue, Vec<LossyNumber>)> {
+    let audit_options = ParserOptions {
+        preserve_number_text: true,
+        ..options.clone()
+    };
+    let mut tokenizer = Tokenizer::new(input);
+    tokenizer.configure(&audit_options);
+    // Keeping the text must not let through literals that `options` rejects
+    tokenizer.allow_overflow(options.preserve_number_text || options.allow_nan);
+    let mut value = parse_whole_input(
+        &mut tokenizer,
+        &audit_options,
+        &mut JsonValuePool::with_limit(0),
+    )?;
+    let mut warnings = Vec::new();
+    audit(
+        &mut value,
+        &mut String::new(),
+        options.preserve_number_text,
+        &mut warnings,
+    );
+    Ok((value, warnings))
+}
+
+/// An escape sequence JSON does not define, let through by
+/// [`parse_json_with_escape_warnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownEscape {
+    /// Byte offset of the backslash in the input.
+    pub position: usize,
+    /// The sequence as written, such as `\'`.
+    pub sequence: String,
+}
+
+/// Formats the warning as `unknown escape \' at position 12`.
+impl fmt::Display for UnknownEscape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown escape {} at position {}",
+            self.sequence, self.position
+        )
+    }
+}
+
+/// Parses `input` with `options` and returns the value together with every
+/// escape sequence that `options.unknown_escapes` let through, in input order.
+///
+/// With [`UnknownEscapePolicy::Reject`](crate::UnknownEscapePolicy::Reject)
+/// there is nothing to report: the first such sequence is an error, as with
+/// [`parse_json_with_options`](crate::parse_json_with_options).
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{ParserOptions, UnknownEscapePolicy, audit::parse_json_with_escape_warnings};
+///
+/// let options = ParserOptions {
+///     unknown_escapes: UnknownEscapePolicy::Unescape,
+///     ..ParserOptions::default()
+/// };
+/// let (value, warnings) = parse_json_with_escape_warnings(r#"["it\'s"]"#, &options)?;
+/// assert_eq!(value.get_index(0).and_then(|s| s.as_str()), Some("it's"));
+/// assert_eq!(warnings[0].to_string(), r"unknown escape \' at position 4");
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`parse_json_with_options`](crate::parse_json_with_options).
+pub fn parse_json_with_escape_warnings(
+    input: &str,
+    options: &ParserOptions,
+) -> JsonResult<(JsonValue, Vec<UnknownEscape>)> {
+    let mut tokenizer = Tokenizer::new(input);
+    tokenizer.configure(options);
+    let value = parse_whole_input(&mut tokenizer, options, &mut JsonValuePool::with_limit(0))?;
+    let warnings = tokenizer
+        .passed_escapes()
+        .iter()
+        .map(|&position| {
+            let escaped = input[position + 1..].chars().next().unwrap_or_default();
+            UnknownEscape {
+                position,
+                sequence: format!("\\{}", escaped),
+            }
+        })
+        .collect();
+    Ok((value, warnings))
+}
+
+/*
+ * Walks the tree, recording lossy numbers under their JSON Pointer and dropping
+ * the number text kept for the audit unless the caller asked for it.
+ */
+fn audit(
+    value: &mut JsonValue,
+    path: &mut String,
+    keep_text: bool,
+    warnings: &mut Vec<LossyNumber>,
+) {
+    match value {
+        JsonValue::Number(number) => {
+            if let Some(literal) = number.raw()
+                && is_lossy(number, literal)
+            {
+                warnings.push(LossyNumber {
+                    path: path.clone(),
+                    literal: literal.to_string(),
+                    value: number.as_f64(),
+                });
+            }
+            if !keep_text {
+                number.clear_raw();
+            }
+        }
+        JsonValue::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let len = path.len();
+                push_segment(path, &index.to_string());
+                audit(item, path, keep_text, warnings);
+                path.truncate(len);
+            }
+        }
+        JsonValue::Object(members) => {
+            for (key, member) in members.iter_mut() {
+                let len = path.len();
+                push_segment(path, key);
+                audit(member, path, keep_text, warnings);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/*
+ * Returns whether the `f64` value of `number` differs from the value of the
+ * literal it was parsed from.
+ */
+fn is_lossy(number: &JsonNumber, literal: &str) -> bool {
+    let value = number.as_f64();
+    // Integers are stored exactly; i128 holds both them and the f64 cast back
+    // without saturating at the 64-bit bounds
+    if let Some(u) = number.as_u64() {
+        return value as i128 != i128::from(u);
+    }
+    if let Some(i) = number.as_i64() {
+        return value as i128 != i128::from(i);
+    }
+    match decimal_parts(literal) {
+        Some(_) if !value.is_finite() => true,
+        Some(parts) => decimal_parts(&format!("{:e}", value)) != Some(parts),
+        // `NaN` and `Infinity` are words, not decimal literals
+        None => false,
+    }
+}
+
+/*
+ * Splits a decimal literal into its sign, significant digits and exponent, so
+ * that spellings of the same value compare equal: `1.50`, `15e-1` and `0.15E1`
+ * all become `(false, "15", -1)`. Zero has no digits and no sign. Returns
+ * `None` for anything that is not a decimal literal.
+ */
+fn decimal_parts(literal: &str) -> Option<(bool, String, i64)> {
+    let (negative, unsigned) = match literal.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, literal),
+    };
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (unsigned, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if integer.is_empty() && fraction.is_empty()
+        || !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let digits = format!("{}{}", integer, fraction);
+    let significant = digits.trim_end_matches('0');
+    let exponent = exponent
+        .checked_sub(fraction.len() as i64)?
+        .checked_add((digits.len() - significant.len()) as i64)?;
+    let significant = significant.trim_start_matches('0');
+    if significant.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+    Some((negative, significant.to_string(), exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JsonError, UnknownEscapePolicy};
+
+    #[test]
+    fn test_decimal_parts_normalizes_spellings() {
+        for literal in ["1.50", "15e-1", "0.15E1", "1.5e+0"] {
+            assert_eq!(
+                decimal_parts(literal),
+                Some((false, "15".to_string(), -1)),
+                "{}",
+                literal
+            );
+        }
+        assert_eq!(decimal_parts("-0.0e5"), Some((false, String::new(), 0)));
+        assert_eq!(decimal_parts("-1200"), Some((true, "12".to_string(), 2)));
+        assert_eq!(decimal_parts("NaN"), None);
+        assert_eq!(decimal_parts("0x1F"), None);
+    }
+
+    #[test]
+    fn test_parse_json_audited_reports_lossy_numbers() {
+        let input = r#"[0.1, 1.50, -0, 9007199254740992, 9007199254740993,
+                        18446744073709551615, -9223372036854775807,
+                        0.30000000000000000001, {"a/b": 123456789012345678901234567890}]"#;
+        let (value, warnings) = parse_json_audited(input, &ParserOptions::default()).unwrap();
+        let found: Vec<(&str, &str)> = warnings
+            .iter()
+            .map(|w| (w.path.as_str(), w.literal.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("/4", "9007199254740993"),
+                ("/5", "18446744073709551615"),
+                ("/6", "-9223372036854775807"),
+                ("/7", "0.30000000000000000001"),
+                ("/8/a~1b", "123456789012345678901234567890"),
+            ]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "/4: 9007199254740993 is read as 9007199254740992"
+        );
+        assert_eq!(
+            value.to_string(),
+            crate::parse_json(input).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_json_audited_keeps_options() {
+        let options = ParserOptions {
+            preserve_number_text: true,
+            allow_nan: true,
+            ..ParserOptions::default()
+        };
+        let (value, warnings) = parse_json_audited("[NaN, 1.50, 2e-400]", &options).unwrap();
+        assert_eq!(value.to_string(), "[null,1.50,2e-400]");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].to_string(), "/2: 2e-400 is read as 0");
+        assert!(parse_json_audited("[1,", &options).is_err());
+
+        let (_, warnings) = parse_json_audited("1e400", &options).unwrap();
+        assert_eq!(warnings[0].to_string(), "(root): 1e400 is read as inf");
+        assert!(matches!(
+            parse_json_audited("1e400", &ParserOptions::default()),
+            Err(JsonError::NumberOutOfRange { position: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_unknown_escape_policies() {
+        let input = r#"{"a\'b": "\x41 \é \n"}"#;
+        let cases = [
+            (UnknownEscapePolicy::Keep, r"\x41 \é "),
+            (UnknownEscapePolicy::Unescape, "x41 é "),
+        ];
+        for (unknown_escapes, expected) in cases {
+            let options = ParserOptions {
+                unknown_escapes,
+                ..ParserOptions::default()
+            };
+            let (value, warnings) = parse_json_with_escape_warnings(input, &options).unwrap();
+            let key = if unknown_escapes == UnknownEscapePolicy::Keep {
+                r"a\'b"
+            } else {
+                "a'b"
+            };
+            assert_eq!(
+                value.get(key).and_then(|s| s.as_str()),
+                Some(format!("{}\n", expected).as_str())
+            );
+            let found: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+            assert_eq!(
+                found,
+                [
+                    r"unknown escape \' at position 3",
+                    r"unknown escape \x at position 10",
+                    r"unknown escape \é at position 15",
+                ]
+            );
+        }
+
+        let err = parse_json_with_escape_warnings(input, &ParserOptions::default()).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::JsonError::InvalidEscape { char: '\'', .. }
+        ));
+        let err = crate::parse_json(r#""\é""#).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::JsonError::InvalidEscape { char: 'é', .. }
+        ));
+    }
+}
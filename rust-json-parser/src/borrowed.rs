@@ -0,0 +1,593 @@
+//! Zero-copy parsing into a document that borrows from the input.
+//!
+//! [`parse_json_borrowed`] produces a [`JsonValueRef`] whose strings and object
+//! keys point back into the input slice whenever they contain no escape
+//! sequences. Only strings that need unescaping are allocated, which greatly
+//! reduces allocations for read-only workloads on large inputs.
+//!
+//! With the `lazy-strings` Cargo feature, string values are not unescaped while
+//! parsing at all: each one keeps its raw slice of the input and is decoded the
+//! first time it is read (see `LazyStr`), so fields that are never accessed
+//! cost no decoding work. Object keys are still decoded eagerly, since they are
+//! needed for lookups.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+#[cfg(feature = "lazy-strings")]
+use std::{fmt, ops::Deref, sync::OnceLock};
+
+use crate::error::{unexpected_end_of_input, unexpected_token_error};
+use crate::number::JsonNumber;
+use crate::options::DEFAULT_MAX_DEPTH;
+#[cfg(feature = "lazy-strings")]
+use crate::tokenizer::unescape;
+use crate::tokenizer::{RawToken, Tokenizer};
+use crate::value::{JsonHasher, JsonValue};
+use crate::{JsonError, JsonResult};
+
+/// The type of the string values of a [`JsonValueRef`].
+///
+/// This is [`Cow<'a, str>`](Cow) unless the `lazy-strings` Cargo feature is
+/// enabled, in which case it is `LazyStr`. Both dereference to `str`, which
+/// keeps code that only reads strings independent of the feature.
+#[cfg(not(feature = "lazy-strings"))]
+pub type JsonStr<'a> = Cow<'a, str>;
+/// The type of the string values of a [`JsonValueRef`], decoded on first access.
+#[cfg(feature = "lazy-strings")]
+pub type JsonStr<'a> = LazyStr<'a>;
+
+/// A string value whose escape sequences are decoded the first time it is read.
+///
+/// Strings without escape sequences are used straight from the input and never
+/// allocate. Escape sequences are validated while parsing, so decoding cannot
+/// fail.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json_borrowed, JsonValueRef};
+///
+/// let value = parse_json_borrowed(r#"["caf\u00e9"]"#)?;
+/// let Some(JsonValueRef::String(s)) = value.get_index(0) else { unreachable!() };
+/// assert_eq!(s.raw(), r"caf\u00e9");
+/// assert_eq!(&**s, "café");
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[cfg(feature = "lazy-strings")]
+#[derive(Clone)]
+pub struct LazyStr<'a> {
+    raw: &'a str,
+    escaped: bool,
+    decoded: OnceLock<String>,
+}
+
+#[cfg(feature = "lazy-strings")]
+impl<'a> LazyStr<'a> {
+    fn new(raw: &'a str) -> Self {
+        Self {
+            raw,
+            escaped: raw.contains('\\'),
+            decoded: OnceLock::new(),
+        }
+    }
+
+    /// Returns the contents as they appear in the input, with any escape
+    /// sequences still encoded.
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// Returns `true` if the contents contain escape sequences, which means that
+    /// reading the string allocates a decoded copy.
+    pub fn is_escaped(&self) -> bool {
+        self.escaped
+    }
+
+    /// Converts this string into an owned `String`, decoding it if needed.
+    pub fn into_owned(self) -> String {
+        match self.decoded.into_inner() {
+            Some(decoded) => decoded,
+            None => unescape(self.raw).into_owned(),
+        }
+    }
+}
+
+#[cfg(feature = "lazy-strings")]
+impl Deref for LazyStr<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        if !self.escaped {
+            return self.raw;
+        }
+        self.decoded.get_or_init(|| unescape(self.raw).into_owned())
+    }
+}
+
+#[cfg(feature = "lazy-strings")]
+impl PartialEq for LazyStr<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+#[cfg(feature = "lazy-strings")]
+impl fmt::Debug for LazyStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A parsed JSON value that borrows its strings from the input where possible.
+///
+/// This mirrors [`JsonValue`], with strings stored as [`JsonStr`] and keys as
+/// [`Cow<'a, str>`](Cow). Use [`into_owned`](JsonValueRef::into_owned) to convert
+/// it into a [`JsonValue`] that no longer depends on the input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValueRef<'a> {
+    /// A JSON string, borrowed unless it contained escape sequences.
+    String(JsonStr<'a>),
+    /// A JSON number.
+    Number(JsonNumber),
+    /// A JSON boolean (`true` or `false`).
+    Boolean(bool),
+    /// The JSON `null` literal.
+    Null,
+    /// An ordered JSON array of values.
+    Array(Vec<JsonValueRef<'a>>),
+    /// A JSON object mapping (possibly borrowed) keys to values.
+    Object(HashMap<Cow<'a, str>, JsonValueRef<'a>, JsonHasher>),
+}
+
+impl<'a> JsonValueRef<'a> {
+    /// Returns `true` if this value is `JsonValueRef::Null`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValueRef::Null)
+    }
+
+    /// Returns the string slice if this is a `JsonValueRef::String`, or `None`
+    /// otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json_borrowed;
+    ///
+    /// let value = parse_json_borrowed(r#""hello""#)?;
+    /// assert_eq!(value.as_str(), Some("hello"));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValueRef::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as an `f64` if this is a `JsonValueRef::Number`, or
+    /// `None` otherwise.
+    pub fn as_f64(&self) -> Option<f64> {
+        let JsonValueRef::Number(n) = self else {
+            return None;
+        };
+        Some(n.as_f64())
+    }
+
+    /// Returns a reference to the inner [`JsonNumber`] if this is a
+    /// `JsonValueRef::Number`, or `None` otherwise.
+    pub fn as_number(&self) -> Option<&JsonNumber> {
+        match self {
+            JsonValueRef::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the inner `bool` if this is a `JsonValueRef::Boolean`, or `None`
+    /// otherwise.
+    pub fn as_bool(&self) -> Option<bool> {
+        let JsonValueRef::Boolean(b) = self else {
+            return None;
+        };
+        Some(*b)
+    }
+
+    /// Returns a reference to the elements if this is a `JsonValueRef::Array`, or
+    /// `None` otherwise.
+    pub fn as_array(&self) -> Option<&Vec<JsonValueRef<'a>>> {
+        match self {
+            JsonValueRef::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the members if this is a `JsonValueRef::Object`, or
+    /// `None` otherwise.
+    pub fn as_object(&self) -> Option<&HashMap<Cow<'a, str>, JsonValueRef<'a>, JsonHasher>> {
+        match self {
+            JsonValueRef::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Looks up a value by key if this is a `JsonValueRef::Object`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json_borrowed;
+    ///
+    /// let value = parse_json_borrowed(r#"{"name": "Alice"}"#)?;
+    /// assert_eq!(value.get("name").and_then(|v| v.as_str()), Some("Alice"));
+    /// assert!(value.get("missing").is_none());
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&JsonValueRef<'a>> {
+        self.as_object().and_then(|o| o.get(key))
+    }
+
+    /// Looks up a value by index if this is a `JsonValueRef::Array`.
+    pub fn get_index(&self, index: usize) -> Option<&JsonValueRef<'a>> {
+        self.as_array().and_then(|a| a.get(index))
+    }
+
+    /// Converts this value into an owned [`JsonValue`], copying any borrowed
+    /// strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, parse_json_borrowed};
+    ///
+    /// let input = r#"{"list": [1, "two", null]}"#;
+    /// let owned = parse_json_borrowed(input)?.into_owned();
+    /// assert_eq!(owned, parse_json(input)?);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn into_owned(self) -> JsonValue {
+        match self {
+            JsonValueRef::String(s) => JsonValue::String(s.into_owned()),
+            JsonValueRef::Number(n) => JsonValue::Number(n),
+            JsonValueRef::Boolean(b) => JsonValue::Boolean(b),
+            JsonValueRef::Null => JsonValue::Null,
+            JsonValueRef::Array(a) => {
+                JsonValue::Array(a.into_iter().map(JsonValueRef::into_owned).collect())
+            }
+            JsonValueRef::Object(o) => JsonValue::Object(
+                o.into_iter()
+                    .map(|(k, v)| (k.into(), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<JsonValueRef<'_>> for JsonValue {
+    fn from(value: JsonValueRef<'_>) -> Self {
+        value.into_owned()
+    }
+}
+
+/*
+ * Recursive descent parser over the tokenizer's borrowed tokens, with a single
+ * token of lookahead.
+ */
+struct BorrowedParser<'input> {
+    tokenizer: Tokenizer<'input>,
+    position: usize,
+    depth: usize,
+}
+
+impl<'input> BorrowedParser<'input> {
+    /*
+     * Consumes the next token, failing with `UnexpectedEndOfInput` if there is none.
+     */
+    fn expect_token(&mut self, expected: &str) -> JsonResult<RawToken<'input>> {
+        let token = self
+            .tokenizer
+            .next_raw_token()?
+            .ok_or_else(|| unexpected_end_of_input(expected, self.position))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn parse_value(&mut self, token: RawToken<'input>) -> JsonResult<JsonValueRef<'input>> {
+        match token {
+            RawToken::LeftBracket | RawToken::LeftBrace => {
+                if self.depth >= DEFAULT_MAX_DEPTH {
+                    return Err(JsonError::DepthLimitExceeded {
+                        max_depth: DEFAULT_MAX_DEPTH,
+                        position: self.position - 1,
+                    });
+                }
+                self.depth += 1;
+                let container = match token {
+                    RawToken::LeftBracket => self.parse_array(),
+                    _ => self.parse_object(),
+                };
+                self.depth -= 1;
+                container
+            }
+            RawToken::String(s) => Ok(JsonValueRef::String(string_value(s))),
+            RawToken::Number(n) => Ok(JsonValueRef::Number(n)),
+            RawToken::Boolean(b) => Ok(JsonValueRef::Boolean(b)),
+            RawToken::Null => Ok(JsonValueRef::Null),
+            token => Err(unexpected_token_error(
+                "valid JSON value",
+                &format!("{:?}", token),
+                self.position - 1,
+            )),
+        }
+    }
+
+    fn parse_array(&mut self) -> JsonResult<JsonValueRef<'input>> {
+        let mut array = Vec::new();
+        let mut token = self.expect_token("valid JSON value or closing bracket")?;
+        if token == RawToken::RightBracket {
+            return Ok(JsonValueRef::Array(array));
+        }
+
+        loop {
+            array.push(self.parse_value(token)?);
+            match self.expect_token(", or closing bracket")? {
+                RawToken::Comma => token = self.expect_token("valid JSON value")?,
+                RawToken::RightBracket => return Ok(JsonValueRef::Array(array)),
+                other => {
+                    return Err(unexpected_token_error(
+                        ", or closing bracket",
+                        &format!("{:?}", other),
+                        self.position - 1,
+                    ));
+                }
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> JsonResult<JsonValueRef<'input>> {
+        let mut object = HashMap::default();
+        let mut token = self.expect_token("string or closing brace")?;
+        if token == RawToken::RightBrace {
+            return Ok(JsonValueRef::Object(object));
+        }
+
+        loop {
+            let RawToken::String(key) = token else {
+                return Err(unexpected_token_error(
+                    "string",
+                    &format!("{:?}", token),
+                    self.position - 1,
+                ));
+            };
+            match self.expect_token(":")? {
+                RawToken::Colon => {}
+                other => {
+                    return Err(unexpected_token_error(
+                        ":",
+                        &format!("{:?}", other),
+                        self.position - 1,
+                    ));
+                }
+            }
+            let value_token = self.expect_token("valid JSON value")?;
+            object.insert(decode_key(key), self.parse_value(value_token)?);
+
+            match self.expect_token(", or closing brace")? {
+                RawToken::Comma => token = self.expect_token("string")?,
+                RawToken::RightBrace => return Ok(JsonValueRef::Object(object)),
+                other => {
+                    return Err(unexpected_token_error(
+                        ", or closing brace",
+                        &format!("{:?}", other),
+                        self.position - 1,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/*
+ * Converts the contents of a string token into a string value. With lazy
+ * strings the token holds the raw contents, since unescaping is deferred.
+ */
+#[cfg(not(feature = "lazy-strings"))]
+fn string_value(contents: Cow<'_, str>) -> JsonStr<'_> {
+    contents
+}
+
+#[cfg(feature = "lazy-strings")]
+fn string_value(contents: Cow<'_, str>) -> JsonStr<'_> {
+    let Cow::Borrowed(raw) = contents else {
+        unreachable!("strings are not unescaped while scanning");
+    };
+    LazyStr::new(raw)
+}
+
+/*
+ * Converts the contents of a string token into an object key, which is always
+ * decoded.
+ */
+#[cfg(not(feature = "lazy-strings"))]
+fn decode_key(contents: Cow<'_, str>) -> Cow<'_, str> {
+    contents
+}
+
+#[cfg(feature = "lazy-strings")]
+fn decode_key(contents: Cow<'_, str>) -> Cow<'_, str> {
+    match contents {
+        Cow::Borrowed(raw) => unescape(raw),
+        decoded => decoded,
+    }
+}
+
+/// Parses a JSON string into a [`JsonValueRef`] that borrows from `input`.
+///
+/// Strings and object keys without escape sequences are returned as slices of
+/// the input; only strings that needed unescaping are allocated.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::parse_json_borrowed;
+///
+/// let input = String::from(r#"{"plain": "text", "escaped": "a\nb"}"#);
+/// let value = parse_json_borrowed(&input)?;
+///
+/// let plain = value.get("plain").and_then(|v| v.as_str()).unwrap();
+/// assert!(input.as_bytes().as_ptr_range().contains(&plain.as_ptr()));
+/// assert_eq!(value.get("escaped").and_then(|v| v.as_str()), Some("a\nb"));
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`parse_json`](crate::parse_json) for invalid input,
+/// including [`JsonError::DepthLimitExceeded`] beyond
+/// [`DEFAULT_MAX_DEPTH`] levels of nesting.
+pub fn parse_json_borrowed(input: &str) -> JsonResult<JsonValueRef<'_>> {
+    let mut tokenizer = Tokenizer::new(input);
+    tokenizer.defer_unescape(cfg!(feature = "lazy-strings"));
+    tokenizer.skip_bom();
+    let mut parser = BorrowedParser {
+        tokenizer,
+        position: 0,
+        depth: 0,
+    };
+    let token = parser.expect_token("valid JSON value")?;
+    let value = parser.parse_value(token)?;
+    // As in `parse_json`, a trailing token that fails to scan is reported the same way
+    match parser.tokenizer.next_raw_token() {
+        Ok(None) => Ok(value),
+        _ => Err(JsonError::TrailingCharacters {
+            position: parser.tokenizer.token_start(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JsonError, parse_json};
+
+    #[test]
+    fn test_primitives() {
+        assert_eq!(
+            parse_json_borrowed("42").unwrap(),
+            JsonValueRef::Number(42.into())
+        );
+        assert_eq!(
+            parse_json_borrowed("true").unwrap(),
+            JsonValueRef::Boolean(true)
+        );
+        assert!(parse_json_borrowed("null").unwrap().is_null());
+        assert_eq!(parse_json_borrowed(r#""hi""#).unwrap().as_str(), Some("hi"));
+    }
+
+    #[cfg(not(feature = "lazy-strings"))]
+    #[test]
+    fn test_strings_borrow_from_input() {
+        let input = r#"{"key": ["value", "esc\"aped"]}"#;
+        let value = parse_json_borrowed(input).unwrap();
+
+        let (key, array) = value.as_object().unwrap().iter().next().unwrap();
+        assert!(matches!(key, Cow::Borrowed("key")));
+        let array = array.as_array().unwrap();
+        assert!(matches!(
+            &array[0],
+            JsonValueRef::String(Cow::Borrowed("value"))
+        ));
+        assert!(matches!(&array[1], JsonValueRef::String(Cow::Owned(s)) if s == "esc\"aped"));
+    }
+
+    #[cfg(feature = "lazy-strings")]
+    #[test]
+    fn test_lazy_strings_decode_on_access() {
+        let value = parse_json_borrowed(r#"{"k\"ey": ["plain", "a\tb\u00e9"]}"#).unwrap();
+        let array = value.get("k\"ey").and_then(|v| v.as_array()).unwrap();
+
+        let JsonValueRef::String(plain) = &array[0] else {
+            panic!("expected a string");
+        };
+        assert!(!plain.is_escaped());
+        let JsonValueRef::String(escaped) = &array[1] else {
+            panic!("expected a string");
+        };
+        assert!(escaped.is_escaped());
+        assert_eq!(escaped.raw(), r"a\tb\u00e9");
+        assert!(escaped.decoded.get().is_none());
+        assert_eq!(&**escaped, "a\tbé");
+        assert!(escaped.decoded.get().is_some());
+        assert_eq!(escaped.clone().into_owned(), "a\tbé");
+    }
+
+    #[cfg(feature = "lazy-strings")]
+    #[test]
+    fn test_lazy_strings_still_reject_invalid_escapes() {
+        assert!(matches!(
+            parse_json_borrowed(r#"["a\qb"]"#),
+            Err(JsonError::InvalidEscape { char: 'q', .. })
+        ));
+    }
+
+    #[test]
+    fn test_escaped_strings_keep_multibyte_characters() {
+        let value = parse_json_borrowed(r#"["\né", "ü\u0041ñ"]"#).unwrap();
+        let array = value.as_array().unwrap();
+        assert_eq!(array[0].as_str(), Some("\né"));
+        assert_eq!(array[1].as_str(), Some("üAñ"));
+    }
+
+    #[test]
+    fn test_matches_owned_parser() {
+        let input = r#"{"users": [{"name": "Ann", "age": 31, "admin": false}], "next": null}"#;
+        let borrowed = parse_json_borrowed(input).unwrap();
+        assert_eq!(borrowed.into_owned(), parse_json(input).unwrap());
+    }
+
+    #[test]
+    fn test_accessors() {
+        let value = parse_json_borrowed(r#"{"list": [1, 2.5], "flag": true}"#).unwrap();
+        assert_eq!(
+            value
+                .get("list")
+                .and_then(|l| l.get_index(1))
+                .and_then(|n| n.as_f64()),
+            Some(2.5)
+        );
+        assert_eq!(value.get("flag").and_then(|f| f.as_bool()), Some(true));
+        assert!(value.get_index(0).is_none());
+    }
+
+    #[test]
+    fn test_byte_order_mark_is_skipped() {
+        let value = parse_json_borrowed("\u{FEFF}[1, \"a\"]").unwrap();
+        assert_eq!(value.get_index(1).and_then(|v| v.as_str()), Some("a"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            parse_json_borrowed("[1, 2,]"),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            parse_json_borrowed(r#"{"a" 1}"#),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            parse_json_borrowed(r#"{1: 2}"#),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            parse_json_borrowed(r#"{"a": 1"#),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+        assert!(matches!(
+            parse_json_borrowed(""),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+        assert!(matches!(
+            parse_json_borrowed(&"[".repeat(100_000)),
+            Err(JsonError::DepthLimitExceeded { .. })
+        ));
+    }
+}
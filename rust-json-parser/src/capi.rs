@@ -0,0 +1,586 @@
+//! C bindings, for embedding the parser in C and C++ applications.
+//!
+//! Documents are parsed into an opaque `RjpValue` handle owned by the caller and
+//! released with `rjp_value_free`. Accessors return borrowed pointers into the
+//! document, which stay valid until the handle is freed. Strings passed in are
+//! UTF-8 buffers with an explicit length; strings returned by accessors are not
+//! NUL-terminated (JSON strings may contain NUL), while serialized documents and
+//! error messages are. `include/rust_json_parser.h` declares everything here.
+
+use crate::encoding::decode_json_text;
+use crate::{JsonError, JsonValue, SerializeOptions, parse_json};
+use std::ffi::{CString, c_char};
+use std::{ptr, slice};
+
+/// An opaque JSON value. Handles returned by `rjp_parse` own their document;
+/// all other pointers borrow from one.
+#[repr(transparent)]
+pub struct RjpValue(JsonValue);
+
+impl RjpValue {
+    fn from_ref(value: &JsonValue) -> *const RjpValue {
+        ptr::from_ref(value).cast()
+    }
+}
+
+/// The type of a JSON value.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RjpType {
+    Null = 0,
+    Boolean = 1,
+    Number = 2,
+    String = 3,
+    Array = 4,
+    Object = 5,
+}
+
+/// Describes why `rjp_parse` failed. `message` is NUL-terminated and owned by
+/// the struct until `rjp_error_free`. `position` (a byte offset), `line` and
+/// `column` (1-based) are only meaningful if `has_position` is set.
+#[repr(C)]
+pub struct RjpError {
+    pub message: *mut c_char,
+    pub position: usize,
+    pub line: usize,
+    pub column: usize,
+    pub has_position: bool,
+}
+
+/// An iterator over the members of an object, created by `rjp_object_iter`.
+pub struct RjpObjectIter {
+    members: Vec<(*const str, *const JsonValue)>,
+    next: usize,
+}
+
+/*
+ * Converts `text` into a C string owned by the caller, escaping NUL bytes,
+ * which C strings cannot hold.
+ */
+fn into_c_string(text: String) -> *mut c_char {
+    let text = if text.contains('\0') {
+        text.replace('\0', "\\u0000")
+    } else {
+        text
+    };
+    CString::new(text)
+        .expect("NUL bytes were escaped")
+        .into_raw()
+}
+
+/*
+ * Borrows the buffer `data` of `len` bytes, or returns `None` if `data` is
+ * null with a nonzero `len`.
+ */
+unsafe fn bytes_from_raw<'a>(data: *const c_char, len: usize) -> Option<&'a [u8]> {
+    if data.is_null() {
+        return (len == 0).then_some(&[]);
+    }
+    // SAFETY: the caller guarantees `data` points to `len` readable bytes
+    Some(unsafe { slice::from_raw_parts(data.cast::<u8>(), len) })
+}
+
+/*
+ * Borrows the UTF-8 buffer `data` of `len` bytes as a `str`, or returns `None`
+ * if it is not valid UTF-8. A null `data` is only accepted with a zero `len`.
+ */
+unsafe fn str_from_raw<'a>(data: *const c_char, len: usize) -> Option<&'a str> {
+    // SAFETY: forwarded from the caller
+    std::str::from_utf8(unsafe { bytes_from_raw(data, len) }?).ok()
+}
+
+/*
+ * Fills `error`, if it is not null, with `message` and the position, line and
+ * column in `location`.
+ */
+unsafe fn fill_error(
+    error: *mut RjpError,
+    message: String,
+    location: Option<(usize, (usize, usize))>,
+) {
+    // SAFETY: the caller guarantees `error` is null or valid for writes
+    let Some(error) = (unsafe { error.as_mut() }) else {
+        return;
+    };
+    let (position, (line, column)) = location.unwrap_or_default();
+    *error = RjpError {
+        message: into_c_string(message),
+        position,
+        line,
+        column,
+        has_position: location.is_some(),
+    };
+}
+
+/*
+ * Fills `error`, if it is not null, with `err` found in `input`.
+ */
+unsafe fn report_error(error: *mut RjpError, err: &JsonError, input: Option<&[u8]>) {
+    let location = input.and_then(|input| Some((err.input_offset()?, err.line_column(input)?)));
+    // SAFETY: forwarded from the caller
+    unsafe { fill_error(error, err.to_string(), location) };
+}
+
+/*
+ * Fills `error`, if it is not null, for an argument that breaks the function's
+ * contract, such as a null pointer where one is required.
+ */
+unsafe fn report_invalid_argument(error: *mut RjpError, message: &str) {
+    // SAFETY: forwarded from the caller
+    unsafe { fill_error(error, format!("Invalid argument: {}", message), None) };
+}
+
+/// Parses the buffer `data` of `len` bytes. The input should be UTF-8, but
+/// UTF-16 is detected and accepted as by `parse_json_bytes`; error positions
+/// for UTF-16 input refer to the input transcoded to UTF-8.
+///
+/// Returns a handle owning the document, to be released with
+/// `rjp_value_free`, or null on failure. On failure, `error` (if not null) is
+/// filled in and must be released with `rjp_error_free`. A null `data` with a
+/// nonzero `len` fails with an invalid argument error.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes (or be null with `len` 0), and
+/// `error` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_parse(
+    data: *const c_char,
+    len: usize,
+    error: *mut RjpError,
+) -> *mut RjpValue {
+    // SAFETY: forwarded from the caller
+    let Some(bytes) = (unsafe { bytes_from_raw(data, len) }) else {
+        // SAFETY: forwarded from the caller
+        unsafe { report_invalid_argument(error, "data is null but len is not 0") };
+        return ptr::null_mut();
+    };
+    // Decoded here rather than by `parse_json_bytes`, to locate errors in the
+    // text they refer to
+    let input = match decode_json_text(bytes) {
+        Ok(input) => input,
+        Err(err) => {
+            // SAFETY: forwarded from the caller
+            unsafe { report_error(error, &err, Some(bytes)) };
+            return ptr::null_mut();
+        }
+    };
+    match parse_json(&input) {
+        Ok(value) => Box::into_raw(Box::new(RjpValue(value))),
+        Err(err) => {
+            // SAFETY: forwarded from the caller
+            unsafe { report_error(error, &err, Some(input.as_bytes())) };
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a document returned by `rjp_parse`. Null is ignored.
+///
+/// # Safety
+///
+/// `value` must be null or a handle returned by `rjp_parse` that has not been
+/// freed; pointers borrowed from it become invalid.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_value_free(value: *mut RjpValue) {
+    if !value.is_null() {
+        // SAFETY: the caller passes a handle created by `Box::into_raw`
+        drop(unsafe { Box::from_raw(value) });
+    }
+}
+
+/// Releases the message of an error filled in by `rjp_parse` and resets it.
+/// Null is ignored.
+///
+/// # Safety
+///
+/// `error` must be null or point to an `RjpError` filled in by `rjp_parse`
+/// (or zeroed).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_error_free(error: *mut RjpError) {
+    // SAFETY: guaranteed by the caller
+    let Some(error) = (unsafe { error.as_mut() }) else {
+        return;
+    };
+    if !error.message.is_null() {
+        // SAFETY: the message was created by `CString::into_raw`
+        drop(unsafe { CString::from_raw(error.message) });
+        error.message = ptr::null_mut();
+    }
+}
+
+/// Returns the type of `value`, or `RJP_NULL` for a null pointer.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_value_type(value: *const RjpValue) -> RjpType {
+    // SAFETY: guaranteed by the caller
+    match unsafe { value.as_ref() }.map(|value| &value.0) {
+        None | Some(JsonValue::Null) => RjpType::Null,
+        Some(JsonValue::Boolean(_)) => RjpType::Boolean,
+        Some(JsonValue::Number(_)) => RjpType::Number,
+        Some(JsonValue::String(_)) => RjpType::String,
+        Some(JsonValue::Array(_)) => RjpType::Array,
+        Some(JsonValue::Object(_)) => RjpType::Object,
+        #[cfg(feature = "custom-values")]
+        Some(JsonValue::Custom(_)) => unreachable!("parsed documents hold no custom values"),
+    }
+}
+
+/// Returns the boolean `value` holds, or `false` if it is not a boolean.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_value_as_bool(value: *const RjpValue) -> bool {
+    // SAFETY: guaranteed by the caller
+    matches!(
+        unsafe { value.as_ref() },
+        Some(RjpValue(JsonValue::Boolean(true)))
+    )
+}
+
+/// Returns the number `value` holds as a `double`, or 0 if it is not a number.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_value_as_double(value: *const RjpValue) -> f64 {
+    // SAFETY: guaranteed by the caller
+    match unsafe { value.as_ref() } {
+        Some(RjpValue(JsonValue::Number(n))) => n.as_f64(),
+        _ => 0.0,
+    }
+}
+
+/// Stores the number `value` holds in `out` and returns `true` if it is an
+/// integer that fits in an `int64_t`; returns `false` otherwise.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API, and `out`
+/// must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_value_as_i64(value: *const RjpValue, out: *mut i64) -> bool {
+    // SAFETY: guaranteed by the caller
+    match unsafe { value.as_ref() } {
+        Some(RjpValue(JsonValue::Number(n))) => match n.as_i64() {
+            Some(i) => {
+                // SAFETY: guaranteed by the caller
+                unsafe { out.write(i) };
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns the UTF-8 bytes of the string `value` holds and stores their count
+/// in `len`, or returns null if it is not a string. The bytes are not
+/// NUL-terminated and borrow from the document.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API, and `len`
+/// must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_value_as_string(
+    value: *const RjpValue,
+    len: *mut usize,
+) -> *const c_char {
+    // SAFETY: guaranteed by the caller
+    match unsafe { value.as_ref() } {
+        Some(RjpValue(JsonValue::String(s))) => {
+            // SAFETY: guaranteed by the caller
+            unsafe { len.write(s.len()) };
+            s.as_ptr().cast()
+        }
+        _ => ptr::null(),
+    }
+}
+
+/// Returns the number of elements of an array or members of an object, or 0
+/// for other values.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_value_len(value: *const RjpValue) -> usize {
+    // SAFETY: guaranteed by the caller
+    match unsafe { value.as_ref() } {
+        Some(RjpValue(JsonValue::Array(items))) => items.len(),
+        Some(RjpValue(JsonValue::Object(members))) => members.len(),
+        _ => 0,
+    }
+}
+
+/// Returns the element at `index` of an array, or null if `value` is not an
+/// array or `index` is out of range.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_value_index(value: *const RjpValue, index: usize) -> *const RjpValue {
+    // SAFETY: guaranteed by the caller
+    match unsafe { value.as_ref() } {
+        Some(RjpValue(JsonValue::Array(items))) => {
+            items.get(index).map_or(ptr::null(), RjpValue::from_ref)
+        }
+        _ => ptr::null(),
+    }
+}
+
+/// Returns the member of an object named by the UTF-8 buffer `key` of
+/// `key_len` bytes, or null if `value` is not an object or has no such member.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API, and `key`
+/// must point to `key_len` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_value_get(
+    value: *const RjpValue,
+    key: *const c_char,
+    key_len: usize,
+) -> *const RjpValue {
+    // SAFETY: guaranteed by the caller
+    let (Some(RjpValue(JsonValue::Object(members))), Some(key)) =
+        (unsafe { value.as_ref() }, unsafe {
+            str_from_raw(key, key_len)
+        })
+    else {
+        return ptr::null();
+    };
+    members.get(key).map_or(ptr::null(), RjpValue::from_ref)
+}
+
+/// Starts iterating over the members of an object. Returns null if `value` is
+/// not an object; otherwise the iterator must be released with
+/// `rjp_object_iter_free`, and is valid as long as the document.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_object_iter(value: *const RjpValue) -> *mut RjpObjectIter {
+    // SAFETY: guaranteed by the caller
+    match unsafe { value.as_ref() } {
+        Some(RjpValue(JsonValue::Object(members))) => {
+            let members = members
+                .iter()
+                .map(|(key, member)| (ptr::from_ref::<str>(key), ptr::from_ref(member)))
+                .collect();
+            Box::into_raw(Box::new(RjpObjectIter { members, next: 0 }))
+        }
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Advances `iter`. Returns `false` when all members have been visited;
+/// otherwise stores the member's key bytes (not NUL-terminated), their count
+/// and the member's value in `key`, `key_len` and `value` and returns `true`.
+///
+/// # Safety
+///
+/// `iter` must be an iterator returned by `rjp_object_iter` whose document is
+/// still alive, and `key`, `key_len` and `value` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_object_iter_next(
+    iter: *mut RjpObjectIter,
+    key: *mut *const c_char,
+    key_len: *mut usize,
+    value: *mut *const RjpValue,
+) -> bool {
+    // SAFETY: guaranteed by the caller
+    let Some(iter) = (unsafe { iter.as_mut() }) else {
+        return false;
+    };
+    let Some(&(name, member)) = iter.members.get(iter.next) else {
+        return false;
+    };
+    iter.next += 1;
+    // SAFETY: the document outlives the iterator, and the outputs are writable
+    unsafe {
+        let name = &*name;
+        key.write(name.as_ptr().cast());
+        key_len.write(name.len());
+        value.write(member.cast());
+    }
+    true
+}
+
+/// Releases an iterator returned by `rjp_object_iter`. Null is ignored.
+///
+/// # Safety
+///
+/// `iter` must be null or an iterator returned by `rjp_object_iter` that has
+/// not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_object_iter_free(iter: *mut RjpObjectIter) {
+    if !iter.is_null() {
+        // SAFETY: the caller passes an iterator created by `Box::into_raw`
+        drop(unsafe { Box::from_raw(iter) });
+    }
+}
+
+/// Serializes `value` to a NUL-terminated JSON string, indented by `indent`
+/// spaces per level or on one line if `indent` is negative, and stores its
+/// length (without the NUL) in `len` unless `len` is null. The string must be
+/// released with `rjp_string_free`. Returns null for a null `value`.
+///
+/// # Safety
+///
+/// `value` must be null or a valid pointer obtained from this API, and `len`
+/// must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_serialize(
+    value: *const RjpValue,
+    indent: i32,
+    len: *mut usize,
+) -> *mut c_char {
+    // SAFETY: guaranteed by the caller
+    let Some(RjpValue(value)) = (unsafe { value.as_ref() }) else {
+        return ptr::null_mut();
+    };
+    let options = SerializeOptions {
+        indent: usize::try_from(indent).ok(),
+        ..SerializeOptions::default()
+    };
+    // Control characters, NUL included, are always escaped in the output
+    let text = value.to_string_with(&options);
+    if !len.is_null() {
+        // SAFETY: guaranteed by the caller
+        unsafe { len.write(text.len()) };
+    }
+    into_c_string(text)
+}
+
+/// Releases a string returned by `rjp_serialize`. Null is ignored.
+///
+/// # Safety
+///
+/// `text` must be null or a string returned by `rjp_serialize` that has not
+/// been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rjp_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        // SAFETY: the string was created by `CString::into_raw`
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn parse(input: &str) -> *mut RjpValue {
+        unsafe { rjp_parse(input.as_ptr().cast(), input.len(), ptr::null_mut()) }
+    }
+
+    unsafe fn bytes<'a>(data: *const c_char, len: usize) -> &'a str {
+        unsafe { std::str::from_utf8(slice::from_raw_parts(data.cast(), len)).unwrap() }
+    }
+
+    #[test]
+    fn test_accessors() {
+        let doc = parse(
+            r#"{"name": "Ann\u0000", "ids": [7, 2.5, true, null], "big": 18446744073709551615}"#,
+        );
+        unsafe {
+            assert_eq!(rjp_value_type(doc), RjpType::Object);
+            assert_eq!(rjp_value_len(doc), 3);
+
+            let mut len = 0;
+            let name = rjp_value_get(doc, "name".as_ptr().cast(), 4);
+            assert_eq!(bytes(rjp_value_as_string(name, &mut len), len), "Ann\0");
+            assert!(rjp_value_get(doc, "nope".as_ptr().cast(), 4).is_null());
+
+            let ids = rjp_value_get(doc, "ids".as_ptr().cast(), 3);
+            let mut i = 0;
+            assert!(rjp_value_as_i64(rjp_value_index(ids, 0), &mut i));
+            assert_eq!(i, 7);
+            assert!(!rjp_value_as_i64(rjp_value_index(ids, 1), &mut i));
+            assert_eq!(rjp_value_as_double(rjp_value_index(ids, 1)), 2.5);
+            assert!(rjp_value_as_bool(rjp_value_index(ids, 2)));
+            assert_eq!(rjp_value_type(rjp_value_index(ids, 3)), RjpType::Null);
+            assert!(rjp_value_index(ids, 4).is_null());
+            let big = rjp_value_get(doc, "big".as_ptr().cast(), 3);
+            assert!(!rjp_value_as_i64(big, &mut i));
+
+            let iter = rjp_object_iter(doc);
+            let (mut key, mut key_len, mut member) = (ptr::null(), 0, ptr::null());
+            let mut keys = Vec::new();
+            while rjp_object_iter_next(iter, &mut key, &mut key_len, &mut member) {
+                keys.push(bytes(key, key_len));
+                assert_eq!(member, rjp_value_get(doc, key, key_len));
+            }
+            rjp_object_iter_free(iter);
+            keys.sort();
+            assert_eq!(keys, ["big", "ids", "name"]);
+            assert!(rjp_object_iter(ids).is_null());
+
+            let text = rjp_serialize(ids, -1, &mut len);
+            assert_eq!(CStr::from_ptr(text).to_str(), Ok("[7,2.5,true,null]"));
+            assert_eq!(len, 17);
+            rjp_string_free(text);
+            rjp_value_free(doc);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = "[1,\n  2,\n  }";
+        let mut error = RjpError {
+            message: ptr::null_mut(),
+            position: 0,
+            line: 0,
+            column: 0,
+            has_position: false,
+        };
+        unsafe {
+            assert!(rjp_parse(input.as_ptr().cast(), input.len(), &mut error).is_null());
+            assert!(error.has_position);
+            assert_eq!((error.position, error.line, error.column), (11, 3, 3));
+            assert!(
+                CStr::from_ptr(error.message)
+                    .to_str()
+                    .unwrap()
+                    .contains("position 11")
+            );
+            rjp_error_free(&mut error);
+            assert!(error.message.is_null());
+
+            let invalid = *b"[1,\n\"\xFF\"]";
+            assert!(rjp_parse(invalid.as_ptr().cast(), invalid.len(), &mut error).is_null());
+            assert!(error.has_position);
+            assert_eq!((error.position, error.line, error.column), (5, 2, 2));
+            rjp_error_free(&mut error);
+
+            assert!(rjp_parse(ptr::null(), 4, &mut error).is_null());
+            assert!(!error.has_position);
+            assert_eq!(
+                CStr::from_ptr(error.message).to_str(),
+                Ok("Invalid argument: data is null but len is not 0")
+            );
+            rjp_error_free(&mut error);
+            // A null `data` of length 0 is empty input, an ordinary parse error
+            assert!(rjp_parse(ptr::null(), 0, &mut error).is_null());
+            assert!(error.has_position);
+            assert_eq!((error.position, error.line, error.column), (0, 1, 1));
+            rjp_error_free(&mut error);
+
+            let utf16: Vec<u8> = "[true]".encode_utf16().flat_map(u16::to_le_bytes).collect();
+            let value = rjp_parse(utf16.as_ptr().cast(), utf16.len(), &mut error);
+            assert!(!value.is_null());
+            rjp_value_free(value);
+        }
+    }
+}
@@ -0,0 +1,637 @@
+//! Conversion of documents to YAML, TOML and CSV, for tools that do not read
+//! JSON.
+//!
+//! [`to_yaml`] writes block-style YAML and accepts any document. [`to_toml`]
+//! needs an object at the root and fails on `null`, which TOML cannot express.
+//! [`to_csv`] writes an array of flat objects as a table with one column per
+//! key. Object members are written in key order in all three formats, so the
+//! output does not depend on the map type behind [`JsonMap`].
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::number::JsonNumber;
+use crate::query::push_segment;
+use crate::strings::escape;
+use crate::value::{JsonKey, JsonMap, JsonValue};
+use crate::{JsonError, JsonResult};
+
+/// A format that [`convert`] can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// YAML 1.2, see [`to_yaml`].
+    Yaml,
+    /// TOML 1.0, see [`to_toml`].
+    Toml,
+    /// Comma-separated values, see [`to_csv`].
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    /// Parses a format name: `yaml` (or `yml`), `toml` or `csv`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!(
+                "unknown format {:?}, expected yaml, toml or csv",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Yaml => "yaml",
+            Format::Toml => "toml",
+            Format::Csv => "csv",
+        })
+    }
+}
+
+/// Converts `value` to `format`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::convert::{Format, convert};
+/// use rust_json_parser::parse_json;
+///
+/// let value = parse_json(r#"[{"id": 1, "name": "a"}, {"id": 2}]"#)?;
+/// assert_eq!(convert(&value, Format::Csv)?, "id,name\n1,a\n2,\n");
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::InvalidConversion`] if `value` cannot be written in
+/// `format`, see [`to_toml`] and [`to_csv`].
+pub fn convert(value: &JsonValue, format: Format) -> JsonResult<String> {
+    match format {
+        Format::Yaml => Ok(to_yaml(value)),
+        Format::Toml => to_toml(value),
+        Format::Csv => to_csv(value),
+    }
+}
+
+/*
+ * Returns the JSON representation of a custom value, and `value` itself otherwise.
+ */
+fn resolved(value: &JsonValue) -> Cow<'_, JsonValue> {
+    match value {
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(custom) => Cow::Owned(custom.to_json()),
+        _ => Cow::Borrowed(value),
+    }
+}
+
+/*
+ * Returns the members of `map` sorted by key.
+ */
+fn sorted_members(map: &JsonMap) -> Vec<(&JsonKey, &JsonValue)> {
+    let mut members: Vec<_> = map.iter().collect();
+    members.sort_by(|a, b| a.0.cmp(b.0));
+    members
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Boolean(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(_) => "custom",
+    }
+}
+
+fn invalid(path: &str, message: impl Into<String>) -> JsonError {
+    JsonError::InvalidConversion {
+        path: path.to_string(),
+        message: message.into(),
+    }
+}
+
+/*
+ * Returns `path` extended by one segment.
+ */
+fn child_path(path: &str, segment: &str) -> String {
+    let mut child = path.to_string();
+    push_segment(&mut child, segment);
+    child
+}
+
+/*
+ * Writes `s` as a double-quoted string. The JSON escapes are valid in YAML and
+ * TOML as well; the remaining control characters (DEL and C1), which neither
+ * allows raw, are written as `\uXXXX`.
+ */
+fn quoted(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in escape(s).chars() {
+        if c.is_control() {
+            result.push_str(&format!("\\u{:04x}", c as u32));
+        } else {
+            result.push(c);
+        }
+    }
+    result.push('"');
+    result
+}
+
+/*
+ * Returns whether `s` can be written as a plain (unquoted) YAML scalar and still
+ * be read back as the same string. This errs on the side of quoting: anything
+ * that YAML 1.1 or 1.2 could read as another type, or that starts with an
+ * indicator character, is quoted.
+ */
+fn is_plain_yaml(s: &str) -> bool {
+    let Some(first) = s.chars().next() else {
+        return false;
+    };
+    if s.trim() != s
+        || "-?:,[]{}#&*!|>'\"%@`".contains(first)
+        || first.is_ascii_digit()
+        || first == '+'
+        || first == '.'
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.ends_with(':')
+        || s.chars().any(char::is_control)
+    {
+        return false;
+    }
+    !matches!(
+        s.to_ascii_lowercase().as_str(),
+        "~" | "null" | "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n"
+    )
+}
+
+fn yaml_string(s: &str) -> String {
+    if is_plain_yaml(s) {
+        s.to_string()
+    } else {
+        quoted(s)
+    }
+}
+
+/*
+ * Returns the YAML for a value written on a single line: a scalar or an empty
+ * array or object. Returns `None` for values that need a block.
+ */
+fn yaml_inline(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::Null => Some("null".to_string()),
+        JsonValue::Boolean(b) => Some(b.to_string()),
+        JsonValue::Number(n) => {
+            let f = n.as_f64();
+            Some(if f.is_nan() {
+                ".nan".to_string()
+            } else if f.is_infinite() {
+                if f > 0.0 { ".inf" } else { "-.inf" }.to_string()
+            } else {
+                n.to_string()
+            })
+        }
+        JsonValue::String(s) => Some(yaml_string(s)),
+        JsonValue::Array(items) if items.is_empty() => Some("[]".to_string()),
+        JsonValue::Object(map) if map.is_empty() => Some("{}".to_string()),
+        JsonValue::Array(_) | JsonValue::Object(_) => None,
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(custom) => yaml_inline(&custom.to_json()),
+    }
+}
+
+/*
+ * Appends the block for `value` to `out`, every line indented by `indent` spaces.
+ */
+fn write_yaml(value: &JsonValue, indent: usize, out: &mut String) {
+    let value = resolved(value);
+    let pad = " ".repeat(indent);
+    if let Some(inline) = yaml_inline(&value) {
+        out.push_str(&pad);
+        out.push_str(&inline);
+        out.push('\n');
+        return;
+    }
+    match value.as_ref() {
+        JsonValue::Array(items) => {
+            for item in items {
+                out.push_str(&pad);
+                out.push('-');
+                match yaml_inline(item) {
+                    Some(inline) => {
+                        out.push(' ');
+                        out.push_str(&inline);
+                        out.push('\n');
+                    }
+                    None => {
+                        // The first line of the nested block goes after the dash
+                        let mut block = String::new();
+                        write_yaml(item, indent + 2, &mut block);
+                        out.push(' ');
+                        out.push_str(&block[indent + 2..]);
+                    }
+                }
+            }
+        }
+        JsonValue::Object(map) => {
+            for (key, member) in sorted_members(map) {
+                out.push_str(&pad);
+                out.push_str(&yaml_string(key));
+                out.push(':');
+                match yaml_inline(member) {
+                    Some(inline) => {
+                        out.push(' ');
+                        out.push_str(&inline);
+                        out.push('\n');
+                    }
+                    None => {
+                        out.push('\n');
+                        write_yaml(member, indent + 2, out);
+                    }
+                }
+            }
+        }
+        _ => unreachable!("scalars are written inline"),
+    }
+}
+
+/// Converts `value` to a YAML document in block style.
+///
+/// Strings are quoted only when YAML would otherwise read them as something
+/// else, such as `"true"`, `"1.5"` or `"- item"`; non-finite numbers are written
+/// as `.nan`, `.inf` and `-.inf`. Every JSON document can be converted.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::convert::to_yaml;
+/// use rust_json_parser::parse_json;
+///
+/// let value = parse_json(r#"{"name": "demo", "tags": ["a", "true"], "owner": {"id": 7}}"#)?;
+/// assert_eq!(
+///     to_yaml(&value),
+///     "name: demo\nowner:\n  id: 7\ntags:\n  - a\n  - \"true\"\n"
+/// );
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+pub fn to_yaml(value: &JsonValue) -> String {
+    let mut out = String::new();
+    write_yaml(value, 0, &mut out);
+    out
+}
+
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+    {
+        key.to_string()
+    } else {
+        quoted(key)
+    }
+}
+
+fn toml_number(n: &JsonNumber, path: &str) -> JsonResult<String> {
+    if let Some(i) = n.as_i64() {
+        return Ok(i.to_string());
+    }
+    if n.is_u64() {
+        return Err(invalid(
+            path,
+            format!("{} is too large for a TOML integer", n),
+        ));
+    }
+    let f = n.as_f64();
+    Ok(if f.is_nan() {
+        "nan".to_string()
+    } else if f.is_infinite() {
+        if f > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        // Unlike `Display`, `Debug` always writes a decimal point or an exponent
+        format!("{:?}", f)
+    })
+}
+
+/*
+ * Returns the TOML for `value` written inline, as the right-hand side of a
+ * key/value pair or inside an inline array or table.
+ */
+fn toml_inline(value: &JsonValue, path: &str) -> JsonResult<String> {
+    match value {
+        JsonValue::Null => Err(invalid(path, "TOML has no null value")),
+        JsonValue::Boolean(b) => Ok(b.to_string()),
+        JsonValue::Number(n) => toml_number(n, path),
+        JsonValue::String(s) => Ok(quoted(s)),
+        JsonValue::Array(items) => {
+            let items = items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| toml_inline(item, &child_path(path, &index.to_string())))
+                .collect::<JsonResult<Vec<_>>>()?;
+            Ok(format!("[{}]", items.join(", ")))
+        }
+        JsonValue::Object(map) if map.is_empty() => Ok("{}".to_string()),
+        JsonValue::Object(map) => {
+            let members = sorted_members(map)
+                .into_iter()
+                .map(|(key, member)| {
+                    let inline = toml_inline(member, &child_path(path, key))?;
+                    Ok(format!("{} = {}", toml_key(key), inline))
+                })
+                .collect::<JsonResult<Vec<_>>>()?;
+            Ok(format!("{{ {} }}", members.join(", ")))
+        }
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(custom) => toml_inline(&custom.to_json(), path),
+    }
+}
+
+/*
+ * Returns whether `value` is written as an array of tables (`[[name]]`): a
+ * non-empty array of objects.
+ */
+fn is_table_array(value: &JsonValue) -> bool {
+    matches!(value, JsonValue::Array(items)
+        if !items.is_empty() && items.iter().all(|item| matches!(*resolved(item), JsonValue::Object(_))))
+}
+
+/*
+ * Appends the members of the table at `keys` to `out`: key/value pairs first,
+ * then nested tables and arrays of tables under their own headers, as TOML
+ * requires.
+ */
+fn write_toml_table(
+    map: &JsonMap,
+    keys: &[String],
+    path: &str,
+    out: &mut String,
+) -> JsonResult<()> {
+    let members = sorted_members(map);
+    for (key, member) in &members {
+        let member = resolved(member);
+        if !matches!(*member, JsonValue::Object(_)) && !is_table_array(&member) {
+            let inline = toml_inline(&member, &child_path(path, key))?;
+            out.push_str(&format!("{} = {}\n", toml_key(key), inline));
+        }
+    }
+    for (key, member) in &members {
+        let member = resolved(member);
+        let mut child_keys = keys.to_vec();
+        child_keys.push(toml_key(key));
+        let header = child_keys.join(".");
+        let member_path = child_path(path, key);
+        match member.as_ref() {
+            JsonValue::Object(child) => {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                out.push_str(&format!("[{}]\n", header));
+                write_toml_table(child, &child_keys, &member_path, out)?;
+            }
+            JsonValue::Array(items) if is_table_array(&member) => {
+                for (index, item) in items.iter().enumerate() {
+                    if !out.is_empty() {
+                        out.push('\n');
+                    }
+                    out.push_str(&format!("[[{}]]\n", header));
+                    let item = resolved(item);
+                    let JsonValue::Object(child) = item.as_ref() else {
+                        unreachable!("arrays of tables hold objects");
+                    };
+                    let item_path = child_path(&member_path, &index.to_string());
+                    write_toml_table(child, &child_keys, &item_path, out)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Converts `value` to a TOML document.
+///
+/// Nested objects become `[tables]` and arrays of objects become
+/// `[[arrays of tables]]`; everything else is written inline.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::convert::to_toml;
+/// use rust_json_parser::parse_json;
+///
+/// let value = parse_json(r#"{"title": "demo", "ratio": 1.0, "server": {"port": 8080},
+///                            "users": [{"name": "a"}, {"name": "b"}]}"#)?;
+/// assert_eq!(
+///     to_toml(&value)?,
+///     "ratio = 1.0\ntitle = \"demo\"\n\n[server]\nport = 8080\n\n\
+///      [[users]]\nname = \"a\"\n\n[[users]]\nname = \"b\"\n"
+/// );
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::InvalidConversion`] if the root is not an object, or
+/// for a `null` or an integer above `i64::MAX` anywhere in the document.
+pub fn to_toml(value: &JsonValue) -> JsonResult<String> {
+    let value = resolved(value);
+    let JsonValue::Object(map) = value.as_ref() else {
+        return Err(invalid(
+            "",
+            format!("a TOML document is a table, found {}", type_name(&value)),
+        ));
+    };
+    let mut out = String::new();
+    write_toml_table(map, &[], "", &mut out)?;
+    Ok(out)
+}
+
+/*
+ * Writes a CSV field, quoting it if it contains a separator, a quote or a line
+ * break, or starts or ends with whitespace.
+ */
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) || text.trim() != text {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn csv_cell(value: &JsonValue, path: &str) -> JsonResult<String> {
+    match value {
+        JsonValue::Null => Ok(String::new()),
+        JsonValue::Boolean(b) => Ok(b.to_string()),
+        JsonValue::Number(n) => Ok(n.to_string()),
+        JsonValue::String(s) => Ok(csv_field(s)),
+        JsonValue::Array(_) | JsonValue::Object(_) => Err(invalid(
+            path,
+            format!("CSV cells cannot hold an {}", type_name(value)),
+        )),
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(custom) => csv_cell(&custom.to_json(), path),
+    }
+}
+
+/// Converts an array of flat objects to CSV, with one row per object.
+///
+/// The header row lists every key found in any object, in key order. Missing
+/// members and `null` are written as empty fields. Lines end with `\n`, and
+/// fields are quoted as RFC 4180 describes. An empty array gives an empty
+/// string.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::convert::to_csv;
+/// use rust_json_parser::parse_json;
+///
+/// let value = parse_json(r#"[{"name": "a, b", "n": 1}, {"name": "say \"hi\"", "ok": true}]"#)?;
+/// assert_eq!(
+///     to_csv(&value)?,
+///     "n,name,ok\n1,\"a, b\",\n,\"say \"\"hi\"\"\",true\n"
+/// );
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::InvalidConversion`] if `value` is not an array, if an
+/// element is not an object, or if a member is an array or an object.
+pub fn to_csv(value: &JsonValue) -> JsonResult<String> {
+    let value = resolved(value);
+    let JsonValue::Array(items) = value.as_ref() else {
+        return Err(invalid(
+            "",
+            format!("CSV needs an array of objects, found {}", type_name(&value)),
+        ));
+    };
+    let rows = items.iter().map(resolved).collect::<Vec<_>>();
+    let mut columns = BTreeSet::new();
+    for (index, row) in rows.iter().enumerate() {
+        match row.as_ref() {
+            JsonValue::Object(map) => columns.extend(map.keys().map(|key| &**key)),
+            other => {
+                return Err(invalid(
+                    &child_path("", &index.to_string()),
+                    format!("CSV rows must be objects, found {}", type_name(other)),
+                ));
+            }
+        }
+    }
+    if rows.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut out = columns
+        .iter()
+        .map(|column| csv_field(column))
+        .collect::<Vec<_>>()
+        .join(",");
+    out.push('\n');
+    for (index, row) in rows.iter().enumerate() {
+        let JsonValue::Object(map) = row.as_ref() else {
+            unreachable!("rows were checked above");
+        };
+        let row_path = child_path("", &index.to_string());
+        let cells = columns
+            .iter()
+            .map(|&column| match map.get(column) {
+                Some(cell) => csv_cell(cell, &child_path(&row_path, column)),
+                None => Ok(String::new()),
+            })
+            .collect::<JsonResult<Vec<_>>>()?;
+        out.push_str(&cells.join(","));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn test_yaml_nesting_and_quoting() {
+        let value = parse_json(
+            r#"{"list": [[1, 2], {"a": null, "b": []}, "", "x: y", "007", " pad"],
+                "empty": {}, "f": 1.5, "plain": "hello world", "weird key": "no"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_yaml(&value),
+            "empty: {}\nf: 1.5\nlist:\n  - - 1\n    - 2\n  - a: null\n    b: []\n  - \"\"\n  \
+             - \"x: y\"\n  - \"007\"\n  - \" pad\"\nplain: hello world\nweird key: \"no\"\n"
+        );
+        assert_eq!(to_yaml(&JsonValue::Number(3.into())), "3\n");
+        assert_eq!(
+            to_yaml(&JsonValue::String("a\u{7f}\n".into())),
+            "\"a\\u007f\\n\"\n"
+        );
+    }
+
+    #[test]
+    fn test_toml_nested_tables_and_inline_values() {
+        let value = parse_json(
+            r#"{"a": {"b": {"c": 1}, "list": [1, "two", {"x": false}]}, "key with space": 2e300}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            to_toml(&value).unwrap(),
+            "\"key with space\" = 2e300\n\n[a]\nlist = [1, \"two\", { x = false }]\n\n[a.b]\nc = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_toml_errors() {
+        for (input, path) in [
+            ("[1]", ""),
+            (r#"{"a": [{"b": null}]}"#, "/a/0/b"),
+            (r#"{"big": 18446744073709551615}"#, "/big"),
+        ] {
+            let err = to_toml(&parse_json(input).unwrap()).unwrap_err();
+            assert!(
+                matches!(&err, JsonError::InvalidConversion { path: p, .. } if p == path),
+                "{}: {:?}",
+                input,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_csv_errors() {
+        let err = to_csv(&parse_json(r#"[{"a": 1}, 2]"#).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot convert /1: CSV rows must be objects, found number"
+        );
+        let err = to_csv(&parse_json(r#"[{"a": {"b": 1}}]"#).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot convert /0/a: CSV cells cannot hold an object"
+        );
+        assert_eq!(to_csv(&parse_json("[]").unwrap()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("YML".parse(), Ok(Format::Yaml));
+        assert_eq!("toml".parse(), Ok(Format::Toml));
+        assert!("xml".parse::<Format>().is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Domain values carried through a [`JsonValue`] tree, enabled by the
+//! `custom-values` feature.
+//!
+//! Parsing never produces [`JsonValue::Custom`]: embedders insert it themselves
+//! to keep a date, decimal or byte buffer in its native form while the tree is
+//! built and transformed, instead of converting it to a string and back at every
+//! step. Whenever the tree is serialized, the value's
+//! [`to_json`](CustomValue::to_json) hook supplies its JSON representation.
+//!
+//! The trait relies only on trait objects and [`Any`], without trait upcasting
+//! or specialization, so it builds with the same compilers as the rest of the
+//! crate.
+
+use std::any::Any;
+use std::fmt;
+
+use crate::value::JsonValue;
+
+/// A domain type that can be stored in a [`JsonValue`] as
+/// [`JsonValue::Custom`].
+///
+/// Only [`type_name`](Self::type_name), [`to_json`](Self::to_json) and
+/// [`clone_box`](Self::clone_box) must be implemented. Everything that does not
+/// know about the concrete type — serialization, equality, hashing, queries,
+/// patches and schema validation — sees the value as its JSON representation.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{CustomValue, JsonValue};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct Bytes(Vec<u8>);
+///
+/// impl CustomValue for Bytes {
+///     fn type_name(&self) -> &str {
+///         "bytes"
+///     }
+///
+///     fn to_json(&self) -> JsonValue {
+///         let hex: String = self.0.iter().map(|b| format!("{:02x}", b)).collect();
+///         JsonValue::String(hex)
+///     }
+///
+///     fn clone_box(&self) -> Box<dyn CustomValue> {
+///         Box::new(self.clone())
+///     }
+/// }
+///
+/// let value = JsonValue::Custom(Box::new(Bytes(vec![0xca, 0xfe])));
+/// assert_eq!(value.to_string(), r#""cafe""#);
+/// assert_eq!(value.as_custom::<Bytes>(), Some(&Bytes(vec![0xca, 0xfe])));
+/// ```
+pub trait CustomValue: AsAny + fmt::Debug + Send + Sync + 'static {
+    /// A name identifying the domain type, such as `"date"` or `"decimal"`.
+    fn type_name(&self) -> &str;
+
+    /// Returns the JSON representation written when the value is serialized.
+    ///
+    /// The representation should not itself contain custom values.
+    fn to_json(&self) -> JsonValue;
+
+    /// Returns a boxed copy of the value, so [`JsonValue`] can be cloned.
+    fn clone_box(&self) -> Box<dyn CustomValue>;
+
+    /// Returns whether the value equals `other`.
+    ///
+    /// By default, two custom values are equal when they have the same
+    /// [`type_name`](Self::type_name) and JSON representation.
+    fn eq_custom(&self, other: &dyn CustomValue) -> bool {
+        self.type_name() == other.type_name() && self.to_json() == other.to_json()
+    }
+}
+
+/// Access to a value as [`Any`], for downcasting a [`CustomValue`] to its
+/// concrete type.
+///
+/// This is implemented for every `'static` type and never needs to be
+/// implemented by hand.
+pub trait AsAny {
+    /// Returns the value as `&dyn Any`.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the value as `&mut dyn Any`.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl dyn CustomValue {
+    /// Returns the value as a `T`, or `None` if it is a different type.
+    pub fn downcast_ref<T: CustomValue>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Returns the value as a mutable `T`, or `None` if it is a different type.
+    pub fn downcast_mut<T: CustomValue>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut()
+    }
+}
+
+impl Clone for Box<dyn CustomValue> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl PartialEq for Box<dyn CustomValue> {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_custom(other.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Decimal {
+        units: i64,
+        scale: u32,
+    }
+
+    impl CustomValue for Decimal {
+        fn type_name(&self) -> &str {
+            "decimal"
+        }
+
+        fn to_json(&self) -> JsonValue {
+            let divisor = 10i64.pow(self.scale);
+            JsonValue::String(format!(
+                "{}.{:0width$}",
+                self.units / divisor,
+                self.units % divisor,
+                width = self.scale as usize
+            ))
+        }
+
+        fn clone_box(&self) -> Box<dyn CustomValue> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Bytes;
+
+    impl CustomValue for Bytes {
+        fn type_name(&self) -> &str {
+            "bytes"
+        }
+
+        fn to_json(&self) -> JsonValue {
+            JsonValue::String(String::new())
+        }
+
+        fn clone_box(&self) -> Box<dyn CustomValue> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn price() -> JsonValue {
+        let mut members = crate::JsonMap::default();
+        members.insert(
+            "price".into(),
+            JsonValue::Custom(Box::new(Decimal {
+                units: 1999,
+                scale: 2,
+            })),
+        );
+        JsonValue::Object(members)
+    }
+
+    #[test]
+    fn test_custom_value_serializes_through_hook() {
+        let value = price();
+        assert_eq!(value.to_string(), r#"{"price": "19.99"}"#);
+        assert_eq!(value.pretty_print(2), "{\n  \"price\": \"19.99\"\n}");
+    }
+
+    #[test]
+    fn test_custom_value_clone_eq_and_downcast() {
+        let mut value = price();
+        assert_eq!(value.clone(), value);
+        assert_ne!(
+            value.get("price"),
+            Some(&JsonValue::String("19.99".to_string()))
+        );
+
+        let decimal = value
+            .pointer_mut("/price")
+            .and_then(|price| price.as_custom_mut::<Decimal>())
+            .unwrap();
+        decimal.units = 2500;
+        assert_eq!(value.to_string(), r#"{"price": "25.00"}"#);
+        assert!(value.get("price").unwrap().as_custom::<Bytes>().is_none());
+    }
+}
@@ -0,0 +1,573 @@
+//! Deserializing JSON directly into Rust types with serde.
+//!
+//! [`Deserializer`] implements `serde::Deserializer` on top of the token
+//! stream, so types deriving `Deserialize` are filled in without building an
+//! intermediate [`JsonValue`](crate::JsonValue) tree. Available with the `serde`
+//! feature.
+
+use std::borrow::Cow;
+
+use serde::de::value::{BorrowedStrDeserializer, StringDeserializer};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+
+use crate::error::{unexpected_end_of_input, unexpected_token_error};
+use crate::options::ParserOptions;
+use crate::tokenizer::{RawToken, Token, Tokenizer};
+use crate::{JsonError, JsonResult};
+
+impl de::Error for JsonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        // The position is filled in by `Deserializer::locate` once the error
+        // reaches the entry point
+        JsonError::Deserialize {
+            message: msg.to_string(),
+            position: 0,
+        }
+    }
+}
+
+/// Deserializes a value of type `T` from a JSON string.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct Config {
+///     name: String,
+///     ports: Vec<u16>,
+///     debug: Option<bool>,
+/// }
+///
+/// let config: Config = rust_json_parser::from_str(r#"{"name": "api", "ports": [80, 443]}"#)?;
+/// assert_eq!(
+///     config,
+///     Config { name: "api".to_string(), ports: vec![80, 443], debug: None }
+/// );
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`parse_json`](crate::parse_json) for malformed
+/// input, and [`JsonError::Deserialize`] when the JSON does not match `T`.
+pub fn from_str<T: DeserializeOwned>(input: &str) -> JsonResult<T> {
+    from_str_with_options(input, &ParserOptions::default())
+}
+
+/// Deserializes a value of type `T` from a JSON string like [`from_str`], using
+/// the given [`ParserOptions`].
+///
+/// The scanning options, the resource limits (`max_depth`, `max_string_length`,
+/// `max_values` and `max_input_size`) and `allow_trailing_commas` are honored.
+/// Repeated keys are passed on to `T`, so `duplicate_keys` has no effect: maps
+/// keep the last occurrence and derived structs reject duplicate fields.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{de::from_str_with_options, ParserOptions};
+///
+/// let ports: Vec<u16> = from_str_with_options("[80, 0x1BB,] // https", &ParserOptions::json5())?;
+/// assert_eq!(ports, [80, 443]);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// See [`from_str`].
+pub fn from_str_with_options<T: DeserializeOwned>(
+    input: &str,
+    options: &ParserOptions,
+) -> JsonResult<T> {
+    let mut deserializer = Deserializer::with_options(input, options);
+    let value = T::deserialize(&mut deserializer).map_err(|err| deserializer.locate(err))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// A `serde::Deserializer` reading JSON from a string.
+///
+/// Most callers want [`from_str`]; use the deserializer directly to drive
+/// `Deserialize` implementations that borrow from the input.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::de::Deserializer;
+/// use serde::Deserialize;
+///
+/// let input = r#"["borrowed", "strings"]"#;
+/// let mut deserializer = Deserializer::new(input);
+/// let words = Vec::<&str>::deserialize(&mut deserializer)?;
+/// deserializer.end()?;
+/// assert_eq!(words, ["borrowed", "strings"]);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+pub struct Deserializer<'de> {
+    tokenizer: Tokenizer<'de>,
+    peeked: Option<RawToken<'de>>,
+    depth: usize,
+    max_depth: usize,
+    allow_trailing_commas: bool,
+}
+
+impl<'de> Deserializer<'de> {
+    /// Creates a deserializer for `input` with the default [`ParserOptions`].
+    pub fn new(input: &'de str) -> Self {
+        Self::with_options(input, &ParserOptions::default())
+    }
+
+    /// Creates a deserializer for `input` that applies the given [`ParserOptions`]
+    /// (see [`from_str_with_options`] for which of them take effect).
+    pub fn with_options(input: &'de str, options: &ParserOptions) -> Self {
+        let mut tokenizer = Tokenizer::new(input);
+        tokenizer.configure(options);
+        tokenizer.skip_bom();
+        Self {
+            tokenizer,
+            peeked: None,
+            depth: 0,
+            max_depth: options.max_depth,
+            allow_trailing_commas: options.allow_trailing_commas,
+        }
+    }
+
+    /// Checks that only whitespace (and comments, if allowed) follows the
+    /// deserialized value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::TrailingCharacters`] if anything else follows.
+    pub fn end(&mut self) -> JsonResult<()> {
+        match self.peek_token() {
+            Ok(None) => Ok(()),
+            _ => Err(JsonError::TrailingCharacters {
+                position: self.tokenizer.token_start(),
+            }),
+        }
+    }
+
+    /*
+     * Attaches the position of the most recent token to errors raised by
+     * `Deserialize` implementations, which do not know where they are.
+     */
+    fn locate(&self, err: JsonError) -> JsonError {
+        match err {
+            JsonError::Deserialize { message, .. } => JsonError::Deserialize {
+                message,
+                position: self.tokenizer.token_start(),
+            },
+            err => err,
+        }
+    }
+
+    fn peek_token(&mut self) -> JsonResult<Option<&RawToken<'de>>> {
+        if self.peeked.is_none() {
+            self.peeked = self.tokenizer.next_raw_token()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next_token(&mut self, expected: &str) -> JsonResult<RawToken<'de>> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self
+                .tokenizer
+                .next_raw_token()?
+                .ok_or_else(|| unexpected_end_of_input(expected, self.tokenizer.offset())),
+        }
+    }
+
+    /*
+     * Consumes the next token, which must be `expected`.
+     */
+    fn expect(&mut self, expected: RawToken<'static>, name: &str) -> JsonResult<()> {
+        let token = self.next_token(name)?;
+        if token != expected {
+            return Err(self.unexpected(token, name));
+        }
+        Ok(())
+    }
+
+    fn unexpected(&self, found: RawToken<'_>, expected: &str) -> JsonError {
+        unexpected_token_error(
+            expected,
+            &format!("{:?}", Token::from(found)),
+            self.tokenizer.token_start(),
+        )
+    }
+
+    /*
+     * Accounts for the value whose first token was just consumed, enforcing
+     * the value count limit.
+     */
+    fn count_value(&mut self) -> JsonResult<()> {
+        let position = self.tokenizer.token_start();
+        self.tokenizer.count_value(position)
+    }
+
+    /*
+     * Accounts for one more level of nesting, enforcing the depth limit.
+     */
+    fn enter_container(&mut self) -> JsonResult<()> {
+        if self.depth >= self.max_depth {
+            return Err(JsonError::DepthLimitExceeded {
+                max_depth: self.max_depth,
+                position: self.tokenizer.token_start(),
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /*
+     * Consumes the comma before the next member of a container, unless the
+     * container ends here. Returns whether another member follows.
+     */
+    fn has_next_member(&mut self, first: bool, closing: &RawToken<'static>) -> JsonResult<bool> {
+        if self.peek_token()? == Some(closing) {
+            return Ok(false);
+        }
+        if !first {
+            self.expect(RawToken::Comma, ",")?;
+            if self.allow_trailing_commas && self.peek_token()? == Some(closing) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = JsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> JsonResult<V::Value> {
+        let token = self.next_token("JSON value")?;
+        self.count_value()?;
+        match token {
+            RawToken::Null => visitor.visit_unit(),
+            RawToken::Boolean(b) => visitor.visit_bool(b),
+            RawToken::Number(n) => {
+                if let Some(u) = n.as_u64() {
+                    visitor.visit_u64(u)
+                } else if let Some(i) = n.as_i64() {
+                    visitor.visit_i64(i)
+                } else {
+                    visitor.visit_f64(n.as_f64())
+                }
+            }
+            RawToken::String(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+            RawToken::String(Cow::Owned(s)) => visitor.visit_string(s),
+            RawToken::LeftBracket => {
+                self.enter_container()?;
+                let value = visitor.visit_seq(Members {
+                    de: &mut *self,
+                    first: true,
+                })?;
+                self.expect(RawToken::RightBracket, "]")?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            RawToken::LeftBrace => {
+                self.enter_container()?;
+                let value = visitor.visit_map(Members {
+                    de: &mut *self,
+                    first: true,
+                })?;
+                self.expect(RawToken::RightBrace, "}")?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            token => Err(self.unexpected(token, "JSON value")),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> JsonResult<V::Value> {
+        if self.peek_token()? == Some(&RawToken::Null) {
+            self.peeked = None;
+            self.count_value()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> JsonResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> JsonResult<V::Value> {
+        let token = self.next_token("enum variant")?;
+        self.count_value()?;
+        match token {
+            // Unit variants are written as a plain string
+            RawToken::String(variant) => {
+                visitor.visit_enum(variant.into_owned().into_deserializer())
+            }
+            // Other variants as an object with a single member
+            RawToken::LeftBrace => {
+                self.enter_container()?;
+                let value = visitor.visit_enum(Variant { de: &mut *self })?;
+                self.expect(RawToken::RightBrace, "}")?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            token => Err(self.unexpected(token, "enum variant")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/*
+ * Gives serde access to the members of an array or object, after its opening
+ * bracket has been consumed.
+ */
+struct Members<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    first: bool,
+}
+
+impl<'de> SeqAccess<'de> for Members<'_, 'de> {
+    type Error = JsonError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> JsonResult<Option<T::Value>> {
+        if !self
+            .de
+            .has_next_member(self.first, &RawToken::RightBracket)?
+        {
+            return Ok(None);
+        }
+        self.first = false;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+impl<'de> MapAccess<'de> for Members<'_, 'de> {
+    type Error = JsonError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> JsonResult<Option<K::Value>> {
+        if !self.de.has_next_member(self.first, &RawToken::RightBrace)? {
+            return Ok(None);
+        }
+        self.first = false;
+        deserialize_key(self.de, seed).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> JsonResult<V::Value> {
+        self.de.expect(RawToken::Colon, ":")?;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/*
+ * Reads an object key, which is always a string (or an identifier, when
+ * unquoted keys are allowed).
+ */
+fn deserialize_key<'de, K: DeserializeSeed<'de>>(
+    de: &mut Deserializer<'de>,
+    seed: K,
+) -> JsonResult<K::Value> {
+    match de.next_token("string")? {
+        RawToken::String(Cow::Borrowed(key)) | RawToken::Identifier(key) => {
+            seed.deserialize(BorrowedStrDeserializer::new(key))
+        }
+        RawToken::String(Cow::Owned(key)) => seed.deserialize(StringDeserializer::new(key)),
+        token => Err(de.unexpected(token, "string")),
+    }
+}
+
+/*
+ * Gives serde access to an enum written as `{"Variant": contents}`.
+ */
+struct Variant<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'a, 'de> EnumAccess<'de> for Variant<'a, 'de> {
+    type Error = JsonError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> JsonResult<(V::Value, Self)> {
+        let variant = deserialize_key(self.de, seed)?;
+        self.de.expect(RawToken::Colon, ":")?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant<'_, 'de> {
+    type Error = JsonError;
+
+    fn unit_variant(self) -> JsonResult<()> {
+        de::Deserialize::deserialize(&mut *self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> JsonResult<T::Value> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> JsonResult<V::Value> {
+        de::Deserializer::deserialize_seq(&mut *self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> JsonResult<V::Value> {
+        de::Deserializer::deserialize_map(&mut *self.de, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        tags: Vec<String>,
+        limits: Option<HashMap<String, f64>>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Point(i32, i32),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[test]
+    fn test_struct_from_str() {
+        let server: Server = from_str(
+            r#"{"host": "localhost", "port": 8080, "limits": {"cpu": 0.5}, "extra": [1]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            server,
+            Server {
+                host: "localhost".to_string(),
+                port: 8080,
+                tags: Vec::new(),
+                limits: Some(HashMap::from([("cpu".to_string(), 0.5)])),
+            }
+        );
+    }
+
+    #[test]
+    fn test_enums() {
+        let shapes: Vec<Shape> = from_str(
+            r#"["Empty", {"Circle": 1.5}, {"Point": [1, -2]}, {"Rect": {"w": 3, "h": 4}}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            shapes,
+            [
+                Shape::Empty,
+                Shape::Circle(1.5),
+                Shape::Point(1, -2),
+                Shape::Rect { w: 3, h: 4 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_mismatches_report_position() {
+        let err = from_str::<Server>(r#"{"host": "a", "port": "80"}"#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::Deserialize { position: 22, ref message } if message.contains("u16")
+        ));
+        let err = from_str::<Server>(r#"{"port": 80}"#).unwrap_err();
+        assert!(matches!(
+            err,
+            JsonError::Deserialize { ref message, .. } if message.contains("missing field `host`")
+        ));
+        assert!(matches!(
+            from_str::<u8>("256"),
+            Err(JsonError::Deserialize { .. })
+        ));
+    }
+
+    #[test]
+    fn test_syntax_errors_and_limits() {
+        assert!(matches!(
+            from_str::<Vec<u8>>("[1, 2,]"),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            from_str::<Vec<u8>>("[1 2]"),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            from_str::<Vec<u8>>("[1] x"),
+            Err(JsonError::TrailingCharacters { position: 4 })
+        ));
+        assert!(matches!(
+            from_str::<Vec<u8>>("[1"),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+        let options = ParserOptions {
+            max_depth: 2,
+            allow_trailing_commas: true,
+            ..ParserOptions::default()
+        };
+        assert_eq!(
+            from_str_with_options::<Vec<Vec<u8>>>("[[1,],]", &options).unwrap(),
+            [[1]]
+        );
+        assert!(matches!(
+            from_str_with_options::<Vec<Vec<Vec<u8>>>>("[[[1]]]", &options),
+            Err(JsonError::DepthLimitExceeded { .. })
+        ));
+        let options = ParserOptions {
+            max_values: 3,
+            ..ParserOptions::default()
+        };
+        assert!(from_str_with_options::<Vec<Option<u8>>>("[1, null]", &options).is_ok());
+        assert!(matches!(
+            from_str_with_options::<Vec<Option<u8>>>("[1, null, 2]", &options),
+            Err(JsonError::ValueCountExceeded { position: 10, .. })
+        ));
+    }
+
+    #[test]
+    fn test_byte_order_mark_is_skipped() {
+        assert_eq!(from_str::<Vec<u8>>("\u{FEFF}[1, 2]").unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn test_json_value_from_str() {
+        let value: crate::JsonValue = from_str(r#"{"a": [1, "b", null]}"#).unwrap();
+        assert_eq!(
+            value,
+            crate::parse_json(r#"{"a": [1, "b", null]}"#).unwrap()
+        );
+    }
+}
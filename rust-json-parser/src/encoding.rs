@@ -0,0 +1,281 @@
+//! Detection of the character encoding of raw JSON input.
+//!
+//! RFC 8259 requires JSON exchanged between systems to be UTF-8, but its
+//! predecessors also allowed UTF-16 and UTF-32, and some tools still produce
+//! them. Because a JSON text starts with an ASCII character, the zero bytes at
+//! its start tell the encodings apart even without a byte order mark.
+
+use std::borrow::Cow;
+
+use crate::parser::decode_utf8;
+use crate::{JsonError, JsonResult};
+
+/*
+ * Encodings recognized from the first bytes of the input.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+/*
+ * Returns the encoding of `bytes` and the length of its byte order mark, if
+ * any. UTF-32 is checked first, as its little-endian mark starts with the
+ * UTF-16 one.
+ */
+pub(crate) fn detect(bytes: &[u8]) -> (Encoding, usize) {
+    match bytes {
+        [0, 0, 0xFE, 0xFF, ..] => (Encoding::Utf32Be, 4),
+        [0xFF, 0xFE, 0, 0, ..] => (Encoding::Utf32Le, 4),
+        [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+        [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+        // Without a byte order mark, the first character is ASCII
+        [0, 0, 0, a, ..] if *a != 0 => (Encoding::Utf32Be, 0),
+        [a, 0, 0, 0, ..] if *a != 0 => (Encoding::Utf32Le, 0),
+        [0, a, ..] if *a != 0 => (Encoding::Utf16Be, 0),
+        [a, 0, ..] if *a != 0 => (Encoding::Utf16Le, 0),
+        _ => (Encoding::Utf8, 0),
+    }
+}
+
+/*
+ * Like `detect`, but for input that is still arriving: returns `None` while
+ * the bytes received so far do not settle the encoding, including the start of
+ * a UTF-8 byte order mark, which must arrive whole to be skipped.
+ */
+pub(crate) fn detect_prefix(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    match bytes {
+        [_, _, _, _, ..] => Some(detect(bytes)),
+        [0xFE, 0xFF, ..] | [0xFF, 0xFE, ..] | [0xEF, 0xBB] | [0, ..] | [_, 0, ..] | [_] | [] => {
+            None
+        }
+        _ => Some((Encoding::Utf8, 0)),
+    }
+}
+
+/*
+ * The error for input detected as UTF-32.
+ */
+pub(crate) fn unsupported(encoding: Encoding) -> JsonError {
+    JsonError::UnsupportedEncoding {
+        encoding: if encoding == Encoding::Utf32Le {
+            "UTF-32LE"
+        } else {
+            "UTF-32BE"
+        }
+        .to_string(),
+    }
+}
+
+/*
+ * Transcodes UTF-16 `bytes` to UTF-8. `offset` is where `bytes` start in the
+ * input, so errors point into the original bytes.
+ */
+fn decode_utf16(bytes: &[u8], offset: usize, big_endian: bool) -> JsonResult<String> {
+    let units = bytes.chunks(2).map(|pair| match *pair {
+        [a, b] if big_endian => u16::from_be_bytes([a, b]),
+        [a, b] => u16::from_le_bytes([a, b]),
+        // An odd trailing byte; stands in for an invalid unit below
+        _ => 0xDC00,
+    });
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut position = offset;
+    for decoded in char::decode_utf16(units) {
+        match decoded {
+            Ok(c) if bytes.len() - (position - offset) >= c.len_utf16() * 2 => {
+                text.push(c);
+                position += c.len_utf16() * 2;
+            }
+            _ => return Err(JsonError::InvalidUtf16 { offset: position }),
+        }
+    }
+    Ok(text)
+}
+
+/*
+ * Decodes raw JSON input to text. UTF-8 input is borrowed as is, with a byte
+ * order mark left for the tokenizer to skip so that error positions stay byte
+ * offsets into `bytes`. UTF-16 input is transcoded, so error positions from
+ * parsing it refer to the transcoded text.
+ */
+pub(crate) fn decode_json_text(bytes: &[u8]) -> JsonResult<Cow<'_, str>> {
+    let (encoding, bom) = detect(bytes);
+    match encoding {
+        Encoding::Utf8 => decode_utf8(bytes).map(Cow::Borrowed),
+        Encoding::Utf16Le => decode_utf16(&bytes[bom..], bom, false).map(Cow::Owned),
+        Encoding::Utf16Be => decode_utf16(&bytes[bom..], bom, true).map(Cow::Owned),
+        Encoding::Utf32Le | Encoding::Utf32Be => Err(unsupported(encoding)),
+    }
+}
+
+/*
+ * Transcodes UTF-16 input that arrives in chunks, which may split code units
+ * and surrogate pairs anywhere.
+ */
+#[derive(Debug)]
+pub(crate) struct Utf16Decoder {
+    big_endian: bool,
+    // Offset of `pending` in the input
+    offset: usize,
+    pending: Vec<u8>,
+}
+
+impl Utf16Decoder {
+    /*
+     * Creates a decoder for input whose first `offset` bytes (the byte order
+     * mark) have been skipped.
+     */
+    pub(crate) fn new(big_endian: bool, offset: usize) -> Self {
+        Self {
+            big_endian,
+            offset,
+            pending: Vec::new(),
+        }
+    }
+
+    /*
+     * Transcodes `chunk`, keeping back an incomplete code unit or a high
+     * surrogate whose pair has not arrived yet.
+     */
+    pub(crate) fn decode(&mut self, chunk: &[u8]) -> JsonResult<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut end = self.pending.len() & !1;
+        if end >= 2 {
+            let last = [self.pending[end - 2], self.pending[end - 1]];
+            let unit = if self.big_endian {
+                u16::from_be_bytes(last)
+            } else {
+                u16::from_le_bytes(last)
+            };
+            if (0xD800..0xDC00).contains(&unit) {
+                end -= 2;
+            }
+        }
+        let text = decode_utf16(&self.pending[..end], self.offset, self.big_endian)?;
+        self.offset += end;
+        self.pending.drain(..end);
+        Ok(text)
+    }
+
+    /*
+     * Checks that the input did not end in the middle of a character.
+     */
+    pub(crate) fn finish(&self) -> JsonResult<()> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(JsonError::InvalidUtf16 {
+                offset: self.offset,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for unit in bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16()) {
+            if big_endian {
+                bytes.extend(unit.to_be_bytes());
+            } else {
+                bytes.extend(unit.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_utf8_is_borrowed() {
+        assert!(matches!(
+            decode_json_text(b"\xEF\xBB\xBF[1]"),
+            Ok(Cow::Borrowed("\u{FEFF}[1]"))
+        ));
+        assert!(matches!(decode_json_text(b"1"), Ok(Cow::Borrowed("1"))));
+        assert!(matches!(decode_json_text(b""), Ok(Cow::Borrowed(""))));
+    }
+
+    #[test]
+    fn test_utf16_is_transcoded() {
+        let text = r#"{"café": "😀"}"#;
+        for big_endian in [false, true] {
+            for bom in [false, true] {
+                assert_eq!(
+                    decode_json_text(&utf16(text, big_endian, bom)).unwrap(),
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_utf16() {
+        // Unpaired high surrogate after `["`
+        let mut bytes = utf16(r#"[""#, false, false);
+        bytes.extend([0x00, 0xD8, b'"', 0, b']', 0]);
+        assert_eq!(
+            decode_json_text(&bytes),
+            Err(JsonError::InvalidUtf16 { offset: 4 })
+        );
+        // Odd number of bytes
+        let mut bytes = utf16("[1]", true, true);
+        bytes.push(0);
+        assert_eq!(
+            decode_json_text(&bytes),
+            Err(JsonError::InvalidUtf16 { offset: 8 })
+        );
+    }
+
+    #[test]
+    fn test_utf16_decoder_joins_chunks() {
+        let text = "[\"😀é\"]";
+        for big_endian in [false, true] {
+            let bytes = utf16(text, big_endian, true);
+            for split in 2..bytes.len() {
+                let mut decoder = Utf16Decoder::new(big_endian, 2);
+                let mut decoded = decoder.decode(&bytes[2..split]).unwrap();
+                decoded.push_str(&decoder.decode(&bytes[split..]).unwrap());
+                decoder.finish().unwrap();
+                assert_eq!(decoded, text);
+            }
+        }
+
+        let mut decoder = Utf16Decoder::new(false, 0);
+        assert_eq!(decoder.decode(b"[\0\x3d").unwrap(), "[");
+        assert_eq!(decoder.finish(), Err(JsonError::InvalidUtf16 { offset: 2 }));
+    }
+
+    #[test]
+    fn test_detect_prefix() {
+        assert_eq!(detect_prefix(b"{\""), Some((Encoding::Utf8, 0)));
+        assert_eq!(detect_prefix(b"\xEF\xBB"), None);
+        assert_eq!(detect_prefix(b"\xEF\xBB\xBF"), Some((Encoding::Utf8, 0)));
+        assert_eq!(detect_prefix(b"{"), None);
+        assert_eq!(detect_prefix(b"{\0"), None);
+        assert_eq!(detect_prefix(b"\xFF\xFE\0"), None);
+        assert_eq!(detect_prefix(b"{\0\"\0"), Some((Encoding::Utf16Le, 0)));
+    }
+
+    #[test]
+    fn test_utf32_is_unsupported() {
+        for (bytes, encoding) in [
+            (&b"\0\0\0[\0\0\0]"[..], "UTF-32BE"),
+            (b"[\0\0\0]\0\0\0", "UTF-32LE"),
+            (b"\xFF\xFE\0\0[\0\0\0", "UTF-32LE"),
+            (b"\0\0\xFE\xFF\0\0\0[", "UTF-32BE"),
+        ] {
+            assert_eq!(
+                decode_json_text(bytes),
+                Err(JsonError::UnsupportedEncoding {
+                    encoding: encoding.to_string()
+                })
+            );
+        }
+    }
+}
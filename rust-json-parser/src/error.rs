@@ -1,8 +1,15 @@
 use std::error::Error;
 use std::fmt;
 
+use crate::value::{JsonMap, JsonValue};
+
 /// Error type representing all possible failures during JSON parsing and serialization.
+///
+/// New variants may be added in minor releases, so matches need a wildcard arm.
+/// Use [`kind`](Self::kind) to handle whole classes of errors without listing
+/// the variants.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum JsonError {
     /// A token was found that does not match what the parser expected at this position.
     UnexpectedToken {
@@ -14,10 +21,78 @@ pub enum JsonError {
     UnexpectedEndOfInput { expected: String, position: usize },
     /// A numeric literal could not be parsed as a valid number.
     InvalidNumber { value: String, position: usize },
+    /// A numeric literal is longer, or has a larger exponent, than allowed by
+    /// [`ParserOptions::max_number_length`](crate::ParserOptions::max_number_length)
+    /// and [`ParserOptions::max_exponent`](crate::ParserOptions::max_exponent),
+    /// or its value is too large for `f64`. `value` holds the start of the
+    /// literal.
+    NumberOutOfRange { value: String, position: usize },
     /// An unrecognized escape sequence was encountered inside a string.
     InvalidEscape { char: char, position: usize },
     /// A `\uXXXX` escape sequence contains an invalid or incomplete hex value.
     InvalidUnicode { sequence: String, position: usize },
+    /// A raw control character (U+0000 to U+001F) appears inside a string; it
+    /// must be written as an escape sequence such as `\n` or `\u0000`.
+    ControlCharacter { char: char, position: usize },
+    /// The input is not valid UTF-8. `offset` is the byte offset of the first
+    /// invalid sequence and `length` its length in bytes (for a truncated
+    /// sequence at the end of the input, the number of bytes remaining).
+    InvalidUtf8 { offset: usize, length: usize },
+    /// The input was detected as UTF-16 but is malformed: `offset` is the byte
+    /// offset of an unpaired surrogate, or of a lone trailing byte.
+    InvalidUtf16 { offset: usize },
+    /// The input is in an encoding other than UTF-8 or UTF-16, such as
+    /// UTF-32, which is named by `encoding`.
+    UnsupportedEncoding { encoding: String },
+    /// An object contains the same key twice and duplicates are rejected (see
+    /// [`DuplicateKeyPolicy`](crate::DuplicateKeyPolicy)).
+    DuplicateKey { key: String, position: usize },
+    /// A JSONPath expression passed to [`JsonValue::query`] is malformed.
+    /// `position` is a byte offset into `query`.
+    InvalidQuery { query: String, position: usize },
+    /// Arrays and objects are nested deeper than the configured maximum.
+    DepthLimitExceeded { max_depth: usize, position: usize },
+    /// A string or object key is longer than
+    /// [`ParserOptions::max_string_length`](crate::ParserOptions::max_string_length).
+    /// `position` is where the string starts.
+    StringLengthExceeded { max_length: usize, position: usize },
+    /// The document holds more values than
+    /// [`ParserOptions::max_values`](crate::ParserOptions::max_values).
+    /// `position` is where the first value over the limit starts.
+    ValueCountExceeded { max_values: usize, position: usize },
+    /// The input is larger than
+    /// [`ParserOptions::max_input_size`](crate::ParserOptions::max_input_size);
+    /// `size` is its length in bytes.
+    InputSizeExceeded { max_size: usize, size: usize },
+    /// Something other than whitespace follows the root value. `position` is the
+    /// byte offset where it starts (see [`parse_json_prefix`](crate::parse_json_prefix)
+    /// for parsing a value at the start of a longer input).
+    TrailingCharacters { position: usize },
+    /// The operation at `index` of a JSON Patch passed to
+    /// [`JsonValue::apply_patch`] is malformed, refers to a location that does
+    /// not exist, or is a `test` that failed.
+    InvalidPatch { index: usize, message: String },
+    /// The document at `index` passed to
+    /// [`concat_documents`](crate::merge::concat_documents) cannot be combined
+    /// with the ones before it: it has a different type, or repeats a key that
+    /// the [`ConcatStrategy`](crate::merge::ConcatStrategy) does not allow.
+    InvalidConcat { index: usize, message: String },
+    /// The value at `path` (a JSON Pointer) cannot be written in the format
+    /// requested from [`convert`](crate::convert::convert), e.g. a `null` in
+    /// TOML or a nested object in a CSV cell.
+    InvalidConversion { path: String, message: String },
+    /// The value at `path` (see [`JsonValue::path`]) is missing or has a
+    /// different type than a typed getter such as [`JsonValue::try_get_str`]
+    /// expected. `found` is `"nothing"` for a missing value.
+    TypeMismatch {
+        path: String,
+        expected: String,
+        found: String,
+    },
+    /// The input is valid JSON but does not match the type being deserialized
+    /// with `from_str` (`serde` feature), e.g. a string where a number was
+    /// expected or a missing struct field.
+    Deserialize { message: String, position: usize },
     /// A file system operation failed (e.g. file not found, permission denied).
     Io { message: String },
 }
@@ -50,6 +125,13 @@ impl fmt::Display for JsonError {
                     position, value,
                 )
             }
+            JsonError::NumberOutOfRange { value, position } => {
+                write!(
+                    f,
+                    "Number out of range at position {}: value {}",
+                    position, value,
+                )
+            }
             JsonError::InvalidEscape { char, position } => {
                 write!(f, "Invalid escape at position {}: char {}", position, char,)
             }
@@ -60,6 +142,109 @@ impl fmt::Display for JsonError {
                     position, sequence,
                 )
             }
+            JsonError::ControlCharacter { char, position } => {
+                write!(
+                    f,
+                    "Unescaped control character at position {}: U+{:04X}",
+                    position, *char as u32,
+                )
+            }
+            JsonError::InvalidUtf8 { offset, length } => {
+                write!(
+                    f,
+                    "Invalid UTF-8 at byte offset {}: {} invalid byte(s)",
+                    offset, length,
+                )
+            }
+            JsonError::InvalidUtf16 { offset } => {
+                write!(f, "Invalid UTF-16 at byte offset {}", offset)
+            }
+            JsonError::UnsupportedEncoding { encoding } => {
+                write!(
+                    f,
+                    "Unsupported encoding {}: JSON must be UTF-8 or UTF-16",
+                    encoding,
+                )
+            }
+            JsonError::DuplicateKey { key, position } => {
+                write!(f, "Duplicate key at position {}: {}", position, key)
+            }
+            JsonError::InvalidQuery { query, position } => {
+                write!(f, "Invalid query at position {}: {}", position, query)
+            }
+            JsonError::DepthLimitExceeded {
+                max_depth,
+                position,
+            } => {
+                write!(
+                    f,
+                    "Depth limit exceeded at position {}: maximum nesting depth is {}",
+                    position, max_depth,
+                )
+            }
+            JsonError::StringLengthExceeded {
+                max_length,
+                position,
+            } => {
+                write!(
+                    f,
+                    "String too long at position {}: maximum length is {} bytes",
+                    position, max_length,
+                )
+            }
+            JsonError::ValueCountExceeded {
+                max_values,
+                position,
+            } => {
+                write!(
+                    f,
+                    "Too many values at position {}: maximum is {}",
+                    position, max_values,
+                )
+            }
+            JsonError::InputSizeExceeded { max_size, size } => {
+                write!(
+                    f,
+                    "Input too large: {} bytes, maximum is {}",
+                    size, max_size,
+                )
+            }
+            JsonError::TrailingCharacters { position } => {
+                write!(
+                    f,
+                    "Trailing characters at position {}: expected end of input",
+                    position
+                )
+            }
+            JsonError::InvalidPatch { index, message } => {
+                write!(f, "Invalid patch at operation {}: {}", index, message)
+            }
+            JsonError::InvalidConcat { index, message } => {
+                write!(f, "Cannot concatenate document {}: {}", index, message)
+            }
+            JsonError::InvalidConversion { path, message } => {
+                let path = if path.is_empty() { "(root)" } else { path };
+                write!(f, "Cannot convert {}: {}", path, message)
+            }
+            JsonError::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => {
+                let path = if path.is_empty() { "(root)" } else { path };
+                write!(
+                    f,
+                    "Type mismatch: expected {} at {}, found {}",
+                    expected, path, found
+                )
+            }
+            JsonError::Deserialize { message, position } => {
+                write!(
+                    f,
+                    "Deserialization error at position {}: {}",
+                    position, message
+                )
+            }
             JsonError::Io { message } => write!(f, "IO error: {}", message),
         }
     }
@@ -67,6 +252,26 @@ impl fmt::Display for JsonError {
 
 impl Error for JsonError {}
 
+/// The broad class of a [`JsonError`], as returned by [`JsonError::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input is not well-formed JSON: a malformed token, a structural error,
+    /// invalid UTF-8 or trailing characters.
+    Syntax,
+    /// The input exceeds a limit set in [`ParserOptions`](crate::ParserOptions),
+    /// such as the nesting depth or the length of a number or string.
+    Limit,
+    /// The input is valid JSON but its content is rejected: a duplicate key, a
+    /// value of the wrong type, or a value that cannot be converted or merged.
+    Data,
+    /// An argument other than the document, such as a JSONPath query or a JSON
+    /// Patch, is malformed or cannot be applied.
+    Argument,
+    /// A file system or other I/O operation failed.
+    Io,
+}
+
 impl From<std::io::Error> for JsonError {
     fn from(err: std::io::Error) -> Self {
         JsonError::Io {
@@ -75,6 +280,337 @@ impl From<std::io::Error> for JsonError {
     }
 }
 
+impl JsonError {
+    /// Returns the class of this error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{ErrorKind, ParserOptions, parse_json, parse_json_with_options};
+    ///
+    /// assert_eq!(parse_json("[1,").unwrap_err().kind(), ErrorKind::Syntax);
+    ///
+    /// let options = ParserOptions {
+    ///     max_depth: 1,
+    ///     ..ParserOptions::default()
+    /// };
+    /// let err = parse_json_with_options("[[]]", &options).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::Limit);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            JsonError::UnexpectedToken { .. }
+            | JsonError::UnexpectedEndOfInput { .. }
+            | JsonError::InvalidNumber { .. }
+            | JsonError::InvalidEscape { .. }
+            | JsonError::InvalidUnicode { .. }
+            | JsonError::ControlCharacter { .. }
+            | JsonError::InvalidUtf8 { .. }
+            | JsonError::InvalidUtf16 { .. }
+            | JsonError::UnsupportedEncoding { .. }
+            | JsonError::TrailingCharacters { .. } => ErrorKind::Syntax,
+            JsonError::NumberOutOfRange { .. }
+            | JsonError::DepthLimitExceeded { .. }
+            | JsonError::StringLengthExceeded { .. }
+            | JsonError::ValueCountExceeded { .. }
+            | JsonError::InputSizeExceeded { .. } => ErrorKind::Limit,
+            JsonError::DuplicateKey { .. }
+            | JsonError::InvalidConcat { .. }
+            | JsonError::InvalidConversion { .. }
+            | JsonError::TypeMismatch { .. }
+            | JsonError::Deserialize { .. } => ErrorKind::Data,
+            JsonError::InvalidQuery { .. } | JsonError::InvalidPatch { .. } => ErrorKind::Argument,
+            JsonError::Io { .. } => ErrorKind::Io,
+        }
+    }
+
+    /// Returns `true` if the input is not well-formed JSON, i.e. the error is of
+    /// kind [`ErrorKind::Syntax`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// assert!(parse_json("{,}").unwrap_err().is_syntax());
+    /// ```
+    pub fn is_syntax(&self) -> bool {
+        self.kind() == ErrorKind::Syntax
+    }
+
+    /// Returns `true` if an I/O operation failed, i.e. the error is of kind
+    /// [`ErrorKind::Io`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json_file;
+    ///
+    /// assert!(parse_json_file("does/not/exist.json").unwrap_err().is_io());
+    /// ```
+    pub fn is_io(&self) -> bool {
+        self.kind() == ErrorKind::Io
+    }
+
+    /// Returns the position recorded in the error, if any: a byte offset into the
+    /// parsed input, or into the query for [`JsonError::InvalidQuery`]. Use
+    /// [`input_offset`](Self::input_offset) to only get offsets into the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// assert_eq!(parse_json("[1, x]").unwrap_err().position(), Some(4));
+    /// ```
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            JsonError::InvalidQuery { position, .. } => Some(*position),
+            _ => self.input_offset(),
+        }
+    }
+
+    /// Returns the name of the error variant, e.g. `"UnexpectedToken"`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let err = parse_json("[1, 2").unwrap_err();
+    /// assert_eq!(err.name(), "UnexpectedEndOfInput");
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            JsonError::UnexpectedToken { .. } => "UnexpectedToken",
+            JsonError::UnexpectedEndOfInput { .. } => "UnexpectedEndOfInput",
+            JsonError::InvalidNumber { .. } => "InvalidNumber",
+            JsonError::NumberOutOfRange { .. } => "NumberOutOfRange",
+            JsonError::InvalidEscape { .. } => "InvalidEscape",
+            JsonError::InvalidUnicode { .. } => "InvalidUnicode",
+            JsonError::ControlCharacter { .. } => "ControlCharacter",
+            JsonError::InvalidUtf8 { .. } => "InvalidUtf8",
+            JsonError::InvalidUtf16 { .. } => "InvalidUtf16",
+            JsonError::UnsupportedEncoding { .. } => "UnsupportedEncoding",
+            JsonError::DuplicateKey { .. } => "DuplicateKey",
+            JsonError::InvalidQuery { .. } => "InvalidQuery",
+            JsonError::DepthLimitExceeded { .. } => "DepthLimitExceeded",
+            JsonError::StringLengthExceeded { .. } => "StringLengthExceeded",
+            JsonError::ValueCountExceeded { .. } => "ValueCountExceeded",
+            JsonError::InputSizeExceeded { .. } => "InputSizeExceeded",
+            JsonError::TrailingCharacters { .. } => "TrailingCharacters",
+            JsonError::InvalidPatch { .. } => "InvalidPatch",
+            JsonError::InvalidConcat { .. } => "InvalidConcat",
+            JsonError::InvalidConversion { .. } => "InvalidConversion",
+            JsonError::TypeMismatch { .. } => "TypeMismatch",
+            JsonError::Deserialize { .. } => "Deserialize",
+            JsonError::Io { .. } => "Io",
+        }
+    }
+
+    /// Returns the byte offset into the parsed input at which the error was
+    /// detected, or `None` for errors that do not refer to the input (such as
+    /// [`JsonError::Io`] or [`JsonError::InvalidQuery`], whose position is an
+    /// offset into the query).
+    pub fn input_offset(&self) -> Option<usize> {
+        match self {
+            JsonError::UnexpectedToken { position, .. }
+            | JsonError::UnexpectedEndOfInput { position, .. }
+            | JsonError::InvalidNumber { position, .. }
+            | JsonError::NumberOutOfRange { position, .. }
+            | JsonError::InvalidEscape { position, .. }
+            | JsonError::InvalidUnicode { position, .. }
+            | JsonError::ControlCharacter { position, .. }
+            | JsonError::DuplicateKey { position, .. }
+            | JsonError::DepthLimitExceeded { position, .. }
+            | JsonError::StringLengthExceeded { position, .. }
+            | JsonError::ValueCountExceeded { position, .. }
+            | JsonError::TrailingCharacters { position }
+            | JsonError::Deserialize { position, .. } => Some(*position),
+            JsonError::InvalidUtf8 { offset, .. } | JsonError::InvalidUtf16 { offset } => {
+                Some(*offset)
+            }
+            JsonError::InvalidQuery { .. }
+            | JsonError::UnsupportedEncoding { .. }
+            | JsonError::InputSizeExceeded { .. }
+            | JsonError::InvalidPatch { .. }
+            | JsonError::InvalidConcat { .. }
+            | JsonError::InvalidConversion { .. }
+            | JsonError::TypeMismatch { .. }
+            | JsonError::Io { .. } => None,
+        }
+    }
+
+    /// Returns the 1-based line and column in `input` at which the error was
+    /// detected, as shown by editors and compilers. Columns count characters, so
+    /// a multi-byte character advances the column by one.
+    ///
+    /// `input` must be the text (or bytes) that produced the error. Returns
+    /// `None` when [`input_offset`](Self::input_offset) does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let input = "{\n  \"a\": tru\n}";
+    /// let err = parse_json(input).unwrap_err();
+    /// assert_eq!(err.line_column(input), Some((2, 8)));
+    /// ```
+    pub fn line_column(&self, input: impl AsRef<[u8]>) -> Option<(usize, usize)> {
+        let input = input.as_ref();
+        let before = &input[..self.input_offset()?.min(input.len())];
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |newline| newline + 1);
+        let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+        // Continuation bytes of multi-byte UTF-8 sequences do not start a character.
+        let column = before[line_start..]
+            .iter()
+            .filter(|&&b| b & 0xC0 != 0x80)
+            .count()
+            + 1;
+        Some((line, column))
+    }
+}
+
+/// Converts an error into a JSON object describing it, for machine-readable
+/// diagnostics.
+///
+/// The object always contains `"error"` (the variant name) and `"message"` (the
+/// `Display` text), plus one member per field of the variant.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, JsonValue};
+///
+/// let err = parse_json(r#""\q""#).unwrap_err();
+/// let diagnostic = JsonValue::from(&err);
+/// assert_eq!(diagnostic.get("error"), Some(&JsonValue::String("InvalidEscape".to_string())));
+/// assert_eq!(diagnostic.get("char"), Some(&JsonValue::String("q".to_string())));
+/// ```
+impl From<&JsonError> for JsonValue {
+    fn from(err: &JsonError) -> Self {
+        let mut object = JsonMap::default();
+        object.insert("error".into(), JsonValue::String(err.name().to_string()));
+        object.insert("message".into(), JsonValue::String(err.to_string()));
+
+        let mut insert_str = |key: &str, value: &str| {
+            object.insert(key.into(), JsonValue::String(value.to_string()));
+        };
+        let position = match err {
+            JsonError::UnexpectedToken {
+                expected,
+                found,
+                position,
+            } => {
+                insert_str("expected", expected);
+                insert_str("found", found);
+                Some(*position)
+            }
+            JsonError::UnexpectedEndOfInput { expected, position } => {
+                insert_str("expected", expected);
+                Some(*position)
+            }
+            JsonError::InvalidNumber { value, position }
+            | JsonError::NumberOutOfRange { value, position } => {
+                insert_str("value", value);
+                Some(*position)
+            }
+            JsonError::InvalidEscape { char, position } => {
+                insert_str("char", &char.to_string());
+                Some(*position)
+            }
+            JsonError::InvalidUnicode { sequence, position } => {
+                insert_str("sequence", sequence);
+                Some(*position)
+            }
+            JsonError::ControlCharacter { char, position } => {
+                insert_str("char", &char.to_string());
+                Some(*position)
+            }
+            JsonError::InvalidUtf8 { offset, length } => {
+                object.insert("offset".into(), JsonValue::Number((*offset).into()));
+                object.insert("length".into(), JsonValue::Number((*length).into()));
+                None
+            }
+            JsonError::InvalidUtf16 { offset } => {
+                object.insert("offset".into(), JsonValue::Number((*offset).into()));
+                None
+            }
+            JsonError::UnsupportedEncoding { encoding } => {
+                insert_str("encoding", encoding);
+                None
+            }
+            JsonError::DuplicateKey { key, position } => {
+                insert_str("key", key);
+                Some(*position)
+            }
+            JsonError::InvalidQuery { query, position } => {
+                insert_str("query", query);
+                Some(*position)
+            }
+            JsonError::DepthLimitExceeded {
+                max_depth,
+                position,
+            } => {
+                object.insert("max_depth".into(), JsonValue::Number((*max_depth).into()));
+                Some(*position)
+            }
+            JsonError::StringLengthExceeded {
+                max_length,
+                position,
+            } => {
+                object.insert("max_length".into(), JsonValue::Number((*max_length).into()));
+                Some(*position)
+            }
+            JsonError::ValueCountExceeded {
+                max_values,
+                position,
+            } => {
+                object.insert("max_values".into(), JsonValue::Number((*max_values).into()));
+                Some(*position)
+            }
+            JsonError::InputSizeExceeded { max_size, size } => {
+                object.insert("max_size".into(), JsonValue::Number((*max_size).into()));
+                object.insert("size".into(), JsonValue::Number((*size).into()));
+                None
+            }
+            JsonError::TrailingCharacters { position } => Some(*position),
+            JsonError::InvalidPatch { index, message }
+            | JsonError::InvalidConcat { index, message } => {
+                object.insert("index".into(), JsonValue::Number((*index).into()));
+                object.insert("detail".into(), JsonValue::String(message.clone()));
+                None
+            }
+            JsonError::InvalidConversion { path, message } => {
+                insert_str("path", path);
+                insert_str("detail", message);
+                None
+            }
+            JsonError::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => {
+                insert_str("path", path);
+                insert_str("expected", expected);
+                insert_str("found", found);
+                None
+            }
+            JsonError::Deserialize { position, .. } => Some(*position),
+            JsonError::Io { .. } => None,
+        };
+        if let Some(position) = position {
+            object.insert("position".into(), JsonValue::Number(position.into()));
+        }
+
+        JsonValue::Object(object)
+    }
+}
+
 /// Creates an [`JsonError::UnexpectedToken`] error with the given context.
 ///
 /// # Examples
@@ -164,6 +700,25 @@ mod tests {
         let _ = format!("{:?}", num_error);
     }
 
+    #[test]
+    fn test_line_column() {
+        let input = "[\n  \"\u{e9}t\u{e9}\", @\n]";
+        let err = crate::parse_json(input).unwrap_err();
+        assert_eq!(err.line_column(input), Some((2, 10)));
+        assert_eq!(err.line_column(input.as_bytes()), Some((2, 10)));
+
+        let err = crate::parse_json("1 2").unwrap_err();
+        assert_eq!(err.line_column("1 2"), Some((1, 3)));
+
+        let err = crate::parse_json_bytes(b"[\n\"\xff\"]").unwrap_err();
+        assert_eq!(err.line_column(b"[\n\"\xff\"]"), Some((2, 2)));
+
+        let io = JsonError::Io {
+            message: "denied".to_string(),
+        };
+        assert_eq!(io.line_column(""), None);
+    }
+
     #[test]
     fn test_invalid_escape_display() {
         let err = JsonError::InvalidEscape {
@@ -193,4 +748,229 @@ mod tests {
         };
         let _: &dyn std::error::Error = &err; // Must implement Error trait
     }
+
+    #[test]
+    fn test_error_to_json_value() {
+        let err = unexpected_token_error("number", "@", 5);
+        let value = JsonValue::from(&err);
+
+        assert_eq!(
+            value.get("error").and_then(|v| v.as_str()),
+            Some("UnexpectedToken")
+        );
+        assert_eq!(
+            value.get("expected").and_then(|v| v.as_str()),
+            Some("number")
+        );
+        assert_eq!(value.get("found").and_then(|v| v.as_str()), Some("@"));
+        assert_eq!(value.get("position").and_then(|v| v.as_f64()), Some(5.0));
+        assert_eq!(
+            value.get("message").and_then(|v| v.as_str()),
+            Some(err.to_string().as_str())
+        );
+    }
+
+    #[test]
+    fn test_depth_limit_error() {
+        let err = JsonError::DepthLimitExceeded {
+            max_depth: 128,
+            position: 7,
+        };
+        assert_eq!(err.name(), "DepthLimitExceeded");
+        assert_eq!(
+            err.to_string(),
+            "Depth limit exceeded at position 7: maximum nesting depth is 128"
+        );
+        let value = JsonValue::from(&err);
+        assert_eq!(
+            value.get("max_depth"),
+            Some(&JsonValue::Number(128.0.into()))
+        );
+        assert_eq!(value.get("position"), Some(&JsonValue::Number(7.0.into())));
+    }
+
+    #[test]
+    fn test_trailing_characters_error() {
+        let err = JsonError::TrailingCharacters { position: 3 };
+        assert_eq!(err.name(), "TrailingCharacters");
+        assert_eq!(
+            err.to_string(),
+            "Trailing characters at position 3: expected end of input"
+        );
+        assert_eq!(
+            JsonValue::from(&err).get("position"),
+            Some(&JsonValue::Number(3.0.into()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_patch_error() {
+        let err = JsonError::InvalidPatch {
+            index: 2,
+            message: "test failed at /a".to_string(),
+        };
+        assert_eq!(err.name(), "InvalidPatch");
+        assert_eq!(
+            err.to_string(),
+            "Invalid patch at operation 2: test failed at /a"
+        );
+        let value = JsonValue::from(&err);
+        assert_eq!(value.get("index"), Some(&JsonValue::Number(2.into())));
+        assert_eq!(value.get("position"), None);
+    }
+
+    #[test]
+    fn test_deserialize_error() {
+        let err = JsonError::Deserialize {
+            message: "missing field `id`".to_string(),
+            position: 12,
+        };
+        assert_eq!(err.name(), "Deserialize");
+        assert_eq!(
+            err.to_string(),
+            "Deserialization error at position 12: missing field `id`"
+        );
+        assert_eq!(
+            JsonValue::from(&err).get("position"),
+            Some(&JsonValue::Number(12.into()))
+        );
+    }
+
+    #[test]
+    fn test_control_character_error() {
+        let err = JsonError::ControlCharacter {
+            char: '\n',
+            position: 4,
+        };
+        assert_eq!(err.name(), "ControlCharacter");
+        assert_eq!(
+            err.to_string(),
+            "Unescaped control character at position 4: U+000A"
+        );
+        let value = JsonValue::from(&err);
+        assert_eq!(value.get("char").and_then(|v| v.as_str()), Some("\n"));
+        assert_eq!(value.get("position"), Some(&JsonValue::Number(4.into())));
+    }
+
+    #[test]
+    fn test_duplicate_key_error() {
+        let err = JsonError::DuplicateKey {
+            key: "id".to_string(),
+            position: 9,
+        };
+        assert_eq!(err.name(), "DuplicateKey");
+        assert_eq!(err.to_string(), "Duplicate key at position 9: id");
+        let value = JsonValue::from(&err);
+        assert_eq!(value.get("key").and_then(|v| v.as_str()), Some("id"));
+        assert_eq!(value.get("position"), Some(&JsonValue::Number(9.into())));
+    }
+
+    #[test]
+    fn test_invalid_utf8_error() {
+        let err = JsonError::InvalidUtf8 {
+            offset: 3,
+            length: 2,
+        };
+        assert_eq!(err.name(), "InvalidUtf8");
+        assert_eq!(
+            err.to_string(),
+            "Invalid UTF-8 at byte offset 3: 2 invalid byte(s)"
+        );
+        let value = JsonValue::from(&err);
+        assert_eq!(value.get("offset"), Some(&JsonValue::Number(3.0.into())));
+        assert_eq!(value.get("length"), Some(&JsonValue::Number(2.0.into())));
+        assert_eq!(value.get("position"), None);
+    }
+
+    #[test]
+    fn test_encoding_errors() {
+        let err = JsonError::InvalidUtf16 { offset: 6 };
+        assert_eq!(err.name(), "InvalidUtf16");
+        assert_eq!(err.to_string(), "Invalid UTF-16 at byte offset 6");
+        assert_eq!(err.input_offset(), Some(6));
+        let value = JsonValue::from(&err);
+        assert_eq!(value.get("offset"), Some(&JsonValue::Number(6.into())));
+
+        let err = JsonError::UnsupportedEncoding {
+            encoding: "UTF-32LE".to_string(),
+        };
+        assert_eq!(err.name(), "UnsupportedEncoding");
+        assert_eq!(
+            err.to_string(),
+            "Unsupported encoding UTF-32LE: JSON must be UTF-8 or UTF-16"
+        );
+        assert!(err.is_syntax());
+        assert_eq!(err.input_offset(), None);
+        let value = JsonValue::from(&err);
+        assert_eq!(
+            value.get("encoding").and_then(|v| v.as_str()),
+            Some("UTF-32LE")
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch_error() {
+        let error = JsonError::TypeMismatch {
+            path: "servers[0].port".to_string(),
+            expected: "number".to_string(),
+            found: "string".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Type mismatch: expected number at servers[0].port, found string"
+        );
+        assert_eq!(error.name(), "TypeMismatch");
+        assert_eq!(error.input_offset(), None);
+
+        let value = JsonValue::from(&error);
+        assert_eq!(
+            value.get("path"),
+            Some(&JsonValue::String("servers[0].port".to_string()))
+        );
+        assert_eq!(
+            value.get("found"),
+            Some(&JsonValue::String("string".to_string()))
+        );
+        assert_eq!(value.get("position"), None);
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let syntax = crate::parse_json(r#"{"a" 1}"#).unwrap_err();
+        assert_eq!(syntax.kind(), ErrorKind::Syntax);
+        assert!(syntax.is_syntax() && !syntax.is_io());
+        assert_eq!(syntax.position(), Some(5));
+
+        let query = JsonError::InvalidQuery {
+            query: "$[".to_string(),
+            position: 2,
+        };
+        assert_eq!(query.kind(), ErrorKind::Argument);
+        assert_eq!((query.position(), query.input_offset()), (Some(2), None));
+
+        let io = JsonError::from(std::io::Error::other("disk full"));
+        assert!(io.is_io() && !io.is_syntax());
+        assert_eq!(io.position(), None);
+
+        let limit = JsonError::InputSizeExceeded {
+            max_size: 1,
+            size: 2,
+        };
+        assert_eq!(limit.kind(), ErrorKind::Limit);
+        let duplicate = JsonError::DuplicateKey {
+            key: "a".to_string(),
+            position: 0,
+        };
+        assert_eq!(duplicate.kind(), ErrorKind::Data);
+    }
+
+    #[test]
+    fn test_io_error_to_json_value_has_no_position() {
+        let err = JsonError::Io {
+            message: "No such file".to_string(),
+        };
+        let value = JsonValue::from(&err);
+        assert_eq!(value.get("error").and_then(|v| v.as_str()), Some("Io"));
+        assert_eq!(value.get("position"), None);
+    }
 }
@@ -0,0 +1,839 @@
+//! File system helpers shared by the serialization and parsing entry points.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+use crate::options::ParserOptions;
+use crate::parser::{parse_json_bytes, parse_json_with_options, read_json_text};
+use crate::value::{JsonValue, SerializeOptions};
+use crate::{JsonError, JsonResult};
+
+/*
+ * Builds the path of the temporary sibling file used by `write_atomic`. The
+ * temporary file must live in the same directory as the target so that the
+ * final rename never crosses a file system boundary.
+ */
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "output".to_string());
+    let temp_name = format!(".{}.{}.tmp", file_name, std::process::id());
+    match path.parent() {
+        Some(parent) => parent.join(temp_name),
+        None => PathBuf::from(temp_name),
+    }
+}
+
+/*
+ * Flushes the directory entry of a completed rename to disk. Directories can
+ * only be opened for syncing on Unix, so this is a no-op elsewhere.
+ */
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> JsonResult<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::File::open(parent)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> JsonResult<()> {
+    Ok(())
+}
+
+/// Atomically replaces the file at `path` with `contents`.
+///
+/// The data is first written to a temporary file next to the target, flushed to
+/// disk with `fsync`, and then renamed over the target. Readers therefore see
+/// either the old file or the complete new file, never a partially written one,
+/// even if the process crashes midway. Permissions of an existing target file
+/// are carried over to the replacement.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_json_parser::files::write_atomic;
+///
+/// write_atomic("config.json", b"{\"debug\": true}")?;
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::Io`] if the temporary file cannot be
+/// created, written or synced, or if the final rename fails. The temporary file is
+/// removed on failure.
+pub fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> JsonResult<()> {
+    let path = path.as_ref();
+    let temp_path = temp_path_for(path);
+
+    let result = (|| {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+        file.write_all(contents)?;
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        sync_parent_dir(path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Returns `true` if `name` matches the shell-style wildcard `pattern`.
+///
+/// `*` matches any run of characters (including none) and `?` matches exactly
+/// one character; every other character matches itself.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::files::glob_match;
+///
+/// assert!(glob_match("*.json", "data.json"));
+/// assert!(glob_match("user-??.json", "user-42.json"));
+/// assert!(!glob_match("*.json", "data.yaml"));
+/// ```
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` seen and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character and retry
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Expands a path whose final component may contain `*` or `?` wildcards into
+/// the sorted list of matching files. Paths without wildcards are returned as-is,
+/// whether or not they exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_json_parser::files::expand_glob;
+///
+/// for path in expand_glob("fixtures/*.json")? {
+///     println!("{}", path.display());
+/// }
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::Io`] if the directory containing the
+/// pattern cannot be read.
+pub fn expand_glob(pattern: impl AsRef<Path>) -> JsonResult<Vec<PathBuf>> {
+    let pattern = pattern.as_ref();
+    let file_pattern = match pattern.file_name().and_then(|name| name.to_str()) {
+        Some(name) if name.contains(['*', '?']) => name,
+        _ => return Ok(vec![pattern.to_path_buf()]),
+    };
+    let dir = match pattern.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    matching_files(dir, file_pattern)
+}
+
+/*
+ * Lists the regular files directly inside `dir` whose names match the wildcard
+ * `pattern`, sorted by path.
+ */
+fn matching_files(dir: &Path, pattern: &str) -> JsonResult<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if entry.file_type()?.is_file() && glob_match(pattern, &name.to_string_lossy()) {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/*
+ * Reads and parses a single file.
+ */
+fn parse_path(path: &Path) -> JsonResult<JsonValue> {
+    parse_json_bytes(&fs::read(path)?)
+}
+
+/// Parses every file directly inside `dir` whose name matches the wildcard
+/// `pattern` (see [`glob_match`]).
+///
+/// Files are visited in sorted path order and parsed lazily as the iterator is
+/// advanced. A file that cannot be read or parsed does not stop the iteration;
+/// its error is yielded next to its path, so callers can aggregate failures.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_json_parser::files::parse_json_dir;
+///
+/// for (path, result) in parse_json_dir("fixtures", "*.json")? {
+///     if let Err(err) = result {
+///         eprintln!("{}: {}", path.display(), err);
+///     }
+/// }
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::Io`] if `dir` cannot be listed.
+pub fn parse_json_dir(
+    dir: impl AsRef<Path>,
+    pattern: &str,
+) -> JsonResult<impl Iterator<Item = (PathBuf, JsonResult<JsonValue>)>> {
+    let paths = matching_files(dir.as_ref(), pattern)?;
+    Ok(paths.into_iter().map(|path| {
+        let result = parse_path(&path);
+        (path, result)
+    }))
+}
+
+/// Like [`parse_json_dir`], but reads and parses the files on a pool of worker
+/// threads (one per available CPU) before returning.
+///
+/// Results are still yielded in sorted path order. Prefer this variant when
+/// loading many files that are all needed anyway.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_json_parser::files::parse_json_dir_parallel;
+///
+/// let failures = parse_json_dir_parallel("fixtures", "*.json")?
+///     .filter(|(_, result)| result.is_err())
+///     .count();
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::Io`] if `dir` cannot be listed.
+pub fn parse_json_dir_parallel(
+    dir: impl AsRef<Path>,
+    pattern: &str,
+) -> JsonResult<impl Iterator<Item = (PathBuf, JsonResult<JsonValue>)>> {
+    let paths = matching_files(dir.as_ref(), pattern)?;
+    let results = map_files_parallel(&paths, false, parse_path);
+    Ok(paths.into_iter().zip(
+        results
+            .into_iter()
+            .map(|result| result.expect("every file is parsed when not failing fast")),
+    ))
+}
+
+/// Calls `f` on each of `paths` on a pool of worker threads (one per available
+/// CPU) and returns the results in the order of `paths`.
+///
+/// With `fail_fast`, workers stop picking up new paths after the first error;
+/// the paths never processed are left as `None`. Otherwise every entry is
+/// `Some`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+/// use rust_json_parser::files::map_files_parallel;
+/// use rust_json_parser::parse_json_file;
+///
+/// let paths = [PathBuf::from("a.json"), PathBuf::from("b.json")];
+/// let results = map_files_parallel(&paths, true, |path| parse_json_file(path));
+/// let valid = results.iter().flatten().filter(|result| result.is_ok()).count();
+/// ```
+pub fn map_files_parallel<T, E, F>(
+    paths: &[PathBuf],
+    fail_fast: bool,
+    f: F,
+) -> Vec<Option<Result<T, E>>>
+where
+    T: Send,
+    E: Send,
+    F: Fn(&Path) -> Result<T, E> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(paths.len());
+
+    // Each worker returns the results it produced, tagged with their index
+    let mut results: Vec<Option<Result<T, E>>> = paths.iter().map(|_| None).collect();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    while !stop.load(Ordering::Relaxed) {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(index) else {
+                            break;
+                        };
+                        let result = f(path);
+                        if fail_fast && result.is_err() {
+                            stop.store(true, Ordering::Relaxed);
+                        }
+                        done.push((index, result));
+                    }
+                    done
+                })
+            })
+            .collect();
+        for handle in handles {
+            let done = handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            for (index, result) in done {
+                results[index] = Some(result);
+            }
+        }
+    });
+    results
+}
+
+/*
+ * Guesses the serialization options a document was written with. The indent
+ * (spaces or tabs) is taken from the first indented line; documents without
+ * line breaks are considered compact.
+ */
+fn detect_serialize_options(contents: &str) -> SerializeOptions {
+    let indent = contents.trim_end().lines().skip(1).find_map(|line| {
+        let indent_char = if line.starts_with('\t') { '\t' } else { ' ' };
+        let width = line.len() - line.trim_start_matches(indent_char).len();
+        (width > 0).then_some((width, indent_char))
+    });
+
+    match indent {
+        Some((indent, indent_char)) => SerializeOptions {
+            indent_char,
+            ..SerializeOptions::pretty(indent)
+        },
+        None if contents.trim_end().contains('\n') => SerializeOptions::pretty(0),
+        None => SerializeOptions::default(),
+    }
+}
+
+/// Loads the JSON file at `path`, applies `update` to the parsed document and
+/// atomically writes the result back.
+///
+/// The indentation of the original file (a single line, or the number of
+/// spaces or tabs per level), the presence of a trailing newline and the text
+/// of number literals (see [`ParserOptions::preserve_number_text`]) are
+/// preserved. Everything else is rewritten as [`JsonValue::to_string_with`]
+/// writes it, so a file that was not written by this crate may change in
+/// places the update did not touch: single-line files get a space after each
+/// colon, blank lines and uneven indentation are normalized, and object
+/// members are written in [`JsonMap`](crate::JsonMap) order. The file is
+/// always written back as UTF-8 without a byte order mark, even if it was read
+/// as UTF-16 (see [`parse_json_bytes`]).
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_json_parser::{JsonValue, files::update_json_file};
+///
+/// update_json_file("config.json", |doc| {
+///     if let JsonValue::Object(map) = doc {
+///         map.insert("debug".into(), JsonValue::Boolean(true));
+///     }
+/// })?;
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::Io`] if the file cannot be read or
+/// written, or any other [`JsonError`] variant if its contents are
+/// not valid JSON. The file is left untouched on error.
+pub fn update_json_file<F>(path: impl AsRef<Path>, update: F) -> JsonResult<()>
+where
+    F: FnOnce(&mut JsonValue),
+{
+    let path = path.as_ref();
+    let contents = read_json_text(path)?;
+    let parse_options = ParserOptions {
+        preserve_number_text: true,
+        ..ParserOptions::default()
+    };
+    let mut document = parse_json_with_options(&contents, &parse_options)?;
+
+    update(&mut document);
+
+    let mut output = document.to_string_with(&detect_serialize_options(&contents));
+    if contents.ends_with('\n') {
+        output.push('\n');
+    }
+    write_atomic(path, output.as_bytes())
+}
+
+/// Reformats the JSON file at `path` with `options` and returns whether its
+/// contents change.
+///
+/// The new contents are exactly what [`JsonValue::to_string_with`] produces, so
+/// set [`SerializeOptions::trailing_newline`] to end files with a newline.
+/// Number literals keep their original text (see
+/// [`ParserOptions::preserve_number_text`]), so `1.50` is not rewritten as `1.5`.
+/// With `write`, a file that changes is replaced atomically (see
+/// [`write_atomic`]); otherwise nothing is written, which checks whether files
+/// are formatted. Files are compared and written as UTF-8 text: a UTF-16 file
+/// (see [`parse_json_bytes`]) whose text is already formatted is left as is,
+/// but one that changes is rewritten as UTF-8, and a UTF-8 byte order mark
+/// always counts as a change and is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_json_parser::SerializeOptions;
+/// use rust_json_parser::files::format_json_file;
+///
+/// let options = SerializeOptions {
+///     trailing_newline: true,
+///     ..SerializeOptions::pretty(2).sorted()
+/// };
+/// if format_json_file("config.json", &options, true)? {
+///     println!("reformatted config.json");
+/// }
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::Io`] if the file cannot be read or written, or any
+/// other [`JsonError`] variant if its contents are not valid JSON. The file is
+/// left untouched on error.
+pub fn format_json_file(
+    path: impl AsRef<Path>,
+    options: &SerializeOptions,
+    write: bool,
+) -> JsonResult<bool> {
+    let path = path.as_ref();
+    let contents = read_json_text(path)?;
+    let parse_options = ParserOptions {
+        preserve_number_text: true,
+        ..ParserOptions::default()
+    };
+    let output = parse_json_with_options(&contents, &parse_options)?.to_string_with(options);
+    if output == contents {
+        return Ok(false);
+    }
+    if write {
+        write_atomic(path, output.as_bytes())?;
+    }
+    Ok(true)
+}
+
+/// How [`split_to_files`] divides a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitStrategy {
+    /// One file per member of a top-level object, holding the member's value.
+    ByKey,
+    /// One file per run of up to this many elements of a top-level array,
+    /// holding them as an array.
+    Chunks(usize),
+}
+
+/*
+ * Builds the file name for one piece from the naming template, replacing
+ * characters that are not allowed in file names with `_`.
+ */
+fn piece_file_name(template: &str, key: &str, index: usize) -> JsonResult<String> {
+    let name: String = template
+        .replace("{index}", &index.to_string())
+        .replace("{key}", key)
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(JsonError::Io {
+            message: format!("invalid file name {:?} for piece {}", name, index),
+        });
+    }
+    Ok(name)
+}
+
+/// Writes the pieces of a large document to separate files in `dir`.
+///
+/// With [`SplitStrategy::ByKey`], every member of the top-level object becomes a
+/// file, taken in key order; with [`SplitStrategy::Chunks`], every run of
+/// elements of the top-level array does. Files are named after `template`, in
+/// which `{key}` stands for the member's key (empty for chunks) and `{index}`
+/// for the position of the piece, starting at 0. Characters that cannot appear
+/// in file names, such as `/`, are replaced with `_`.
+///
+/// Each file is written atomically (see [`write_atomic`]) and pretty-printed with
+/// two spaces per level. Returns the paths of the written files in order.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_json_parser::{parse_json, files::{split_to_files, SplitStrategy}};
+///
+/// let export = parse_json(r#"{"users": [], "orders": []}"#)?;
+/// let written = split_to_files(&export, SplitStrategy::ByKey, "export", "{key}.json")?;
+/// assert_eq!(written.len(), 2);
+///
+/// let rows = parse_json("[1, 2, 3, 4, 5]")?;
+/// split_to_files(&rows, SplitStrategy::Chunks(2), "rows", "rows-{index}.json")?;
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::Io`] if `value` is not an object
+/// (for [`SplitStrategy::ByKey`]) or a non-empty chunk size is not given with an
+/// array (for [`SplitStrategy::Chunks`]), if two pieces would get the same file
+/// name, or if a file cannot be written. Nothing is written when the document or
+/// the names are rejected.
+pub fn split_to_files(
+    value: &JsonValue,
+    strategy: SplitStrategy,
+    dir: impl AsRef<Path>,
+    template: &str,
+) -> JsonResult<Vec<PathBuf>> {
+    let pieces: Vec<(String, JsonValue)> = match (strategy, value) {
+        (SplitStrategy::ByKey, JsonValue::Object(members)) => {
+            let mut keys: Vec<&str> = members.keys().map(|key| &**key).collect();
+            keys.sort_unstable();
+            keys.into_iter()
+                .enumerate()
+                .map(|(index, key)| {
+                    Ok((piece_file_name(template, key, index)?, members[key].clone()))
+                })
+                .collect::<JsonResult<_>>()?
+        }
+        (SplitStrategy::Chunks(size), JsonValue::Array(items)) if size > 0 => items
+            .chunks(size)
+            .enumerate()
+            .map(|(index, chunk)| {
+                Ok((
+                    piece_file_name(template, "", index)?,
+                    JsonValue::Array(chunk.to_vec()),
+                ))
+            })
+            .collect::<JsonResult<_>>()?,
+        _ => {
+            return Err(JsonError::Io {
+                message: match strategy {
+                    SplitStrategy::ByKey => "only an object can be split by key".to_string(),
+                    SplitStrategy::Chunks(_) => {
+                        "only an array can be split into chunks of at least one element".to_string()
+                    }
+                },
+            });
+        }
+    };
+
+    let mut names: Vec<&str> = pieces.iter().map(|(name, _)| name.as_str()).collect();
+    names.sort_unstable();
+    if let Some(pair) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(JsonError::Io {
+            message: format!("several pieces would be written to {}", pair[0]),
+        });
+    }
+
+    let options = SerializeOptions {
+        trailing_newline: true,
+        ..SerializeOptions::pretty(2)
+    };
+    let dir = dir.as_ref();
+    pieces
+        .into_iter()
+        .map(|(name, piece)| {
+            let path = dir.join(name);
+            piece.write_to_file_atomic(&path, &options)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::parser::parse_json;
+
+    /*
+     * Creates a fresh, empty directory under the system temp dir for a test.
+     */
+    pub(crate) fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rust_json_parser_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_atomic_creates_file() {
+        let dir = test_dir("atomic_create");
+        let path = dir.join("out.json");
+        write_atomic(&path, b"[1,2]").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[1,2]");
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file_and_cleans_up() {
+        let dir = test_dir("atomic_replace");
+        let path = dir.join("out.json");
+        fs::write(&path, "old contents that are longer").unwrap();
+
+        write_atomic(&path, b"{}").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        let leftovers = fs::read_dir(&dir).unwrap().count();
+        assert_eq!(leftovers, 1);
+    }
+
+    #[test]
+    fn test_write_atomic_missing_directory_is_io_error() {
+        let dir = test_dir("atomic_missing");
+        let path = dir.join("no-such-dir").join("out.json");
+        let result = write_atomic(&path, b"{}");
+        assert!(matches!(result, Err(crate::JsonError::Io { .. })));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*.json", ".json"));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(glob_match("a*b", "abab"));
+        assert!(glob_match("??.json", "ab.json"));
+        assert!(!glob_match("??.json", "abc.json"));
+        assert!(!glob_match("a*b", "abc"));
+        assert!(glob_match("exact.json", "exact.json"));
+    }
+
+    #[test]
+    fn test_format_json_file() {
+        let dir = test_dir("format_file");
+        let path = dir.join("data.json");
+        fs::write(&path, r#"{"b":1.50,"a":[true]}"#).unwrap();
+        let options = SerializeOptions {
+            trailing_newline: true,
+            ..SerializeOptions::pretty(2).sorted()
+        };
+        let formatted = "{\n  \"a\": [\n    true\n  ],\n  \"b\": 1.50\n}\n";
+
+        assert!(format_json_file(&path, &options, false).unwrap());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            r#"{"b":1.50,"a":[true]}"#
+        );
+        assert!(format_json_file(&path, &options, true).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), formatted);
+        assert!(!format_json_file(&path, &options, true).unwrap());
+
+        fs::write(&path, "[1,").unwrap();
+        assert!(format_json_file(&path, &options, true).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[1,");
+    }
+
+    #[test]
+    fn test_expand_glob() {
+        let dir = test_dir("expand_glob");
+        for name in ["b.json", "a.json", "notes.txt"] {
+            fs::write(dir.join(name), "{}").unwrap();
+        }
+        fs::create_dir(dir.join("dir.json")).unwrap();
+
+        let paths = expand_glob(dir.join("*.json")).unwrap();
+        assert_eq!(paths, vec![dir.join("a.json"), dir.join("b.json")]);
+
+        let literal = dir.join("missing.json");
+        assert_eq!(expand_glob(&literal).unwrap(), vec![literal]);
+    }
+
+    #[test]
+    fn test_parse_json_dir_sequential_and_parallel() {
+        let dir = test_dir("parse_dir");
+        fs::write(dir.join("b.json"), "[1,").unwrap();
+        fs::write(dir.join("a.json"), r#"{"ok": true}"#).unwrap();
+        fs::write(dir.join("c.json"), "null").unwrap();
+        fs::write(dir.join("skip.txt"), "not json").unwrap();
+
+        let sequential: Vec<_> = parse_json_dir(&dir, "*.json").unwrap().collect();
+        let parallel: Vec<_> = parse_json_dir_parallel(&dir, "*.json").unwrap().collect();
+
+        for results in [sequential, parallel] {
+            let names: Vec<_> = results
+                .iter()
+                .map(|(path, _)| path.file_name().unwrap().to_str().unwrap())
+                .collect();
+            assert_eq!(names, vec!["a.json", "b.json", "c.json"]);
+            assert!(results[0].1.is_ok());
+            assert!(results[1].1.is_err());
+            assert_eq!(results[2].1, Ok(JsonValue::Null));
+        }
+    }
+
+    #[test]
+    fn test_parse_json_dir_missing_directory() {
+        let dir = test_dir("parse_dir_missing").join("nope");
+        assert!(matches!(
+            parse_json_dir(&dir, "*.json"),
+            Err(crate::JsonError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn test_detect_serialize_options() {
+        assert_eq!(
+            detect_serialize_options(r#"{"a": 1}"#),
+            SerializeOptions::default()
+        );
+        assert_eq!(
+            detect_serialize_options("{\n    \"a\": [\n        1\n    ]\n}\n"),
+            SerializeOptions::pretty(4)
+        );
+    }
+
+    #[test]
+    fn test_update_json_file_preserves_formatting() {
+        let dir = test_dir("update_file");
+        let path = dir.join("config.json");
+        fs::write(&path, "{\n    \"debug\": false\n}\n").unwrap();
+
+        update_json_file(&path, |doc| {
+            if let JsonValue::Object(map) = doc {
+                map.insert("debug".into(), JsonValue::Boolean(true));
+            }
+        })
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\n    \"debug\": true\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_update_json_file_preserves_tabs_and_numbers() {
+        let dir = test_dir("update_tabs");
+        let path = dir.join("config.json");
+        fs::write(&path, "{\n\t\"limits\": [\n\t\t1.50,\n\t\t1e2\n\t]\n}\n").unwrap();
+
+        update_json_file(&path, |doc| {
+            if let JsonValue::Object(map) = doc
+                && let Some(JsonValue::Array(limits)) = map.get_mut("limits")
+            {
+                limits.push(JsonValue::Boolean(true));
+            }
+        })
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\n\t\"limits\": [\n\t\t1.50,\n\t\t1e2,\n\t\ttrue\n\t]\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_update_json_file_writes_utf16_as_utf8() {
+        let dir = test_dir("update_utf16");
+        let path = dir.join("config.json");
+        let utf16: Vec<u8> = "\u{FEFF}{\"debug\": false}"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        fs::write(&path, utf16).unwrap();
+
+        update_json_file(&path, |doc| {
+            if let JsonValue::Object(map) = doc {
+                map.insert("debug".into(), JsonValue::Boolean(true));
+            }
+        })
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"debug\": true}");
+    }
+
+    #[test]
+    fn test_update_json_file_invalid_json_leaves_file_untouched() {
+        let dir = test_dir("update_invalid");
+        let path = dir.join("broken.json");
+        fs::write(&path, "{\"a\": 1").unwrap();
+
+        let result = update_json_file(&path, |_| panic!("update must not run"));
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"a\": 1");
+    }
+
+    #[test]
+    fn test_split_to_files_by_key_and_chunks() {
+        let dir = test_dir("split");
+        let value = parse_json(r#"{"users": [1], "a/b": {"x": null}}"#).unwrap();
+        let written =
+            split_to_files(&value, SplitStrategy::ByKey, &dir, "{index}-{key}.json").unwrap();
+        assert_eq!(written, [dir.join("0-a_b.json"), dir.join("1-users.json")]);
+        assert_eq!(fs::read_to_string(&written[1]).unwrap(), "[\n  1\n]\n");
+
+        let rows = parse_json("[1, 2, 3, 4, 5]").unwrap();
+        let written =
+            split_to_files(&rows, SplitStrategy::Chunks(2), &dir, "rows-{index}.json").unwrap();
+        assert_eq!(written.len(), 3);
+        assert_eq!(
+            parse_json(&fs::read_to_string(&written[2]).unwrap()).unwrap(),
+            parse_json("[5]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_split_to_files_rejects_before_writing() {
+        let dir = test_dir("split_rejects");
+        let value = parse_json(r#"{"a": 1, "b": 2}"#).unwrap();
+        for (strategy, template) in [
+            (SplitStrategy::ByKey, "same.json"),
+            (SplitStrategy::ByKey, ".."),
+            (SplitStrategy::Chunks(2), "{index}.json"),
+        ] {
+            assert!(split_to_files(&value, strategy, &dir, template).is_err());
+        }
+        let rows = parse_json("[1]").unwrap();
+        assert!(split_to_files(&rows, SplitStrategy::Chunks(0), &dir, "{index}.json").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+}
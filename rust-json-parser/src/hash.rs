@@ -0,0 +1,488 @@
+//! Structural hashing for fast comparison and diffing of large documents.
+//!
+//! A structural hash depends only on the content of a value: object members are
+//! combined independently of their order, so two documents that compare equal
+//! always hash equal. [`HashedJson`] precomputes the hash of every subtree once,
+//! which lets repeated comparisons and [`diff`](HashedJson::diff) skip identical
+//! subtrees without walking them.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::query::push_segment;
+use crate::value::JsonValue;
+
+const TAG_NULL: u8 = 0;
+const TAG_BOOLEAN: u8 = 1;
+const TAG_NUMBER: u8 = 2;
+const TAG_STRING: u8 = 3;
+const TAG_ARRAY: u8 = 4;
+const TAG_OBJECT: u8 = 5;
+
+/*
+ * Hashes a scalar value. Containers are hashed from their children's hashes by
+ * `combine_array` and `combine_object`.
+ */
+fn hash_scalar(value: &JsonValue) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match value {
+        JsonValue::Null => TAG_NULL.hash(&mut hasher),
+        JsonValue::Boolean(b) => (TAG_BOOLEAN, b).hash(&mut hasher),
+        // `JsonNumber` hashes by value, consistently with its `PartialEq`
+        JsonValue::Number(n) => (TAG_NUMBER, n).hash(&mut hasher),
+        JsonValue::String(s) => (TAG_STRING, s).hash(&mut hasher),
+        JsonValue::Array(_) | JsonValue::Object(_) => unreachable!("containers are combined"),
+        // Hashed like its JSON representation, which the default equality compares
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(custom) => return structural_hash(&custom.to_json()),
+    }
+    hasher.finish()
+}
+
+fn combine_array(hashes: impl ExactSizeIterator<Item = u64>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (TAG_ARRAY, hashes.len()).hash(&mut hasher);
+    for hash in hashes {
+        hash.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/*
+ * Members are hashed individually and summed, so the result does not depend on
+ * the (unspecified) iteration order of the map.
+ */
+fn combine_object<'a>(members: impl ExactSizeIterator<Item = (&'a str, u64)>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (TAG_OBJECT, members.len()).hash(&mut hasher);
+    let sum = members.fold(0u64, |sum, (key, hash)| {
+        let mut member = DefaultHasher::new();
+        (key, hash).hash(&mut member);
+        sum.wrapping_add(member.finish())
+    });
+    sum.hash(&mut hasher);
+    hasher.finish()
+}
+
+/*
+ * Computes the structural hash of `value` without keeping the subtree hashes.
+ */
+pub(crate) fn structural_hash(value: &JsonValue) -> u64 {
+    match value {
+        JsonValue::Array(items) => combine_array(items.iter().map(structural_hash)),
+        JsonValue::Object(members) => combine_object(
+            members
+                .iter()
+                .map(|(key, value)| (&**key, structural_hash(value))),
+        ),
+        scalar => hash_scalar(scalar),
+    }
+}
+
+/*
+ * Precomputed hashes of a container's children.
+ */
+#[derive(Debug, Clone)]
+enum Children<'a> {
+    Scalar,
+    Array(Vec<HashedJson<'a>>),
+    Object(HashMap<&'a str, HashedJson<'a>>),
+}
+
+/// A borrowed [`JsonValue`] annotated with the structural hash of every subtree.
+///
+/// Build it once for a document that is compared many times (e.g. the expected
+/// state in a drift detection job). Comparisons then cost a single integer
+/// comparison, and diffs only descend into subtrees whose hashes differ.
+///
+/// Unequal hashes prove that two subtrees differ. Equal hashes are confirmed by
+/// comparing the values, so a hash collision never hides a difference.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, JsonValue, hash::{HashedJson, JsonDiff}};
+///
+/// let expected = parse_json(r#"{"replicas": 3, "image": "app:1.0"}"#)?;
+/// let actual = parse_json(r#"{"image": "app:1.0", "replicas": 5}"#)?;
+///
+/// let expected = HashedJson::new(&expected);
+/// let actual = HashedJson::new(&actual);
+/// assert!(!expected.structural_eq(&actual));
+/// assert_eq!(
+///     expected.diff(&actual),
+///     vec![JsonDiff::Changed {
+///         path: "/replicas".to_string(),
+///         old: JsonValue::Number(3.0.into()),
+///         new: JsonValue::Number(5.0.into()),
+///     }]
+/// );
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct HashedJson<'a> {
+    value: &'a JsonValue,
+    hash: u64,
+    children: Children<'a>,
+}
+
+impl<'a> HashedJson<'a> {
+    /// Hashes `value` and all of its subtrees.
+    pub fn new(value: &'a JsonValue) -> Self {
+        match value {
+            JsonValue::Array(items) => {
+                let children: Vec<HashedJson<'a>> = items.iter().map(HashedJson::new).collect();
+                Self {
+                    value,
+                    hash: combine_array(children.iter().map(|child| child.hash)),
+                    children: Children::Array(children),
+                }
+            }
+            JsonValue::Object(members) => {
+                let children: HashMap<&'a str, HashedJson<'a>> = members
+                    .iter()
+                    .map(|(key, value)| (&**key, HashedJson::new(value)))
+                    .collect();
+                Self {
+                    value,
+                    hash: combine_object(children.iter().map(|(key, child)| (*key, child.hash))),
+                    children: Children::Object(children),
+                }
+            }
+            scalar => Self {
+                value,
+                hash: hash_scalar(scalar),
+                children: Children::Scalar,
+            },
+        }
+    }
+
+    /// Returns the value this tree of hashes was built from.
+    pub fn value(&self) -> &'a JsonValue {
+        self.value
+    }
+
+    /// Returns the structural hash of the whole value.
+    pub fn structural_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns `true` if both values are equal. Different hashes answer at
+    /// once; equal hashes are confirmed with a full comparison of the values.
+    pub fn structural_eq(&self, other: &HashedJson<'_>) -> bool {
+        self.hash == other.hash && self.value == other.value
+    }
+
+    /// Lists the differences that turn `self` into `other`.
+    ///
+    /// Paths are [JSON Pointers](https://www.rfc-editor.org/rfc/rfc6901) (the root
+    /// is `""`). Arrays are compared index by index; object members are reported
+    /// in key order. Subtrees with equal hashes are compared once as a whole and
+    /// skipped if they are equal; only subtrees whose hashes differ are walked.
+    pub fn diff(&self, other: &HashedJson<'_>) -> Vec<JsonDiff> {
+        let mut diffs = Vec::new();
+        let mut path = String::new();
+        diff_into(self, other, &mut path, &mut diffs);
+        diffs
+    }
+}
+
+/// A single difference reported by [`HashedJson::diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonDiff {
+    /// A member or element present only in the new document.
+    Added {
+        /// JSON Pointer to the added value.
+        path: String,
+        /// The added value.
+        value: JsonValue,
+    },
+    /// A member or element present only in the old document.
+    Removed {
+        /// JSON Pointer to the removed value.
+        path: String,
+        /// The removed value.
+        value: JsonValue,
+    },
+    /// A value that differs between both documents.
+    Changed {
+        /// JSON Pointer to the changed value.
+        path: String,
+        /// The value in the old document.
+        old: JsonValue,
+        /// The value in the new document.
+        new: JsonValue,
+    },
+}
+
+impl JsonDiff {
+    /// Returns the JSON Pointer to the value that differs.
+    pub fn path(&self) -> &str {
+        match self {
+            JsonDiff::Added { path, .. }
+            | JsonDiff::Removed { path, .. }
+            | JsonDiff::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// Formats the difference as one line: `+ /path: value` for an addition,
+/// `- /path: value` for a removal and `~ /path: old -> new` for a change. The
+/// root is written as `(root)`.
+impl fmt::Display for JsonDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = match self.path() {
+            "" => "(root)",
+            path => path,
+        };
+        match self {
+            JsonDiff::Added { value, .. } => write!(f, "+ {}: {}", path, value),
+            JsonDiff::Removed { value, .. } => write!(f, "- {}: {}", path, value),
+            JsonDiff::Changed { old, new, .. } => write!(f, "~ {}: {} -> {}", path, old, new),
+        }
+    }
+}
+
+/// Lists the differences that turn `a` into `b`, ignoring the order of object
+/// members.
+///
+/// This is [`HashedJson::diff`] for two documents that are only compared once.
+/// Use [`render_diff`] to print the result.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{json_structural_diff, parse_json, render_diff};
+///
+/// let a = parse_json(r#"{"id": 1, "tags": ["x"], "owner": "ann"}"#)?;
+/// let b = parse_json(r#"{"owner": "bob", "id": 1, "tags": ["x", "y"]}"#)?;
+///
+/// let diffs = json_structural_diff(&a, &b);
+/// assert_eq!(diffs.len(), 2);
+/// assert_eq!(diffs[0].path(), "/owner");
+/// assert_eq!(render_diff(&diffs), "~ /owner: \"ann\" -> \"bob\"\n+ /tags/1: \"y\"\n");
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+pub fn json_structural_diff(a: &JsonValue, b: &JsonValue) -> Vec<JsonDiff> {
+    HashedJson::new(a).diff(&HashedJson::new(b))
+}
+
+/// Renders differences as text, one [`JsonDiff`] per line. Returns an empty
+/// string if there are none.
+pub fn render_diff(diffs: &[JsonDiff]) -> String {
+    diffs.iter().map(|diff| format!("{}\n", diff)).collect()
+}
+
+fn diff_into(old: &HashedJson, new: &HashedJson, path: &mut String, diffs: &mut Vec<JsonDiff>) {
+    // On a hash collision, the walk below finds the differences
+    if old.hash == new.hash && old.value == new.value {
+        return;
+    }
+    let base_len = path.len();
+
+    match (&old.children, &new.children) {
+        (Children::Array(old_items), Children::Array(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                push_segment(path, &index.to_string());
+                match (old_items.get(index), new_items.get(index)) {
+                    (Some(o), Some(n)) => diff_into(o, n, path, diffs),
+                    (Some(o), None) => diffs.push(JsonDiff::Removed {
+                        path: path.clone(),
+                        value: o.value.clone(),
+                    }),
+                    (None, Some(n)) => diffs.push(JsonDiff::Added {
+                        path: path.clone(),
+                        value: n.value.clone(),
+                    }),
+                    (None, None) => unreachable!(),
+                }
+                path.truncate(base_len);
+            }
+        }
+        (Children::Object(old_members), Children::Object(new_members)) => {
+            let mut keys: Vec<&str> = old_members
+                .keys()
+                .chain(
+                    new_members
+                        .keys()
+                        .filter(|key| !old_members.contains_key(*key)),
+                )
+                .copied()
+                .collect();
+            keys.sort_unstable();
+
+            for key in keys {
+                push_segment(path, key);
+                match (old_members.get(key), new_members.get(key)) {
+                    (Some(o), Some(n)) => diff_into(o, n, path, diffs),
+                    (Some(o), None) => diffs.push(JsonDiff::Removed {
+                        path: path.clone(),
+                        value: o.value.clone(),
+                    }),
+                    (None, Some(n)) => diffs.push(JsonDiff::Added {
+                        path: path.clone(),
+                        value: n.value.clone(),
+                    }),
+                    (None, None) => unreachable!(),
+                }
+                path.truncate(base_len);
+            }
+        }
+        _ => diffs.push(JsonDiff::Changed {
+            path: path.clone(),
+            old: old.value.clone(),
+            new: new.value.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn test_hash_ignores_member_order() {
+        let a = parse_json(r#"{"a": 1, "b": [true, null], "c": {"d": "e"}}"#).unwrap();
+        let b = parse_json(r#"{"c": {"d": "e"}, "b": [true, null], "a": 1}"#).unwrap();
+        assert_eq!(structural_hash(&a), structural_hash(&b));
+        assert_eq!(HashedJson::new(&a).structural_hash(), structural_hash(&a));
+    }
+
+    #[test]
+    fn test_hash_distinguishes_values() {
+        let values = [
+            "null",
+            "false",
+            "true",
+            "0",
+            "1",
+            r#""1""#,
+            "[]",
+            "{}",
+            "[1, 2]",
+            "[2, 1]",
+            r#"{"a": 1}"#,
+            r#"{"b": 1}"#,
+            "[[]]",
+        ];
+        let hashes: Vec<u64> = values
+            .iter()
+            .map(|input| structural_hash(&parse_json(input).unwrap()))
+            .collect();
+        for (i, a) in hashes.iter().enumerate() {
+            for b in &hashes[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_negative_zero_hashes_like_zero() {
+        assert_eq!(
+            structural_hash(&JsonValue::Number(0.0.into())),
+            structural_hash(&JsonValue::Number((-0.0).into()))
+        );
+    }
+
+    #[test]
+    fn test_hash_collisions_are_not_equal() {
+        let old = parse_json(r#"{"a": [1, 2], "b": true}"#).unwrap();
+        let new = parse_json(r#"{"a": [1, 3], "b": true}"#).unwrap();
+        let old = HashedJson::new(&old);
+        let mut new = HashedJson::new(&new);
+        // Forge a collision at the root and in the changed element
+        new.hash = old.hash;
+        if let Children::Object(members) = &mut new.children
+            && let Some(HashedJson {
+                children: Children::Array(items),
+                ..
+            }) = members.get_mut("a")
+        {
+            items[1].hash = structural_hash(&JsonValue::Number(2.0.into()));
+        }
+
+        assert!(!old.structural_eq(&new));
+        assert_eq!(
+            old.diff(&new),
+            vec![JsonDiff::Changed {
+                path: "/a/1".to_string(),
+                old: JsonValue::Number(2.0.into()),
+                new: JsonValue::Number(3.0.into()),
+            }]
+        );
+        assert!(old.structural_eq(&old.clone()));
+        assert!(old.value().structural_hash_eq(old.value()));
+        assert!(!old.value().structural_hash_eq(new.value()));
+    }
+
+    #[test]
+    fn test_diff_reports_paths() {
+        let old = parse_json(r#"{"keep": [1, 2], "list": [1, 2, 3], "gone": 1, "a/b": {"x": 1}}"#)
+            .unwrap();
+        let new =
+            parse_json(r#"{"keep": [1, 2], "list": [1, 5], "new": 2, "a/b": {"x": "1"}}"#).unwrap();
+
+        let diffs = HashedJson::new(&old).diff(&HashedJson::new(&new));
+
+        assert_eq!(
+            diffs,
+            vec![
+                JsonDiff::Changed {
+                    path: "/a~1b/x".to_string(),
+                    old: JsonValue::Number(1.0.into()),
+                    new: JsonValue::String("1".to_string()),
+                },
+                JsonDiff::Removed {
+                    path: "/gone".to_string(),
+                    value: JsonValue::Number(1.0.into()),
+                },
+                JsonDiff::Changed {
+                    path: "/list/1".to_string(),
+                    old: JsonValue::Number(2.0.into()),
+                    new: JsonValue::Number(5.0.into()),
+                },
+                JsonDiff::Removed {
+                    path: "/list/2".to_string(),
+                    value: JsonValue::Number(3.0.into()),
+                },
+                JsonDiff::Added {
+                    path: "/new".to_string(),
+                    value: JsonValue::Number(2.0.into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_of_equal_or_replaced_roots() {
+        let a = parse_json("[1, {}]").unwrap();
+        let b = parse_json(r#"{"x": 1}"#).unwrap();
+        assert!(HashedJson::new(&a).diff(&HashedJson::new(&a)).is_empty());
+        assert_eq!(
+            HashedJson::new(&a).diff(&HashedJson::new(&b)),
+            vec![JsonDiff::Changed {
+                path: String::new(),
+                old: a.clone(),
+                new: b.clone(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_render_diff() {
+        let a = parse_json(r#"{"keep": [1], "drop": null, "n": 1}"#).unwrap();
+        let b = parse_json(r#"{"n": 1.0, "keep": [1, {"k": "v"}]}"#).unwrap();
+        assert_eq!(
+            render_diff(&json_structural_diff(&a, &b)),
+            "- /drop: null\n+ /keep/1: {\"k\": \"v\"}\n"
+        );
+        assert_eq!(render_diff(&json_structural_diff(&a, &a)), "");
+        assert_eq!(
+            json_structural_diff(&a, &JsonValue::Null)[0].to_string(),
+            format!("~ (root): {} -> null", a)
+        );
+    }
+}
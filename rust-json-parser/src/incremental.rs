@@ -0,0 +1,472 @@
+//! Incremental parsing of JSON that arrives in arbitrary chunks.
+//!
+//! [`IncrementalParser`] accepts bytes as they are received (e.g. from a socket)
+//! and keeps track of whether a complete top-level value has been seen. Chunks may
+//! split the input anywhere, including in the middle of a token or a multi-byte
+//! UTF-8 sequence. UTF-16 input is detected from its first bytes, like
+//! [`parse_json_bytes`] does, and transcoded as it arrives.
+
+use crate::encoding::{Encoding, Utf16Decoder, detect_prefix, unsupported};
+use crate::parser::{decode_utf8, parse_json, parse_json_bytes};
+use crate::value::JsonValue;
+use crate::{JsonError, JsonResult};
+
+/// The state of an [`IncrementalParser`] after a call to
+/// [`feed`](IncrementalParser::feed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedStatus {
+    /// The root value is not complete yet; more bytes are required.
+    NeedMoreData,
+    /// A complete root value has been received and parsed successfully. Call
+    /// [`finish`](IncrementalParser::finish) to retrieve it.
+    Complete,
+}
+
+/// A parser that is fed input piece by piece and parses the document once it
+/// is complete.
+///
+/// Incoming bytes are buffered and scanned once, so the cost of feeding is linear
+/// in the total input size regardless of how it is chunked. Containers and strings
+/// are recognized as complete as soon as their closing delimiter arrives; a bare
+/// number or literal at the root is only known to be complete when it is followed
+/// by whitespace or when [`finish`](IncrementalParser::finish) is called. Only
+/// whitespace may follow the root value, as for [`parse_json`].
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{FeedStatus, IncrementalParser, JsonValue};
+///
+/// let mut parser = IncrementalParser::new();
+/// assert_eq!(parser.feed(br#"{"name": "Al"#)?, FeedStatus::NeedMoreData);
+/// assert_eq!(parser.feed(br#"ice"}"#)?, FeedStatus::Complete);
+///
+/// let value = parser.finish()?;
+/// assert_eq!(value.get("name"), Some(&JsonValue::String("Alice".to_string())));
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct IncrementalParser {
+    // Bytes received while the encoding is not known yet
+    pending: Vec<u8>,
+    decoder: Option<Decoder>,
+    // The document so far, as UTF-8
+    buffer: Vec<u8>,
+    scanned: usize,
+    depth: usize,
+    in_string: bool,
+    in_escape: bool,
+    in_scalar: bool,
+    complete: bool,
+    // Leave parsing the completed document to `finish`
+    defer_parse: bool,
+    value: Option<JsonValue>,
+    // Bytes received after the root value, and where anything else was found
+    after_value: usize,
+    trailing: Option<usize>,
+}
+
+impl IncrementalParser {
+    /// Creates a new, empty `IncrementalParser`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /*
+     * Creates a parser that only finds where the document ends while it is fed,
+     * and parses it in `finish`, so that the parse can be moved off the thread
+     * that reads the input.
+     */
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    pub(crate) fn deferred() -> Self {
+        Self {
+            defer_parse: true,
+            ..Self::default()
+        }
+    }
+
+    /// Appends `chunk` to the buffered input and reports whether the root value is
+    /// complete.
+    ///
+    /// Once the value is complete it is parsed immediately, so syntax errors are
+    /// reported by the `feed` call that completes the document. Bytes fed after the
+    /// value is complete are checked to be whitespace and then dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns any [`JsonError`] produced by [`parse_json`] if the completed
+    /// document is invalid, [`JsonError::InvalidUtf8`] or
+    /// [`JsonError::InvalidUtf16`] if it is malformed in its detected encoding,
+    /// [`JsonError::UnsupportedEncoding`] if it is UTF-32, or
+    /// [`JsonError::TrailingCharacters`] if anything but whitespace follows it.
+    pub fn feed(&mut self, chunk: &[u8]) -> JsonResult<FeedStatus> {
+        let transcoded;
+        let bytes = match &mut self.decoder {
+            Some(Decoder::Utf8) => chunk,
+            Some(Decoder::Utf16(decoder)) => {
+                transcoded = decoder.decode(chunk)?.into_bytes();
+                &transcoded
+            }
+            None => {
+                self.pending.extend_from_slice(chunk);
+                let Some((encoding, bom)) = detect_prefix(&self.pending) else {
+                    return Ok(FeedStatus::NeedMoreData);
+                };
+                let pending = std::mem::take(&mut self.pending);
+                transcoded = match encoding {
+                    Encoding::Utf8 => {
+                        self.decoder = Some(Decoder::Utf8);
+                        // A byte order mark is kept so that positions stay byte
+                        // offsets, but not scanned
+                        if pending.starts_with(b"\xEF\xBB\xBF") {
+                            self.scanned = 3;
+                        }
+                        pending
+                    }
+                    Encoding::Utf16Le | Encoding::Utf16Be => {
+                        let mut decoder = Utf16Decoder::new(encoding == Encoding::Utf16Be, bom);
+                        let text = decoder.decode(&pending[bom..])?;
+                        self.decoder = Some(Decoder::Utf16(decoder));
+                        text.into_bytes()
+                    }
+                    Encoding::Utf32Le | Encoding::Utf32Be => return Err(unsupported(encoding)),
+                };
+                &transcoded
+            }
+        };
+        if self.complete {
+            return self.check_trailing(bytes);
+        }
+        self.buffer.extend_from_slice(bytes);
+
+        match self.scan() {
+            Some(end) => {
+                let rest = self.buffer.split_off(end);
+                if !self.defer_parse {
+                    self.value = Some(parse_bytes(&self.buffer)?);
+                    // The document is no longer needed
+                    self.buffer = Vec::new();
+                }
+                self.complete = true;
+                self.after_value = end;
+                self.check_trailing(&rest)
+            }
+            None => Ok(FeedStatus::NeedMoreData),
+        }
+    }
+
+    /*
+     * Checks bytes received after the root value, which may only be whitespace.
+     */
+    fn check_trailing(&mut self, bytes: &[u8]) -> JsonResult<FeedStatus> {
+        if self.trailing.is_none()
+            && let Some(index) = bytes
+                .iter()
+                .position(|b| !matches!(b, b' ' | b'\n' | b'\t' | b'\r'))
+        {
+            self.trailing = Some(self.after_value + index);
+        }
+        self.after_value += bytes.len();
+        match self.trailing {
+            Some(position) => Err(JsonError::TrailingCharacters { position }),
+            None => Ok(FeedStatus::Complete),
+        }
+    }
+
+    /// Returns `true` once a complete root value has been received.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Signals the end of the input and returns the parsed value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::UnexpectedEndOfInput`] if the input ended before the
+    /// root value was complete, or any other [`JsonError`] if it is invalid,
+    /// including [`JsonError::TrailingCharacters`] if anything but whitespace
+    /// followed it.
+    pub fn finish(self) -> JsonResult<JsonValue> {
+        if let Some(position) = self.trailing {
+            return Err(JsonError::TrailingCharacters { position });
+        }
+        match &self.decoder {
+            // Too short to tell the encoding while it was arriving
+            None => return parse_json_bytes(&self.pending),
+            Some(Decoder::Utf16(decoder)) => decoder.finish()?,
+            Some(Decoder::Utf8) => {}
+        }
+        match self.value {
+            Some(value) => Ok(value),
+            None => parse_bytes(&self.buffer),
+        }
+    }
+
+    /*
+     * Scans the bytes received since the last call and returns the end offset
+     * of the root value if it has been completed.
+     */
+    fn scan(&mut self) -> Option<usize> {
+        while self.scanned < self.buffer.len() {
+            let index = self.scanned;
+            let byte = self.buffer[index];
+            self.scanned += 1;
+
+            if self.in_string {
+                if self.in_escape {
+                    self.in_escape = false;
+                } else if byte == b'\\' {
+                    self.in_escape = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                    if self.depth == 0 {
+                        return Some(index + 1);
+                    }
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' => {
+                    // An unbalanced closing token is invalid; completing here lets
+                    // the parser report it.
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.depth == 0 {
+                        return Some(index + 1);
+                    }
+                }
+                b' ' | b'\n' | b'\t' | b'\r' => {
+                    if self.depth == 0 && self.in_scalar {
+                        return Some(index);
+                    }
+                }
+                _ => {
+                    if self.depth == 0 {
+                        self.in_scalar = true;
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/*
+ * How the input is turned into the UTF-8 buffer, once its encoding is known.
+ */
+#[derive(Debug)]
+enum Decoder {
+    Utf8,
+    Utf16(Utf16Decoder),
+}
+
+/*
+ * Decodes a complete buffer as UTF-8 and parses it.
+ */
+fn parse_bytes(bytes: &[u8]) -> JsonResult<JsonValue> {
+    parse_json(decode_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonError;
+
+    #[test]
+    fn test_single_chunk() {
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(b"[1, 2]").unwrap(), FeedStatus::Complete);
+        assert_eq!(
+            parser.finish().unwrap().as_array().map(|a| a.len()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_byte_by_byte_with_escapes_and_unicode() {
+        let input = r#"{"text": "a \"quoted\" \\ ñ value", "list": [true, null]}"#;
+        let mut parser = IncrementalParser::new();
+        let bytes = input.as_bytes();
+
+        for (i, byte) in bytes.iter().enumerate() {
+            let status = parser.feed(&[*byte]).unwrap();
+            if i + 1 < bytes.len() {
+                assert_eq!(status, FeedStatus::NeedMoreData);
+            } else {
+                assert_eq!(status, FeedStatus::Complete);
+            }
+        }
+
+        let value = parser.finish().unwrap();
+        assert_eq!(
+            value.get("text"),
+            Some(&JsonValue::String("a \"quoted\" \\ ñ value".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_root_scalar_completes_on_whitespace_or_finish() {
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(b"12").unwrap(), FeedStatus::NeedMoreData);
+        assert_eq!(parser.feed(b"3").unwrap(), FeedStatus::NeedMoreData);
+        assert_eq!(parser.finish().unwrap(), JsonValue::Number(123.0.into()));
+
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(b" tru").unwrap(), FeedStatus::NeedMoreData);
+        assert_eq!(parser.feed(b"e\n").unwrap(), FeedStatus::Complete);
+        assert!(parser.is_complete());
+        assert_eq!(parser.finish().unwrap(), JsonValue::Boolean(true));
+    }
+
+    #[test]
+    fn test_root_string_split_mid_token() {
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(b"\"hel").unwrap(), FeedStatus::NeedMoreData);
+        assert_eq!(parser.feed(b"lo\"").unwrap(), FeedStatus::Complete);
+        assert_eq!(
+            parser.finish().unwrap(),
+            JsonValue::String("hello".to_string())
+        );
+    }
+
+    #[test]
+    fn test_finish_before_complete_is_end_of_input() {
+        let mut parser = IncrementalParser::new();
+        parser.feed(b"[1, 2").unwrap();
+        assert!(matches!(
+            parser.finish(),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_document_reported_on_completion() {
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(b"[1 ").unwrap(), FeedStatus::NeedMoreData);
+        assert!(matches!(
+            parser.feed(b"2]"),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+    }
+
+    #[test]
+    fn test_deferred_parse() {
+        let mut parser = IncrementalParser::deferred();
+        assert_eq!(
+            parser.feed(b"[1, {\"a\": ").unwrap(),
+            FeedStatus::NeedMoreData
+        );
+        assert_eq!(parser.feed(b"2}] ").unwrap(), FeedStatus::Complete);
+        assert!(parser.is_complete());
+        assert_eq!(parser.finish(), parse_json(r#"[1, {"a": 2}]"#));
+
+        // Errors inside the document only surface in `finish`
+        let mut parser = IncrementalParser::deferred();
+        assert_eq!(parser.feed(b"[1 2]").unwrap(), FeedStatus::Complete);
+        assert_eq!(parser.finish(), parse_json("[1 2]"));
+        let mut parser = IncrementalParser::deferred();
+        parser.feed(b"[1] x").unwrap_err();
+        assert_eq!(
+            parser.finish(),
+            Err(JsonError::TrailingCharacters { position: 4 })
+        );
+    }
+
+    #[test]
+    fn test_trailing_characters_rejected() {
+        for (chunks, position) in [
+            (&[&b"[1] x"[..]][..], 4),
+            (&[b"1 2"], 2),
+            (&[b"[1]", b"]"], 3),
+            (&[b"{}", b"  \n", b" }"], 6),
+        ] {
+            let mut parser = IncrementalParser::new();
+            let mut result = Ok(FeedStatus::NeedMoreData);
+            for chunk in chunks {
+                result = parser.feed(chunk);
+            }
+            let expected = JsonError::TrailingCharacters { position };
+            assert_eq!(result, Err(expected.clone()));
+            assert_eq!(parser.finish(), Err(expected));
+        }
+
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(b"[1] ").unwrap(), FeedStatus::Complete);
+        assert_eq!(parser.feed(b"\n\t").unwrap(), FeedStatus::Complete);
+        assert_eq!(parser.finish().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_byte_order_mark_and_utf16() {
+        let mut parser = IncrementalParser::new();
+        assert_eq!(
+            parser.feed(b"\xEF\xBB\xBF 7 ").unwrap(),
+            FeedStatus::Complete
+        );
+        assert_eq!(parser.finish().unwrap(), JsonValue::Number(7.into()));
+        // A byte order mark split across chunks
+        for split in 1..3 {
+            let bytes = b"\xEF\xBB\xBF{\"a\": 1}";
+            let mut parser = IncrementalParser::new();
+            parser.feed(&bytes[..split]).unwrap();
+            assert_eq!(parser.feed(&bytes[split..]).unwrap(), FeedStatus::Complete);
+            assert_eq!(parser.finish().unwrap(), parse_json(r#"{"a": 1}"#).unwrap());
+        }
+
+        let text = r#"{"a": ["≛", 1]} "#;
+        let expected = parse_json(text).unwrap();
+        for big_endian in [false, true] {
+            for bom in [false, true] {
+                let bytes: Vec<u8> = bom
+                    .then_some(0xFEFF)
+                    .into_iter()
+                    .chain(text.encode_utf16())
+                    .flat_map(|unit| {
+                        if big_endian {
+                            unit.to_be_bytes()
+                        } else {
+                            unit.to_le_bytes()
+                        }
+                    })
+                    .collect();
+                let mut parser = IncrementalParser::new();
+                for byte in &bytes {
+                    parser.feed(&[*byte]).unwrap();
+                }
+                assert!(parser.is_complete());
+                assert_eq!(parser.finish().unwrap(), expected);
+                assert_eq!(parse_json_bytes(&bytes).unwrap(), expected);
+            }
+        }
+
+        let mut parser = IncrementalParser::new();
+        assert!(matches!(
+            parser.feed(b"[\0\0\0"),
+            Err(JsonError::UnsupportedEncoding { .. })
+        ));
+        // Too short to be detected before the end
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(b"1").unwrap(), FeedStatus::NeedMoreData);
+        assert_eq!(parser.finish().unwrap(), JsonValue::Number(1.into()));
+    }
+
+    #[test]
+    fn test_utf8_sequence_split_across_chunks() {
+        let bytes = "\"é\"".as_bytes();
+        let mut parser = IncrementalParser::new();
+        assert_eq!(parser.feed(&bytes[..2]).unwrap(), FeedStatus::NeedMoreData);
+        assert_eq!(parser.feed(&bytes[2..]).unwrap(), FeedStatus::Complete);
+        assert_eq!(parser.finish().unwrap(), JsonValue::String("é".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_reported_with_offset() {
+        let mut parser = IncrementalParser::new();
+        assert!(matches!(
+            parser.feed(b"[\"\xff\"]"),
+            Err(JsonError::InvalidUtf8 { offset: 2, .. })
+        ));
+    }
+}
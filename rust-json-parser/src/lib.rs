@@ -1,21 +1,105 @@
 //! A JSON parser and serializer library implemented in Rust.
 //!
 //! Provides a tokenizer, recursive descent parser, and value types for
-//! parsing JSON strings or files into structured [`JsonValue`] representations,
+//! parsing JSON strings or files into structured [`JsonValue`] representations
+//! (or into a stream of events via [`StreamingParser`]),
 //! and serializing them back to JSON strings.
+//!
+//! With the `serde` feature, [`JsonValue`] implements `serde::Serialize` and
+//! `serde::Deserialize`, so it can be embedded in types handled by other formats,
+//! and `from_str` deserializes JSON straight into any `Deserialize` type.
+//!
+//! The `testing` feature adds `assert_json_eq!` and `assert_json_includes!`,
+//! which report structural differences between documents when they fail.
+//!
+//! The `custom-values` feature adds `JsonValue::Custom`, which carries domain
+//! types such as dates or decimals through a tree and serializes them through
+//! their `CustomValue` hooks.
+//!
+//! For read-only access, [`JsonDocument`] stores a parsed document in a few flat
+//! buffers instead of a tree of separately allocated nodes.
+//!
+//! With the `async` feature, `parse_json_file_async` and
+//! `parse_json_async_reader` read documents through tokio without blocking the
+//! runtime.
+//!
+//! [`audit::parse_json_audited`] reports number literals that lose precision as
+//! `f64`, with their paths.
 
+pub mod arena;
+#[cfg(feature = "async")]
+pub mod async_io;
+pub mod audit;
+pub mod borrowed;
+#[cfg(feature = "capi")]
+mod capi;
+pub mod convert;
+#[cfg(feature = "custom-values")]
+pub mod custom;
+#[cfg(feature = "serde")]
+pub mod de;
+mod encoding;
 pub mod error;
+pub mod files;
+pub mod hash;
+pub mod incremental;
+pub mod merge;
+pub mod normalize;
+pub mod number;
+pub mod options;
 pub mod parser;
+pub mod patch;
+pub mod pool;
+mod query;
+pub mod recovery;
+pub mod schema;
+#[cfg(feature = "serde")]
+mod serde_support;
+pub mod stats;
+pub mod streaming;
+pub mod strings;
+pub mod tagged;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod tokenizer;
+pub mod truncate;
 pub mod value;
 
 // Re-export types - make them accessible from the top level
 // Without this: users write `use my_lib::parser::parse_json`
 // With this: users write `use my_lib::parse_json` (cleaner!)
-pub use error::JsonError;
-pub use parser::{JsonParser, parse_json, parse_json_file};
-pub use tokenizer::{Token, Tokenizer};
-pub use value::JsonValue;
+pub use arena::{JsonDocument, JsonNode};
+#[cfg(feature = "async")]
+pub use async_io::{parse_json_async_reader, parse_json_file_async};
+#[cfg(feature = "lazy-strings")]
+pub use borrowed::LazyStr;
+pub use borrowed::{JsonStr, JsonValueRef, parse_json_borrowed};
+#[cfg(feature = "custom-values")]
+pub use custom::CustomValue;
+#[cfg(feature = "serde")]
+pub use de::from_str;
+pub use error::{ErrorKind, JsonError};
+pub use hash::{JsonDiff, json_structural_diff, render_diff};
+pub use incremental::{FeedStatus, IncrementalParser};
+pub use merge::{ArrayMerge, ConcatStrategy, MergePreference, MergeStrategy, concat_documents};
+pub use normalize::NormalizeOptions;
+pub use number::JsonNumber;
+pub use options::{DuplicateKeyPolicy, ParserOptions, UnknownEscapePolicy};
+pub use parser::{
+    JsonParser, JsonStream, extract_json, parse_json, parse_json_buf_reader, parse_json_bytes,
+    parse_json_file, parse_json_prefix, parse_json_reader, parse_json_with_options, validate_json,
+    validate_json_bytes,
+};
+pub use patch::json_diff;
+pub use pool::JsonValuePool;
+pub use recovery::parse_json_lenient;
+pub use schema::{ValidationError, validate};
+pub use stats::{DocumentStats, KeyHistogram, TypeCounts};
+pub use streaming::{JsonEventHandler, StreamingParser};
+pub use tokenizer::{SpannedToken, Token, Tokenizer};
+pub use value::{
+    JsonArray, JsonEntry, JsonHasher, JsonKey, JsonMap, JsonValue, Separators, SerializeOptions,
+};
 
 // Type alias for convenience
 // Users can write Result<JsonValue> instead of std::result::Result<JsonValue, JsonError>
@@ -30,7 +114,7 @@ mod tests {
     fn test_integration() {
         // Test the full parsing pipeline
         let mut parser = JsonParser::new("42").unwrap();
-        assert_eq!(parser.parse().unwrap(), JsonValue::Number(42.0));
+        assert_eq!(parser.parse().unwrap(), JsonValue::Number(42.0.into()));
 
         let mut parser = JsonParser::new("true").unwrap();
         assert_eq!(parser.parse().unwrap(), JsonValue::Boolean(true));
@@ -69,3 +153,6 @@ mod tests {
 
 #[cfg(feature = "python")]
 mod python_bindings;
+
+#[cfg(feature = "wasm")]
+mod wasm_bindings;
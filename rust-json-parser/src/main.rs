@@ -1,14 +1,1268 @@
-use rust_json_parser::parser::parse_json;
+//! Command line front end: parses a JSON file and prints it back.
+//!
+//! Usage:
+//!
+//! - `parse_file [--format text|json] [output options] [<path>]` parses a single
+//!   file and prints it. Without a path, or with `-`, the document is read from
+//!   stdin, so the tool can sit at the end of a pipeline. The output options
+//!   are `--pretty` (pretty-print with two spaces per level), `--indent N`
+//!   (pretty-print with N spaces per level), `--tabs` (pretty-print with one
+//!   tab per level), `--minify` (no whitespace at all), `--sort-keys`,
+//!   `--ensure-ascii` (escape non-ASCII characters), `--compact` or `--spaced`
+//!   (choose the separators), and `--single-quotes` and `--unquoted-keys` for
+//!   JSON5-style output. `--query Q` prints only the value selected by `Q`, a
+//!   JSON Pointer such as `/users/0/email` or a dotted path such as
+//!   `users[0].email`; `--raw` prints a selected string without quotes or
+//!   escapes.
+//! - `parse_file stats [<path>]` prints the number of values of each type, the
+//!   maximum nesting depth, the number of object keys, the longest string and
+//!   the approximate memory the parsed document takes. Without a path, the
+//!   document is read from stdin.
+//! - `parse_file check [--quiet] [--fail-fast] [--diagnostics] <path-or-glob>...`
+//!   validates many files in parallel and prints a pass/fail summary. Globs
+//!   (`*`, `?`) are expanded in the final path component, so they also work
+//!   when quoted. `--diagnostics` reports each failure as
+//!   `file:line:col: message`, the format editors and pre-commit hooks
+//!   understand.
+//! - `parse_file validate [--quiet] [--fail-fast] <path-or-glob>...` is
+//!   `parse_file check --diagnostics`.
+//! - `parse_file split (--by-key | --chunk N) [--name TEMPLATE] [--out DIR] <path>`
+//!   writes each member of a top-level object, or each run of N elements of a
+//!   top-level array, to its own file in `DIR` (the current directory by
+//!   default). `TEMPLATE` names the files using `{key}` and `{index}`; it
+//!   defaults to `{key}.json` or `chunk-{index}.json`.
+//! - `parse_file concat [--on-conflict reject|first|last|merge] [--out FILE]
+//!   <path-or-glob>...` combines documents, the inverse of `split`: arrays are
+//!   concatenated and objects united, with `--on-conflict` deciding what happens
+//!   to repeated keys (`reject` by default). Globs expand in sorted order. The
+//!   result is printed, or written atomically to `FILE`.
+//! - `parse_file fmt (--write | --check) [--sort-keys] [--indent N]
+//!   <path-or-glob>...` reformats files in place, pretty-printed with N spaces
+//!   per level (2 by default) and a trailing newline. Each file is replaced
+//!   atomically and only if it changes; the changed files are listed. With
+//!   `--check`, nothing is written and the command fails if any file would
+//!   change.
+//! - `parse_file convert --to yaml|toml|csv [<path>]` prints the document in
+//!   another format. TOML needs an object at the root and cannot hold `null`;
+//!   CSV needs an array of flat objects. Without a path, the document is read
+//!   from stdin.
+//!
+//! Exit codes:
+//!
+//! | Code | Meaning                                  |
+//! |------|------------------------------------------|
+//! | 0    | The input was parsed successfully        |
+//! | 1    | The input (or any checked file) is not valid JSON, or cannot be converted; `fmt --check` found unformatted files |
+//! | 2    | The input (or any checked file) could not be read, or a pattern matches no file |
+//! | 3    | `--query` selects no value               |
+//! | 64   | The command line arguments are invalid   |
+//!
+//! With `--format json`, failures are reported on stdout as a single JSON object
+//! (see the `From<&JsonError> for JsonValue` conversion) instead of as text on
+//! stderr, so CI systems can consume them.
+
+use rust_json_parser::convert::{Format, convert};
+use rust_json_parser::files::{
+    SplitStrategy, expand_glob, format_json_file, map_files_parallel, split_to_files,
+};
+use rust_json_parser::{
+    ConcatStrategy, DocumentStats, JsonError, JsonMap, JsonResult, JsonValue, MergeStrategy,
+    Separators, SerializeOptions, concat_documents, parse_json_bytes, parse_json_file,
+    validate_json_bytes,
+};
 use std::env;
 use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: parse_file [--format text|json] [--pretty] [--indent N | --tabs]
+                  [--minify] [--sort-keys] [--ensure-ascii] [--compact | --spaced]
+                  [--single-quotes] [--unquoted-keys]
+                  [--query POINTER-OR-PATH [--raw]] [<path>]
+       parse_file stats [<path>]
+       parse_file check [--quiet] [--fail-fast] [--diagnostics] <path-or-glob>...
+       parse_file validate [--quiet] [--fail-fast] <path-or-glob>...
+       parse_file split (--by-key | --chunk N) [--name TEMPLATE] [--out DIR] <path>
+       parse_file concat [--on-conflict reject|first|last|merge] [--out FILE] <path-or-glob>...
+       parse_file fmt (--write | --check) [--sort-keys] [--indent N] <path-or-glob>...
+       parse_file convert --to yaml|toml|csv [<path>]";
+
+/// Spaces per level used by `--pretty` when no `--indent` is given.
+const DEFAULT_PRETTY_INDENT: usize = 2;
+
+/// The path argument that stands for stdin, which is also the default.
+const STDIN_PATH: &str = "-";
+
+const EXIT_INVALID_JSON: u8 = 1;
+const EXIT_IO_ERROR: u8 = 2;
+const EXIT_NO_MATCH: u8 = 3;
+const EXIT_USAGE: u8 = 64;
+
+/// How diagnostics are reported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Parsed command line arguments.
+#[derive(Debug, PartialEq)]
+struct Cli {
+    path: String,
+    format: OutputFormat,
+    output: SerializeOptions,
+    query: Option<String>,
+    raw: bool,
+}
+
+impl Cli {
+    /// Returns whether the document is read from stdin.
+    fn reads_stdin(&self) -> bool {
+        self.path == STDIN_PATH
+    }
+
+    /// Returns the name of the input used in diagnostics.
+    fn display_path(&self) -> &str {
+        if self.reads_stdin() {
+            "<stdin>"
+        } else {
+            &self.path
+        }
+    }
+}
+
+/// Arguments of the `check` subcommand.
+#[derive(Debug, PartialEq)]
+struct CheckArgs {
+    patterns: Vec<String>,
+    quiet: bool,
+    fail_fast: bool,
+    /// Report failures as `file:line:col: message`.
+    diagnostics: bool,
+}
+
+/// Why a checked file is invalid.
+#[derive(Debug)]
+struct Failure {
+    error: JsonError,
+    /// The 1-based line and column of the error, if it refers to the contents.
+    line_column: Option<(usize, usize)>,
+}
+
+/// Arguments of the `split` subcommand.
+#[derive(Debug, PartialEq)]
+struct SplitArgs {
+    path: String,
+    strategy: SplitStrategy,
+    template: String,
+    out_dir: PathBuf,
+}
+
+/// Arguments of the `concat` subcommand.
+#[derive(Debug, PartialEq)]
+struct ConcatArgs {
+    patterns: Vec<String>,
+    strategy: ConcatStrategy,
+    out: Option<PathBuf>,
+}
+
+/// Arguments of the `fmt` subcommand.
+#[derive(Debug, PartialEq)]
+struct FmtArgs {
+    patterns: Vec<String>,
+    output: SerializeOptions,
+    /// Only report files that would change (`--check`) instead of rewriting them.
+    check: bool,
+}
+
+/// Arguments of the `convert` subcommand.
+#[derive(Debug, PartialEq)]
+struct ConvertArgs {
+    path: String,
+    format: Format,
+}
+
+/// The action selected on the command line.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Print(Cli),
+    /// Print statistics about the document at this path (`-` for stdin).
+    Stats(String),
+    Check(CheckArgs),
+    Split(SplitArgs),
+    Concat(ConcatArgs),
+    Fmt(FmtArgs),
+    Convert(ConvertArgs),
+}
+
+/*
+ * Parses the arguments following the program name into a command.
+ */
+fn parse_command(args: Vec<String>) -> Result<Command, String> {
+    match args.first().map(String::as_str) {
+        Some("stats") => parse_stats_args(args.into_iter().skip(1)).map(Command::Stats),
+        Some("check") => parse_check_args(args.into_iter().skip(1)).map(Command::Check),
+        Some("validate") => parse_check_args(args.into_iter().skip(1)).map(|check| {
+            Command::Check(CheckArgs {
+                diagnostics: true,
+                ..check
+            })
+        }),
+        Some("split") => parse_split_args(args.into_iter().skip(1)).map(Command::Split),
+        Some("concat") => parse_concat_args(args.into_iter().skip(1)).map(Command::Concat),
+        Some("fmt") => parse_fmt_args(args.into_iter().skip(1)).map(Command::Fmt),
+        Some("convert") => parse_convert_args(args.into_iter().skip(1)).map(Command::Convert),
+        _ => parse_args(args).map(Command::Print),
+    }
+}
+
+/*
+ * Parses the arguments of the `stats` subcommand: an optional path.
+ */
+fn parse_stats_args<I: IntoIterator<Item = String>>(args: I) -> Result<String, String> {
+    let mut path = None;
+    for arg in args {
+        if arg.starts_with('-') && arg != STDIN_PATH {
+            return Err(format!("unknown option: {}", arg));
+        } else if path.replace(arg).is_some() {
+            return Err("expected a single path".to_string());
+        }
+    }
+    Ok(path.unwrap_or_else(|| STDIN_PATH.to_string()))
+}
+
+/*
+ * Parses the arguments of the `check` subcommand.
+ */
+fn parse_check_args<I: IntoIterator<Item = String>>(args: I) -> Result<CheckArgs, String> {
+    let mut check = CheckArgs {
+        patterns: Vec::new(),
+        quiet: false,
+        fail_fast: false,
+        diagnostics: false,
+    };
+    for arg in args {
+        match arg.as_str() {
+            "--quiet" | "-q" => check.quiet = true,
+            "--fail-fast" => check.fail_fast = true,
+            "--diagnostics" => check.diagnostics = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => check.patterns.push(arg),
+        }
+    }
+    if check.patterns.is_empty() {
+        return Err("check requires at least one path".to_string());
+    }
+    Ok(check)
+}
+
+/*
+ * Parses the arguments of the `split` subcommand.
+ */
+fn parse_split_args<I: IntoIterator<Item = String>>(args: I) -> Result<SplitArgs, String> {
+    let mut path = None;
+    let mut strategy = None;
+    let mut template = None;
+    let mut out_dir = PathBuf::from(".");
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--by-key" => {
+                if strategy.replace(SplitStrategy::ByKey).is_some() {
+                    return Err("expected one of --by-key or --chunk".to_string());
+                }
+            }
+            "--chunk" => {
+                let value = args.next().ok_or("--chunk requires a value")?;
+                let size = value
+                    .parse()
+                    .ok()
+                    .filter(|&size| size > 0)
+                    .ok_or_else(|| format!("invalid chunk size: {}", value))?;
+                if strategy.replace(SplitStrategy::Chunks(size)).is_some() {
+                    return Err("expected one of --by-key or --chunk".to_string());
+                }
+            }
+            "--name" => template = Some(args.next().ok_or("--name requires a value")?),
+            "--out" => out_dir = PathBuf::from(args.next().ok_or("--out requires a value")?),
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option: {}", arg));
+            }
+            _ => {
+                if path.replace(arg).is_some() {
+                    return Err("expected a single path".to_string());
+                }
+            }
+        }
+    }
+
+    let strategy = strategy.ok_or("split requires --by-key or --chunk N")?;
+    let template = template.unwrap_or_else(|| {
+        match strategy {
+            SplitStrategy::ByKey => "{key}.json",
+            SplitStrategy::Chunks(_) => "chunk-{index}.json",
+        }
+        .to_string()
+    });
+    Ok(SplitArgs {
+        path: path.ok_or("missing path")?,
+        strategy,
+        template,
+        out_dir,
+    })
+}
+
+/*
+ * Parses the arguments of the `concat` subcommand.
+ */
+fn parse_concat_args<I: IntoIterator<Item = String>>(args: I) -> Result<ConcatArgs, String> {
+    let mut concat = ConcatArgs {
+        patterns: Vec::new(),
+        strategy: ConcatStrategy::Reject,
+        out: None,
+    };
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--on-conflict" => {
+                let value = args.next().ok_or("--on-conflict requires a value")?;
+                concat.strategy = match value.as_str() {
+                    "reject" => ConcatStrategy::Reject,
+                    "first" => ConcatStrategy::KeepFirst,
+                    "last" => ConcatStrategy::KeepLast,
+                    "merge" => ConcatStrategy::Merge(MergeStrategy::default()),
+                    other => return Err(format!("unknown conflict policy: {}", other)),
+                };
+            }
+            "--out" => {
+                concat.out = Some(PathBuf::from(args.next().ok_or("--out requires a value")?))
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => concat.patterns.push(arg),
+        }
+    }
+    if concat.patterns.is_empty() {
+        return Err("concat requires at least one path".to_string());
+    }
+    Ok(concat)
+}
+
+/*
+ * Parses the arguments of the `fmt` subcommand.
+ */
+fn parse_fmt_args<I: IntoIterator<Item = String>>(args: I) -> Result<FmtArgs, String> {
+    let mut fmt = FmtArgs {
+        patterns: Vec::new(),
+        output: SerializeOptions {
+            trailing_newline: true,
+            ..SerializeOptions::pretty(DEFAULT_PRETTY_INDENT)
+        },
+        check: false,
+    };
+    let mut write = false;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let indent_value = match arg.as_str() {
+            "--indent" => Some(args.next().ok_or("--indent requires a value")?),
+            _ => arg.strip_prefix("--indent=").map(str::to_string),
+        };
+        if let Some(value) = indent_value {
+            let indent = value
+                .parse()
+                .map_err(|_| format!("invalid indent: {}", value))?;
+            fmt.output.indent = Some(indent);
+            continue;
+        }
+        match arg.as_str() {
+            "--write" => write = true,
+            "--check" => fmt.check = true,
+            "--sort-keys" => fmt.output.sort_keys = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => fmt.patterns.push(arg),
+        }
+    }
+    if write == fmt.check {
+        return Err("fmt requires exactly one of --write and --check".to_string());
+    }
+    if fmt.patterns.is_empty() {
+        return Err("fmt requires at least one path".to_string());
+    }
+    Ok(fmt)
+}
+
+/*
+ * Parses the arguments of the `convert` subcommand.
+ */
+fn parse_convert_args<I: IntoIterator<Item = String>>(args: I) -> Result<ConvertArgs, String> {
+    let mut path = None;
+    let mut format = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => format = Some(args.next().ok_or("--to requires a value")?.parse()?),
+            _ if arg.starts_with('-') && arg != STDIN_PATH => {
+                return Err(format!("unknown option: {}", arg));
+            }
+            _ if path.is_some() => return Err("expected a single path".to_string()),
+            _ => path = Some(arg),
+        }
+    }
+    Ok(ConvertArgs {
+        path: path.unwrap_or_else(|| STDIN_PATH.to_string()),
+        format: format.ok_or("convert requires --to")?,
+    })
+}
+
+/*
+ * Parses the arguments of the default (print) command.
+ */
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Cli, String> {
+    let mut path = None;
+    let mut format = OutputFormat::Text;
+    let mut output = SerializeOptions {
+        trailing_newline: true,
+        ..SerializeOptions::default()
+    };
+    let mut pretty = false;
+    let mut minify = false;
+    let mut query = None;
+    let mut raw = false;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let format_value = match arg.as_str() {
+            "--format" => Some(args.next().ok_or("--format requires a value")?),
+            _ => arg.strip_prefix("--format=").map(str::to_string),
+        };
+        let indent_value = match arg.as_str() {
+            "--indent" => Some(args.next().ok_or("--indent requires a value")?),
+            _ => arg.strip_prefix("--indent=").map(str::to_string),
+        };
+        let query_value = match arg.as_str() {
+            "--query" => Some(args.next().ok_or("--query requires a value")?),
+            _ => arg.strip_prefix("--query=").map(str::to_string),
+        };
+
+        if let Some(value) = query_value {
+            query = Some(value);
+        } else if let Some(value) = indent_value {
+            let indent = value
+                .parse()
+                .map_err(|_| format!("invalid indent: {}", value))?;
+            output.indent = Some(indent);
+            output.indent_char = ' ';
+        } else if let Some(value) = format_value {
+            format = match value.as_str() {
+                "text" => OutputFormat::Text,
+                "json" => OutputFormat::Json,
+                other => return Err(format!("unknown format: {}", other)),
+            };
+        } else if arg == "--tabs" {
+            output.indent = Some(1);
+            output.indent_char = '\t';
+        } else if arg == "--pretty" {
+            pretty = true;
+        } else if arg == "--minify" {
+            minify = true;
+        } else if arg == "--raw" {
+            raw = true;
+        } else if arg == "--sort-keys" {
+            output.sort_keys = true;
+        } else if arg == "--ensure-ascii" {
+            output.ensure_ascii = true;
+        } else if arg == "--compact" {
+            output.separators = Separators::Compact;
+        } else if arg == "--spaced" {
+            output.separators = Separators::Spaced;
+        } else if arg == "--single-quotes" {
+            output.single_quotes = true;
+        } else if arg == "--unquoted-keys" {
+            output.unquoted_keys = true;
+        } else if arg.starts_with('-') && arg != "-" {
+            return Err(format!("unknown option: {}", arg));
+        } else if path.replace(arg).is_some() {
+            return Err("expected a single path".to_string());
+        }
+    }
+
+    if minify {
+        if pretty || output.indent.is_some() {
+            return Err("--minify cannot be combined with pretty-printing".to_string());
+        }
+        output.separators = Separators::Compact;
+    } else if pretty && output.indent.is_none() {
+        output.indent = Some(DEFAULT_PRETTY_INDENT);
+    }
+    if raw && query.is_none() {
+        return Err("--raw requires --query".to_string());
+    }
+
+    Ok(Cli {
+        path: path.unwrap_or_else(|| STDIN_PATH.to_string()),
+        format,
+        output,
+        query,
+        raw,
+    })
+}
+
+/*
+ * Selects the value named by `query`: a JSON Pointer if it is empty or starts
+ * with `/`, a dotted path otherwise.
+ */
+fn select<'a>(value: &'a JsonValue, query: &str) -> Option<&'a JsonValue> {
+    if query.is_empty() || query.starts_with('/') {
+        value.pointer(query)
+    } else {
+        value.path(query)
+    }
+}
+
+/*
+ * Maps an error to the exit code of its failure class.
+ */
+fn exit_code_for(err: &JsonError) -> u8 {
+    match err {
+        JsonError::Io { .. } => EXIT_IO_ERROR,
+        _ => EXIT_INVALID_JSON,
+    }
+}
+
+/*
+ * Reports a failure in the requested format and returns its exit code.
+ */
+fn report_error(cli: &Cli, err: &JsonError) -> ExitCode {
+    match cli.format {
+        OutputFormat::Text => eprintln!("{}: {}", cli.display_path(), err),
+        OutputFormat::Json => {
+            let mut diagnostic = JsonMap::default();
+            diagnostic.insert(
+                "file".into(),
+                JsonValue::String(cli.display_path().to_string()),
+            );
+            diagnostic.insert("valid".into(), JsonValue::Boolean(false));
+            diagnostic.insert("diagnostic".into(), JsonValue::from(err));
+            println!("{}", JsonValue::Object(diagnostic));
+        }
+    }
+    ExitCode::from(exit_code_for(err))
+}
+
+/*
+ * Reads and parses a single file, discarding the value.
+ */
+fn validate_file(path: &Path) -> Result<(), Failure> {
+    let contents = fs::read(path).map_err(|err| Failure {
+        error: err.into(),
+        line_column: None,
+    })?;
+    validate_json_bytes(&contents).map_err(|error| Failure {
+        line_column: error.line_column(&contents),
+        error,
+    })
+}
+
+/*
+ * Expands the path patterns given to `check` and `fmt`. A pattern that matches
+ * no file is an error, so that a mistyped glob does not pass as an empty run.
+ */
+fn expand_patterns(patterns: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        match expand_glob(pattern) {
+            Ok(matched) if matched.is_empty() => {
+                return Err(format!("{}: no files match", pattern));
+            }
+            Ok(matched) => paths.extend(matched),
+            Err(err) => return Err(format!("{}: {}", pattern, err)),
+        }
+    }
+    Ok(paths)
+}
+
+/*
+ * Runs the `check` subcommand and returns the process exit code.
+ */
+fn run_check(args: &CheckArgs) -> ExitCode {
+    let paths = match expand_patterns(&args.patterns) {
+        Ok(paths) => paths,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::from(EXIT_IO_ERROR);
+        }
+    };
+
+    let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+    let mut exit_code = 0;
+    for (path, result) in
+        paths
+            .iter()
+            .zip(map_files_parallel(&paths, args.fail_fast, validate_file))
+    {
+        match result {
+            Some(Ok(())) => {
+                passed += 1;
+                if !args.quiet {
+                    println!("ok    {}", path.display());
+                }
+            }
+            Some(Err(failure)) => {
+                failed += 1;
+                exit_code = exit_code.max(exit_code_for(&failure.error));
+                match failure.line_column {
+                    Some((line, column)) if args.diagnostics => {
+                        println!("{}:{}:{}: {}", path.display(), line, column, failure.error)
+                    }
+                    _ if args.diagnostics => println!("{}: {}", path.display(), failure.error),
+                    _ => println!("FAIL  {}: {}", path.display(), failure.error),
+                }
+            }
+            None => skipped += 1,
+        }
+    }
+
+    let mut summary = format!("{} passed, {} failed", passed, failed);
+    if skipped > 0 {
+        summary.push_str(&format!(", {} skipped", skipped));
+    }
+    println!("{}", summary);
+    ExitCode::from(exit_code)
+}
+
+/*
+ * Runs the `split` subcommand and returns the process exit code.
+ */
+fn run_split(args: &SplitArgs) -> ExitCode {
+    let result = parse_json_file(&args.path)
+        .and_then(|value| split_to_files(&value, args.strategy, &args.out_dir, &args.template));
+    match result {
+        Ok(written) => {
+            for path in written {
+                println!("{}", path.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}: {}", args.path, err);
+            ExitCode::from(exit_code_for(&err))
+        }
+    }
+}
+
+/*
+ * Runs the `concat` subcommand and returns the process exit code.
+ */
+fn run_concat(args: &ConcatArgs) -> ExitCode {
+    let mut documents = Vec::new();
+    for pattern in &args.patterns {
+        let paths = match expand_glob(pattern) {
+            Ok(paths) => paths,
+            Err(err) => {
+                eprintln!("{}: {}", pattern, err);
+                return ExitCode::from(exit_code_for(&err));
+            }
+        };
+        for path in paths {
+            match fs::read(&path)
+                .map_err(JsonError::from)
+                .and_then(|bytes| parse_json_bytes(&bytes))
+            {
+                Ok(value) => documents.push(value),
+                Err(err) => {
+                    eprintln!("{}: {}", path.display(), err);
+                    return ExitCode::from(exit_code_for(&err));
+                }
+            }
+        }
+    }
+
+    let output = SerializeOptions {
+        trailing_newline: true,
+        ..SerializeOptions::default()
+    };
+    let result = concat_documents(documents, args.strategy).and_then(|value| match &args.out {
+        Some(path) => value.write_to_file_atomic(path, &output),
+        None => print_value(&value, &output),
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("parse_file concat: {}", err);
+            ExitCode::from(exit_code_for(&err))
+        }
+    }
+}
+
+/*
+ * Runs the `fmt` subcommand and returns the process exit code.
+ */
+fn run_fmt(args: &FmtArgs) -> ExitCode {
+    let paths = match expand_patterns(&args.patterns) {
+        Ok(paths) => paths,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::from(EXIT_IO_ERROR);
+        }
+    };
+
+    let (mut changed, mut unchanged, mut failed) = (0, 0, 0);
+    let mut exit_code = 0;
+    for path in &paths {
+        match format_json_file(path, &args.output, !args.check) {
+            Ok(true) => {
+                changed += 1;
+                if args.check {
+                    exit_code = exit_code.max(EXIT_INVALID_JSON);
+                    println!("would reformat {}", path.display());
+                } else {
+                    println!("reformatted {}", path.display());
+                }
+            }
+            Ok(false) => unchanged += 1,
+            Err(err) => {
+                failed += 1;
+                exit_code = exit_code.max(exit_code_for(&err));
+                eprintln!("{}: {}", path.display(), err);
+            }
+        }
+    }
+
+    let verb = if args.check {
+        "would be reformatted"
+    } else {
+        "reformatted"
+    };
+    let mut summary = format!("{} {}, {} unchanged", changed, verb, unchanged);
+    if failed > 0 {
+        summary.push_str(&format!(", {} failed", failed));
+    }
+    println!("{}", summary);
+    ExitCode::from(exit_code)
+}
+
+/*
+ * Runs the `convert` subcommand and returns the process exit code.
+ */
+fn run_convert(args: &ConvertArgs) -> ExitCode {
+    let result = parse_input(&args.path)
+        .and_then(|value| convert(&value, args.format))
+        .and_then(|text| Ok(io::stdout().lock().write_all(text.as_bytes())?));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let name = if args.path == STDIN_PATH {
+                "<stdin>"
+            } else {
+                &args.path
+            };
+            eprintln!("{}: {}", name, err);
+            ExitCode::from(exit_code_for(&err))
+        }
+    }
+}
+
+/*
+ * Streams `value` to stdout with the requested output options, so that large
+ * documents are not serialized into an intermediate string first.
+ */
+fn print_value(value: &JsonValue, options: &SerializeOptions) -> JsonResult<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    value.write_to(&mut out, options)?;
+    out.flush()?;
+    Ok(())
+}
+
+/*
+ * Reads all of stdin and parses it.
+ */
+fn parse_stdin() -> JsonResult<JsonValue> {
+    let mut input = Vec::new();
+    io::stdin().lock().read_to_end(&mut input)?;
+    parse_json_bytes(&input)
+}
+
+/*
+ * Parses the file at `path`, or stdin if it is `-`.
+ */
+fn parse_input(path: &str) -> JsonResult<JsonValue> {
+    if path == STDIN_PATH {
+        parse_stdin()
+    } else {
+        parse_json_file(path)
+    }
+}
+
+/*
+ * Formats a byte count with a binary unit, e.g. `1.5 KiB`.
+ */
+fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/*
+ * Renders the report printed by the `stats` subcommand.
+ */
+fn format_stats(stats: &DocumentStats) -> String {
+    let mut report = format!("values:          {}\n", stats.types.total());
+    for (type_name, count) in stats.types.iter() {
+        report.push_str(&format!("  {:<14} {}\n", format!("{}:", type_name), count));
+    }
+    report.push_str(&format!("max depth:       {}\n", stats.max_depth));
+    report.push_str(&format!("object keys:     {}\n", stats.object_keys));
+    match &stats.longest_string_path {
+        Some(path) => report.push_str(&format!(
+            "longest string:  {} bytes at {}\n",
+            stats.longest_string,
+            if path.is_empty() { "(root)" } else { path }
+        )),
+        None => report.push_str("longest string:  -\n"),
+    }
+    report.push_str(&format!(
+        "approx. memory:  {} ({} bytes)\n",
+        human_size(stats.approx_memory),
+        stats.approx_memory
+    ));
+    report
+}
+
+/*
+ * Runs the `stats` subcommand and returns the process exit code.
+ */
+fn run_stats(path: &str) -> ExitCode {
+    match parse_input(path) {
+        Ok(value) => {
+            print!("{}", format_stats(&value.stats()));
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            let name = if path == STDIN_PATH { "<stdin>" } else { path };
+            eprintln!("{}: {}", name, err);
+            ExitCode::from(exit_code_for(&err))
+        }
+    }
+}
+
+/*
+ * Runs the default command: parse one file (or stdin) and print it.
+ */
+fn run_print(cli: &Cli) -> ExitCode {
+    let result = parse_input(&cli.path);
+
+    let value = match result {
+        Ok(value) => value,
+        Err(err) => return report_error(cli, &err),
+    };
+    let selected = match &cli.query {
+        Some(query) => match select(&value, query) {
+            Some(selected) => selected,
+            None => {
+                eprintln!("{}: no value at {}", cli.display_path(), query);
+                return ExitCode::from(EXIT_NO_MATCH);
+            }
+        },
+        None => &value,
+    };
+    let printed = match selected {
+        JsonValue::String(s) if cli.raw => {
+            writeln!(io::stdout().lock(), "{}", s).map_err(JsonError::from)
+        }
+        _ => print_value(selected, &cli.output),
+    };
+    match printed {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => report_error(cli, &err),
+    }
+}
+
+fn main() -> ExitCode {
+    match parse_command(env::args().skip(1).collect()) {
+        Ok(Command::Print(cli)) => run_print(&cli),
+        Ok(Command::Stats(path)) => run_stats(&path),
+        Ok(Command::Check(args)) => run_check(&args),
+        Ok(Command::Split(args)) => run_split(&args),
+        Ok(Command::Concat(args)) => run_concat(&args),
+        Ok(Command::Fmt(args)) => run_fmt(&args),
+        Ok(Command::Convert(args)) => run_convert(&args),
+        Err(message) => {
+            eprintln!("parse_file: {}\n{}", message, USAGE);
+            ExitCode::from(EXIT_USAGE)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_json_parser::parse_json;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_path_only() {
+        let cli = parse_args(args(&["data.json"])).unwrap();
+        assert_eq!(cli.path, "data.json");
+        assert_eq!(cli.format, OutputFormat::Text);
+        assert_eq!(cli.output.indent, None);
+        assert!(cli.output.trailing_newline);
+    }
 
-fn main() {
-    let path = env::args().nth(1).expect("Usage: parse_file <path>");
-    let contents =
-        fs::read_to_string(&path).unwrap_or_else(|_| panic!("Failed to read file: {}", path));
+    #[test]
+    fn test_parse_args_output_options() {
+        let cli = parse_args(args(&[
+            "--indent",
+            "4",
+            "--sort-keys",
+            "--ensure-ascii",
+            "--spaced",
+            "data.json",
+        ]))
+        .unwrap();
+        assert_eq!(cli.output.indent, Some(4));
+        assert_eq!(cli.output.indent_char, ' ');
+        assert!(cli.output.sort_keys);
+        assert!(cli.output.ensure_ascii);
+        assert_eq!(cli.output.separators, Separators::Spaced);
+
+        let cli = parse_args(args(&["--tabs", "--compact", "data.json"])).unwrap();
+        assert_eq!(cli.output.indent, Some(1));
+        assert_eq!(cli.output.indent_char, '\t');
+        assert_eq!(cli.output.separators, Separators::Compact);
+        assert!(!cli.output.single_quotes && !cli.output.unquoted_keys);
+
+        let cli = parse_args(args(&["--single-quotes", "--unquoted-keys", "data.json"])).unwrap();
+        assert!(cli.output.single_quotes && cli.output.unquoted_keys);
+
+        let cli = parse_args(args(&["--indent=2", "data.json"])).unwrap();
+        assert_eq!(cli.output.indent, Some(2));
+        assert!(parse_args(args(&["--indent", "two", "data.json"])).is_err());
+        assert!(parse_args(args(&["data.json", "--indent"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_pretty_and_minify() {
+        let cli = parse_args(args(&["--pretty", "data.json"])).unwrap();
+        assert_eq!(cli.output.indent, Some(DEFAULT_PRETTY_INDENT));
+
+        let cli = parse_args(args(&["--indent", "4", "--pretty", "data.json"])).unwrap();
+        assert_eq!(cli.output.indent, Some(4));
+
+        let cli = parse_args(args(&["--minify", "data.json"])).unwrap();
+        assert_eq!(cli.output.indent, None);
+        assert_eq!(cli.output.separators, Separators::Compact);
+
+        assert!(parse_args(args(&["--minify", "--pretty", "data.json"])).is_err());
+        assert!(parse_args(args(&["--tabs", "--minify", "data.json"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_query() {
+        let cli = parse_args(args(&["--query", "/users/0/email", "--raw", "a.json"])).unwrap();
+        assert_eq!(cli.query.as_deref(), Some("/users/0/email"));
+        assert!(cli.raw);
+        let cli = parse_args(args(&["--query=users[0]"])).unwrap();
+        assert_eq!(cli.query.as_deref(), Some("users[0]"));
+        assert!(!cli.raw);
+        assert!(parse_args(args(&["--raw", "a.json"])).is_err());
+        assert!(parse_args(args(&["a.json", "--query"])).is_err());
+    }
+
+    #[test]
+    fn test_select_pointer_or_path() {
+        let value = parse_json(r#"{"users": [{"email": "a@b.c"}], "a/b": 1}"#).unwrap();
+        let email = JsonValue::String("a@b.c".to_string());
+        assert_eq!(select(&value, "/users/0/email"), Some(&email));
+        assert_eq!(select(&value, "users[0].email"), Some(&email));
+        assert_eq!(select(&value, "/a~1b"), Some(&JsonValue::Number(1.into())));
+        assert_eq!(select(&value, ""), Some(&value));
+        assert_eq!(select(&value, "/users/1"), None);
+        assert_eq!(select(&value, "users.email"), None);
+    }
+
+    #[test]
+    fn test_parse_args_format() {
+        let cli = parse_args(args(&["--format", "json", "data.json"])).unwrap();
+        assert_eq!(cli.format, OutputFormat::Json);
+
+        let cli = parse_args(args(&["data.json", "--format=text"])).unwrap();
+        assert_eq!(cli.format, OutputFormat::Text);
+    }
+
+    #[test]
+    fn test_parse_args_defaults_to_stdin() {
+        for list in [&[][..], &["--pretty"], &["-"]] {
+            let cli = parse_args(args(list)).unwrap();
+            assert!(cli.reads_stdin());
+            assert_eq!(cli.display_path(), "<stdin>");
+        }
+        assert_eq!(
+            parse_args(args(&["a.json"])).unwrap().display_path(),
+            "a.json"
+        );
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse_args(args(&["--format"])).is_err());
+        assert!(parse_args(args(&["--format", "xml", "a.json"])).is_err());
+        assert!(parse_args(args(&["--verbose", "a.json"])).is_err());
+        assert!(parse_args(args(&["a.json", "b.json"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_check_command() {
+        let command = parse_command(args(&["check", "--quiet", "a.json", "*.json"])).unwrap();
+        assert_eq!(
+            command,
+            Command::Check(CheckArgs {
+                patterns: vec!["a.json".to_string(), "*.json".to_string()],
+                quiet: true,
+                fail_fast: false,
+                diagnostics: false,
+            })
+        );
+        let Command::Check(check) =
+            parse_command(args(&["check", "--diagnostics", "a.json"])).unwrap()
+        else {
+            panic!("expected check");
+        };
+        assert!(check.diagnostics && !check.quiet);
+        assert!(parse_command(args(&["check"])).is_err());
+        assert!(parse_command(args(&["check", "--bogus", "a.json"])).is_err());
+        assert_eq!(
+            parse_command(args(&["validate", "--fail-fast", "a.json"])).unwrap(),
+            Command::Check(CheckArgs {
+                patterns: vec!["a.json".to_string()],
+                quiet: false,
+                fail_fast: true,
+                diagnostics: true,
+            })
+        );
+        assert!(parse_command(args(&["validate"])).is_err());
+    }
+
+    #[test]
+    fn test_stats_command() {
+        assert_eq!(
+            parse_command(args(&["stats", "a.json"])).unwrap(),
+            Command::Stats("a.json".to_string())
+        );
+        assert_eq!(
+            parse_command(args(&["stats"])).unwrap(),
+            Command::Stats(STDIN_PATH.to_string())
+        );
+        assert!(parse_command(args(&["stats", "a.json", "b.json"])).is_err());
+
+        let stats = parse_json(r#"{"a": ["xy"]}"#).unwrap().stats();
+        let report = format_stats(&stats);
+        assert!(report.starts_with("values:          3\n  null:          0\n"));
+        assert!(report.contains("max depth:       2\n"));
+        assert!(report.contains("longest string:  2 bytes at /a/0\n"));
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+
+    #[test]
+    fn test_parse_split_command() {
+        let command = parse_command(args(&[
+            "split",
+            "--chunk",
+            "100",
+            "--out",
+            "parts",
+            "rows.json",
+        ]))
+        .unwrap();
+        assert_eq!(
+            command,
+            Command::Split(SplitArgs {
+                path: "rows.json".to_string(),
+                strategy: SplitStrategy::Chunks(100),
+                template: "chunk-{index}.json".to_string(),
+                out_dir: PathBuf::from("parts"),
+            })
+        );
+        let Command::Split(split) = parse_command(args(&[
+            "split",
+            "--by-key",
+            "--name",
+            "{key}.out",
+            "a.json",
+        ]))
+        .unwrap() else {
+            panic!("expected split");
+        };
+        assert_eq!(split.strategy, SplitStrategy::ByKey);
+        assert_eq!(split.template, "{key}.out");
+
+        assert!(parse_command(args(&["split", "a.json"])).is_err());
+        assert!(parse_command(args(&["split", "--chunk", "0", "a.json"])).is_err());
+        assert!(parse_command(args(&["split", "--by-key", "--chunk", "2", "a.json"])).is_err());
+        assert!(parse_command(args(&["split", "--by-key"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_concat_command() {
+        let command =
+            parse_command(args(&["concat", "parts/*.json", "--out", "all.json"])).unwrap();
+        assert_eq!(
+            command,
+            Command::Concat(ConcatArgs {
+                patterns: vec!["parts/*.json".to_string()],
+                strategy: ConcatStrategy::Reject,
+                out: Some(PathBuf::from("all.json")),
+            })
+        );
+        let Command::Concat(concat) = parse_command(args(&[
+            "concat",
+            "--on-conflict",
+            "last",
+            "a.json",
+            "b.json",
+        ]))
+        .unwrap() else {
+            panic!("expected concat");
+        };
+        assert_eq!(concat.strategy, ConcatStrategy::KeepLast);
+        assert_eq!(concat.out, None);
+
+        assert!(parse_command(args(&["concat"])).is_err());
+        assert!(parse_command(args(&["concat", "--on-conflict", "union", "a.json"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_fmt_args() {
+        let Command::Fmt(fmt) = parse_command(args(&["fmt", "--write", "*.json"])).unwrap() else {
+            panic!("expected fmt");
+        };
+        assert_eq!(fmt.patterns, vec!["*.json".to_string()]);
+        assert!(!fmt.check);
+        assert_eq!(fmt.output.indent, Some(DEFAULT_PRETTY_INDENT));
+        assert!(fmt.output.trailing_newline);
+        assert!(!fmt.output.sort_keys);
+
+        let Command::Fmt(fmt) = parse_command(args(&[
+            "fmt",
+            "--check",
+            "--sort-keys",
+            "--indent",
+            "4",
+            "a.json",
+            "b.json",
+        ]))
+        .unwrap() else {
+            panic!("expected fmt");
+        };
+        assert!(fmt.check);
+        assert!(fmt.output.sort_keys);
+        assert_eq!(fmt.output.indent, Some(4));
+        assert_eq!(fmt.patterns.len(), 2);
+
+        assert!(parse_command(args(&["fmt", "a.json"])).is_err());
+        assert!(parse_command(args(&["fmt", "--write", "--check", "a.json"])).is_err());
+        assert!(parse_command(args(&["fmt", "--write"])).is_err());
+        assert!(parse_command(args(&["fmt", "--write", "--indent", "x", "a.json"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_convert_args() {
+        assert_eq!(
+            parse_command(args(&["convert", "--to", "yaml", "data.json"])).unwrap(),
+            Command::Convert(ConvertArgs {
+                path: "data.json".to_string(),
+                format: Format::Yaml,
+            })
+        );
+        assert_eq!(
+            parse_command(args(&["convert", "--to", "csv"])).unwrap(),
+            Command::Convert(ConvertArgs {
+                path: STDIN_PATH.to_string(),
+                format: Format::Csv,
+            })
+        );
+        assert!(parse_command(args(&["convert", "data.json"])).is_err());
+        assert!(parse_command(args(&["convert", "--to", "xml", "data.json"])).is_err());
+    }
+
+    #[test]
+    fn test_validate_files() {
+        let dir = env::temp_dir().join(format!("parse_file_check_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let good = dir.join("good.json");
+        let bad = dir.join("bad.json");
+        fs::write(&good, "[1, 2]").unwrap();
+        fs::write(&bad, "[1,\n 2").unwrap();
+        let missing = dir.join("missing.json");
+
+        let results = map_files_parallel(&[good, bad, missing], false, validate_file);
+
+        assert!(matches!(results[0], Some(Ok(()))));
+        let Some(Err(failure)) = &results[1] else {
+            panic!("expected a failure");
+        };
+        assert!(matches!(
+            failure.error,
+            JsonError::UnexpectedEndOfInput { .. }
+        ));
+        assert_eq!(failure.line_column, Some((2, 3)));
+        assert!(matches!(
+            &results[2],
+            Some(Err(Failure {
+                error: JsonError::Io { .. },
+                line_column: None,
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_expand_patterns_rejects_empty_globs() {
+        let dir = env::temp_dir().join(format!("parse_file_globs_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a.json");
+        fs::write(&file, "{}").unwrap();
+        let pattern = |name: &str| dir.join(name).to_string_lossy().into_owned();
+
+        assert_eq!(
+            expand_patterns(&[pattern("*.json")]),
+            Ok(vec![file.clone()])
+        );
+        let empty = pattern("*.yaml");
+        assert_eq!(
+            expand_patterns(&[pattern("*.json"), empty.clone()]),
+            Err(format!("{}: no files match", empty))
+        );
+        // Plain paths are kept, so a missing file is reported when it is read
+        assert_eq!(
+            expand_patterns(&[pattern("missing.json")]),
+            Ok(vec![dir.join("missing.json")])
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
 
-    match parse_json(&contents) {
-        Ok(value) => println!("{}", value),
-        Err(e) => eprintln!("Parse error: {:?}", e),
+    #[test]
+    fn test_exit_codes_by_failure_class() {
+        let io = JsonError::Io {
+            message: "denied".to_string(),
+        };
+        assert_eq!(exit_code_for(&io), EXIT_IO_ERROR);
+        let syntax = parse_json("[1,").unwrap_err();
+        assert_eq!(exit_code_for(&syntax), EXIT_INVALID_JSON);
     }
 }
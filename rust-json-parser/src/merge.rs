@@ -0,0 +1,286 @@
+//! Deep merging of documents, such as configuration defaults and overrides.
+//!
+//! [`JsonValue::merge`] combines objects member by member, recursively. A
+//! [`MergeStrategy`] decides what happens where both documents have a value
+//! that is not an object on both sides: arrays can be replaced or concatenated,
+//! and other conflicts are resolved in favour of either side.
+//!
+//! [`concat_documents`] combines whole documents instead, such as the shards
+//! written by [`split_to_files`](crate::files::split_to_files): arrays are
+//! concatenated and objects are united under a [`ConcatStrategy`].
+
+use crate::value::JsonValue;
+use crate::{JsonError, JsonResult};
+
+/// How [`JsonValue::merge`] resolves values present on both sides.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, ArrayMerge, MergeStrategy};
+///
+/// let mut plugins = parse_json(r#"{"plugins": ["lint", "fmt"]}"#)?;
+/// let strategy = MergeStrategy {
+///     arrays: ArrayMerge::ConcatUnique,
+///     ..MergeStrategy::default()
+/// };
+/// plugins.merge(parse_json(r#"{"plugins": ["fmt", "test"]}"#)?, strategy);
+/// assert_eq!(plugins, parse_json(r#"{"plugins": ["lint", "fmt", "test"]}"#)?);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeStrategy {
+    /// What to do when both sides have an array.
+    pub arrays: ArrayMerge,
+    /// Which side wins any other conflict: two scalars, values of different
+    /// types, or two arrays under [`ArrayMerge::Replace`].
+    pub prefer: MergePreference,
+}
+
+/// How two arrays are merged. See [`MergeStrategy::arrays`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// Keep one of the arrays, chosen by [`MergeStrategy::prefer`].
+    #[default]
+    Replace,
+    /// Append the elements of the other array.
+    Concat,
+    /// Append the elements of the other array, then drop elements equal to an
+    /// earlier one (see [`JsonValue::dedup_array_in_place`]).
+    ConcatUnique,
+}
+
+/// Which side wins a conflict. See [`MergeStrategy::prefer`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergePreference {
+    /// The value being merged in wins, as when overrides are merged into
+    /// defaults.
+    #[default]
+    Right,
+    /// The existing value wins, as when defaults are merged into overrides.
+    Left,
+}
+
+/*
+ * Merges `other` into `target`. Called through `JsonValue::merge`.
+ */
+pub(crate) fn deep_merge(target: &mut JsonValue, other: JsonValue, strategy: MergeStrategy) {
+    match (target, other) {
+        (JsonValue::Object(members), JsonValue::Object(other_members)) => {
+            for (key, value) in other_members {
+                match members.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value, strategy),
+                    None => {
+                        members.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target @ JsonValue::Array(_), JsonValue::Array(other_items))
+            if strategy.arrays != ArrayMerge::Replace =>
+        {
+            let JsonValue::Array(items) = target else {
+                unreachable!("matched as an array");
+            };
+            items.extend(other_items);
+            if strategy.arrays == ArrayMerge::ConcatUnique {
+                target.dedup_array_in_place();
+            }
+        }
+        (target, other) => {
+            if strategy.prefer == MergePreference::Right {
+                *target = other;
+            }
+        }
+    }
+}
+
+/// How [`concat_documents`] resolves a key present in several objects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConcatStrategy {
+    /// Fail with [`JsonError::InvalidConcat`], since shards are expected to
+    /// have disjoint keys.
+    #[default]
+    Reject,
+    /// Keep the value from the first document with the key.
+    KeepFirst,
+    /// Keep the value from the last document with the key.
+    KeepLast,
+    /// Merge the values with [`JsonValue::merge`], in document order.
+    Merge(MergeStrategy),
+}
+
+/// Combines several documents into one: arrays are concatenated in order and
+/// objects are united, with `strategy` deciding what happens to a key present in
+/// more than one of them.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{ConcatStrategy, concat_documents, parse_json};
+///
+/// let rows = concat_documents([parse_json("[1, 2]")?, parse_json("[3]")?], ConcatStrategy::Reject)?;
+/// assert_eq!(rows, parse_json("[1, 2, 3]")?);
+///
+/// let shards = [parse_json(r#"{"a": 1, "b": 2}"#)?, parse_json(r#"{"b": 3}"#)?];
+/// let config = concat_documents(shards, ConcatStrategy::KeepLast)?;
+/// assert_eq!(config, parse_json(r#"{"a": 1, "b": 3}"#)?);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::InvalidConcat`] if there are no documents, if the first
+/// document is neither an array nor an object, if a later one does not have the
+/// same type as the first, or if two objects share a key under
+/// [`ConcatStrategy::Reject`]. `index` is the position of the offending
+/// document.
+pub fn concat_documents<I>(values: I, strategy: ConcatStrategy) -> JsonResult<JsonValue>
+where
+    I: IntoIterator<Item = JsonValue>,
+{
+    let invalid = |index: usize, message: String| JsonError::InvalidConcat { index, message };
+    let mut values = values.into_iter();
+    let mut combined = values
+        .next()
+        .ok_or_else(|| invalid(0, "no documents were given".to_string()))?;
+    if !matches!(combined, JsonValue::Array(_) | JsonValue::Object(_)) {
+        return Err(invalid(0, "expected an array or an object".to_string()));
+    }
+
+    for (index, value) in values.enumerate().map(|(i, value)| (i + 1, value)) {
+        match (&mut combined, value) {
+            (JsonValue::Array(items), JsonValue::Array(more)) => items.extend(more),
+            (JsonValue::Object(members), JsonValue::Object(more)) => {
+                for (key, value) in more {
+                    match (members.get_mut(&key), strategy) {
+                        (None, _) | (Some(_), ConcatStrategy::KeepLast) => {
+                            members.insert(key, value);
+                        }
+                        (Some(_), ConcatStrategy::KeepFirst) => {}
+                        (Some(existing), ConcatStrategy::Merge(merge)) => {
+                            deep_merge(existing, value, merge)
+                        }
+                        (Some(_), ConcatStrategy::Reject) => {
+                            return Err(invalid(index, format!("duplicate key {:?}", key)));
+                        }
+                    }
+                }
+            }
+            (JsonValue::Array(_), _) => {
+                return Err(invalid(
+                    index,
+                    "expected an array like the first document".to_string(),
+                ));
+            }
+            _ => {
+                return Err(invalid(
+                    index,
+                    "expected an object like the first document".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(combined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    fn merged(left: &str, right: &str, strategy: MergeStrategy) -> JsonValue {
+        let mut value = parse_json(left).unwrap();
+        value.merge(parse_json(right).unwrap(), strategy);
+        value
+    }
+
+    #[test]
+    fn test_merge_layers_objects() {
+        let defaults = r#"{"server": {"port": 80, "tls": {"enabled": false}}, "tags": ["a"]}"#;
+        let overrides = r#"{"server": {"tls": {"enabled": true, "cert": "x.pem"}}, "tags": ["b"],
+                            "debug": null}"#;
+        assert_eq!(
+            merged(defaults, overrides, MergeStrategy::default()),
+            parse_json(
+                r#"{"server": {"port": 80, "tls": {"enabled": true, "cert": "x.pem"}},
+                    "tags": ["b"], "debug": null}"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let left = r#"{"n": 1, "list": [1, 2], "kind": {"a": 1}}"#;
+        let right = r#"{"n": 2, "list": [2, 3], "kind": "flat"}"#;
+        let cases = [
+            (
+                ArrayMerge::Replace,
+                MergePreference::Left,
+                r#"{"n": 1, "list": [1, 2], "kind": {"a": 1}}"#,
+            ),
+            (
+                ArrayMerge::Concat,
+                MergePreference::Right,
+                r#"{"n": 2, "list": [1, 2, 2, 3], "kind": "flat"}"#,
+            ),
+            (
+                ArrayMerge::ConcatUnique,
+                MergePreference::Left,
+                r#"{"n": 1, "list": [1, 2, 3], "kind": {"a": 1}}"#,
+            ),
+        ];
+        for (arrays, prefer, expected) in cases {
+            let strategy = MergeStrategy { arrays, prefer };
+            assert_eq!(
+                merged(left, right, strategy),
+                parse_json(expected).unwrap(),
+                "{:?}",
+                strategy
+            );
+        }
+    }
+
+    #[test]
+    fn test_concat_documents() {
+        let docs = |list: &[&str]| {
+            list.iter()
+                .map(|s| parse_json(s).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            concat_documents(docs(&["[1]", "[]", "[2, [3]]"]), ConcatStrategy::Reject).unwrap(),
+            parse_json("[1, 2, [3]]").unwrap()
+        );
+
+        let shards = docs(&[r#"{"a": {"x": 1}, "b": 1}"#, r#"{"a": {"y": 2}}"#]);
+        let cases = [
+            (ConcatStrategy::KeepFirst, r#"{"a": {"x": 1}, "b": 1}"#),
+            (ConcatStrategy::KeepLast, r#"{"a": {"y": 2}, "b": 1}"#),
+            (
+                ConcatStrategy::Merge(MergeStrategy::default()),
+                r#"{"a": {"x": 1, "y": 2}, "b": 1}"#,
+            ),
+        ];
+        for (strategy, expected) in cases {
+            assert_eq!(
+                concat_documents(shards.clone(), strategy).unwrap(),
+                parse_json(expected).unwrap(),
+                "{:?}",
+                strategy
+            );
+        }
+
+        let concat_index = |err: JsonError| match err {
+            JsonError::InvalidConcat { index, .. } => index,
+            other => panic!("unexpected error: {:?}", other),
+        };
+        let rejected = concat_documents(shards, ConcatStrategy::Reject).unwrap_err();
+        assert_eq!(concat_index(rejected), 1);
+        for (list, index) in [(&["[1]", "{}"][..], 1), (&["1", "2"], 0), (&[], 0)] {
+            let err = concat_documents(docs(list), ConcatStrategy::Reject).unwrap_err();
+            assert_eq!(concat_index(err), index, "{:?}", list);
+        }
+    }
+}
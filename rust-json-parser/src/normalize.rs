@@ -0,0 +1,142 @@
+//! Normalization of documents into a canonical shape, for content-addressed
+//! caches and snapshot tests that must not depend on incidental details of the
+//! input.
+//!
+//! [`JsonValue::normalize`] rewrites a tree in place as chosen by
+//! [`NormalizeOptions`]. Object members have no order in a [`JsonMap`](crate::JsonMap)
+//! (unless the `btreemap` feature is enabled), so keys are sorted when the
+//! normalized value is serialized: use [`JsonValue::to_canonical_string`] or
+//! [`SerializeOptions::sorted`](crate::SerializeOptions::sorted) to get the same
+//! text for equal documents.
+
+use crate::value::JsonValue;
+
+/// What [`JsonValue::normalize`] changes. Every step is off by default.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, NormalizeOptions};
+///
+/// let mut value = parse_json(r#"{"tags": ["b", "a", "b"], "note": null}"#)?;
+/// value.normalize(&NormalizeOptions {
+///     remove_nulls: true,
+///     ..NormalizeOptions::default()
+/// });
+/// assert_eq!(value, parse_json(r#"{"tags": ["b", "a", "b"]}"#)?);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Write numbers in a single form: integral values such as `1.0` or `1e2`
+    /// become integers, and literals kept by
+    /// [`ParserOptions::preserve_number_text`](crate::ParserOptions::preserve_number_text)
+    /// are dropped.
+    pub canonical_numbers: bool,
+    /// Remove repeated elements from every array, keeping the first occurrence
+    /// (see [`JsonValue::dedup_array_in_place`]).
+    pub dedup_arrays: bool,
+    /// Remove object members whose value is `null`. `null` array elements are
+    /// kept, since removing them would shift the elements after them.
+    pub remove_nulls: bool,
+}
+
+impl NormalizeOptions {
+    /// Returns options with every step enabled.
+    pub fn all() -> Self {
+        Self {
+            canonical_numbers: true,
+            dedup_arrays: true,
+            remove_nulls: true,
+        }
+    }
+}
+
+impl JsonValue {
+    /// Normalizes this value and everything nested in it, as chosen by
+    /// `options`. Children are normalized before their parent, so arrays are
+    /// deduplicated after their elements' numbers and `null` members have been
+    /// normalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, NormalizeOptions};
+    ///
+    /// let mut a = parse_json(r#"{"ids": [1.0, 2, 1], "x": null, "y": {"z": 1e1}}"#)?;
+    /// let mut b = parse_json(r#"{"y": {"z": 10}, "ids": [1, 2]}"#)?;
+    /// a.normalize(&NormalizeOptions::all());
+    /// b.normalize(&NormalizeOptions::all());
+    /// assert_eq!(a.to_canonical_string(), b.to_canonical_string());
+    /// assert_eq!(a.to_canonical_string().as_deref(), Some(r#"{"ids":[1,2],"y":{"z":10}}"#));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn normalize(&mut self, options: &NormalizeOptions) {
+        match self {
+            JsonValue::Number(n) if options.canonical_numbers => n.normalize(),
+            JsonValue::Array(items) => {
+                for item in items.iter_mut() {
+                    item.normalize(options);
+                }
+                if options.dedup_arrays {
+                    self.dedup_array_in_place();
+                }
+            }
+            JsonValue::Object(members) => {
+                for member in members.values_mut() {
+                    member.normalize(options);
+                }
+                if options.remove_nulls {
+                    members.retain(|_, member| !member.is_null());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParserOptions, parse_json, parse_json_with_options};
+
+    #[test]
+    fn test_canonical_numbers() {
+        let options = ParserOptions {
+            preserve_number_text: true,
+            ..ParserOptions::default()
+        };
+        let mut value =
+            parse_json_with_options("[1.0, 1e2, -0.0, -2.50, 0.5, 2.5e-1, -3E0]", &options)
+                .unwrap();
+        value.normalize(&NormalizeOptions {
+            canonical_numbers: true,
+            ..NormalizeOptions::default()
+        });
+        assert_eq!(value.to_string(), "[1,100,0,-2.5,0.5,0.25,-3]");
+        assert_eq!(value.get_index(0).and_then(JsonValue::as_u64), Some(1));
+        assert_eq!(value.get_index(6).and_then(JsonValue::as_i64), Some(-3));
+    }
+
+    #[test]
+    fn test_dedup_and_remove_nulls_recurse() {
+        let mut value =
+            parse_json(r#"[{"a": [1, 1, null], "b": null}, {"a": [1, null]}, {"c": {"d": null}}]"#)
+                .unwrap();
+        value.normalize(&NormalizeOptions::all());
+        assert_eq!(
+            value,
+            parse_json(r#"[{"a": [1, null]}, {"c": {}}]"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_default_options_change_nothing() {
+        let input = r#"{"a": [2, 2], "b": null, "c": 1.0}"#;
+        let mut value = parse_json(input).unwrap();
+        value.normalize(&NormalizeOptions::default());
+        assert_eq!(value, parse_json(input).unwrap());
+        assert_eq!(value.get("a").map(JsonValue::len), Some(2));
+        assert!(value.contains_key("b"));
+    }
+}
@@ -0,0 +1,453 @@
+//! Lossless representation of JSON numbers.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/*
+ * The numeric value of a `JsonNumber`. Non-negative integers are always stored
+ * as `PosInt` and negative ones as `NegInt`, so every integer has a single
+ * representation.
+ */
+#[derive(Debug, Clone, Copy)]
+enum N {
+    PosInt(u64),
+    NegInt(i64),
+    Float(f64),
+}
+
+/// A JSON number that keeps integers exact.
+///
+/// Integer literals that fit in an `i64` or `u64` are stored as integers, so IDs
+/// such as `9007199254740993` survive a parse/serialize round trip unchanged.
+/// All other numbers are stored as `f64`. When parsed with
+/// [`ParserOptions::preserve_number_text`](crate::ParserOptions::preserve_number_text),
+/// the original literal is kept as well and reproduced verbatim on output.
+///
+/// Numbers compare by value: `1`, `1.0` and `1e0` are equal.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, JsonNumber, JsonValue};
+///
+/// let value = parse_json("[9007199254740993, -7, 2.5]")?;
+/// assert_eq!(value.to_string(), "[9007199254740993,-7,2.5]");
+///
+/// let id = JsonNumber::from(9007199254740993u64);
+/// assert_eq!(id.as_u64(), Some(9007199254740993));
+/// assert_eq!(JsonNumber::from(1), JsonNumber::from(1.0));
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonNumber {
+    n: N,
+    raw: Option<Box<str>>,
+}
+
+/*
+ * Canonical form used for equality and hashing: integral floats compare equal
+ * to the matching integer.
+ */
+#[derive(PartialEq)]
+enum Canonical {
+    Int(i128),
+    Float(f64),
+}
+
+impl JsonNumber {
+    /*
+     * Parses a JSON numeric literal that has already been validated by the
+     * tokenizer. Integers that overflow 64 bits fall back to `f64`.
+     */
+    pub(crate) fn parse_literal(literal: &str, keep_raw: bool) -> Option<Self> {
+        let is_integer = !literal.contains(['.', 'e', 'E']);
+        let n = if is_integer {
+            match literal.parse::<u64>() {
+                Ok(u) => Some(N::PosInt(u)),
+                // `-0` is not a valid u64 literal but is still zero
+                Err(_) => literal.parse::<i64>().ok().map(|i| match i {
+                    0 => N::PosInt(0),
+                    i => N::NegInt(i),
+                }),
+            }
+        } else {
+            None
+        };
+        let n = match n {
+            Some(n) => n,
+            None => N::Float(literal.parse::<f64>().ok()?),
+        };
+        Some(Self {
+            n,
+            raw: keep_raw.then(|| literal.into()),
+        })
+    }
+
+    /*
+     * Builds a number from the decimal digits of an integer of any size, such
+     * as a Python `int`, keeping the digits as its literal so it serializes
+     * exactly. Beyond `f64` range, the value saturates at `f64::MAX` rather than
+     * becoming infinite, which would be written as `null`.
+     */
+    #[cfg(any(test, feature = "python"))]
+    pub(crate) fn from_integer_text(digits: &str) -> Option<Self> {
+        let mut number = Self::parse_literal(digits, true)?;
+        if let N::Float(f) = &mut number.n
+            && f.is_infinite()
+        {
+            *f = f64::MAX.copysign(*f);
+        }
+        Some(number)
+    }
+
+    /// Returns the value as an `i64` if it is an integer within range.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::PosInt(u) => i64::try_from(u).ok(),
+            N::NegInt(i) => Some(i),
+            N::Float(_) => None,
+        }
+    }
+
+    /// Returns the value as a `u64` if it is a non-negative integer within range.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            N::PosInt(u) => Some(u),
+            N::NegInt(_) | N::Float(_) => None,
+        }
+    }
+
+    /// Returns the value as an `f64`. Integers beyond 2^53 may lose precision.
+    pub fn as_f64(&self) -> f64 {
+        match self.n {
+            N::PosInt(u) => u as f64,
+            N::NegInt(i) => i as f64,
+            N::Float(f) => f,
+        }
+    }
+
+    /// Returns `true` if the number is stored as an integer representable as `i64`.
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    /// Returns `true` if the number is stored as a non-negative integer.
+    pub fn is_u64(&self) -> bool {
+        matches!(self.n, N::PosInt(_))
+    }
+
+    /// Returns `true` if the number is stored as an `f64` (it had a fraction or an
+    /// exponent, or did not fit in 64 bits).
+    pub fn is_f64(&self) -> bool {
+        matches!(self.n, N::Float(_))
+    }
+
+    /// Returns the literal this number was parsed from, if it was retained.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json_with_options, ParserOptions};
+    ///
+    /// let options = ParserOptions {
+    ///     preserve_number_text: true,
+    ///     ..ParserOptions::default()
+    /// };
+    /// let value = parse_json_with_options("1.50e2", &options)?;
+    /// assert_eq!(value.as_number().and_then(|n| n.raw()), Some("1.50e2"));
+    /// assert_eq!(value.to_string(), "1.50e2");
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    /*
+     * Drops the retained literal, as if the number had been parsed without
+     * `preserve_number_text`.
+     */
+    pub(crate) fn clear_raw(&mut self) {
+        self.raw = None;
+    }
+
+    /*
+     * Drops the retained literal and stores integral floats that fit in an
+     * `i64` or `u64` as integers, so numbers that compare equal also serialize
+     * identically (`1.0`, `1e0` and `1` all become `1`, and `-0.0` becomes `0`).
+     */
+    pub(crate) fn normalize(&mut self) {
+        self.raw = None;
+        if let N::Float(f) = self.n
+            && f.fract() == 0.0
+        {
+            // `u64::MAX as f64` is 2^64, which is already out of range
+            if (0.0..u64::MAX as f64).contains(&f) || f == 0.0 {
+                self.n = N::PosInt(f as u64);
+            } else if (i64::MIN as f64..0.0).contains(&f) {
+                self.n = N::NegInt(f as i64);
+            }
+        }
+    }
+
+    /*
+     * Returns how `SerializeOptions::allow_nan` writes a NaN or infinite
+     * number: its source text if kept, otherwise `NaN`, `Infinity` or
+     * `-Infinity`. Returns `None` for finite numbers.
+     */
+    pub(crate) fn non_finite_text(&self) -> Option<&str> {
+        match self.n {
+            N::Float(n) if n.is_finite() => None,
+            N::Float(_) if self.raw.is_some() => self.raw.as_deref(),
+            N::Float(n) if n.is_nan() => Some("NaN"),
+            N::Float(n) if n > 0.0 => Some("Infinity"),
+            N::Float(_) => Some("-Infinity"),
+            N::PosInt(_) | N::NegInt(_) => None,
+        }
+    }
+
+    /*
+     * Formats the number as ECMAScript's `Number.prototype.toString` formats the
+     * nearest `f64`, as required by RFC 8785. Returns `None` for NaN and
+     * infinities, which have no JSON representation.
+     */
+    pub(crate) fn to_ecmascript_string(&self) -> Option<String> {
+        let value = self.as_f64();
+        if !value.is_finite() {
+            return None;
+        }
+        if value == 0.0 {
+            return Some("0".to_string());
+        }
+        // `{:e}` yields the shortest digits that round-trip, e.g. `-1.2345e-7`
+        let scientific = format!("{:e}", value.abs());
+        let (mantissa, exponent) = scientific.split_once('e')?;
+        let digits = mantissa.replace('.', "");
+        let k = digits.len() as i32;
+        let n = exponent.parse::<i32>().ok()? + 1;
+        let sign = if value < 0.0 { "-" } else { "" };
+
+        let body = if k <= n && n <= 21 {
+            format!("{}{}", digits, "0".repeat((n - k) as usize))
+        } else if 0 < n && n <= 21 {
+            format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+        } else if -6 < n && n <= 0 {
+            format!("0.{}{}", "0".repeat(-n as usize), digits)
+        } else {
+            let fraction = if k > 1 {
+                format!(".{}", &digits[1..])
+            } else {
+                String::new()
+            };
+            let exponent_sign = if n > 0 { "+" } else { "-" };
+            format!(
+                "{}{}e{}{}",
+                &digits[..1],
+                fraction,
+                exponent_sign,
+                (n - 1).abs()
+            )
+        };
+        Some(format!("{}{}", sign, body))
+    }
+
+    fn canonical(&self) -> Canonical {
+        match self.n {
+            N::PosInt(u) => Canonical::Int(u.into()),
+            N::NegInt(i) => Canonical::Int(i.into()),
+            // Integral floats within i128 range compare equal to integers; the
+            // range check also rules out infinities and NaN
+            N::Float(f) if f.fract() == 0.0 && f.abs() < 1e38 => Canonical::Int(f as i128),
+            N::Float(f) => Canonical::Float(f),
+        }
+    }
+}
+
+impl PartialEq for JsonNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+impl Hash for JsonNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.canonical() {
+            Canonical::Int(i) => (0u8, i).hash(state),
+            Canonical::Float(f) => (1u8, f.to_bits()).hash(state),
+        }
+    }
+}
+
+/// Writes the number as JSON, as [`SerializeOptions::default`] does: NaN and
+/// infinite numbers, which JSON cannot represent, are written as `null`.
+///
+/// [`SerializeOptions::default`]: crate::SerializeOptions::default
+impl fmt::Display for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.non_finite_text().is_some() {
+            return f.write_str("null");
+        }
+        if let Some(raw) = &self.raw {
+            return f.write_str(raw);
+        }
+        match self.n {
+            N::PosInt(u) => write!(f, "{}", u),
+            N::NegInt(i) => write!(f, "{}", i),
+            N::Float(n) if n.trunc() == n => write!(f, "{}", n.trunc()),
+            N::Float(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+macro_rules! impl_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for JsonNumber {
+                fn from(value: $t) -> Self {
+                    let n = match u64::try_from(value) {
+                        Ok(u) => N::PosInt(u),
+                        Err(_) => N::NegInt(value as i64),
+                    };
+                    Self { n, raw: None }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl From<f64> for JsonNumber {
+    fn from(value: f64) -> Self {
+        Self {
+            n: N::Float(value),
+            raw: None,
+        }
+    }
+}
+
+impl From<f32> for JsonNumber {
+    fn from(value: f32) -> Self {
+        f64::from(value).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(s: &str) -> JsonNumber {
+        JsonNumber::parse_literal(s, false).unwrap()
+    }
+
+    #[test]
+    fn test_integers_are_exact() {
+        assert_eq!(literal("9007199254740993").as_u64(), Some(9007199254740993));
+        assert_eq!(literal("-9223372036854775808").as_i64(), Some(i64::MIN));
+        assert_eq!(literal("18446744073709551615").as_u64(), Some(u64::MAX));
+        assert!(literal("42").is_u64());
+        assert!(literal("-42").is_i64());
+        assert!(!literal("-42").is_u64());
+    }
+
+    #[test]
+    fn test_floats_and_overflow() {
+        assert!(literal("2.5").is_f64());
+        assert!(literal("1e3").is_f64());
+        assert_eq!(literal("1e3").as_i64(), None);
+        // Does not fit in 64 bits
+        let big = literal("18446744073709551616");
+        assert!(big.is_f64());
+        assert_eq!(big.as_f64(), 18446744073709551616.0);
+    }
+
+    #[test]
+    fn test_ecmascript_formatting() {
+        let cases = [
+            ("0", "0"),
+            ("-0.0", "0"),
+            ("1", "1"),
+            ("-1.5", "-1.5"),
+            ("9007199254740993", "9007199254740992"),
+            ("1e21", "1e+21"),
+            ("123456789012345680000", "123456789012345680000"),
+            ("0.000001", "0.000001"),
+            ("0.0000001", "1e-7"),
+            ("1.5e-7", "1.5e-7"),
+            ("5e-324", "5e-324"),
+            ("1.7976931348623157e308", "1.7976931348623157e+308"),
+            ("123.456", "123.456"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                literal(input).to_ecmascript_string().as_deref(),
+                Some(expected),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(JsonNumber::from(f64::INFINITY).to_ecmascript_string(), None);
+    }
+
+    #[test]
+    fn test_equality_is_numeric() {
+        assert_eq!(literal("1"), literal("1.0"));
+        assert_eq!(literal("100"), literal("1e2"));
+        assert_eq!(JsonNumber::from(-3), JsonNumber::from(-3.0));
+        assert_eq!(JsonNumber::from(0.0), JsonNumber::from(-0.0));
+        assert_ne!(literal("1"), literal("1.5"));
+        assert_ne!(literal("9007199254740993"), literal("9007199254740992"));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(literal("9007199254740993").to_string(), "9007199254740993");
+        assert_eq!(literal("-7").to_string(), "-7");
+        assert_eq!(literal("2.50").to_string(), "2.5");
+        assert_eq!(literal("1.0").to_string(), "1");
+        assert_eq!(
+            JsonNumber::parse_literal("2.50", true).unwrap().to_string(),
+            "2.50"
+        );
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(JsonNumber::from(f).to_string(), "null");
+        }
+        assert_eq!(
+            JsonNumber::parse_literal("1e400", true)
+                .unwrap()
+                .to_string(),
+            "null"
+        );
+    }
+
+    #[test]
+    fn test_from_integer_text() {
+        let big = JsonNumber::from_integer_text("1180591620717411303424").unwrap();
+        assert_eq!(big.to_string(), "1180591620717411303424");
+        assert_eq!(big.as_f64(), 1180591620717411303424.0);
+        let huge = format!("-1{}", "0".repeat(400));
+        let huge = JsonNumber::from_integer_text(&huge).unwrap();
+        assert_eq!(huge.to_string(), format!("-1{}", "0".repeat(400)));
+        assert_eq!(huge.as_f64(), -f64::MAX);
+        assert_eq!(
+            JsonNumber::from_integer_text("12"),
+            Some(JsonNumber::from(12))
+        );
+    }
+
+    #[test]
+    fn test_non_finite_text() {
+        assert_eq!(JsonNumber::from(f64::NAN).non_finite_text(), Some("NaN"));
+        assert_eq!(
+            JsonNumber::from(f64::NEG_INFINITY).non_finite_text(),
+            Some("-Infinity")
+        );
+        assert_eq!(
+            JsonNumber::parse_literal("-1e400", true)
+                .unwrap()
+                .non_finite_text(),
+            Some("-1e400")
+        );
+        assert_eq!(literal("1.5").non_finite_text(), None);
+        assert_eq!(literal("7").non_finite_text(), None);
+    }
+}
@@ -0,0 +1,262 @@
+//! Configuration for the parsing entry points.
+
+/// Default for [`ParserOptions::max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Default for [`ParserOptions::max_number_length`].
+pub const DEFAULT_MAX_NUMBER_LENGTH: usize = 1024;
+
+/// Default for [`ParserOptions::max_exponent`].
+pub const DEFAULT_MAX_EXPONENT: u32 = 1000;
+
+/// Controls how JSON input is parsed by
+/// [`parse_json_with_options`](crate::parse_json_with_options) and
+/// [`JsonParser::with_options`](crate::JsonParser::with_options).
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json_with_options, JsonError, ParserOptions};
+///
+/// let options = ParserOptions {
+///     max_depth: 2,
+///     ..ParserOptions::default()
+/// };
+/// assert!(parse_json_with_options("[[1]]", &options).is_ok());
+/// assert!(matches!(
+///     parse_json_with_options("[[[1]]]", &options),
+///     Err(JsonError::DepthLimitExceeded { .. })
+/// ));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserOptions {
+    /// Maximum number of nested arrays and objects. Deeper input is rejected with
+    /// [`JsonError::DepthLimitExceeded`](crate::JsonError::DepthLimitExceeded),
+    /// which protects against maliciously nested documents.
+    ///
+    /// [`parse_json`](crate::parse_json) itself does not recurse, but dropping,
+    /// cloning, comparing and formatting a [`JsonValue`](crate::JsonValue) take
+    /// stack space for every level, so a value nested a few thousand levels deep
+    /// can overflow the stack. When raising this limit far above the default,
+    /// free such values with
+    /// [`JsonValuePool::recycle`](crate::JsonValuePool::recycle), which does not
+    /// recurse.
+    pub max_depth: usize,
+    /// Keep the source text of every number literal, so serializing the parsed
+    /// value reproduces it verbatim (e.g. `1.50` rather than `1.5`). See
+    /// [`JsonNumber::raw`](crate::JsonNumber::raw). This also accepts literals
+    /// too large for `f64` such as `1e400`, whose text is kept even though
+    /// their `f64` value is infinity.
+    pub preserve_number_text: bool,
+    /// Maximum length in bytes of a number literal. Longer literals are rejected
+    /// with [`JsonError::NumberOutOfRange`](crate::JsonError::NumberOutOfRange)
+    /// instead of being rounded to the nearest `f64`.
+    pub max_number_length: usize,
+    /// Maximum absolute value of the exponent written in a number literal (the
+    /// `400` in `1e400`). Larger exponents are rejected with
+    /// [`JsonError::NumberOutOfRange`](crate::JsonError::NumberOutOfRange)
+    /// instead of silently becoming zero. Literals whose value overflows `f64`
+    /// are rejected the same way regardless of this limit, unless
+    /// `preserve_number_text` or `allow_nan` is set.
+    pub max_exponent: u32,
+    /// Maximum length in bytes of a string or object key, counted as written in
+    /// the input between the quotes (so escape sequences count in full). Longer
+    /// strings are rejected with
+    /// [`JsonError::StringLengthExceeded`](crate::JsonError::StringLengthExceeded).
+    /// Unlimited by default.
+    pub max_string_length: usize,
+    /// Maximum number of values in a document, counting every string, number,
+    /// boolean, `null`, array and object but not object keys. Larger documents
+    /// are rejected with
+    /// [`JsonError::ValueCountExceeded`](crate::JsonError::ValueCountExceeded) as
+    /// soon as the first value over the limit starts. Unlimited by default.
+    pub max_values: usize,
+    /// Maximum size of the input in bytes, checked before anything is parsed.
+    /// Larger inputs are rejected with
+    /// [`JsonError::InputSizeExceeded`](crate::JsonError::InputSizeExceeded).
+    /// Unlimited by default.
+    pub max_input_size: usize,
+    /// Enforce the RFC 8259 grammar (the default). Malformed numbers such as
+    /// `0123`, `-`, `1.` or `1e2e3` are rejected with
+    /// [`JsonError::InvalidNumber`](crate::JsonError::InvalidNumber) pointing at
+    /// the offending byte, any stray character outside strings with
+    /// [`JsonError::UnexpectedToken`](crate::JsonError::UnexpectedToken), and raw
+    /// control characters inside strings with
+    /// [`JsonError::ControlCharacter`](crate::JsonError::ControlCharacter).
+    ///
+    /// When `false`, the lenient behaviour of earlier releases is kept: numbers
+    /// are accepted if `f64` parsing understands them, unrecognized
+    /// non-punctuation bytes between tokens are skipped and control characters
+    /// are kept in strings as they are.
+    pub strict: bool,
+    /// Skip `// line` and `/* block */` comments between tokens.
+    pub allow_comments: bool,
+    /// Accept a comma after the last member of an array or object, as in `[1, 2,]`.
+    pub allow_trailing_commas: bool,
+    /// Accept strings delimited by single quotes, as in `'it\'s'`.
+    pub allow_single_quotes: bool,
+    /// Accept object keys written as bare identifiers (letters, digits, `_` and
+    /// `$`, not starting with a digit), as in `{name: "Ann"}`. The words `true`,
+    /// `false` and `null` must still be quoted when used as keys.
+    pub allow_unquoted_keys: bool,
+    /// Accept the bare words `NaN`, `Infinity` and `-Infinity` as numbers, as
+    /// written by Python's `json.dumps` with its default settings. Write them back
+    /// with [`SerializeOptions::allow_nan`](crate::SerializeOptions::allow_nan).
+    /// When unquoted keys are also allowed, these words must still be quoted
+    /// when used as keys. This also accepts literals too large for `f64` such as
+    /// `1e400`, which are read as infinity.
+    pub allow_nan: bool,
+    /// Accept integers written in hexadecimal (`0x1F`) or binary (`0b1010`),
+    /// optionally preceded by `-`. They are converted to ordinary numbers, so
+    /// serializing the parsed value writes them in decimal.
+    pub allow_hex_and_binary: bool,
+    /// Accept raw strings delimited by backticks (`` `...` ``) or triple quotes
+    /// (`"""..."""`). They may span several lines and are taken verbatim:
+    /// backslashes are not treated as escapes and control characters such as
+    /// newlines are kept. Serializing the parsed value writes ordinary, escaped
+    /// JSON strings.
+    pub allow_multiline_strings: bool,
+    /// What to do when an object contains the same key more than once.
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// What to do with a backslash followed by a character JSON does not define
+    /// an escape for, such as `\x41` or `\'` (which is valid when single quotes
+    /// are allowed).
+    pub unknown_escapes: UnknownEscapePolicy,
+    /// Whether equal object keys share one allocation. Keys are interned for
+    /// the whole document (and, in a [`JsonStream`](crate::JsonStream), across
+    /// documents), which saves memory when many objects have the same shape,
+    /// such as the rows of a large array. Requires the `shared-keys` feature.
+    #[cfg(feature = "shared-keys")]
+    pub intern_keys: bool,
+}
+
+/// How repeated keys within one JSON object are handled. See
+/// [`ParserOptions::duplicate_keys`].
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json_with_options, DuplicateKeyPolicy, JsonValue, ParserOptions};
+///
+/// let input = r#"{"a": 1, "a": 2}"#;
+/// let options = ParserOptions {
+///     duplicate_keys: DuplicateKeyPolicy::KeepFirst,
+///     ..ParserOptions::default()
+/// };
+/// let value = parse_json_with_options(input, &options)?;
+/// assert_eq!(value.get("a"), Some(&JsonValue::Number(1.into())));
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// The last occurrence wins (the behaviour of most JSON parsers).
+    #[default]
+    KeepLast,
+    /// The first occurrence wins and later ones are ignored.
+    KeepFirst,
+    /// Fail with [`JsonError::DuplicateKey`](crate::JsonError::DuplicateKey).
+    Reject,
+}
+
+/// How escape sequences JSON does not define are handled. See
+/// [`ParserOptions::unknown_escapes`].
+///
+/// The lenient policies help with producers that cannot be fixed; the sequences
+/// they let through are listed by
+/// [`parse_json_with_escape_warnings`](crate::audit::parse_json_with_escape_warnings).
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json_with_options, JsonValue, ParserOptions, UnknownEscapePolicy};
+///
+/// let input = r#""it\'s \x41""#;
+/// let options = |unknown_escapes| ParserOptions {
+///     unknown_escapes,
+///     ..ParserOptions::default()
+/// };
+/// assert!(parse_json_with_options(input, &options(UnknownEscapePolicy::Reject)).is_err());
+/// assert_eq!(
+///     parse_json_with_options(input, &options(UnknownEscapePolicy::Keep))?,
+///     JsonValue::String(r"it\'s \x41".to_string())
+/// );
+/// assert_eq!(
+///     parse_json_with_options(input, &options(UnknownEscapePolicy::Unescape))?,
+///     JsonValue::String("it's x41".to_string())
+/// );
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownEscapePolicy {
+    /// Fail with [`JsonError::InvalidEscape`](crate::JsonError::InvalidEscape).
+    #[default]
+    Reject,
+    /// Keep the backslash and the character as written.
+    Keep,
+    /// Drop the backslash and keep the character, so `\'` becomes `'`.
+    Unescape,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            preserve_number_text: false,
+            max_number_length: DEFAULT_MAX_NUMBER_LENGTH,
+            max_exponent: DEFAULT_MAX_EXPONENT,
+            max_string_length: usize::MAX,
+            max_values: usize::MAX,
+            max_input_size: usize::MAX,
+            strict: true,
+            allow_comments: false,
+            allow_trailing_commas: false,
+            allow_single_quotes: false,
+            allow_unquoted_keys: false,
+            allow_nan: false,
+            allow_hex_and_binary: false,
+            allow_multiline_strings: false,
+            duplicate_keys: DuplicateKeyPolicy::KeepLast,
+            unknown_escapes: UnknownEscapePolicy::Reject,
+            #[cfg(feature = "shared-keys")]
+            intern_keys: false,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Returns options for relaxed, JSON5-style input such as hand-written
+    /// configuration files: comments, trailing commas, single-quoted strings,
+    /// unquoted keys, hexadecimal or binary integers and multi-line raw strings
+    /// are all accepted.
+    /// Everything else keeps its default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, parse_json_with_options, ParserOptions};
+    ///
+    /// let config = r#"{
+    ///     // Where to listen
+    ///     host: 'localhost',
+    ///     ports: [8080, 8081,],
+    ///     mask: 0xFF,
+    /// }"#;
+    /// let value = parse_json_with_options(config, &ParserOptions::json5())?;
+    /// assert_eq!(
+    ///     value,
+    ///     parse_json(r#"{"host": "localhost", "ports": [8080, 8081], "mask": 255}"#)?
+    /// );
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn json5() -> Self {
+        Self {
+            allow_comments: true,
+            allow_trailing_commas: true,
+            allow_single_quotes: true,
+            allow_unquoted_keys: true,
+            allow_hex_and_binary: true,
+            allow_multiline_strings: true,
+            ..Self::default()
+        }
+    }
+}
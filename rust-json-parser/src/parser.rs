@@ -1,10 +1,19 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::VecDeque;
 
-use crate::JsonResult;
+use crate::encoding::decode_json_text;
 use crate::error::{unexpected_end_of_input, unexpected_token_error};
-use crate::tokenizer::{Token, Tokenizer};
-use crate::value::JsonValue;
+use crate::incremental::IncrementalParser;
+use crate::number::JsonNumber;
+use crate::options::{DuplicateKeyPolicy, ParserOptions};
+use crate::pool::JsonValuePool;
+use crate::query::{parse_index, unescape_token};
+use crate::tokenizer::{RawToken, SpannedToken, Token, Tokenizer, unescape};
+use crate::value::{JsonArray, JsonKey, JsonMap, JsonValue};
+use crate::{JsonError, JsonResult};
 use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
 
 /*
  * Utility function to error upon missing expected comma
@@ -91,14 +100,34 @@ fn err_on_unexpected_closing_token(
     Ok(())
 }
 
+/*
+ * Number of tokens buffered ahead of the parser. Objects need to look one token
+ * past a string to tell keys (followed by a colon) from values.
+ */
+const LOOKAHEAD: usize = 2;
+
 /// A recursive descent parser that converts a token stream into a [`JsonValue`] tree.
-pub struct JsonParser {
-    tokens: Vec<Token>,
-    current: usize,
+///
+/// Tokens are pulled from the [`Tokenizer`] on demand, so only a couple of tokens
+/// are held in memory at any time instead of the whole token stream.
+///
+/// This parser keeps the classic two-phase design (lexing into [`Token`]s, then
+/// parsing them), which makes each step easy to inspect when debugging or
+/// teaching. [`parse_json`] uses a faster single-pass parser and should be
+/// preferred otherwise. Both report error positions as byte offsets into the
+/// input.
+pub struct JsonParser<'input> {
+    tokenizer: Tokenizer<'input>,
+    lookahead: VecDeque<SpannedToken>,
+    depth: usize,
+    max_depth: usize,
+    allow_trailing_commas: bool,
+    duplicate_keys: DuplicateKeyPolicy,
 }
 
-impl JsonParser {
-    /// Tokenizes the input string and creates a new `JsonParser` ready to parse.
+impl<'input> JsonParser<'input> {
+    /// Creates a new `JsonParser` ready to parse, reading the first tokens of the
+    /// input.
     ///
     /// # Examples
     ///
@@ -111,12 +140,46 @@ impl JsonParser {
     ///
     /// # Errors
     ///
-    /// Returns a [`JsonError`](crate::JsonError) if the input contains invalid tokens
-    /// (see [`Tokenizer::tokenize`](crate::Tokenizer::tokenize)).
-    pub fn new(input: &str) -> JsonResult<Self> {
+    /// Returns a [`JsonError`] if the first tokens of the input are
+    /// invalid (see [`Tokenizer::tokenize`](crate::Tokenizer::tokenize)). Errors in
+    /// later tokens are reported by [`parse`](Self::parse).
+    pub fn new(input: &'input str) -> JsonResult<Self> {
+        Self::with_options(input, &ParserOptions::default())
+    }
+
+    /// Creates a new `JsonParser` that applies the given [`ParserOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{JsonError, JsonParser, ParserOptions};
+    ///
+    /// let options = ParserOptions {
+    ///     max_depth: 1,
+    ///     ..ParserOptions::default()
+    /// };
+    /// let mut parser = JsonParser::with_options("[[]]", &options)?;
+    /// assert!(matches!(parser.parse(), Err(JsonError::DepthLimitExceeded { .. })));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`new`](Self::new).
+    pub fn with_options(input: &'input str, options: &ParserOptions) -> JsonResult<Self> {
         let mut tokenizer = Tokenizer::new(input);
-        let tokens = tokenizer.tokenize()?;
-        Ok(Self { current: 0, tokens })
+        tokenizer.configure(options);
+        tokenizer.skip_bom();
+        let mut parser = Self {
+            tokenizer,
+            lookahead: VecDeque::with_capacity(LOOKAHEAD),
+            depth: 0,
+            max_depth: options.max_depth,
+            allow_trailing_commas: options.allow_trailing_commas,
+            duplicate_keys: options.duplicate_keys,
+        };
+        parser.fill_lookahead()?;
+        Ok(parser)
     }
 
     /// Parses the token stream and returns the root [`JsonValue`].
@@ -134,18 +197,34 @@ impl JsonParser {
     ///
     /// # Errors
     ///
-    /// Returns [`JsonError::UnexpectedToken`](crate::JsonError::UnexpectedToken) if the
+    /// Returns [`JsonError::UnexpectedToken`] if the
     /// token stream contains structurally invalid JSON (e.g. missing commas, colons, or
     /// mismatched brackets), or
-    /// [`JsonError::UnexpectedEndOfInput`](crate::JsonError::UnexpectedEndOfInput) if the
-    /// input ends before a complete value is formed.
+    /// [`JsonError::UnexpectedEndOfInput`] if the
+    /// input ends before a complete value is formed, or
+    /// [`JsonError::DepthLimitExceeded`] if the
+    /// input is nested deeper than the configured maximum, or
+    /// [`JsonError::TrailingCharacters`] if another token follows the value.
     pub fn parse(&mut self) -> JsonResult<JsonValue> {
-        match self.peek() {
-            Some(Token::LeftBrace) => self.parse_object(),
-            Some(Token::LeftBracket) => self.parse_array(),
-            Some(_) => self.parse_primitive(),
-            None => Err(unexpected_end_of_input("string", self.current)),
+        if self.peek().is_some() {
+            self.tokenizer.count_value(self.position())?;
         }
+        let value = match self.peek() {
+            Some(Token::LeftBrace) => self.parse_object()?,
+            Some(Token::LeftBracket) => self.parse_array()?,
+            Some(_) => {
+                let value = self.parse_primitive()?;
+                self.advance()?;
+                value
+            }
+            None => return Err(unexpected_end_of_input("string", self.position())),
+        };
+        if self.peek().is_some() {
+            return Err(JsonError::TrailingCharacters {
+                position: self.position(),
+            });
+        }
+        Ok(value)
     }
 
     /*
@@ -154,15 +233,15 @@ impl JsonParser {
     fn parse_primitive(&mut self) -> JsonResult<JsonValue> {
         match self.peek() {
             Some(Token::String(s)) => Ok(JsonValue::String(s.clone())),
-            Some(Token::Number(n)) => Ok(JsonValue::Number(*n)),
+            Some(Token::Number(n)) => Ok(JsonValue::Number(n.clone())),
             Some(Token::Boolean(b)) => Ok(JsonValue::Boolean(*b)),
             Some(Token::Null) => Ok(JsonValue::Null),
             Some(token) => Err(unexpected_token_error(
                 "string",
                 &format!("{:?}", token),
-                self.current,
+                self.position(),
             )),
-            None => Err(unexpected_end_of_input("string", self.current)),
+            None => Err(unexpected_end_of_input("string", self.position())),
         }
     }
 
@@ -173,7 +252,14 @@ impl JsonParser {
      * requires the opening bracket to be consumed beforehand.
      */
     fn parse_array(&mut self) -> JsonResult<JsonValue> {
-        self.advance(); // Consume opening [
+        self.enter_container()?;
+        let array = self.parse_array_members();
+        self.depth -= 1;
+        array
+    }
+
+    fn parse_array_members(&mut self) -> JsonResult<JsonValue> {
+        self.advance()?; // Consume opening [
         let mut array = Vec::new();
         let mut expect_comma = false;
 
@@ -181,67 +267,82 @@ impl JsonParser {
             match token {
                 // Start of array
                 Token::LeftBracket => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
 
+                    let start = self.position();
                     let nested_array = self.parse_array()?;
                     array.push(nested_array);
+                    self.tokenizer.count_value(start)?;
                     expect_comma = true;
                 }
                 // End of array
                 Token::RightBracket => {
-                    self.advance(); // Consume closig ]
+                    self.advance()?; // Consume closig ]
                     return Ok(JsonValue::Array(array));
                 }
                 // Start of object (opening { is consumed by parse_object())
                 Token::LeftBrace => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
 
+                    let start = self.position();
                     let nested_object = self.parse_object()?;
                     array.push(nested_object);
+                    self.tokenizer.count_value(start)?;
                     expect_comma = true;
                 }
                 Token::String(s) => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
 
                     array.push(JsonValue::String(s.clone()));
-                    self.advance();
+
+                    self.tokenizer.count_value(self.position())?;
+                    self.advance()?;
                     expect_comma = true;
                 }
                 Token::Number(n) => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
 
-                    array.push(JsonValue::Number(*n));
-                    self.advance();
+                    array.push(JsonValue::Number(n.clone()));
+
+                    self.tokenizer.count_value(self.position())?;
+                    self.advance()?;
                     expect_comma = true;
                 }
                 Token::Boolean(b) => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
 
                     array.push(JsonValue::Boolean(*b));
-                    self.advance();
+
+                    self.tokenizer.count_value(self.position())?;
+                    self.advance()?;
                     expect_comma = true;
                 }
                 Token::Null => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
 
                     array.push(JsonValue::Null);
-                    self.advance();
+
+                    self.tokenizer.count_value(self.position())?;
+                    self.advance()?;
                     expect_comma = true;
                 }
                 Token::Comma => {
-                    self.advance(); // Consume comma
+                    self.advance()?; // Consume comma
                     let token = self.peek().ok_or(unexpected_end_of_input(
                         "string, bool, number or object",
-                        self.current,
+                        self.position(),
                     ))?;
 
-                    err_on_unexpected_comma(expect_comma, "closing bracket", self.current)?;
+                    err_on_unexpected_comma(expect_comma, "closing bracket", self.position())?;
+                    if self.allow_trailing_commas && token == &Token::RightBracket {
+                        continue;
+                    }
                     err_on_unexpected_closing_token(
                         token,
                         &Token::RightBracket,
                         "string, bool, number or object",
                         "]",
-                        self.current,
+                        self.position(),
                     )?;
                     expect_comma = false;
                 }
@@ -249,13 +350,13 @@ impl JsonParser {
                     return Err(unexpected_token_error(
                         "valid JSON value",
                         &format!("{:?}", token),
-                        self.current,
+                        self.position(),
                     ));
                 }
             };
         }
 
-        Err(unexpected_end_of_input("closing bracket", self.current))
+        Err(unexpected_end_of_input("closing bracket", self.position()))
     }
 
     /*
@@ -265,9 +366,16 @@ impl JsonParser {
      * consumes the opening brace.
      */
     fn parse_object(&mut self) -> JsonResult<JsonValue> {
-        self.advance(); // Consume opening {
+        self.enter_container()?;
+        let object = self.parse_object_members();
+        self.depth -= 1;
+        object
+    }
+
+    fn parse_object_members(&mut self) -> JsonResult<JsonValue> {
+        self.advance()?; // Consume opening {
         let mut key = String::new();
-        let mut object = HashMap::new();
+        let mut object = JsonMap::default();
         let mut colon_found = false;
         let mut expect_comma = false;
 
@@ -275,113 +383,129 @@ impl JsonParser {
             match token {
                 // Start of object
                 Token::LeftBrace => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
+                    err_on_unexpected_value_before_colon(colon_found, "{", self.position())?;
 
-                    if colon_found {
-                        let nested_object = self.parse_object()?;
-                        object.insert(key.clone(), nested_object);
-                        colon_found = false;
-                        expect_comma = true;
-                    }
+                    let nested_object = self.parse_object()?;
+                    self.insert_member(&mut object, &key, nested_object)?;
+                    colon_found = false;
+                    expect_comma = true;
                 }
                 // End of object
                 Token::RightBrace => {
-                    self.advance(); // Consume closing }
+                    self.advance()?; // Consume closing }
                     return Ok(JsonValue::Object(object));
                 }
                 // Start of array (end of array is handled in parse_array())
                 Token::LeftBracket => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
+                    err_on_unexpected_value_before_colon(colon_found, "[", self.position())?;
 
-                    if colon_found {
-                        let array = self.parse_array()?;
-                        object.insert(key.clone(), array);
-                        colon_found = false;
-                        expect_comma = true;
-                    }
+                    let array = self.parse_array()?;
+                    self.insert_member(&mut object, &key, array)?;
+                    colon_found = false;
+                    expect_comma = true;
                 }
                 // Key or string value
                 Token::String(s) => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
 
                     // Unexpected end of input
-                    let next_token =
-                        self.get_token(self.current + 1)
-                            .ok_or(unexpected_end_of_input(
-                                match colon_found {
-                                    true => ",",
-                                    false => ":",
-                                },
-                                self.current,
-                            ))?;
+                    let next = self.peek_next().ok_or(unexpected_end_of_input(
+                        match colon_found {
+                            true => ",",
+                            false => ":",
+                        },
+                        self.tokenizer.offset(),
+                    ))?;
 
                     // All good! Key?
-                    if next_token_is_expected_colon(colon_found, next_token, self.current)? {
+                    if next_token_is_expected_colon(colon_found, &next.token, next.start)? {
                         key = s.clone();
                     // Or value?
                     } else {
-                        object.insert(key.clone(), JsonValue::String(s.clone()));
+                        self.insert_member(&mut object, &key, JsonValue::String(s.clone()))?;
                         colon_found = false;
                         expect_comma = true;
                     }
-                    self.advance();
+                    self.advance()?;
                 }
                 Token::Number(n) => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
                     err_on_unexpected_value_before_colon(
                         colon_found,
                         &n.to_string(),
-                        self.current,
+                        self.position(),
                     )?;
 
-                    object.insert(key.clone(), JsonValue::Number(*n));
+                    self.insert_member(&mut object, &key, JsonValue::Number(n.clone()))?;
                     colon_found = false;
                     expect_comma = true;
 
-                    self.advance();
+                    self.advance()?;
                 }
                 Token::Boolean(b) => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
                     err_on_unexpected_value_before_colon(
                         colon_found,
                         &b.to_string(),
-                        self.current,
+                        self.position(),
                     )?;
 
-                    object.insert(key.clone(), JsonValue::Boolean(*b));
+                    self.insert_member(&mut object, &key, JsonValue::Boolean(*b))?;
                     colon_found = false;
                     expect_comma = true;
 
-                    self.advance();
+                    self.advance()?;
                 }
                 Token::Null => {
-                    err_on_missing_expected_comma(expect_comma, token, self.current)?;
-                    err_on_unexpected_value_before_colon(colon_found, "null", self.current)?;
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
+                    err_on_unexpected_value_before_colon(colon_found, "null", self.position())?;
 
-                    object.insert(key.clone(), JsonValue::Null);
+                    self.insert_member(&mut object, &key, JsonValue::Null)?;
                     colon_found = false;
                     expect_comma = true;
 
-                    self.advance();
+                    self.advance()?;
+                }
+                // Unquoted key, only produced when unquoted keys are allowed
+                Token::Identifier(name) => {
+                    err_on_missing_expected_comma(expect_comma, token, self.position())?;
+                    if colon_found {
+                        return Err(unexpected_token_error(
+                            "valid JSON value",
+                            name,
+                            self.position(),
+                        ));
+                    }
+                    let next = self
+                        .peek_next()
+                        .ok_or(unexpected_end_of_input(":", self.tokenizer.offset()))?;
+                    next_token_is_expected_colon(false, &next.token, next.start)?;
+                    key = name.clone();
+                    self.advance()?;
                 }
                 Token::Colon => {
                     colon_found = true;
-                    self.advance();
+                    self.advance()?;
                 }
                 Token::Comma => {
-                    self.advance(); // Consume comma
+                    self.advance()?; // Consume comma
                     let token = self.peek().ok_or(unexpected_end_of_input(
                         "string, bool, number or object",
-                        self.current,
+                        self.position(),
                     ))?;
 
-                    err_on_unexpected_comma(expect_comma, "closing brace", self.current)?;
+                    err_on_unexpected_comma(expect_comma, "closing brace", self.position())?;
+                    if self.allow_trailing_commas && token == &Token::RightBrace {
+                        continue;
+                    }
                     err_on_unexpected_closing_token(
                         token,
                         &Token::RightBrace,
                         "string",
                         "}",
-                        self.current,
+                        self.position(),
                     )?;
                     expect_comma = false;
                 }
@@ -389,95 +513,1111 @@ impl JsonParser {
                     return Err(unexpected_token_error(
                         "valid JSON value",
                         &format!("{:?}", token),
-                        self.current,
+                        self.position(),
                     ));
                 }
             };
         }
 
-        Err(unexpected_end_of_input("closing brace", self.current))
+        Err(unexpected_end_of_input("closing brace", self.position()))
+    }
+
+    /*
+     * Adds a member to an object under construction, applying the duplicate key
+     * policy.
+     */
+    fn insert_member(
+        &mut self,
+        object: &mut JsonMap,
+        key: &str,
+        value: JsonValue,
+    ) -> JsonResult<()> {
+        self.tokenizer.count_value(self.position())?;
+        match self.duplicate_keys {
+            DuplicateKeyPolicy::KeepLast => {
+                object.insert(key.into(), value);
+            }
+            DuplicateKeyPolicy::KeepFirst => {
+                object.entry(key.into()).or_insert(value);
+            }
+            DuplicateKeyPolicy::Reject => {
+                if object.contains_key(key) {
+                    return Err(JsonError::DuplicateKey {
+                        key: key.to_string(),
+                        position: self.position(),
+                    });
+                }
+                object.insert(key.into(), value);
+            }
+        }
+        Ok(())
+    }
+
+    /*
+     * Accounts for one more level of nesting, enforcing the depth limit before
+     * recursing any further.
+     */
+    fn enter_container(&mut self) -> JsonResult<()> {
+        if self.depth >= self.max_depth {
+            return Err(JsonError::DepthLimitExceeded {
+                max_depth: self.max_depth,
+                position: self.position(),
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /*
+     * Pulls tokens from the tokenizer until the lookahead buffer is full or the
+     * input is exhausted.
+     */
+    fn fill_lookahead(&mut self) -> JsonResult<()> {
+        while self.lookahead.len() < LOOKAHEAD {
+            match self.tokenizer.next_spanned_token()? {
+                Some(token) => self.lookahead.push_back(token),
+                None => break,
+            }
+        }
+        Ok(())
     }
 
     /*
      * Look at current token without advancing
      */
     fn peek(&self) -> Option<&Token> {
-        if !self.is_at_end() {
-            return self.tokens.get(self.current);
+        self.lookahead.front().map(|spanned| &spanned.token)
+    }
+
+    /*
+     * Look at the token after the current one
+     */
+    fn peek_next(&self) -> Option<&SpannedToken> {
+        self.lookahead.get(1)
+    }
+
+    /*
+     * Byte offset of the current token, or of the end of the input once every
+     * token has been consumed
+     */
+    fn position(&self) -> usize {
+        self.lookahead
+            .front()
+            .map_or(self.tokenizer.offset(), |spanned| spanned.start)
+    }
+
+    /*
+     * Move forward, discarding the current token and reading the next one
+     */
+    fn advance(&mut self) -> JsonResult<()> {
+        self.lookahead.pop_front();
+        self.fill_lookahead()
+    }
+}
+
+/*
+ * Builds object keys. With `ParserOptions::intern_keys` (and the `shared-keys`
+ * feature) it remembers every key handed out, so equal keys share one `Arc<str>`;
+ * otherwise each key is a fresh `String`, reusing pooled buffers.
+ */
+struct KeyInterner {
+    #[cfg(feature = "shared-keys")]
+    seen: Option<std::collections::HashSet<JsonKey, crate::value::JsonHasher>>,
+}
+
+impl KeyInterner {
+    #[cfg_attr(not(feature = "shared-keys"), allow(unused_variables))]
+    fn new(options: &ParserOptions) -> Self {
+        Self {
+            #[cfg(feature = "shared-keys")]
+            seen: options.intern_keys.then(Default::default),
+        }
+    }
+
+    #[cfg(not(feature = "shared-keys"))]
+    fn make_key(&mut self, pool: &mut JsonValuePool, key: Cow<'_, str>) -> JsonKey {
+        pool.make_string(key)
+    }
+
+    #[cfg(feature = "shared-keys")]
+    fn make_key(&mut self, _pool: &mut JsonValuePool, key: Cow<'_, str>) -> JsonKey {
+        let Some(seen) = &mut self.seen else {
+            return JsonKey::from(key);
+        };
+        if let Some(shared) = seen.get(&*key) {
+            return shared.clone();
         }
-        None
+        let shared = JsonKey::from(key);
+        seen.insert(shared.clone());
+        shared
     }
+}
 
+/*
+ * What `SinglePassParser` makes of the values it reads. `Build` assembles a
+ * `JsonValue` tree; `Skip` keeps nothing, so that `validate_json` and the
+ * parts of the input `extract_json` passes over are checked by the same
+ * grammar, with the same errors, without allocating.
+ */
+trait Sink<'input> {
+    type Value;
+    type Array;
+    type Object;
+    type Key: Default;
+
+    fn string(&mut self, s: Cow<'input, str>) -> Self::Value;
+    fn number(&mut self, n: JsonNumber) -> Self::Value;
+    fn boolean(&mut self, b: bool) -> Self::Value;
+    fn null(&mut self) -> Self::Value;
+    fn key(&mut self, key: Cow<'input, str>) -> Self::Key;
+    fn array(&mut self) -> Self::Array;
+    fn object(&mut self) -> Self::Object;
+    fn push(&mut self, array: &mut Self::Array, value: Self::Value);
+    fn insert(&mut self, object: &mut Self::Object, key: Self::Key, value: Self::Value);
     /*
-     * Get a token by index (useful to look further ahead)
+     * Fails with `DuplicateKey` if `object` already has `key`, which starts at
+     * `position`, and duplicates are rejected.
      */
-    fn get_token(&self, index: usize) -> Option<&Token> {
-        self.tokens.get(index)
+    fn check_duplicate(
+        &self,
+        object: &Self::Object,
+        key: &Self::Key,
+        position: usize,
+    ) -> JsonResult<()>;
+    fn finish_array(&mut self, array: Self::Array) -> Self::Value;
+    fn finish_object(&mut self, object: Self::Object) -> Self::Value;
+}
+
+/*
+ * Builds the parsed value, allocating containers and strings through a
+ * `JsonValuePool`.
+ */
+struct Build<'a> {
+    pool: &'a mut JsonValuePool,
+    keys: &'a mut KeyInterner,
+    duplicate_keys: DuplicateKeyPolicy,
+}
+
+impl<'input> Sink<'input> for Build<'_> {
+    type Value = JsonValue;
+    type Array = JsonArray;
+    type Object = JsonMap;
+    type Key = JsonKey;
+
+    fn string(&mut self, s: Cow<'input, str>) -> JsonValue {
+        JsonValue::String(self.pool.make_string(s))
+    }
+
+    fn number(&mut self, n: JsonNumber) -> JsonValue {
+        JsonValue::Number(n)
+    }
+
+    fn boolean(&mut self, b: bool) -> JsonValue {
+        JsonValue::Boolean(b)
+    }
+
+    fn null(&mut self) -> JsonValue {
+        JsonValue::Null
+    }
+
+    fn key(&mut self, key: Cow<'input, str>) -> JsonKey {
+        self.keys.make_key(self.pool, key)
+    }
+
+    fn array(&mut self) -> JsonArray {
+        self.pool.take_array()
+    }
+
+    fn object(&mut self) -> JsonMap {
+        self.pool.take_object()
+    }
+
+    fn push(&mut self, array: &mut JsonArray, value: JsonValue) {
+        array.push(value);
+    }
+
+    fn insert(&mut self, object: &mut JsonMap, key: JsonKey, value: JsonValue) {
+        if self.duplicate_keys == DuplicateKeyPolicy::KeepFirst {
+            object.entry(key).or_insert(value);
+        } else {
+            object.insert(key, value);
+        }
+    }
+
+    fn check_duplicate(&self, object: &JsonMap, key: &JsonKey, position: usize) -> JsonResult<()> {
+        if self.duplicate_keys == DuplicateKeyPolicy::Reject && object.contains_key(&**key) {
+            return Err(JsonError::DuplicateKey {
+                key: key.to_string(),
+                position,
+            });
+        }
+        Ok(())
+    }
+
+    fn finish_array(&mut self, array: JsonArray) -> JsonValue {
+        JsonValue::Array(array)
+    }
+
+    fn finish_object(&mut self, object: JsonMap) -> JsonValue {
+        JsonValue::Object(object)
+    }
+}
+
+/*
+ * Only checks the syntax. Keys are not kept, so duplicates go unnoticed; this
+ * matches the default `DuplicateKeyPolicy::KeepLast`.
+ */
+struct Skip;
+
+impl<'input> Sink<'input> for Skip {
+    type Value = ();
+    type Array = ();
+    type Object = ();
+    type Key = ();
+
+    fn string(&mut self, _s: Cow<'input, str>) {}
+    fn number(&mut self, _n: JsonNumber) {}
+    fn boolean(&mut self, _b: bool) {}
+    fn null(&mut self) {}
+    fn key(&mut self, _key: Cow<'input, str>) {}
+    fn array(&mut self) {}
+    fn object(&mut self) {}
+    fn push(&mut self, _array: &mut (), _value: ()) {}
+    fn insert(&mut self, _object: &mut (), _key: (), _value: ()) {}
+
+    fn check_duplicate(&self, _object: &(), _key: &(), _position: usize) -> JsonResult<()> {
+        Ok(())
+    }
+
+    fn finish_array(&mut self, _array: ()) {}
+    fn finish_object(&mut self, _object: ()) {}
+}
+
+/*
+ * A container whose members are still being parsed by `SinglePassParser`.
+ * Objects keep the key of the member whose value is being parsed.
+ */
+enum Frame<A, O, K> {
+    Array(A),
+    Object(O, K),
+}
+
+/*
+ * Single-pass parser used by `parse_json`. It reads one token at a time straight
+ * from the input slice, never buffers tokens and hands string contents to the
+ * sink without cloning. Open containers live on an explicit stack rather than
+ * the call stack, so parsing itself never overflows the stack, however large
+ * `max_depth` is. The resulting tree is still dropped, cloned, compared and
+ * formatted recursively, which is why `max_depth` stays limited by default.
+ * Error positions are byte offsets of the offending token.
+ */
+struct SinglePassParser<'input, 'a, S> {
+    tokenizer: &'a mut Tokenizer<'input>,
+    max_depth: usize,
+    // Containers already open around the value being parsed
+    depth: usize,
+    allow_trailing_commas: bool,
+    sink: S,
+}
+
+impl<'input, S: Sink<'input>> SinglePassParser<'input, '_, S> {
+    /*
+     * Consumes the next token, failing with `UnexpectedEndOfInput` if there is none.
+     */
+    fn expect_token(&mut self, expected: &str) -> JsonResult<RawToken<'input>> {
+        self.tokenizer
+            .next_raw_token()?
+            .ok_or_else(|| unexpected_end_of_input(expected, self.tokenizer.offset()))
+    }
+
+    /*
+     * Builds the error for an unexpected `token`. A string scanned with
+     * unescaping deferred is decoded first, so the error reads as the one from
+     * the two-phase parser.
+     */
+    fn unexpected(&self, expected: &str, token: &RawToken) -> JsonError {
+        let found = match token {
+            RawToken::String(raw) if self.tokenizer.unescape_deferred() => {
+                format!("{:?}", RawToken::String(unescape(raw)))
+            }
+            token => format!("{:?}", token),
+        };
+        unexpected_token_error(expected, &found, self.tokenizer.token_start())
     }
 
     /*
-     * Move forward, return previous token
+     * Checks that one more container can be opened inside the `open` ones of
+     * the value being parsed.
      */
-    fn advance(&mut self) -> Option<&Token> {
-        let token = self.tokens.get(self.current);
-        self.current += 1;
-        token
+    fn check_depth(&self, open: usize) -> JsonResult<()> {
+        if self.depth + open >= self.max_depth {
+            return Err(JsonError::DepthLimitExceeded {
+                max_depth: self.max_depth,
+                position: self.tokenizer.token_start(),
+            });
+        }
+        Ok(())
     }
 
     /*
-     * Check if the input has been consumed
+     * Validates an object key token and consumes the colon that follows it.
      */
-    fn is_at_end(&self) -> bool {
-        self.current >= self.tokens.len()
+    fn expect_key(&mut self, token: RawToken<'input>) -> JsonResult<S::Key> {
+        let key = match token {
+            RawToken::String(key) => key,
+            // Only produced when unquoted keys are allowed
+            RawToken::Identifier(name) => Cow::Borrowed(name),
+            other => return Err(self.unexpected("string", &other)),
+        };
+        match self.expect_token(":")? {
+            RawToken::Colon => Ok(self.sink.key(key)),
+            other => Err(self.unexpected(":", &other)),
+        }
     }
+
+    fn parse(&mut self) -> JsonResult<S::Value> {
+        let token = self.expect_token("valid JSON value")?;
+        self.parse_value(token)
+    }
+
+    /*
+     * Parses the value that starts with the already consumed `token`.
+     */
+    fn parse_value(&mut self, mut token: RawToken<'input>) -> JsonResult<S::Value> {
+        let mut stack: Vec<Frame<S::Array, S::Object, S::Key>> = Vec::new();
+
+        loop {
+            self.tokenizer.count_value(self.tokenizer.token_start())?;
+            // Read a value, or open a container and continue with its first member
+            let mut value = match token {
+                RawToken::LeftBracket => {
+                    self.check_depth(stack.len())?;
+                    token = self.expect_token("valid JSON value or closing bracket")?;
+                    if token != RawToken::RightBracket {
+                        stack.push(Frame::Array(self.sink.array()));
+                        continue;
+                    }
+                    let array = self.sink.array();
+                    self.sink.finish_array(array)
+                }
+                RawToken::LeftBrace => {
+                    self.check_depth(stack.len())?;
+                    token = self.expect_token("string or closing brace")?;
+                    if token != RawToken::RightBrace {
+                        let key = self.expect_key(token)?;
+                        stack.push(Frame::Object(self.sink.object(), key));
+                        token = self.expect_token("valid JSON value")?;
+                        continue;
+                    }
+                    let object = self.sink.object();
+                    self.sink.finish_object(object)
+                }
+                RawToken::String(s) => self.sink.string(s),
+                RawToken::Number(n) => self.sink.number(n),
+                RawToken::Boolean(b) => self.sink.boolean(b),
+                RawToken::Null => self.sink.null(),
+                other => return Err(self.unexpected("valid JSON value", &other)),
+            };
+
+            // Store the value in its parent, closing every container that ends here
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(value),
+                    Some(Frame::Array(items)) => {
+                        self.sink.push(items, value);
+                        match self.expect_token(", or closing bracket")? {
+                            RawToken::Comma => {
+                                token = self.expect_token("valid JSON value")?;
+                                if !(self.allow_trailing_commas && token == RawToken::RightBracket)
+                                {
+                                    break;
+                                }
+                            }
+                            RawToken::RightBracket => {}
+                            other => return Err(self.unexpected(", or closing bracket", &other)),
+                        }
+                        let Some(Frame::Array(items)) = stack.pop() else {
+                            unreachable!()
+                        };
+                        value = self.sink.finish_array(items);
+                    }
+                    Some(Frame::Object(members, key)) => {
+                        self.sink.insert(members, std::mem::take(key), value);
+                        match self.expect_token(", or closing brace")? {
+                            RawToken::Comma => {
+                                let key_token = self.expect_token("string")?;
+                                if !(self.allow_trailing_commas
+                                    && key_token == RawToken::RightBrace)
+                                {
+                                    let position = self.tokenizer.token_start();
+                                    *key = self.expect_key(key_token)?;
+                                    self.sink.check_duplicate(members, key, position)?;
+                                    token = self.expect_token("valid JSON value")?;
+                                    break;
+                                }
+                            }
+                            RawToken::RightBrace => {}
+                            other => return Err(self.unexpected(", or closing brace", &other)),
+                        }
+                        let Some(Frame::Object(members, _)) = stack.pop() else {
+                            unreachable!()
+                        };
+                        value = self.sink.finish_object(members);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/*
+ * Navigation used by `extract_json`: everything along the way to the target is
+ * skipped with the same grammar as `validate_json`.
+ */
+impl<'input> SinglePassParser<'input, '_, Skip> {
+    /*
+     * Checks the value that starts with the already consumed `token`, nested
+     * inside `depth` open containers. The tokenizer defers unescaping, so
+     * escaped strings are validated without being decoded.
+     */
+    fn skip_value(&mut self, token: RawToken<'input>, depth: usize) -> JsonResult<()> {
+        self.tokenizer.defer_unescape(true);
+        self.depth = depth;
+        self.parse_value(token)
+    }
+
+    /*
+     * Moves from the just opened object, nested inside `depth` open containers,
+     * to the value of its member `name`, skipping the members before it. Returns
+     * that value's first token, or `None` once the object has ended.
+     */
+    fn find_member(&mut self, name: &str, depth: usize) -> JsonResult<Option<RawToken<'input>>> {
+        self.depth = depth;
+        self.check_depth(0)?;
+        // Keys and the value found are read decoded, so they can be compared and built
+        self.tokenizer.defer_unescape(false);
+        let mut token = self.expect_token("string or closing brace")?;
+        if token == RawToken::RightBrace {
+            return Ok(None);
+        }
+        loop {
+            let matched = match &token {
+                RawToken::String(key) => key == name,
+                RawToken::Identifier(key) => *key == name,
+                _ => false,
+            };
+            self.expect_key(token)?;
+            let value = self.expect_token("valid JSON value")?;
+            if matched {
+                return Ok(Some(value));
+            }
+            self.skip_value(value, depth + 1)?;
+            match self.expect_token(", or closing brace")? {
+                RawToken::Comma => {
+                    self.tokenizer.defer_unescape(false);
+                    token = self.expect_token("string")?;
+                    if self.allow_trailing_commas && token == RawToken::RightBrace {
+                        return Ok(None);
+                    }
+                }
+                RawToken::RightBrace => return Ok(None),
+                other => return Err(self.unexpected(", or closing brace", &other)),
+            }
+        }
+    }
+
+    /*
+     * Like `find_member`, but moves from the just opened array to its element at
+     * `index`.
+     */
+    fn find_element(&mut self, index: usize, depth: usize) -> JsonResult<Option<RawToken<'input>>> {
+        self.depth = depth;
+        self.check_depth(0)?;
+        self.tokenizer.defer_unescape(false);
+        let mut token = self.expect_token("valid JSON value or closing bracket")?;
+        if token == RawToken::RightBracket {
+            return Ok(None);
+        }
+        for _ in 0..index {
+            self.skip_value(token, depth + 1)?;
+            match self.expect_token(", or closing bracket")? {
+                RawToken::Comma => {
+                    self.tokenizer.defer_unescape(false);
+                    token = self.expect_token("valid JSON value")?;
+                    if self.allow_trailing_commas && token == RawToken::RightBracket {
+                        return Ok(None);
+                    }
+                }
+                RawToken::RightBracket => return Ok(None),
+                other => return Err(self.unexpected(", or closing bracket", &other)),
+            }
+        }
+        Ok(Some(token))
+    }
+}
+
+/// Parses a JSON string and returns the corresponding [`JsonValue`].
+///
+/// This is the main entry point for parsing JSON. Tokenizing and parsing happen
+/// in a single pass over the input, without materializing a token stream; error
+/// positions are byte offsets into `input`. A byte order mark (U+FEFF) at the
+/// start of the input is ignored.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, JsonValue};
+///
+/// let value = parse_json(r#"{"name": "Alice"}"#)?;
+/// assert_eq!(value.get("name"), Some(&JsonValue::String("Alice".to_string())));
+///
+/// let value = parse_json("\u{FEFF}[1, 2, 3]")?;
+/// assert_eq!(value.as_array().map(|a| a.len()), Some(3));
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns a [`JsonError`] if the input is not valid JSON. This includes
+/// tokenization errors (invalid characters, malformed strings or numbers) and structural
+/// errors (missing commas, unclosed brackets, etc.),
+/// [`JsonError::DepthLimitExceeded`] if the input is nested deeper than
+/// [`DEFAULT_MAX_DEPTH`](crate::options::DEFAULT_MAX_DEPTH) levels, and
+/// [`JsonError::TrailingCharacters`] if anything but whitespace follows the value.
+pub fn parse_json(input: &str) -> JsonResult<JsonValue> {
+    parse_json_with_options(input, &ParserOptions::default())
+}
+
+/// Parses a JSON string like [`parse_json`], using the given [`ParserOptions`].
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json_with_options, ParserOptions};
+///
+/// let options = ParserOptions {
+///     max_depth: 1_000,
+///     ..ParserOptions::default()
+/// };
+/// let deep = format!("{}{}", "[".repeat(500), "]".repeat(500));
+/// assert!(parse_json_with_options(&deep, &options).is_ok());
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`parse_json`], and
+/// [`JsonError::DepthLimitExceeded`] if arrays and objects are nested deeper than
+/// `options.max_depth`.
+pub fn parse_json_with_options(input: &str, options: &ParserOptions) -> JsonResult<JsonValue> {
+    // An empty pool never allocates, so this costs nothing over plain `Vec::new()`
+    parse_json_pooled(input, options, &mut JsonValuePool::with_limit(0))
+}
+
+/*
+ * Entry point shared by `parse_json_with_options` and `JsonValuePool::parse`.
+ */
+pub(crate) fn parse_json_pooled(
+    input: &str,
+    options: &ParserOptions,
+    pool: &mut JsonValuePool,
+) -> JsonResult<JsonValue> {
+    let mut tokenizer = Tokenizer::new(input);
+    tokenizer.configure(options);
+    parse_whole_input(&mut tokenizer, options, pool)
+}
+
+/*
+ * Parses the tokenizer's input, which must hold a single value. The tokenizer
+ * must have been configured with `options`, and is left for callers that
+ * inspect what it recorded while scanning.
+ */
+pub(crate) fn parse_whole_input(
+    tokenizer: &mut Tokenizer<'_>,
+    options: &ParserOptions,
+    pool: &mut JsonValuePool,
+) -> JsonResult<JsonValue> {
+    tokenizer.skip_bom();
+    let value = parse_leading_value(tokenizer, options, pool)?;
+    // Anything left must be whitespace (or comments, if allowed); a trailing
+    // token that fails to scan is reported the same way
+    match tokenizer.next_raw_token() {
+        Ok(None) => Ok(value),
+        _ => Err(JsonError::TrailingCharacters {
+            position: tokenizer.token_start(),
+        }),
+    }
+}
+
+/// Parses the JSON value at the start of `input` and returns it together with the
+/// number of bytes it spans, ignoring whatever follows.
+///
+/// Unlike [`parse_json`], this does not require the value to make up the whole
+/// input, which is useful for extracting a document embedded in a larger text.
+/// Leading whitespace and a byte order mark count towards the bytes consumed;
+/// trailing whitespace does not. Use [`JsonStream`] to read several values
+/// written one after another.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json_prefix, JsonValue};
+///
+/// let (value, consumed) = parse_json_prefix("[1, 2] and more")?;
+/// assert_eq!(value.as_array().map(|a| a.len()), Some(2));
+/// assert_eq!(consumed, 6);
+///
+/// let (value, consumed) = parse_json_prefix(" 42 garbage")?;
+/// assert_eq!(value, JsonValue::Number(42.into()));
+/// assert_eq!(consumed, 3);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`parse_json`] for the value itself, except
+/// [`JsonError::TrailingCharacters`].
+pub fn parse_json_prefix(input: &str) -> JsonResult<(JsonValue, usize)> {
+    let options = ParserOptions::default();
+    let mut tokenizer = Tokenizer::new(input);
+    tokenizer.configure(&options);
+    tokenizer.skip_bom();
+    let value = parse_leading_value(&mut tokenizer, &options, &mut JsonValuePool::with_limit(0))?;
+    Ok((value, tokenizer.offset()))
+}
+
+/// Checks that `input` is valid JSON without building a [`JsonValue`].
+///
+/// This accepts and rejects exactly the inputs that [`parse_json`] does, with
+/// the same errors, but only tracks which arrays and objects are open: strings
+/// are validated where they are (escape sequences included) and nothing else
+/// is allocated. Use it when only an accept/reject decision is needed.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{validate_json, JsonError};
+///
+/// assert!(validate_json(r#"{"items": [1, "two\n", null]}"#).is_ok());
+/// assert!(matches!(
+///     validate_json(r#"{"items": [1, }"#),
+///     Err(JsonError::UnexpectedToken { position: 14, .. })
+/// ));
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`parse_json`].
+pub fn validate_json(input: &str) -> JsonResult<()> {
+    let options = ParserOptions::default();
+    let mut tokenizer = Tokenizer::new(input);
+    tokenizer.configure(&options);
+    tokenizer.skip_bom();
+    tokenizer.defer_unescape(true);
+    SinglePassParser {
+        tokenizer: &mut tokenizer,
+        max_depth: options.max_depth,
+        depth: 0,
+        allow_trailing_commas: options.allow_trailing_commas,
+        sink: Skip,
+    }
+    .parse()?;
+    match tokenizer.next_raw_token() {
+        Ok(None) => Ok(()),
+        _ => Err(JsonError::TrailingCharacters {
+            position: tokenizer.token_start(),
+        }),
+    }
+}
+
+/// Checks that raw bytes are valid JSON, like [`validate_json`]. The encoding
+/// is detected as described for [`parse_json_bytes`].
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{validate_json_bytes, JsonError};
+///
+/// assert!(validate_json_bytes(b"[true]").is_ok());
+/// let err = validate_json_bytes(b"[\"\xff\"]").unwrap_err();
+/// assert_eq!(err, JsonError::InvalidUtf8 { offset: 2, length: 1 });
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`parse_json_bytes`].
+pub fn validate_json_bytes(input: &[u8]) -> JsonResult<()> {
+    validate_json(&decode_json_text(input)?)
+}
+
+/// Extracts the value at a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901)
+/// such as `"/data/items/3/id"` straight from JSON text, without parsing the
+/// rest of the document into a [`JsonValue`].
+///
+/// Members and elements before the target are only checked for syntax, as by
+/// [`validate_json`], and reading stops right after the target, so pulling one
+/// field out of a large payload costs a fraction of a full [`parse_json`]
+/// followed by [`JsonValue::pointer`]. Returns `Ok(None)` if nothing matches.
+///
+/// Because reading stops early, syntax errors after the target (or after the
+/// point where it turned out to be missing) are not reported, and if an object
+/// repeats the key being looked up, the first occurrence is used.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{extract_json, JsonValue};
+///
+/// let input = r#"{"meta": {"count": 2}, "data": {"items": [{"id": 7}, {"id": 8}]}}"#;
+/// assert_eq!(
+///     extract_json(input, "/data/items/1/id")?,
+///     Some(JsonValue::Number(8.into()))
+/// );
+/// assert_eq!(extract_json(input, "/data/items/5")?, None);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::InvalidQuery`] if `pointer` is neither empty nor starts
+/// with `/`, and otherwise the errors [`parse_json`] would report for the part
+/// of the input that was read.
+pub fn extract_json(input: &str, pointer: &str) -> JsonResult<Option<JsonValue>> {
+    let references = match pointer.strip_prefix('/') {
+        Some(rest) => rest.split('/').collect(),
+        None if pointer.is_empty() => Vec::new(),
+        None => {
+            return Err(JsonError::InvalidQuery {
+                query: pointer.to_string(),
+                position: 0,
+            });
+        }
+    };
+    let options = ParserOptions::default();
+    let mut tokenizer = Tokenizer::new(input);
+    tokenizer.configure(&options);
+    tokenizer.skip_bom();
+    let mut skipper = SinglePassParser {
+        tokenizer: &mut tokenizer,
+        max_depth: options.max_depth,
+        depth: 0,
+        allow_trailing_commas: options.allow_trailing_commas,
+        sink: Skip,
+    };
+
+    let mut token = skipper.expect_token("valid JSON value")?;
+    for (depth, reference) in references.iter().enumerate() {
+        skipper
+            .tokenizer
+            .count_value(skipper.tokenizer.token_start())?;
+        let found = match token {
+            RawToken::LeftBrace => skipper.find_member(&unescape_token(reference), depth)?,
+            RawToken::LeftBracket => match parse_index(reference) {
+                Some(index) => skipper.find_element(index, depth)?,
+                None => None,
+            },
+            _ => None,
+        };
+        match found {
+            Some(next) => token = next,
+            None => return Ok(None),
+        }
+    }
+
+    SinglePassParser {
+        tokenizer: &mut tokenizer,
+        max_depth: options.max_depth,
+        depth: references.len(),
+        allow_trailing_commas: options.allow_trailing_commas,
+        sink: Build {
+            pool: &mut JsonValuePool::with_limit(0),
+            keys: &mut KeyInterner::new(&options),
+            duplicate_keys: options.duplicate_keys,
+        },
+    }
+    .parse_value(token)
+    .map(Some)
+}
+
+/*
+ * Parses the first value of the tokenizer's input, leaving the tokenizer right
+ * after it. The tokenizer must have been configured with `options`.
+ */
+fn parse_leading_value(
+    tokenizer: &mut Tokenizer<'_>,
+    options: &ParserOptions,
+    pool: &mut JsonValuePool,
+) -> JsonResult<JsonValue> {
+    SinglePassParser {
+        tokenizer,
+        max_depth: options.max_depth,
+        depth: 0,
+        allow_trailing_commas: options.allow_trailing_commas,
+        sink: Build {
+            pool,
+            keys: &mut KeyInterner::new(options),
+            duplicate_keys: options.duplicate_keys,
+        },
+    }
+    .parse()
+}
+
+/// An iterator over a sequence of JSON values written back to back, such as
+/// newline-delimited JSON or the output of tools that print one document after
+/// another (`{"a": 1} {"b": 2}`).
+///
+/// Each item is a value together with the byte offset at which it starts in the
+/// input. Values may be separated by any amount of whitespace, or by none at all
+/// when the boundary is unambiguous (`[1][2]`). Iteration stops after the first
+/// error.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{JsonStream, JsonValue};
+///
+/// let mut stream = JsonStream::new(r#"{"a": 1} {"b": 2}
+/// [3]"#);
+/// let (offset, value) = stream.next().unwrap()?;
+/// assert_eq!(offset, 0);
+/// assert_eq!(value.get("a"), Some(&JsonValue::Number(1.into())));
+/// assert_eq!(stream.next().unwrap()?.0, 9);
+/// assert_eq!(stream.next().unwrap()?.0, 18);
+/// assert!(stream.next().is_none());
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+pub struct JsonStream<'input> {
+    tokenizer: Tokenizer<'input>,
+    max_depth: usize,
+    allow_trailing_commas: bool,
+    duplicate_keys: DuplicateKeyPolicy,
+    pool: JsonValuePool,
+    keys: KeyInterner,
+    failed: bool,
+}
+
+impl<'input> JsonStream<'input> {
+    /// Creates a `JsonStream` over `input` with the default [`ParserOptions`].
+    pub fn new(input: &'input str) -> Self {
+        Self::with_options(input, &ParserOptions::default())
+    }
+
+    /// Creates a `JsonStream` over `input` that parses every value with the given
+    /// [`ParserOptions`].
+    pub fn with_options(input: &'input str, options: &ParserOptions) -> Self {
+        let mut tokenizer = Tokenizer::new(input);
+        tokenizer.configure(options);
+        tokenizer.skip_bom();
+        Self {
+            tokenizer,
+            max_depth: options.max_depth,
+            allow_trailing_commas: options.allow_trailing_commas,
+            duplicate_keys: options.duplicate_keys,
+            pool: JsonValuePool::with_limit(0),
+            keys: KeyInterner::new(options),
+            failed: false,
+        }
+    }
+
+    /*
+     * Parses the next value, returning `Ok(None)` once only whitespace is left.
+     */
+    fn next_value(&mut self) -> JsonResult<Option<(usize, JsonValue)>> {
+        let Some(token) = self.tokenizer.next_raw_token()? else {
+            return Ok(None);
+        };
+        let offset = self.tokenizer.token_start();
+        self.tokenizer.reset_value_count();
+        let value = SinglePassParser {
+            tokenizer: &mut self.tokenizer,
+            max_depth: self.max_depth,
+            depth: 0,
+            allow_trailing_commas: self.allow_trailing_commas,
+            sink: Build {
+                pool: &mut self.pool,
+                keys: &mut self.keys,
+                duplicate_keys: self.duplicate_keys,
+            },
+        }
+        .parse_value(token)?;
+        Ok(Some((offset, value)))
+    }
+}
+
+impl Iterator for JsonStream<'_> {
+    type Item = JsonResult<(usize, JsonValue)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.next_value() {
+            Ok(item) => item.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for JsonStream<'_> {}
+
+/*
+ * Validates that `bytes` are UTF-8, reporting the location of the first invalid
+ * sequence otherwise.
+ */
+pub(crate) fn decode_utf8(bytes: &[u8]) -> JsonResult<&str> {
+    std::str::from_utf8(bytes).map_err(|err| {
+        let offset = err.valid_up_to();
+        JsonError::InvalidUtf8 {
+            offset,
+            // `None` means the input ends in the middle of a sequence
+            length: err.error_len().unwrap_or(bytes.len() - offset),
+        }
+    })
+}
+
+/*
+ * Reads a whole file as text, detecting its encoding like `parse_json_bytes`.
+ * Invalid UTF-8 is reported as `JsonError::InvalidUtf8` rather than as an IO
+ * error.
+ */
+pub(crate) fn read_json_text(path: impl AsRef<Path>) -> JsonResult<String> {
+    let bytes = fs::read(path)?;
+    if let Cow::Owned(text) = decode_json_text(&bytes)? {
+        return Ok(text);
+    }
+    // Validated above, so this conversion cannot fail
+    Ok(String::from_utf8(bytes).expect("bytes are valid UTF-8"))
+}
+
+/// Parses JSON from raw bytes.
+///
+/// The encoding is detected from the first bytes as RFC 8259 suggests: UTF-8,
+/// with or without a byte order mark, is validated in place, so buffers read
+/// from a socket or file need no intermediate `String`. UTF-16 in either byte
+/// order, recognized by its byte order mark or by the zero bytes around the
+/// first ASCII character, is transcoded first; error positions then refer to
+/// the transcoded text.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json_bytes, JsonError};
+///
+/// let value = parse_json_bytes(br#"{"ok": true}"#)?;
+/// assert!(value.get("ok").is_some());
+///
+/// // UTF-16LE with a byte order mark
+/// let value = parse_json_bytes(b"\xff\xfe[\x001\x00]\x00")?;
+/// assert_eq!(value.as_array().map(|a| a.len()), Some(1));
+///
+/// let err = parse_json_bytes(b"[\"\xff\"]").unwrap_err();
+/// assert_eq!(err, JsonError::InvalidUtf8 { offset: 2, length: 1 });
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::InvalidUtf8`] or [`JsonError::InvalidUtf16`] if the input
+/// is malformed in its detected encoding, [`JsonError::UnsupportedEncoding`] if
+/// it is UTF-32, or the same errors as [`parse_json`] if it is not valid JSON.
+pub fn parse_json_bytes(input: &[u8]) -> JsonResult<JsonValue> {
+    parse_json(&decode_json_text(input)?)
+}
+
+/// Reads a file at the given path and parses its contents as JSON. The path can
+/// be anything that converts to a [`Path`], such as a `&str` or a `PathBuf`.
+/// The encoding is detected as described for [`parse_json_bytes`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::path::PathBuf;
+///
+/// use rust_json_parser::parse_json_file;
+///
+/// let value = parse_json_file("data.json")?;
+/// println!("{}", value);
+///
+/// let config = PathBuf::from("config").join("settings.json");
+/// let settings = parse_json_file(&config)?;
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`JsonError::Io`] if the file cannot be read (e.g. not
+/// found or permission denied), [`JsonError::InvalidUtf8`],
+/// [`JsonError::InvalidUtf16`] or [`JsonError::UnsupportedEncoding`] if it
+/// cannot be decoded, or any other [`JsonError`] variant if the file contents
+/// are not valid JSON.
+pub fn parse_json_file(path: impl AsRef<Path>) -> JsonResult<JsonValue> {
+    parse_json_bytes(&fs::read(path)?)
 }
 
-/// Parses a JSON string and returns the corresponding [`JsonValue`].
+/// Reads JSON from any [`io::Read`](std::io::Read) source (sockets, stdin,
+/// decompression streams, ...) and parses it.
 ///
-/// This is the main entry point for parsing JSON. It tokenizes and parses in one step.
+/// The reader is wrapped in a [`BufReader`] and consumed until end of input; see
+/// [`parse_json_buf_reader`] to supply an already-buffered reader.
 ///
 /// # Examples
 ///
 /// ```
-/// use rust_json_parser::{parse_json, JsonValue};
-///
-/// let value = parse_json(r#"{"name": "Alice"}"#)?;
-/// assert_eq!(value.get("name"), Some(&JsonValue::String("Alice".to_string())));
+/// use rust_json_parser::{parse_json_reader, JsonValue};
 ///
-/// let value = parse_json("[1, 2, 3]")?;
-/// assert_eq!(value.as_array().map(|a| a.len()), Some(3));
+/// let input: &[u8] = br#"{"ok": true}"#;
+/// let value = parse_json_reader(input)?;
+/// assert_eq!(value.get("ok"), Some(&JsonValue::Boolean(true)));
 /// # Ok::<(), rust_json_parser::JsonError>(())
 /// ```
 ///
 /// # Errors
 ///
-/// Returns a [`JsonError`](crate::JsonError) if the input is not valid JSON. This includes
-/// tokenization errors (invalid characters, malformed strings or numbers) and structural
-/// errors (missing commas, unclosed brackets, etc.).
-pub fn parse_json(input: &str) -> JsonResult<JsonValue> {
-    JsonParser::new(input)?.parse()
+/// Returns [`JsonError::Io`] if reading fails, [`JsonError::InvalidUtf8`] if the
+/// input is not valid UTF-8, or any other [`JsonError`] variant if the input
+/// is not valid JSON.
+pub fn parse_json_reader<R: Read>(reader: R) -> JsonResult<JsonValue> {
+    parse_json_buf_reader(BufReader::new(reader))
 }
 
-/// Reads a file at the given path and parses its contents as JSON.
+/// Reads JSON from a [`BufRead`] source and parses it.
+///
+/// The reader's internal buffer is handed chunk by chunk to an
+/// [`IncrementalParser`], so no additional copy of the input is made while
+/// reading, and a malformed document is reported as soon as its root value is
+/// complete.
 ///
 /// # Examples
 ///
-/// ```no_run
-/// use rust_json_parser::parse_json_file;
+/// ```
+/// use std::io::Cursor;
+/// use rust_json_parser::parse_json_buf_reader;
 ///
-/// let value = parse_json_file("data.json")?;
-/// println!("{}", value);
+/// let value = parse_json_buf_reader(Cursor::new("[1, 2, 3]"))?;
+/// assert_eq!(value.as_array().map(|a| a.len()), Some(3));
 /// # Ok::<(), rust_json_parser::JsonError>(())
 /// ```
 ///
 /// # Errors
 ///
-/// Returns [`JsonError::Io`](crate::JsonError::Io) if the file cannot be read (e.g. not
-/// found or permission denied), or any other [`JsonError`](crate::JsonError) variant if the
-/// file contents are not valid JSON.
-pub fn parse_json_file(path: &str) -> JsonResult<JsonValue> {
-    let contents = fs::read_to_string(path)?;
-    parse_json(&contents)
+/// Returns [`JsonError::Io`] if reading fails, [`JsonError::InvalidUtf8`] if the
+/// input is not valid UTF-8, or any other [`JsonError`] variant if the input
+/// is not valid JSON.
+pub fn parse_json_buf_reader<R: BufRead>(mut reader: R) -> JsonResult<JsonValue> {
+    let mut parser = IncrementalParser::new();
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        let len = chunk.len();
+        parser.feed(chunk)?;
+        reader.consume(len);
+    }
+    parser.finish()
 }
 
 #[cfg(test)]
@@ -505,20 +1645,31 @@ mod tests {
         assert!(matches!(parser, Err(JsonError::UnexpectedToken { .. })));
     }
 
+    #[test]
+    fn test_parser_reports_later_tokenize_errors_on_parse() {
+        // Tokens are read on demand, so errors past the lookahead surface in parse()
+        let mut parser = JsonParser::new(r#"[1, 2, "\q"]"#).unwrap();
+        assert!(matches!(
+            parser.parse(),
+            Err(JsonError::InvalidEscape { .. })
+        ));
+    }
+
     // === Primitive Parsing Tests ===
 
     #[test]
     fn test_parse_number() {
         let mut parser = JsonParser::new("42").unwrap();
         let value = parser.parse().unwrap();
-        assert_eq!(value, JsonValue::Number(42.0));
+        assert_eq!(value, JsonValue::Number(42.0.into()));
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_parse_negative_number() {
         let mut parser = JsonParser::new("-3.14").unwrap();
         let value = parser.parse().unwrap();
-        assert_eq!(value, JsonValue::Number(-3.14));
+        assert_eq!(value, JsonValue::Number((-3.14).into()));
     }
 
     #[test]
@@ -653,6 +1804,674 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_error_missing_value_in_object() {
+        assert!(parse_json(r#"{"a": }"#).is_err());
+        assert!(parse_json("{{").is_err());
+    }
+
+    #[test]
+    fn test_parse_json_error_positions_are_byte_offsets() {
+        match parse_json(r#"{"key": 1 2}"#) {
+            Err(JsonError::UnexpectedToken {
+                expected, position, ..
+            }) => {
+                assert_eq!(expected, ", or closing brace");
+                assert_eq!(position, 10);
+            }
+            other => panic!("Expected UnexpectedToken, got {:?}", other),
+        }
+        assert!(matches!(
+            parse_json("[1, 2"),
+            Err(JsonError::UnexpectedEndOfInput { position: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_json_bytes_reports_invalid_utf8() {
+        assert_eq!(
+            parse_json_bytes(b"[\"\xff\"]"),
+            Err(JsonError::InvalidUtf8 {
+                offset: 2,
+                length: 1
+            })
+        );
+        // A truncated multi-byte sequence at the end covers the remaining bytes
+        assert_eq!(
+            parse_json_bytes(b"\"\xe2\x82"),
+            Err(JsonError::InvalidUtf8 {
+                offset: 1,
+                length: 2
+            })
+        );
+        assert_eq!(
+            parse_json_bytes("[\"é\"]".as_bytes()).unwrap(),
+            JsonValue::Array(vec![JsonValue::String("é".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_mixed_escapes_and_unicode_round_trip() {
+        let input = r#"{"clé\n": ["ñandú\t🦀", "\"日本\" \u00fc"]}"#;
+        let value = parse_json(input).unwrap();
+        let items = value.get("clé\n").and_then(|v| v.as_array()).unwrap();
+        assert_eq!(items[0].as_str(), Some("ñandú\t🦀"));
+        assert_eq!(items[1].as_str(), Some("\"日本\" ü"));
+        assert_eq!(parse_json(&value.to_string()).unwrap(), value);
+        let mut parser = JsonParser::new(input).unwrap();
+        assert_eq!(parser.parse().unwrap(), value);
+    }
+
+    #[test]
+    fn test_large_integers_round_trip() {
+        let input = "[9007199254740993,-9223372036854775808,18446744073709551615,1.5]";
+        assert_eq!(parse_json(input).unwrap().to_string(), input);
+        assert_eq!(
+            JsonParser::new(input).unwrap().parse().unwrap().to_string(),
+            input
+        );
+    }
+
+    #[test]
+    fn test_preserve_number_text() {
+        let options = ParserOptions {
+            preserve_number_text: true,
+            ..ParserOptions::default()
+        };
+        let input = r#"{"price": 1.50, "big": 1E+2}"#;
+        let value = parse_json_with_options(input, &options).unwrap();
+        assert_eq!(value.get("price").unwrap().to_string(), "1.50");
+        assert_eq!(value.get("big").unwrap().to_string(), "1E+2");
+        // Formatting is kept, but values still compare numerically
+        assert_eq!(value, parse_json(input).unwrap());
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_malformed_numbers() {
+        for (input, position) in [
+            ("0123", 1),
+            ("-", 1),
+            ("1.", 2),
+            ("1.e5", 2),
+            ("1e", 2),
+            ("1e2e3", 3),
+            ("--1", 1),
+            ("[1, -01]", 6),
+        ] {
+            match parse_json(input) {
+                Err(JsonError::InvalidNumber { position: p, .. }) => {
+                    assert_eq!(p, position, "{}", input)
+                }
+                other => panic!("Expected InvalidNumber for {}, got {:?}", input, other),
+            }
+        }
+        for input in ["0", "-0", "10", "-1.5e-3", "2E+10", "0.0"] {
+            assert!(parse_json(input).is_ok(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_stray_characters() {
+        assert_eq!(
+            parse_json("[1 \u{00e9} 2]"),
+            Err(JsonError::UnexpectedToken {
+                expected: "Valid JSON value".to_string(),
+                found: "é".to_string(),
+                position: 3,
+            })
+        );
+        assert!(parse_json("[1,\u{000b}2]").is_err());
+    }
+
+    #[test]
+    fn test_lenient_mode() {
+        let options = ParserOptions {
+            strict: false,
+            ..ParserOptions::default()
+        };
+        assert_eq!(
+            parse_json_with_options("0123", &options).unwrap(),
+            JsonValue::Number(123.into())
+        );
+        assert_eq!(
+            parse_json_with_options("[1. \u{00e9}]", &options).unwrap(),
+            JsonValue::Array(vec![JsonValue::Number(1.0.into())])
+        );
+        assert_eq!(
+            parse_json_with_options("\"a\tb\"", &options).unwrap(),
+            JsonValue::String("a\tb".to_string())
+        );
+        let mut parser = JsonParser::with_options("0123", &options).unwrap();
+        assert_eq!(parser.parse().unwrap(), JsonValue::Number(123.into()));
+        // The two-phase parser is strict by default as well
+        assert!(JsonParser::new("0123").is_err());
+    }
+
+    #[test]
+    fn test_allow_comments() {
+        let input = "// settings\n{\"a\": /* inline */ 1}";
+        assert!(parse_json(input).is_err());
+        let options = ParserOptions {
+            allow_comments: true,
+            ..ParserOptions::default()
+        };
+        let expected = parse_json(r#"{"a": 1}"#).unwrap();
+        assert_eq!(parse_json_with_options(input, &options).unwrap(), expected);
+        let mut parser = JsonParser::with_options(input, &options).unwrap();
+        assert_eq!(parser.parse().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_allow_trailing_commas() {
+        let input = r#"{"list": [1, 2,], "nested": {"b": true,},}"#;
+        assert!(parse_json(input).is_err());
+        let options = ParserOptions {
+            allow_trailing_commas: true,
+            ..ParserOptions::default()
+        };
+        let expected = parse_json(r#"{"list": [1, 2], "nested": {"b": true}}"#).unwrap();
+        assert_eq!(parse_json_with_options(input, &options).unwrap(), expected);
+        let mut parser = JsonParser::with_options(input, &options).unwrap();
+        assert_eq!(parser.parse().unwrap(), expected);
+
+        // Only a single comma after at least one member is allowed
+        for input in ["[,]", "[1,,]", "{,}", r#"{"a": 1,,}"#] {
+            assert!(
+                parse_json_with_options(input, &options).is_err(),
+                "{}",
+                input
+            );
+            let parser = JsonParser::with_options(input, &options);
+            assert!(parser.and_then(|mut p| p.parse()).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_json5_options() {
+        let input = "{\n  // comment\n  name: 'Ann',\n  $tags: ['a', \"b\",],\n  'quoted key': 'it\\'s',\n}";
+        let expected =
+            parse_json(r#"{"name": "Ann", "$tags": ["a", "b"], "quoted key": "it's"}"#).unwrap();
+        assert!(parse_json(input).is_err());
+        let options = ParserOptions::json5();
+        assert_eq!(parse_json_with_options(input, &options).unwrap(), expected);
+        let mut parser = JsonParser::with_options(input, &options).unwrap();
+        assert_eq!(parser.parse().unwrap(), expected);
+
+        // Identifiers are only accepted as keys
+        for input in ["[foo]", "{a: b}", "bare", "{a b}"] {
+            assert!(
+                parse_json_with_options(input, &options).is_err(),
+                "{}",
+                input
+            );
+            let parser = JsonParser::with_options(input, &options);
+            assert!(parser.and_then(|mut p| p.parse()).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_multiline_strings_serialize_escaped() {
+        let input = "{\"script\": `echo \"hi\"\nexit 1`}";
+        let options = ParserOptions {
+            allow_multiline_strings: true,
+            ..ParserOptions::default()
+        };
+        let value = parse_json_with_options(input, &options).unwrap();
+        assert_eq!(
+            value.get("script").and_then(JsonValue::as_str),
+            Some("echo \"hi\"\nexit 1")
+        );
+        assert_eq!(value.to_string(), r#"{"script": "echo \"hi\"\nexit 1"}"#);
+        assert_eq!(parse_json(&value.to_string()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_duplicate_key_policies() {
+        let input = r#"{"a": 1, "a": [2]}"#;
+        let with_policy = |duplicate_keys| ParserOptions {
+            duplicate_keys,
+            ..ParserOptions::default()
+        };
+        let first = with_policy(DuplicateKeyPolicy::KeepFirst);
+        let reject = with_policy(DuplicateKeyPolicy::Reject);
+
+        let value = parse_json(input).unwrap();
+        assert_eq!(
+            value.get("a").and_then(|v| v.get_index(0)),
+            Some(&JsonValue::Number(2.into()))
+        );
+        let value = parse_json_with_options(input, &first).unwrap();
+        assert_eq!(value.get("a"), Some(&JsonValue::Number(1.into())));
+        let mut parser = JsonParser::with_options(input, &first).unwrap();
+        assert_eq!(parser.parse().unwrap(), value);
+
+        assert_eq!(
+            parse_json_with_options(input, &reject),
+            Err(JsonError::DuplicateKey {
+                key: "a".to_string(),
+                position: 9,
+            })
+        );
+        let mut parser = JsonParser::with_options(input, &reject).unwrap();
+        assert!(matches!(
+            parser.parse(),
+            Err(JsonError::DuplicateKey { .. })
+        ));
+        // Equal keys in different objects are fine
+        assert!(parse_json_with_options(r#"[{"a": 1}, {"a": 2}]"#, &reject).is_ok());
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let options = ParserOptions {
+            max_depth: 3,
+            ..ParserOptions::default()
+        };
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        assert!(parse_json_with_options(&nested(3), &options).is_ok());
+        assert!(parse_json_with_options(r#"[{"a": [1]}]"#, &options).is_ok());
+        assert_eq!(
+            parse_json_with_options(r#"[{"a": [{}]}]"#, &options),
+            Err(JsonError::DepthLimitExceeded {
+                max_depth: 3,
+                position: 8,
+            })
+        );
+
+        let too_deep = nested(4);
+        let mut parser = JsonParser::with_options(&too_deep, &options).unwrap();
+        assert!(matches!(
+            parser.parse(),
+            Err(JsonError::DepthLimitExceeded { max_depth: 3, .. })
+        ));
+        let deep_enough = nested(3);
+        let mut parser = JsonParser::with_options(&deep_enough, &options).unwrap();
+        assert!(parser.parse().is_ok());
+    }
+
+    #[test]
+    fn test_resource_limits() {
+        let limits = |max_string_length, max_values, max_input_size| ParserOptions {
+            max_string_length,
+            max_values,
+            max_input_size,
+            ..ParserOptions::default()
+        };
+
+        // Strings and keys are measured as written, between the quotes
+        let options = limits(3, usize::MAX, usize::MAX);
+        assert!(parse_json_with_options(r#"{"abc": "\n!"}"#, &options).is_ok());
+        assert_eq!(
+            parse_json_with_options(r#"["abc", "abcd"]"#, &options),
+            Err(JsonError::StringLengthExceeded {
+                max_length: 3,
+                position: 8,
+            })
+        );
+        assert!(matches!(
+            parse_json_with_options(r#"{"abcd": 1}"#, &options),
+            Err(JsonError::StringLengthExceeded { position: 1, .. })
+        ));
+
+        // Keys are not values; the root and every container are
+        let options = limits(usize::MAX, 5, usize::MAX);
+        assert!(parse_json_with_options(r#"{"a": 1, "b": [true, null]}"#, &options).is_ok());
+        assert_eq!(
+            parse_json_with_options(r#"{"a": 1, "b": [true, null, 2]}"#, &options),
+            Err(JsonError::ValueCountExceeded {
+                max_values: 5,
+                position: 27,
+            })
+        );
+        let mut parser = JsonParser::with_options("[1, [2], 3]", &options).unwrap();
+        assert!(parser.parse().is_ok());
+        let mut parser = JsonParser::with_options("[1, [2], {}, 3]", &options).unwrap();
+        assert!(matches!(
+            parser.parse(),
+            Err(JsonError::ValueCountExceeded { max_values: 5, .. })
+        ));
+        // A stream applies the limit to each document
+        let values = JsonStream::with_options("[1, 2, 3] [4, 5, 6]", &options);
+        assert_eq!(values.filter(Result::is_ok).count(), 2);
+
+        let options = limits(usize::MAX, usize::MAX, 8);
+        assert!(parse_json_with_options("[1, 2]  ", &options).is_ok());
+        assert_eq!(
+            parse_json_with_options("[1, 2, 3]", &options),
+            Err(JsonError::InputSizeExceeded {
+                max_size: 8,
+                size: 9,
+            })
+        );
+        assert!(JsonParser::with_options("[1, 2, 3]", &options).is_err());
+    }
+
+    #[test]
+    fn test_default_depth_limit_rejects_hostile_nesting() {
+        let hostile = "[".repeat(1_000_000);
+        assert!(matches!(
+            parse_json(&hostile),
+            Err(JsonError::DepthLimitExceeded { .. })
+        ));
+        assert!(matches!(
+            JsonParser::new(&hostile).unwrap().parse(),
+            Err(JsonError::DepthLimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_deep_nesting_does_not_use_call_stack() {
+        let depth = 200_000;
+        let input = format!("{}{}", r#"{"a":["#.repeat(depth), "]}".repeat(depth));
+        let options = ParserOptions {
+            max_depth: usize::MAX,
+            ..ParserOptions::default()
+        };
+        let value = parse_json_with_options(&input, &options).unwrap();
+        // The default recursive drop would overflow
+        JsonValuePool::with_limit(0).recycle(value);
+    }
+
+    #[test]
+    fn test_parse_json_matches_two_phase_parser() {
+        let input = r#"{"a": [1, "two", {"b": null}], "c": true, "d": "e\u0021"}"#;
+        let two_phase = JsonParser::new(input).unwrap().parse().unwrap();
+        assert_eq!(parse_json(input).unwrap(), two_phase);
+    }
+
+    #[test]
+    fn test_two_phase_parser_reports_byte_offsets() {
+        for (input, position) in [
+            ("[1 2]", 3),
+            ("[1, }", 4),
+            (r#"{"a" 1}"#, 5),
+            (r#"{"key": tru}"#, 8),
+            (r#"{"a": 1, "b""#, 12),
+            ("  [1,", 5),
+            ("", 0),
+        ] {
+            let err = JsonParser::new(input).and_then(|mut parser| parser.parse());
+            assert_eq!(err.unwrap_err().input_offset(), Some(position), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_two_phase_parser_rejects_containers_as_keys() {
+        for (input, position) in [("{{}}", 1), ("{[1]}", 1), (r#"{"a" {}}"#, 5)] {
+            let err = JsonParser::new(input).and_then(|mut parser| parser.parse());
+            assert!(
+                matches!(err, Err(JsonError::UnexpectedToken { position: p, .. }) if p == position),
+                "{}: {:?}",
+                input,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_json_file_accepts_paths() {
+        let dir = crate::files::tests::test_dir("parse_file_paths");
+        let path = dir.join("doc.json");
+        std::fs::write(&path, b"[1, 2]").unwrap();
+        assert_eq!(
+            parse_json_file(&path).unwrap(),
+            parse_json("[1, 2]").unwrap()
+        );
+        assert_eq!(parse_json_file(path.to_str().unwrap()).unwrap().len(), 2);
+        assert!(
+            parse_json_file(dir.join("missing.json"))
+                .unwrap_err()
+                .is_io()
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_validate_json_matches_parse_json() {
+        let deep = "[".repeat(crate::options::DEFAULT_MAX_DEPTH + 1);
+        for input in [
+            r#"{"a": [1, 2.5e3, "x\u0041\n", true, false, null, {}, []], "b": {"c": {}}}"#,
+            "  42  ",
+            "",
+            "[1 2]",
+            "[1,]",
+            r#"{"a" 1}"#,
+            r#"{"a": 1,}"#,
+            r#"{1: 2}"#,
+            "[1, 2}",
+            r#"["a\qb"]"#,
+            r#"["\ud800"]"#,
+            "[01]",
+            "[1",
+            "1 2",
+            "]",
+            "{\"a\": \"\u{1}\"}",
+            r#"["\n" 1]"#,
+            r#"{"a\tb" 1}"#,
+            r#"[1 "\u00e9\"x"]"#,
+            &deep,
+        ] {
+            assert_eq!(
+                validate_json(input),
+                parse_json(input).map(|_| ()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_json_matches_pointer() {
+        let input = r#"{
+            "meta": {"skip": ["x\ty", {"deep": [[], {}]}], "n": -1.5e3},
+            "data": {"items": [0, "one", {"id": 2, "tags": ["a"]}, null]},
+            "a/b": {"m~n": true, "caf\u00e9": "\u00e9t\u00e9"},
+            "": [false]
+        }"#;
+        let document = parse_json(input).unwrap();
+        for pointer in [
+            "",
+            "/meta",
+            "/meta/n",
+            "/data/items",
+            "/data/items/0",
+            "/data/items/1",
+            "/data/items/2/tags/0",
+            "/data/items/3",
+            "/data/items/4",
+            "/data/items/01",
+            "/data/items/-",
+            "/data/items/2/id/x",
+            "/a~1b/m~0n",
+            "/a~1b/café",
+            "/",
+            "//0",
+            "/missing",
+            "/meta/skip/1/deep/1",
+        ] {
+            assert_eq!(
+                extract_json(input, pointer).unwrap().as_ref(),
+                document.pointer(pointer),
+                "{}",
+                pointer
+            );
+        }
+    }
+
+    #[test]
+    fn test_extract_json_errors() {
+        assert!(matches!(
+            extract_json("{}", "a"),
+            Err(JsonError::InvalidQuery { position: 0, .. })
+        ));
+        // Errors before or inside the target are reported like parse_json does
+        for (input, pointer) in [
+            (r#"{"a": [1 2], "b": 3}"#, "/b"),
+            (r#"{"a": "\q", "b": 3}"#, "/b"),
+            (r#"{"a" 1}"#, "/a"),
+            (r#"{"b": [1,]}"#, "/b"),
+            ("[1, 2", "/5"),
+            ("", ""),
+        ] {
+            assert_eq!(
+                extract_json(input, pointer).unwrap_err(),
+                parse_json(input).unwrap_err(),
+                "{}",
+                input
+            );
+        }
+        // Reading stops after the target
+        assert_eq!(
+            extract_json(r#"{"a": 1, "b": oops"#, "/a").unwrap(),
+            Some(JsonValue::Number(1.into()))
+        );
+        let levels = crate::options::DEFAULT_MAX_DEPTH + 1;
+        let deep = format!("{}1", r#"{"a":"#.repeat(levels));
+        let path = "/a".repeat(levels);
+        assert!(matches!(
+            extract_json(&deep, &path),
+            Err(JsonError::DepthLimitExceeded { .. })
+        ));
+        // The levels above the target count towards the limit of the target itself
+        let deep = format!("{}{}", r#"{"a":"#.repeat(100), "[".repeat(40));
+        assert_eq!(
+            extract_json(&deep, &"/a".repeat(100)).unwrap_err(),
+            parse_json(&deep).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_byte_order_mark_is_skipped() {
+        let expected = parse_json(r#"{"a": [1]}"#).unwrap();
+        assert_eq!(parse_json("\u{FEFF}{\"a\": [1]}").unwrap(), expected);
+        assert_eq!(
+            parse_json_bytes(b"\xEF\xBB\xBF{\"a\": [1]}").unwrap(),
+            expected
+        );
+        assert!(validate_json("\u{FEFF}[]").is_ok());
+        let mut parser = JsonParser::new("\u{FEFF}{\"a\": [1]}").unwrap();
+        assert_eq!(parser.parse().unwrap(), expected);
+        let values: Vec<_> = JsonStream::new("\u{FEFF}1 2")
+            .collect::<JsonResult<_>>()
+            .unwrap();
+        assert_eq!(values, [(3, 1.into()), (5, 2.into())]);
+        assert_eq!(extract_json("\u{FEFF}[7]", "/0").unwrap(), Some(7.into()));
+        // Positions still count the mark's bytes
+        assert_eq!(
+            parse_json("\u{FEFF}[1,]"),
+            Err(JsonError::UnexpectedToken {
+                expected: "valid JSON value".to_string(),
+                found: "RightBracket".to_string(),
+                position: 6,
+            })
+        );
+        // Only a single mark at the very start is skipped
+        assert!(parse_json(" \u{FEFF}1").is_err());
+        assert!(parse_json("\u{FEFF}\u{FEFF}1").is_err());
+    }
+
+    #[test]
+    fn test_parse_json_bytes_detects_utf16() {
+        let text = r#"{"name": "Zoë", "emoji": "😀"}"#;
+        let expected = parse_json(text).unwrap();
+        let le: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let be: Vec<u8> = std::iter::once(0xFEFF)
+            .chain(text.encode_utf16())
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        assert_eq!(parse_json_bytes(&le).unwrap(), expected);
+        assert_eq!(parse_json_bytes(&be).unwrap(), expected);
+        assert!(validate_json_bytes(&be).is_ok());
+        assert!(matches!(
+            parse_json_bytes(b"{\0\0\0}\0\0\0"),
+            Err(JsonError::UnsupportedEncoding { .. })
+        ));
+    }
+
+    #[test]
+    fn test_trailing_characters_rejected() {
+        for (input, position) in [
+            ("42 garbage", 3),
+            ("[1,2] xyz", 6),
+            ("{} {}", 3),
+            ("\"a\"\"b", 3),
+            ("null ]", 5),
+        ] {
+            assert_eq!(
+                parse_json(input),
+                Err(JsonError::TrailingCharacters { position }),
+                "{}",
+                input
+            );
+        }
+        assert!(parse_json(" [1] \n\t").is_ok());
+
+        let options = ParserOptions {
+            allow_comments: true,
+            ..ParserOptions::default()
+        };
+        assert!(parse_json_with_options("[1] // done", &options).is_ok());
+        assert_eq!(
+            parse_json_bytes(b"1 2"),
+            Err(JsonError::TrailingCharacters { position: 2 })
+        );
+
+        // The other parsers stop at the same place
+        struct Ignore;
+        impl crate::JsonEventHandler for Ignore {}
+        for (input, position) in [
+            ("42 43", 3),
+            ("[1]]", 3),
+            ("{}}", 2),
+            ("{} {}", 3),
+            ("null ]", 5),
+            ("\"a\" \"b\"", 4),
+        ] {
+            let expected = Err(JsonError::TrailingCharacters { position });
+            assert_eq!(parse_json(input), expected, "{}", input);
+            assert_eq!(
+                JsonParser::new(input).and_then(|mut parser| parser.parse()),
+                expected,
+                "{}",
+                input
+            );
+            assert_eq!(
+                crate::parse_json_borrowed(input).map(|value| value.into_owned()),
+                expected,
+                "{}",
+                input
+            );
+            assert_eq!(
+                crate::StreamingParser::new(input).parse_with(&mut Ignore),
+                Err(JsonError::TrailingCharacters { position }),
+                "{}",
+                input
+            );
+        }
+        assert!(JsonParser::new("[1] ").unwrap().parse().is_ok());
+    }
+
+    #[test]
+    fn test_parse_json_prefix() {
+        assert_eq!(
+            parse_json_prefix("{\"a\": [1]}tail").unwrap(),
+            (parse_json("{\"a\": [1]}").unwrap(), 10)
+        );
+        assert_eq!(
+            parse_json_prefix("  true  ").unwrap(),
+            (JsonValue::Boolean(true), 6)
+        );
+        assert!(matches!(
+            parse_json_prefix("[1, "),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+        assert_eq!(
+            parse_json_prefix("\u{FEFF}[1] [2]").unwrap(),
+            (parse_json("[1]").unwrap(), 6)
+        );
+    }
+
     // === Arrays Tests ===
 
     #[test]
@@ -664,16 +2483,16 @@ mod tests {
     #[test]
     fn test_parse_array_single() {
         let value = parse_json("[1]").unwrap();
-        assert_eq!(value, JsonValue::Array(vec![JsonValue::Number(1.0)]));
+        assert_eq!(value, JsonValue::Array(vec![JsonValue::Number(1.0.into())]));
     }
 
     #[test]
     fn test_parse_array_multiple() {
         let value = parse_json("[1, 2, 3]").unwrap();
         let expected = JsonValue::Array(vec![
-            JsonValue::Number(1.0),
-            JsonValue::Number(2.0),
-            JsonValue::Number(3.0),
+            JsonValue::Number(1.0.into()),
+            JsonValue::Number(2.0.into()),
+            JsonValue::Number(3.0.into()),
         ]);
         assert_eq!(value, expected);
     }
@@ -682,7 +2501,7 @@ mod tests {
     fn test_parse_array_mixed_types() {
         let value = parse_json(r#"[1, "two", true, null]"#).unwrap();
         let expected = JsonValue::Array(vec![
-            JsonValue::Number(1.0),
+            JsonValue::Number(1.0.into()),
             JsonValue::String("two".to_string()),
             JsonValue::Boolean(true),
             JsonValue::Null,
@@ -694,8 +2513,14 @@ mod tests {
     fn test_parse_nested_arrays() {
         let value = parse_json("[[1, 2], [3, 4]]").unwrap();
         let expected = JsonValue::Array(vec![
-            JsonValue::Array(vec![JsonValue::Number(1.0), JsonValue::Number(2.0)]),
-            JsonValue::Array(vec![JsonValue::Number(3.0), JsonValue::Number(4.0)]),
+            JsonValue::Array(vec![
+                JsonValue::Number(1.0.into()),
+                JsonValue::Number(2.0.into()),
+            ]),
+            JsonValue::Array(vec![
+                JsonValue::Number(3.0.into()),
+                JsonValue::Number(4.0.into()),
+            ]),
         ]);
         assert_eq!(value, expected);
     }
@@ -704,7 +2529,7 @@ mod tests {
     fn test_parse_deeply_nested() {
         let value = parse_json("[[[1]]]").unwrap();
         let expected = JsonValue::Array(vec![JsonValue::Array(vec![JsonValue::Array(vec![
-            JsonValue::Number(1.0),
+            JsonValue::Number(1.0.into()),
         ])])]);
         assert_eq!(value, expected);
     }
@@ -719,7 +2544,7 @@ mod tests {
     #[test]
     fn test_array_get_index() {
         let value = parse_json("[10, 20, 30]").unwrap();
-        assert_eq!(value.get_index(1), Some(&JsonValue::Number(20.0)));
+        assert_eq!(value.get_index(1), Some(&JsonValue::Number(20.0.into())));
         assert_eq!(value.get_index(5), None);
     }
 
@@ -728,14 +2553,14 @@ mod tests {
     #[test]
     fn test_parse_empty_object() {
         let value = parse_json("{}").unwrap();
-        assert_eq!(value, JsonValue::Object(HashMap::new()));
+        assert_eq!(value, JsonValue::Object(JsonMap::default()));
     }
 
     #[test]
     fn test_parse_object_single_key() {
         let value = parse_json(r#"{"key": "value"}"#).unwrap();
-        let mut expected = HashMap::new();
-        expected.insert("key".to_string(), JsonValue::String("value".to_string()));
+        let mut expected = JsonMap::default();
+        expected.insert("key".into(), JsonValue::String("value".to_string()));
         assert_eq!(value, JsonValue::Object(expected));
     }
 
@@ -747,7 +2572,7 @@ mod tests {
                 obj.get("name"),
                 Some(&JsonValue::String("Alice".to_string()))
             );
-            assert_eq!(obj.get("age"), Some(&JsonValue::Number(30.0)));
+            assert_eq!(obj.get("age"), Some(&JsonValue::Number(30.0.into())));
         } else {
             panic!("Expected object");
         }
@@ -758,7 +2583,7 @@ mod tests {
         let value = parse_json(r#"{"outer": {"inner": 1}}"#).unwrap();
         if let JsonValue::Object(outer) = value {
             if let Some(JsonValue::Object(inner)) = outer.get("outer") {
-                assert_eq!(inner.get("inner"), Some(&JsonValue::Number(1.0)));
+                assert_eq!(inner.get("inner"), Some(&JsonValue::Number(1.0.into())));
             } else {
                 panic!("Expected nested object");
             }
@@ -808,6 +2633,100 @@ mod tests {
         assert_eq!(value.get("missing"), None);
     }
 
+    // === Reader Tests ===
+
+    #[test]
+    fn test_parse_json_reader() {
+        let input: &[u8] = br#"{"items": [1, 2, 3], "name": "caf\u00e9"}"#;
+        let value = parse_json_reader(input).unwrap();
+        assert_eq!(
+            value
+                .get("items")
+                .and_then(|v| v.as_array())
+                .map(|a| a.len()),
+            Some(3)
+        );
+        assert_eq!(value.get("name").and_then(|v| v.as_str()), Some("café"));
+    }
+
+    #[test]
+    fn test_parse_json_buf_reader_small_buffer() {
+        // A tiny buffer forces the document to arrive in many chunks
+        let input = r#"{"text": "ñandú", "nested": {"list": [true, false, null]}}"#;
+        let reader = BufReader::with_capacity(3, input.as_bytes());
+        let value = parse_json_buf_reader(reader).unwrap();
+        assert_eq!(value, parse_json(input).unwrap());
+    }
+
+    #[test]
+    fn test_parse_json_reader_errors() {
+        assert!(matches!(
+            parse_json_reader(&b"[1, 2"[..]),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+        assert!(matches!(
+            parse_json_reader(&b"\"\xff\""[..]),
+            Err(JsonError::InvalidUtf8 {
+                offset: 1,
+                length: 1
+            })
+        ));
+        // UTF-16 is detected as by parse_json_bytes
+        for input in [
+            &b"[\x001\x00]\x00"[..],
+            b"\xff\xfe[\x001\x00]\x00",
+            b"\x00\x22\x22\x5b\x00\x22",
+        ] {
+            assert_eq!(
+                parse_json_reader(input).unwrap(),
+                parse_json_bytes(input).unwrap()
+            );
+        }
+        // Trailing input is rejected as by parse_json
+        for input in ["[1] x", "1 2", "[1]]"] {
+            assert_eq!(
+                parse_json_reader(input.as_bytes()),
+                parse_json(input),
+                "{}",
+                input
+            );
+        }
+    }
+
+    // === Stream Tests ===
+
+    #[test]
+    fn test_stream_yields_values_with_offsets() {
+        let input = "{\"a\": 1}\n[1][2] 3 \"x\"null  ";
+        let items: Vec<(usize, JsonValue)> =
+            JsonStream::new(input).collect::<JsonResult<_>>().unwrap();
+        let offsets: Vec<usize> = items.iter().map(|(offset, _)| *offset).collect();
+        assert_eq!(offsets, [0, 9, 12, 16, 18, 21]);
+        assert_eq!(items[3].1, JsonValue::Number(3.into()));
+        assert_eq!(items[5].1, JsonValue::Null);
+        assert_eq!(JsonStream::new(" \n ").count(), 0);
+    }
+
+    #[test]
+    fn test_stream_stops_after_error() {
+        let mut stream = JsonStream::new("[1] [2, ] [3]");
+        assert!(stream.next().unwrap().is_ok());
+        assert!(matches!(
+            stream.next(),
+            Some(Err(JsonError::UnexpectedToken { position: 8, .. }))
+        ));
+        assert!(stream.next().is_none());
+
+        let options = ParserOptions {
+            allow_trailing_commas: true,
+            ..ParserOptions::default()
+        };
+        assert_eq!(
+            JsonStream::with_options("[1] [2, ] [3]", &options).count(),
+            3
+        );
+    }
+
     // === Serialization Tests ===
 
     #[test]
@@ -837,4 +2756,34 @@ mod tests {
         assert!(output.contains("\"nested\": 1"));
         assert!(output.contains("\"more\": \"end\""));
     }
+
+    #[cfg(feature = "shared-keys")]
+    #[test]
+    fn test_intern_keys_shares_repeated_keys() {
+        use std::sync::Arc;
+
+        fn id_keys(value: &JsonValue) -> Vec<JsonKey> {
+            match value {
+                JsonValue::Array(items) => items.iter().flat_map(id_keys).collect(),
+                JsonValue::Object(members) => vec![members.get_key_value("id").unwrap().0.clone()],
+                other => panic!("unexpected value {:?}", other),
+            }
+        }
+
+        let input = r#"[{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]"#;
+        let options = ParserOptions {
+            intern_keys: true,
+            ..ParserOptions::default()
+        };
+        let keys = id_keys(&parse_json_with_options(input, &options).unwrap());
+        assert!(Arc::ptr_eq(&keys[0], &keys[1]));
+        let keys = id_keys(&parse_json_with_options(input, &ParserOptions::default()).unwrap());
+        assert!(!Arc::ptr_eq(&keys[0], &keys[1]));
+
+        // A stream shares keys across documents
+        let keys: Vec<JsonKey> = JsonStream::with_options(r#"{"id": 1} {"id": 2}"#, &options)
+            .flat_map(|item| id_keys(&item.unwrap().1))
+            .collect();
+        assert!(Arc::ptr_eq(&keys[0], &keys[1]));
+    }
 }
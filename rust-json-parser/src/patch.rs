@@ -0,0 +1,428 @@
+//! JSON Patch (RFC 6902) and JSON Merge Patch (RFC 7386).
+//!
+//! A patch is an array of operation objects such as
+//! `{"op": "add", "path": "/tags/-", "value": "new"}`. Patches are applied with
+//! [`JsonValue::apply_patch`] and generated from two documents with
+//! [`json_diff`].
+//!
+//! A merge patch is a partial document instead, such as
+//! `{"replicas": 3, "debug": null}`, applied with [`JsonValue::merge_patch`].
+
+use std::borrow::Cow;
+
+use crate::query::{parse_index, push_segment, unescape_token};
+use crate::value::{JsonMap, JsonValue};
+use crate::{JsonError, JsonResult};
+
+/*
+ * Builds the error for the operation at `index` of a patch.
+ */
+fn patch_error(index: usize, message: impl Into<String>) -> JsonError {
+    JsonError::InvalidPatch {
+        index,
+        message: message.into(),
+    }
+}
+
+/*
+ * Splits a non-empty JSON Pointer into the pointer to its parent and its last,
+ * unescaped reference token.
+ */
+fn split_pointer(pointer: &str) -> Option<(&str, Cow<'_, str>)> {
+    let slash = pointer.rfind('/')?;
+    Some((&pointer[..slash], unescape_token(&pointer[slash + 1..])))
+}
+
+/*
+ * Returns the string member `name` of an operation.
+ */
+fn string_member<'a>(operation: &'a JsonValue, name: &str, index: usize) -> JsonResult<&'a str> {
+    let value = operation
+        .get(name)
+        .ok_or_else(|| patch_error(index, format!("missing \"{}\"", name)))?;
+    let pointer = value
+        .as_str()
+        .ok_or_else(|| patch_error(index, format!("\"{}\" must be a string", name)))?;
+    if name != "op" && !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(patch_error(
+            index,
+            format!("\"{}\" is not a JSON Pointer: {}", name, pointer),
+        ));
+    }
+    Ok(pointer)
+}
+
+fn add(target: &mut JsonValue, path: &str, value: JsonValue, index: usize) -> JsonResult<()> {
+    let Some((parent, token)) = split_pointer(path) else {
+        *target = value;
+        return Ok(());
+    };
+    match target.pointer_mut(parent) {
+        Some(JsonValue::Object(members)) => {
+            members.insert(token.into(), value);
+            Ok(())
+        }
+        Some(JsonValue::Array(items)) => {
+            let position = match token.as_ref() {
+                "-" => items.len(),
+                token => parse_index(token)
+                    .filter(|&i| i <= items.len())
+                    .ok_or_else(|| patch_error(index, format!("invalid array index: {}", path)))?,
+            };
+            items.insert(position, value);
+            Ok(())
+        }
+        _ => Err(patch_error(index, format!("path does not exist: {}", path))),
+    }
+}
+
+fn remove(target: &mut JsonValue, path: &str, index: usize) -> JsonResult<JsonValue> {
+    let missing = || patch_error(index, format!("path does not exist: {}", path));
+    let Some((parent, token)) = split_pointer(path) else {
+        return Ok(std::mem::replace(target, JsonValue::Null));
+    };
+    match target.pointer_mut(parent) {
+        Some(JsonValue::Object(members)) => members.remove(token.as_ref()).ok_or_else(missing),
+        Some(JsonValue::Array(items)) => parse_index(&token)
+            .filter(|&i| i < items.len())
+            .map(|i| items.remove(i))
+            .ok_or_else(missing),
+        _ => Err(missing()),
+    }
+}
+
+/*
+ * Applies a single operation of a patch.
+ */
+fn apply_operation(target: &mut JsonValue, operation: &JsonValue, index: usize) -> JsonResult<()> {
+    let op = string_member(operation, "op", index)?;
+    let path = string_member(operation, "path", index)?;
+    let value = || {
+        operation
+            .get("value")
+            .cloned()
+            .ok_or_else(|| patch_error(index, "missing \"value\""))
+    };
+    match op {
+        "add" => add(target, path, value()?, index),
+        "remove" => remove(target, path, index).map(drop),
+        "replace" => {
+            let value = value()?;
+            let slot = target
+                .pointer_mut(path)
+                .ok_or_else(|| patch_error(index, format!("path does not exist: {}", path)))?;
+            *slot = value;
+            Ok(())
+        }
+        "move" => {
+            let from = string_member(operation, "from", index)?;
+            if path.starts_with(from) && path[from.len()..].starts_with('/') {
+                return Err(patch_error(
+                    index,
+                    format!("cannot move {} into its own child {}", from, path),
+                ));
+            }
+            let value = remove(target, from, index)?;
+            add(target, path, value, index)
+        }
+        "copy" => {
+            let from = string_member(operation, "from", index)?;
+            let value = target
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| patch_error(index, format!("path does not exist: {}", from)))?;
+            add(target, path, value, index)
+        }
+        "test" => {
+            if target.pointer(path) == Some(&value()?) {
+                Ok(())
+            } else {
+                Err(patch_error(index, format!("test failed at {}", path)))
+            }
+        }
+        other => Err(patch_error(index, format!("unknown operation: {}", other))),
+    }
+}
+
+/*
+ * Applies every operation of `patch` to `target` in order. Called through
+ * `JsonValue::apply_patch`, which takes care of leaving the target unchanged
+ * on failure.
+ */
+pub(crate) fn apply(target: &mut JsonValue, patch: &JsonValue) -> JsonResult<()> {
+    let operations = patch
+        .as_array()
+        .ok_or_else(|| patch_error(0, "a patch must be an array of operations"))?;
+    for (index, operation) in operations.iter().enumerate() {
+        apply_operation(target, operation, index)?;
+    }
+    Ok(())
+}
+
+/*
+ * Applies a merge patch to `target`: members of an object patch are merged
+ * recursively, with `null` removing the member, and any other patch replaces
+ * the target.
+ */
+pub(crate) fn merge(target: &mut JsonValue, patch: &JsonValue) {
+    let JsonValue::Object(patch_members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !matches!(target, JsonValue::Object(_)) {
+        *target = JsonValue::Object(JsonMap::default());
+    }
+    let JsonValue::Object(members) = target else {
+        unreachable!("target was replaced by an object");
+    };
+    for (key, value) in patch_members {
+        if value.is_null() {
+            members.remove(key);
+        } else {
+            merge(members.entry(key.clone()).or_insert(JsonValue::Null), value);
+        }
+    }
+}
+
+fn operation(op: &str, path: &str, value: Option<&JsonValue>) -> JsonValue {
+    let mut members = JsonMap::default();
+    members.insert("op".into(), JsonValue::String(op.to_string()));
+    members.insert("path".into(), JsonValue::String(path.to_string()));
+    if let Some(value) = value {
+        members.insert("value".into(), value.clone());
+    }
+    JsonValue::Object(members)
+}
+
+fn diff_into(a: &JsonValue, b: &JsonValue, path: &mut String, patch: &mut Vec<JsonValue>) {
+    if a == b {
+        return;
+    }
+    match (a, b) {
+        (JsonValue::Object(old), JsonValue::Object(new)) => {
+            let mut keys: Vec<&str> = old.keys().chain(new.keys()).map(|key| &**key).collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for key in keys {
+                let length = path.len();
+                push_segment(path, key);
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_into(old, new, path, patch),
+                    (Some(_), None) => patch.push(operation("remove", path, None)),
+                    (None, Some(new)) => patch.push(operation("add", path, Some(new))),
+                    (None, None) => unreachable!("key comes from one of the objects"),
+                }
+                path.truncate(length);
+            }
+        }
+        (JsonValue::Array(old), JsonValue::Array(new)) => {
+            let length = path.len();
+            for (index, (old, new)) in old.iter().zip(new).enumerate() {
+                push_segment(path, &index.to_string());
+                diff_into(old, new, path, patch);
+                path.truncate(length);
+            }
+            // Remove surplus elements from the end so earlier indices stay valid
+            for index in (new.len()..old.len()).rev() {
+                push_segment(path, &index.to_string());
+                patch.push(operation("remove", path, None));
+                path.truncate(length);
+            }
+            for item in new.iter().skip(old.len()) {
+                push_segment(path, "-");
+                patch.push(operation("add", path, Some(item)));
+                path.truncate(length);
+            }
+        }
+        _ => patch.push(operation("replace", path, Some(b))),
+    }
+}
+
+/// Generates a JSON Patch that transforms `a` into `b`.
+///
+/// Object members are compared by key and arrays index by index, so inserting
+/// an element near the start of an array shows up as a series of replacements
+/// rather than a single `add`. The patch is correct but not necessarily the
+/// shortest possible.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{json_diff, parse_json};
+///
+/// let a = parse_json(r#"{"name": "app", "tags": ["x"], "debug": true}"#)?;
+/// let b = parse_json(r#"{"name": "api", "tags": ["x", "y"]}"#)?;
+/// let patch = json_diff(&a, &b);
+/// assert_eq!(patch.as_array().map(Vec::len), Some(3));
+///
+/// let mut patched = a.clone();
+/// patched.apply_patch(&patch)?;
+/// assert_eq!(patched, b);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+pub fn json_diff(a: &JsonValue, b: &JsonValue) -> JsonValue {
+    let mut patch = Vec::new();
+    diff_into(a, b, &mut String::new(), &mut patch);
+    JsonValue::Array(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    fn patched(doc: &str, patch: &str) -> JsonResult<JsonValue> {
+        let mut value = parse_json(doc).unwrap();
+        value.apply_patch(&parse_json(patch).unwrap())?;
+        Ok(value)
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        let cases = [
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#,
+                r#"{"baz": "qux", "foo": "bar"}"#,
+            ),
+            (
+                r#"{"foo": ["bar", "baz"]}"#,
+                r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#,
+                r#"{"foo": ["bar", "qux", "baz"]}"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": "bar"}"#,
+                r#"[{"op": "remove", "path": "/baz"}]"#,
+                r#"{"foo": "bar"}"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": "bar"}"#,
+                r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#,
+                r#"{"baz": "boo", "foo": "bar"}"#,
+            ),
+            (
+                r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+                r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#,
+                r#"{"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}"#,
+            ),
+            (
+                r#"{"foo": ["all", "grass", "cows", "eat"]}"#,
+                r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#,
+                r#"{"foo": ["all", "cows", "eat", "grass"]}"#,
+            ),
+            (
+                r#"{"foo": ["bar"]}"#,
+                r#"[{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]"#,
+                r#"{"foo": ["bar", ["abc", "def"]]}"#,
+            ),
+            (
+                r#"{"a/b": {"m~n": 1}}"#,
+                r#"[{"op": "copy", "from": "/a~1b/m~0n", "path": "/c"},
+                    {"op": "test", "path": "/c", "value": 1.0}]"#,
+                r#"{"a/b": {"m~n": 1}, "c": 1}"#,
+            ),
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "replace", "path": "", "value": [1]}]"#,
+                r#"[1]"#,
+            ),
+        ];
+        for (doc, patch, expected) in cases {
+            assert_eq!(
+                patched(doc, patch).unwrap(),
+                parse_json(expected).unwrap(),
+                "{}",
+                patch
+            );
+        }
+    }
+
+    #[test]
+    fn test_failures_leave_document_unchanged() {
+        let doc = r#"{"foo": [1, 2]}"#;
+        let failures = [
+            (r#"[{"op": "test", "path": "/foo/0", "value": "1"}]"#, 0),
+            (
+                r#"[{"op": "add", "path": "/bar", "value": 1}, {"op": "remove", "path": "/baz"}]"#,
+                1,
+            ),
+            (r#"[{"op": "add", "path": "/foo/3", "value": 1}]"#, 0),
+            (r#"[{"op": "add", "path": "/a/b", "value": 1}]"#, 0),
+            (r#"[{"op": "replace", "path": "/foo/01", "value": 1}]"#, 0),
+            (r#"[{"op": "move", "from": "/foo", "path": "/foo/0"}]"#, 0),
+            (r#"[{"op": "add", "path": "foo", "value": 1}]"#, 0),
+            (r#"[{"op": "add", "path": "/x"}]"#, 0),
+            (r#"[{"op": "frobnicate", "path": "/foo"}]"#, 0),
+            (r#"{"op": "remove", "path": "/foo"}"#, 0),
+        ];
+        for (patch, failed_index) in failures {
+            let mut value = parse_json(doc).unwrap();
+            let err = value.apply_patch(&parse_json(patch).unwrap()).unwrap_err();
+            assert!(
+                matches!(err, JsonError::InvalidPatch { index, .. } if index == failed_index),
+                "{}: {:?}",
+                patch,
+                err
+            );
+            assert_eq!(value, parse_json(doc).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_diff_round_trips() {
+        let pairs = [
+            ("{}", "{}"),
+            (r#"{"a": 1}"#, r#"{"a": 1.0}"#),
+            (
+                r#"{"a": 1, "b/c": [1, 2, 3]}"#,
+                r#"{"b/c": [1, 5], "d": null}"#,
+            ),
+            (r#"[1, [2, 3]]"#, r#"[1, [2, 3, 4], {"x": true}]"#),
+            (r#"{"a": [1]}"#, r#"{"a": {"0": 1}}"#),
+            ("1", r#""one""#),
+        ];
+        for (a, b) in pairs {
+            let (a, b) = (parse_json(a).unwrap(), parse_json(b).unwrap());
+            let patch = json_diff(&a, &b);
+            let mut patched = a.clone();
+            patched.apply_patch(&patch).unwrap();
+            assert_eq!(patched, b, "{}", patch);
+        }
+        let a = parse_json(r#"{"a": 1}"#).unwrap();
+        assert_eq!(json_diff(&a, &a), JsonValue::Array(Vec::new()));
+    }
+
+    #[test]
+    fn test_merge_patch_rfc_examples() {
+        let cases = [
+            (r#"{"a": "b"}"#, r#"{"a": "c"}"#, r#"{"a": "c"}"#),
+            (r#"{"a": "b"}"#, r#"{"b": "c"}"#, r#"{"a": "b", "b": "c"}"#),
+            (r#"{"a": "b"}"#, r#"{"a": null}"#, "{}"),
+            (r#"{"a": "b", "b": "c"}"#, r#"{"a": null}"#, r#"{"b": "c"}"#),
+            (r#"{"a": ["b"]}"#, r#"{"a": "c"}"#, r#"{"a": "c"}"#),
+            (r#"{"a": "c"}"#, r#"{"a": ["b"]}"#, r#"{"a": ["b"]}"#),
+            (
+                r#"{"a": {"b": "c"}}"#,
+                r#"{"a": {"b": "d", "c": null}}"#,
+                r#"{"a": {"b": "d"}}"#,
+            ),
+            (r#"{"a": [{"b": "c"}]}"#, r#"{"a": [1]}"#, r#"{"a": [1]}"#),
+            (r#"["a", "b"]"#, r#"["c", "d"]"#, r#"["c", "d"]"#),
+            (r#"{"a": "b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a": "foo"}"#, "null", "null"),
+            (r#"{"a": "foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e": null}"#, r#"{"a": 1}"#, r#"{"e": null, "a": 1}"#),
+            (r#"[1, 2]"#, r#"{"a": "b", "c": null}"#, r#"{"a": "b"}"#),
+            (
+                "{}",
+                r#"{"a": {"bb": {"ccc": null}}}"#,
+                r#"{"a": {"bb": {}}}"#,
+            ),
+        ];
+        for (doc, patch, expected) in cases {
+            let mut value = parse_json(doc).unwrap();
+            value.merge_patch(&parse_json(patch).unwrap());
+            assert_eq!(value, parse_json(expected).unwrap(), "{} + {}", doc, patch);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Buffer recycling for parse, modify and serialize loops.
+//!
+//! A [`JsonValuePool`] keeps the `Vec`, `HashMap` and `String` buffers of values
+//! that are no longer needed and hands them back to the parser for the next
+//! document. In steady state, a proxy that parses, tweaks and re-serializes
+//! similarly shaped messages then performs almost no allocations.
+
+use std::borrow::Cow;
+
+use crate::JsonResult;
+use crate::options::ParserOptions;
+use crate::parser::parse_json_pooled;
+use crate::value::{JsonArray, JsonMap, JsonValue};
+
+/// Default for the number of buffers of each kind a [`JsonValuePool`] retains.
+pub const DEFAULT_POOL_LIMIT: usize = 1024;
+
+/// A pool of reusable buffers for building [`JsonValue`]s.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{JsonValue, JsonValuePool};
+///
+/// let mut pool = JsonValuePool::new();
+/// for message in [r#"{"id": 1, "tags": ["a"]}"#, r#"{"id": 2, "tags": ["b"]}"#] {
+///     let mut value = pool.parse(message)?;
+///     if let JsonValue::Object(members) = &mut value {
+///         members.insert("seen".into(), JsonValue::Boolean(true));
+///     }
+///     let _output = value.to_string();
+///     // Hand the buffers back for the next message
+///     pool.recycle(value);
+/// }
+/// assert!(pool.pooled() > 0);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug)]
+pub struct JsonValuePool {
+    arrays: Vec<JsonArray>,
+    objects: Vec<JsonMap>,
+    strings: Vec<String>,
+    limit: usize,
+}
+
+impl Default for JsonValuePool {
+    fn default() -> Self {
+        Self::with_limit(DEFAULT_POOL_LIMIT)
+    }
+}
+
+impl JsonValuePool {
+    /// Creates an empty pool that retains up to [`DEFAULT_POOL_LIMIT`] buffers
+    /// of each kind.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty pool that retains up to `limit` buffers of each kind.
+    /// Buffers recycled beyond the limit are freed.
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            arrays: Vec::new(),
+            objects: Vec::new(),
+            strings: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Returns the total number of buffers currently held by the pool.
+    pub fn pooled(&self) -> usize {
+        self.arrays.len() + self.objects.len() + self.strings.len()
+    }
+
+    /// Parses `input` like [`parse_json`](crate::parse_json), building the result
+    /// from pooled buffers where possible.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`parse_json`](crate::parse_json).
+    pub fn parse(&mut self, input: &str) -> JsonResult<JsonValue> {
+        self.parse_with_options(input, &ParserOptions::default())
+    }
+
+    /// Parses `input` like [`parse_json_with_options`](crate::parse_json_with_options),
+    /// building the result from pooled buffers where possible.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as
+    /// [`parse_json_with_options`](crate::parse_json_with_options).
+    pub fn parse_with_options(
+        &mut self,
+        input: &str,
+        options: &ParserOptions,
+    ) -> JsonResult<JsonValue> {
+        parse_json_pooled(input, options, self)
+    }
+
+    /// Takes `value` apart and keeps its buffers for reuse. Nested values are
+    /// recycled too, without recursion, so this also frees values nested too
+    /// deeply to be dropped normally.
+    pub fn recycle(&mut self, value: JsonValue) {
+        let mut pending = vec![value];
+        while let Some(value) = pending.pop() {
+            match value {
+                JsonValue::String(s) => self.put_string(s),
+                #[cfg(feature = "custom-values")]
+                JsonValue::Custom(_) => {}
+                JsonValue::Array(mut items) => {
+                    pending.append(&mut items);
+                    if self.arrays.len() < self.limit {
+                        self.arrays.push(items);
+                    }
+                }
+                JsonValue::Object(mut members) => {
+                    // A `BTreeMap` keeps no spare capacity, so only its contents are reused
+                    #[cfg(feature = "btreemap")]
+                    let drained = std::mem::take(&mut members);
+                    #[cfg(not(feature = "btreemap"))]
+                    let drained = members.drain();
+                    for (key, value) in drained {
+                        // Shared keys may still be held by other documents
+                        #[cfg(not(feature = "shared-keys"))]
+                        self.put_string(key);
+                        #[cfg(feature = "shared-keys")]
+                        drop(key);
+                        pending.push(value);
+                    }
+                    if self.objects.len() < self.limit {
+                        self.objects.push(members);
+                    }
+                }
+                JsonValue::Number(_) | JsonValue::Boolean(_) | JsonValue::Null => {}
+            }
+        }
+    }
+
+    fn put_string(&mut self, mut s: String) {
+        if self.strings.len() < self.limit {
+            s.clear();
+            self.strings.push(s);
+        }
+    }
+
+    /*
+     * Returns an empty array buffer, reusing a pooled one if available.
+     */
+    pub(crate) fn take_array(&mut self) -> JsonArray {
+        self.arrays.pop().unwrap_or_default()
+    }
+
+    /*
+     * Returns an empty object buffer, reusing a pooled one if available.
+     */
+    pub(crate) fn take_object(&mut self) -> JsonMap {
+        self.objects.pop().unwrap_or_default()
+    }
+
+    /*
+     * Converts parsed string contents into an owned `String`. Borrowed contents
+     * are copied into a pooled buffer; already owned ones are used as they are.
+     */
+    pub(crate) fn make_string(&mut self, contents: Cow<'_, str>) -> String {
+        match contents {
+            Cow::Owned(s) => s,
+            Cow::Borrowed(s) => match self.strings.pop() {
+                Some(mut buffer) => {
+                    buffer.push_str(s);
+                    buffer
+                }
+                None => s.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn test_pooled_parse_matches_parse_json() {
+        let input = r#"{"a": [1, "two", {"b": null}], "c": "d\n"}"#;
+        let mut pool = JsonValuePool::new();
+        for _ in 0..3 {
+            let value = pool.parse(input).unwrap();
+            assert_eq!(value, parse_json(input).unwrap());
+            pool.recycle(value);
+        }
+    }
+
+    #[test]
+    fn test_recycled_buffers_are_reused() {
+        let mut pool = JsonValuePool::new();
+        let value = pool.parse(r#"[["x", "y"], {"k": "v"}]"#).unwrap();
+        pool.recycle(value);
+        // 2 arrays, 1 object and 4 strings (including the key, unless keys are shared)
+        let shared_keys = usize::from(cfg!(feature = "shared-keys"));
+        assert_eq!(pool.pooled(), 7 - shared_keys);
+
+        let value = pool.parse(r#"[["p", "q"], {"k": "w"}]"#).unwrap();
+        assert_eq!(pool.pooled(), 0);
+        assert_eq!(
+            value
+                .get_index(1)
+                .and_then(|o| o.get("k"))
+                .and_then(|v| v.as_str()),
+            Some("w")
+        );
+    }
+
+    #[test]
+    fn test_limit_bounds_pool_size() {
+        let mut pool = JsonValuePool::with_limit(1);
+        pool.recycle(parse_json(r#"[[], [], {"a": "b"}, {}]"#).unwrap());
+        assert_eq!(pool.pooled(), 3);
+    }
+
+    #[test]
+    fn test_parse_errors_are_reported() {
+        let mut pool = JsonValuePool::new();
+        assert!(pool.parse("[1,").is_err());
+    }
+}
@@ -1,23 +1,97 @@
+use crate::options::DEFAULT_MAX_DEPTH;
 use crate::parse_json as parse;
-use crate::parse_json_file as parse_file;
-use crate::{JsonError, JsonValue};
-use pyo3::exceptions::{PyIOError, PyTypeError, PyValueError};
+use crate::parser::read_json_text;
+use crate::tagged::{TYPE_KEY, VALUE_KEY};
+use crate::{
+    DuplicateKeyPolicy, IncrementalParser, JsonError, JsonMap, JsonNumber, JsonValue, KeyHistogram,
+    ParserOptions, Separators, SerializeOptions, TypeCounts, parse_json_with_options,
+    validate as validate_schema, validate_json as validate_syntax,
+};
+use pyo3::create_exception;
+use pyo3::exceptions::{
+    PyIOError, PyImportError, PyIndexError, PyKeyError, PyRecursionError, PyTypeError, PyValueError,
+};
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
-use std::collections::HashMap;
+use pyo3::types::{PyBytes, PyDict, PyInt, PyList, PyString};
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Instant;
 
-/// Utility function to convert a JsonValue instance (value) into a PyAny instance
-fn json_value_to_py<'py>(value: JsonValue, py: Python<'py>) -> Result<Bound<'py, PyAny>, PyErr> {
+/// Number of bytes of output buffered by [`dump`] between calls to `fp.write()`.
+const DUMP_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Size argument passed to `fp.read()` by [`load`].
+const LOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Python callables applied by [`json_value_to_py`] while converting a value.
+#[derive(Default)]
+struct Hooks<'a, 'py> {
+    /// Called with the decoded representation of tagged values, by type name.
+    tags: Option<&'a Bound<'py, PyDict>>,
+    /// Called with the literal of every integer instead of creating an `int`.
+    parse_int: Option<&'a Bound<'py, PyAny>>,
+    /// Called with the literal of every other number instead of creating a `float`.
+    parse_float: Option<&'a Bound<'py, PyAny>>,
+}
+
+/// Utility function to convert a JsonNumber into a Python `int` or `float`.
+/// The numeric hooks are only called for numbers parsed with their literal
+/// retained, so `NaN` and the infinities always become floats.
+fn json_number_to_py<'py>(
+    n: &JsonNumber,
+    py: Python<'py>,
+    hooks: &Hooks<'_, 'py>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let integral = n.raw().filter(|raw| !raw.contains(['.', 'e', 'E']));
+    let hook = if integral.is_some() {
+        hooks.parse_int
+    } else {
+        hooks.parse_float
+    };
+    if let (Some(raw), Some(hook)) = (n.raw(), hook) {
+        return hook.call1((raw,));
+    }
+    if let Some(u) = n.as_u64() {
+        Ok(u.into_pyobject(py)?.into_any())
+    } else if let Some(i) = n.as_i64() {
+        Ok(i.into_pyobject(py)?.into_any())
+    } else if let Some(raw) = integral {
+        // Integers beyond 64 bits are only exact in their literal
+        py.get_type::<PyInt>().call1((raw,))
+    } else {
+        Ok(n.as_f64().into_pyobject(py)?.into_any())
+    }
+}
+
+/// Utility function to convert a JsonValue instance (value) into a PyAny instance.
+/// Tagged values whose type has an entry in `hooks.tags` are passed to that hook.
+fn json_value_to_py<'py>(
+    value: JsonValue,
+    py: Python<'py>,
+    hooks: &Hooks<'_, 'py>,
+) -> Result<Bound<'py, PyAny>, PyErr> {
+    if let Some(tags) = hooks.tags
+        && let Some((type_name, _)) = value.as_tagged()
+        && let Some(hook) = tags.get_item(type_name)?
+    {
+        let JsonValue::Object(mut members) = value else {
+            unreachable!("tagged values are objects");
+        };
+        let repr = members.remove(VALUE_KEY).unwrap_or(JsonValue::Null);
+        return hook.call1((json_value_to_py(repr, py, hooks)?,));
+    }
     match value {
         JsonValue::Null => Ok(py.None().into_bound(py)),
         JsonValue::Boolean(b) => Ok(b.into_pyobject(py)?.to_owned().into_any()),
-        JsonValue::Number(n) => Ok(n.into_pyobject(py)?.to_owned().into_any()),
+        JsonValue::Number(n) => json_number_to_py(&n, py, hooks),
         JsonValue::String(s) => Ok(s.into_pyobject(py)?.to_owned().into_any()),
         JsonValue::Array(arr) => {
             let items: Vec<_> = arr
                 .into_iter()
-                .map(|v| json_value_to_py(v, py))
+                .map(|v| json_value_to_py(v, py, hooks))
                 .collect::<Result<Vec<_>, _>>()?;
             let list = PyList::new(py, items)?;
             Ok(list.to_owned().into_any())
@@ -25,42 +99,112 @@ fn json_value_to_py<'py>(value: JsonValue, py: Python<'py>) -> Result<Bound<'py,
         JsonValue::Object(obj) => {
             let dict = PyDict::new(py);
             for (k, v) in obj {
-                dict.set_item(k, json_value_to_py(v, py)?)?;
+                dict.set_item(&*k, json_value_to_py(v, py, hooks)?)?;
             }
             Ok(dict.into_any())
         }
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(custom) => json_value_to_py(custom.to_json(), py, hooks),
     }
 }
 
-/// Utility function to convert a PyAny instance (value) into a JsonValue instance
-fn py_to_json_value(obj: &Bound<PyAny>) -> PyResult<JsonValue> {
+/// Utility function to convert a PyAny instance (value) into a JsonValue instance.
+/// Objects of other types are replaced by what `default` returns for them.
+fn py_to_json_value(obj: &Bound<PyAny>, default: Option<&Bound<PyAny>>) -> PyResult<JsonValue> {
+    py_to_json_value_nested(obj, default, &mut Vec::new())
+}
+
+/// Records that `obj`, a container or an object passed to `default`, is being
+/// converted. Like `json.dumps`, a container that contains itself raises
+/// `ValueError`; nesting deeper than [`DEFAULT_MAX_DEPTH`], which would not
+/// parse back with the default limits, raises `RecursionError`.
+fn enter_container(obj: &Bound<PyAny>, markers: &mut Vec<usize>) -> PyResult<()> {
+    let id = obj.as_ptr() as usize;
+    if markers.contains(&id) {
+        return Err(PyValueError::new_err("Circular reference detected"));
+    }
+    if markers.len() >= DEFAULT_MAX_DEPTH {
+        return Err(PyRecursionError::new_err(format!(
+            "Maximum nesting depth of {} exceeded while serializing",
+            DEFAULT_MAX_DEPTH
+        )));
+    }
+    markers.push(id);
+    Ok(())
+}
+
+/// [`py_to_json_value`] for a value nested in the containers in `markers`.
+fn py_to_json_value_nested(
+    obj: &Bound<PyAny>,
+    default: Option<&Bound<PyAny>>,
+    markers: &mut Vec<usize>,
+) -> PyResult<JsonValue> {
     if obj.is_none() {
         return Ok(JsonValue::Null);
     }
     if let Ok(b) = obj.extract::<bool>() {
         return Ok(JsonValue::Boolean(b));
     }
+    if obj.is_instance_of::<PyInt>() {
+        if let Ok(i) = obj.extract::<i64>() {
+            return Ok(JsonValue::Number(i.into()));
+        }
+        if let Ok(u) = obj.extract::<u64>() {
+            return Ok(JsonValue::Number(u.into()));
+        }
+        // Larger integers keep their exact digits; `int.__repr__` ignores any
+        // `__str__` of int subclasses, as `json.dumps` does
+        let digits = obj
+            .py()
+            .get_type::<PyInt>()
+            .call_method1("__repr__", (obj,))?;
+        let digits = digits.cast::<PyString>()?;
+        return JsonNumber::from_integer_text(digits.to_str()?)
+            .map(JsonValue::Number)
+            .ok_or_else(|| PyValueError::new_err(format!("Invalid integer: {}", digits)));
+    }
     if let Ok(n) = obj.extract::<f64>() {
-        return Ok(JsonValue::Number(n));
+        return Ok(JsonValue::Number(n.into()));
     }
     if let Ok(s) = obj.extract::<String>() {
         return Ok(JsonValue::String(s));
     }
     if let Ok(list) = obj.cast::<PyList>() {
+        enter_container(obj, markers)?;
         let arr: Vec<_> = list
             .into_iter()
-            .map(|v| py_to_json_value(&v))
+            .map(|v| py_to_json_value_nested(&v, default, markers))
             .collect::<Result<Vec<_>, _>>()?;
+        markers.pop();
         return Ok(JsonValue::Array(arr));
     }
     if let Ok(dict) = obj.cast::<PyDict>() {
-        let mut object = HashMap::new();
+        enter_container(obj, markers)?;
+        let mut object = JsonMap::default();
         for (k, v) in dict.iter() {
             let key: String = k.extract()?;
-            object.insert(key, py_to_json_value(&v)?);
+            object.insert(
+                key.as_str().into(),
+                py_to_json_value_nested(&v, default, markers)?,
+            );
         }
+        markers.pop();
         return Ok(JsonValue::Object(object));
     }
+    if let Some(default) = default {
+        let replacement = default.call1((obj,))?;
+        if replacement.is(obj) {
+            return Err(PyTypeError::new_err(format!(
+                "default returned its argument unchanged: {:?}",
+                obj
+            )));
+        }
+        // Guards against `default` returning objects that need `default` again
+        enter_container(obj, markers)?;
+        let value = py_to_json_value_nested(&replacement, Some(default), markers)?;
+        markers.pop();
+        return Ok(value);
+    }
 
     Err(PyTypeError::new_err(format!("{:?}", obj)))
 }
@@ -71,7 +215,7 @@ impl<'py> IntoPyObject<'py> for JsonValue {
     type Error = PyErr;
 
     fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
-        json_value_to_py(self, py)
+        json_value_to_py(self, py, &Hooks::default())
     }
 }
 
@@ -96,6 +240,10 @@ impl From<JsonError> for PyErr {
                 "Invalid numeric value at position {}: {}",
                 position, value
             )),
+            JsonError::NumberOutOfRange { value, position } => PyValueError::new_err(format!(
+                "Numeric value out of range at position {}: {}",
+                position, value
+            )),
             JsonError::InvalidEscape { char, position } => PyValueError::new_err(format!(
                 "Invalid escape sequence at position {}: {}",
                 position, char
@@ -104,49 +252,301 @@ impl From<JsonError> for PyErr {
                 "Invalid unicode sequence at position {}: {}",
                 position, sequence
             )),
+            JsonError::ControlCharacter { char, position } => PyValueError::new_err(format!(
+                "Unescaped control character at position {}: U+{:04X}",
+                position, char as u32
+            )),
+            JsonError::InvalidUtf8 { offset, length } => PyValueError::new_err(format!(
+                "Invalid UTF-8 at byte offset {}: {} invalid byte(s)",
+                offset, length
+            )),
+            JsonError::InvalidUtf16 { offset } => {
+                PyValueError::new_err(format!("Invalid UTF-16 at byte offset {}", offset))
+            }
+            JsonError::UnsupportedEncoding { encoding } => PyValueError::new_err(format!(
+                "Unsupported encoding {}: JSON must be UTF-8 or UTF-16",
+                encoding
+            )),
+            JsonError::DuplicateKey { key, position } => {
+                PyValueError::new_err(format!("Duplicate key at position {}: {}", position, key))
+            }
+            JsonError::InvalidQuery { query, position } => {
+                PyValueError::new_err(format!("Invalid query at position {}: {}", position, query))
+            }
+            JsonError::DepthLimitExceeded {
+                max_depth,
+                position,
+            } => PyValueError::new_err(format!(
+                "Maximum nesting depth of {} exceeded at position {}",
+                max_depth, position
+            )),
+            JsonError::StringLengthExceeded {
+                max_length,
+                position,
+            } => PyValueError::new_err(format!(
+                "String longer than {} bytes at position {}",
+                max_length, position
+            )),
+            JsonError::ValueCountExceeded {
+                max_values,
+                position,
+            } => PyValueError::new_err(format!(
+                "More than {} values at position {}",
+                max_values, position
+            )),
+            JsonError::InputSizeExceeded { max_size, size } => PyValueError::new_err(format!(
+                "Input of {} bytes exceeds the maximum size of {} bytes",
+                size, max_size
+            )),
+            JsonError::TrailingCharacters { position } => PyValueError::new_err(format!(
+                "Trailing characters after JSON value at position {}",
+                position
+            )),
+            JsonError::InvalidPatch { index, message } => {
+                PyValueError::new_err(format!("Invalid patch at operation {}: {}", index, message))
+            }
+            JsonError::InvalidConcat { index, message } => PyValueError::new_err(format!(
+                "Cannot concatenate document {}: {}",
+                index, message
+            )),
+            JsonError::InvalidConversion { path, message } => PyValueError::new_err(format!(
+                "Cannot convert {}: {}",
+                if path.is_empty() { "(root)" } else { &path },
+                message
+            )),
+            JsonError::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => PyTypeError::new_err(format!(
+                "Type mismatch: expected {} at {}, found {}",
+                expected,
+                if path.is_empty() { "(root)" } else { &path },
+                found
+            )),
+            JsonError::Deserialize { message, position } => PyValueError::new_err(format!(
+                "Deserialization error at position {}: {}",
+                position, message
+            )),
             JsonError::Io { message } => PyIOError::new_err(message),
         }
     }
 }
 
+create_exception!(
+    rust_json_parser,
+    JSONDecodeError,
+    PyValueError,
+    "Raised when a document is not valid JSON.
+
+Mirrors ``json.JSONDecodeError``: ``msg`` is the unformatted error message,
+``doc`` the document being parsed, ``pos`` the index in ``doc`` where parsing
+failed, and ``lineno`` and ``colno`` the 1-based line and column of ``pos``.
+For documents read from a file-like object, ``doc`` is None and ``pos`` and
+``colno`` count bytes instead of characters."
+);
+
+/// Utility function to raise a [`JSONDecodeError`] at the given position for a
+/// parse error.
+fn new_decode_error(
+    py: Python<'_>,
+    err: JsonError,
+    doc: Option<&str>,
+    pos: usize,
+    lineno: usize,
+    colno: usize,
+) -> PyErr {
+    let msg = PyErr::from(err).value(py).to_string();
+    let decode_error = JSONDecodeError::new_err(format!(
+        "{}: line {} column {} (char {})",
+        msg, lineno, colno, pos
+    ));
+    let value = decode_error.value(py);
+    let attributes = value
+        .setattr("msg", msg)
+        .and_then(|_| value.setattr("doc", doc))
+        .and_then(|_| value.setattr("pos", pos))
+        .and_then(|_| value.setattr("lineno", lineno))
+        .and_then(|_| value.setattr("colno", colno));
+    match attributes {
+        Ok(()) => decode_error,
+        Err(err) => err,
+    }
+}
+
+/// Utility function to convert an error from parsing `doc` into a
+/// [`JSONDecodeError`], or as usual if it does not refer to a position in `doc`.
+fn decode_error(py: Python<'_>, err: JsonError, doc: &str) -> PyErr {
+    match (err.input_offset(), err.line_column(doc)) {
+        (Some(offset), Some((lineno, colno))) => {
+            // Python indexes strings by character
+            let pos = doc.as_bytes()[..offset.min(doc.len())]
+                .iter()
+                .filter(|&&b| b & 0xC0 != 0x80)
+                .count();
+            new_decode_error(py, err, Some(doc), pos, lineno, colno)
+        }
+        _ => err.into(),
+    }
+}
+
+/// Utility function to convert an error from parsing a streamed document into a
+/// [`JSONDecodeError`], given the byte offsets of the newlines read so far.
+fn stream_decode_error(py: Python<'_>, err: JsonError, newlines: &[usize]) -> PyErr {
+    let Some(offset) = err.input_offset() else {
+        return err.into();
+    };
+    let line = newlines.partition_point(|&newline| newline < offset);
+    let line_start = if line == 0 { 0 } else { newlines[line - 1] + 1 };
+    new_decode_error(py, err, None, offset, line + 1, offset - line_start + 1)
+}
+
+/// Builds [`ParserOptions`] from the keyword arguments shared by the parsing
+/// functions.
+fn parser_options(
+    allow_comments: bool,
+    allow_trailing_commas: bool,
+    allow_nan: bool,
+    max_depth: usize,
+    duplicate_keys: &str,
+) -> PyResult<ParserOptions> {
+    let duplicate_keys = match duplicate_keys {
+        "last" => DuplicateKeyPolicy::KeepLast,
+        "first" => DuplicateKeyPolicy::KeepFirst,
+        "error" => DuplicateKeyPolicy::Reject,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "duplicate_keys must be 'last', 'first' or 'error', not {:?}",
+                other
+            )));
+        }
+    };
+    Ok(ParserOptions {
+        max_depth,
+        allow_comments,
+        allow_trailing_commas,
+        allow_nan,
+        duplicate_keys,
+        // Keeps integers beyond 64 bits exact and feeds the numeric hooks
+        preserve_number_text: true,
+        ..ParserOptions::default()
+    })
+}
+
 /// Parse a JSON string and return the corresponding Python object.
 ///
+/// The input is parsed with the GIL released, so other Python threads keep
+/// running meanwhile; only the conversion to Python objects holds it.
+///
 /// Args:
 ///     input: A string containing valid JSON.
+///     allow_comments: Skip ``//`` and ``/* */`` comments. Defaults to False.
+///     allow_trailing_commas: Accept a comma after the last array or object
+///         member. Defaults to False.
+///     allow_nan: Accept ``NaN``, ``Infinity`` and ``-Infinity``, as written by
+///         ``json.dumps`` with its default settings. Defaults to False.
+///     max_depth: Maximum nesting depth of arrays and objects. Defaults to 128.
+///     duplicate_keys: What to do with repeated object keys: ``"last"`` (the
+///         default) or ``"first"`` keeps that occurrence, ``"error"`` raises.
+///     tag_hooks: Optional dict mapping type names to callables. A tagged value
+///         such as ``{"$type": "datetime", "value": "2024-05-01"}`` whose type
+///         is in the dict is replaced by the result of calling the hook with its
+///         decoded ``"value"``. Other tagged values are returned as dicts.
+///     parse_int: Optional callable called with the text of every integer, such
+///         as ``"42"``, whose result replaces the ``int``.
+///     parse_float: Optional callable called with the text of every other
+///         number, such as ``"3.10"``, whose result replaces the ``float``
+///         (e.g. ``decimal.Decimal``). ``NaN`` and ``Infinity`` are not passed.
 ///
 /// Returns:
-///     The parsed JSON as a Python object (dict, list, str, float, bool, or None).
+///     The parsed JSON as a Python object (dict, list, str, int, float, bool,
+///     or None). Integers of any size are returned exactly as ``int``.
 ///
 /// Raises:
-///     ValueError: If the input is not valid JSON.
+///     JSONDecodeError: If the input is not valid JSON.
+///     ValueError: If an option is invalid.
 ///
 /// Examples:
 ///     >>> parse_json('{"name": "Alice", "age": 30}')
-///     {'name': 'Alice', 'age': 30.0}
+///     {'name': 'Alice', 'age': 30}
 ///
 ///     >>> parse_json('[1, 2, 3]')
-///     [1.0, 2.0, 3.0]
+///     [1, 2, 3]
 ///
 ///     >>> parse_json('"hello"')
 ///     'hello'
 ///
+///     >>> parse_json('[1, 2,] // config', allow_comments=True, allow_trailing_commas=True)
+///     [1, 2]
+///
+///     >>> parse_json('{"$type": "set", "value": [1, 2]}', tag_hooks={"set": set})
+///     {1, 2}
+///
+///     >>> parse_json('[1.10, 2]', parse_float=decimal.Decimal)
+///     [Decimal('1.10'), 2]
+///
 ///     >>> parse_json('null')
 #[pyfunction]
-fn parse_json<'py>(py: Python<'py>, input: &str) -> PyResult<Bound<'py, PyAny>> {
-    let result = parse(input)?;
-    result.into_pyobject(py)
+#[pyo3(signature = (
+    input,
+    *,
+    allow_comments = false,
+    allow_trailing_commas = false,
+    allow_nan = false,
+    max_depth = DEFAULT_MAX_DEPTH,
+    duplicate_keys = "last",
+    tag_hooks = None,
+    parse_int = None,
+    parse_float = None,
+))]
+// One parameter per keyword argument of the Python signature
+#[allow(clippy::too_many_arguments)]
+fn parse_json<'py>(
+    py: Python<'py>,
+    input: &str,
+    allow_comments: bool,
+    allow_trailing_commas: bool,
+    allow_nan: bool,
+    max_depth: usize,
+    duplicate_keys: &str,
+    tag_hooks: Option<&Bound<'py, PyDict>>,
+    parse_int: Option<&Bound<'py, PyAny>>,
+    parse_float: Option<&Bound<'py, PyAny>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let options = parser_options(
+        allow_comments,
+        allow_trailing_commas,
+        allow_nan,
+        max_depth,
+        duplicate_keys,
+    )?;
+    let result = py
+        .detach(|| parse_json_with_options(input, &options))
+        .map_err(|err| decode_error(py, err, input))?;
+    let hooks = Hooks {
+        tags: tag_hooks,
+        parse_int,
+        parse_float,
+    };
+    json_value_to_py(result, py, &hooks)
 }
 
 /// Parse a JSON file and return the corresponding Python object.
 ///
+/// Like ``parse_json``, the file is read and parsed with the GIL released.
+///
 /// Args:
-///     path: Path to a file containing valid JSON.
+///     path: Path to a file containing valid JSON, encoded as UTF-8 (with or
+///         without a byte order mark) or UTF-16.
+///     allow_comments, allow_trailing_commas, allow_nan, max_depth, duplicate_keys,
+///     tag_hooks, parse_int, parse_float: Same as for ``parse_json``.
 ///
 /// Returns:
-///     The parsed JSON as a Python object (dict, list, str, float, bool, or None).
+///     The parsed JSON as a Python object (dict, list, str, int, float, bool, or None).
 ///
 /// Raises:
-///     ValueError: If the file contents are not valid JSON.
+///     JSONDecodeError: If the file contents are not valid JSON.
+///     ValueError: If the file cannot be decoded, or an option is invalid.
 ///     OSError: If the file cannot be read.
 ///
 /// Examples:
@@ -155,9 +555,861 @@ fn parse_json<'py>(py: Python<'py>, input: &str) -> PyResult<Bound<'py, PyAny>>
 ///
 ///     >>> parse_json_file("data/users.json")
 ///     [{'name': 'Alice'}, {'name': 'Bob'}]
+///
+///     >>> parse_json_file("settings.jsonc", allow_comments=True)
+///     {'theme': 'dark'}
+#[pyfunction]
+#[pyo3(signature = (
+    path,
+    *,
+    allow_comments = false,
+    allow_trailing_commas = false,
+    allow_nan = false,
+    max_depth = DEFAULT_MAX_DEPTH,
+    duplicate_keys = "last",
+    tag_hooks = None,
+    parse_int = None,
+    parse_float = None,
+))]
+// One parameter per keyword argument of the Python signature
+#[allow(clippy::too_many_arguments)]
+fn parse_json_file<'py>(
+    py: Python<'py>,
+    path: &str,
+    allow_comments: bool,
+    allow_trailing_commas: bool,
+    allow_nan: bool,
+    max_depth: usize,
+    duplicate_keys: &str,
+    tag_hooks: Option<&Bound<'py, PyDict>>,
+    parse_int: Option<&Bound<'py, PyAny>>,
+    parse_float: Option<&Bound<'py, PyAny>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let options = parser_options(
+        allow_comments,
+        allow_trailing_commas,
+        allow_nan,
+        max_depth,
+        duplicate_keys,
+    )?;
+    let contents = py.detach(|| read_json_text(path))?;
+    let result = py
+        .detach(|| parse_json_with_options(&contents, &options))
+        .map_err(|err| decode_error(py, err, &contents))?;
+    let hooks = Hooks {
+        tags: tag_hooks,
+        parse_int,
+        parse_float,
+    };
+    json_value_to_py(result, py, &hooks)
+}
+
+/// Returns the path `fp` names if it is a `str` or an `os.PathLike`, or `None`
+/// for a file-like object.
+fn file_path(fp: &Bound<PyAny>) -> PyResult<Option<PathBuf>> {
+    if fp.is_instance_of::<PyString>() || fp.hasattr("__fspath__")? {
+        Ok(Some(fp.extract()?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Read a JSON document from a file path or a file-like object and return the
+/// corresponding Python object.
+///
+/// A path (``str`` or ``os.PathLike``) is opened, read and parsed in Rust with
+/// the GIL released. For a file-like object, ``fp.read()`` is called repeatedly
+/// with a chunk size until it returns an empty result. Both text files
+/// (returning ``str``) and binary files (returning ``bytes``, e.g.
+/// ``gzip.open(path)``) are supported.
+///
+/// Args:
+///     fp: A path, or a file-like object with a ``read`` method.
+///
+/// Returns:
+///     The parsed JSON as a Python object (dict, list, str, int, float, bool, or None).
+///
+/// Raises:
+///     JSONDecodeError: If the contents are not valid JSON.
+///     ValueError: If the file at a path is not valid UTF-8 or UTF-16.
+///     OSError: If the file at a path cannot be read.
+///     TypeError: If ``fp.read()`` returns something other than str or bytes.
+///     Any exception raised by ``fp.read()``.
+///
+/// Examples:
+///     >>> load("config.json")
+///     {'key': 'value'}
+///
+///     >>> with open("config.json") as fp:
+///     ...     load(fp)
+///     {'key': 'value'}
+///
+///     >>> with gzip.open("data.json.gz") as fp:
+///     ...     load(fp)
+///     [1, 2, 3]
+#[pyfunction]
+fn load<'py>(py: Python<'py>, fp: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    if let Some(path) = file_path(fp)? {
+        let contents = py.detach(|| read_json_text(path))?;
+        return py
+            .detach(|| parse(&contents))
+            .map_err(|err| decode_error(py, err, &contents))?
+            .into_pyobject(py);
+    }
+    let mut parser = IncrementalParser::new();
+    let mut newlines = Vec::new();
+    let mut read = 0;
+    loop {
+        let chunk = fp.call_method1("read", (LOAD_CHUNK_SIZE,))?;
+        let bytes = if let Ok(text) = chunk.cast::<PyString>() {
+            text.to_str()?.as_bytes()
+        } else if let Ok(bytes) = chunk.cast::<PyBytes>() {
+            bytes.as_bytes()
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "fp.read() must return str or bytes, not {}",
+                chunk.get_type().name()?
+            )));
+        };
+        if bytes.is_empty() {
+            break;
+        }
+        newlines.extend(
+            bytes
+                .iter()
+                .enumerate()
+                .filter(|&(_, &b)| b == b'\n')
+                .map(|(index, _)| read + index),
+        );
+        read += bytes.len();
+        parser
+            .feed(bytes)
+            .map_err(|err| stream_decode_error(py, err, &newlines))?;
+    }
+    parser
+        .finish()
+        .map_err(|err| stream_decode_error(py, err, &newlines))?
+        .into_pyobject(py)
+}
+
+/// Where a [`JsonLinesIterator`] reads its lines from.
+enum LineSource {
+    /// A file opened from a path.
+    File(BufReader<File>),
+    /// A file-like object, read with `readline()`.
+    Object(Py<PyAny>),
+    /// A string and the offset of the next line in it.
+    Text(String, usize),
+}
+
+/// A lazy iterator over the values of a JSON Lines document, returned by
+/// ``iter_json_lines``.
+///
+/// Each line is read and parsed when the iterator is advanced, so memory use
+/// does not grow with the size of the input. Blank lines are skipped.
+#[pyclass(module = "rust_json_parser")]
+struct JsonLinesIterator {
+    source: LineSource,
+    lineno: usize,
+}
+
+impl JsonLinesIterator {
+    /// Returns the next line without its line ending, or `None` at the end of
+    /// the input.
+    fn next_line(&mut self, py: Python<'_>) -> PyResult<Option<String>> {
+        let mut line = match &mut self.source {
+            LineSource::File(reader) => {
+                let mut line = String::new();
+                if reader.read_line(&mut line).map_err(JsonError::from)? == 0 {
+                    return Ok(None);
+                }
+                line
+            }
+            LineSource::Object(fp) => {
+                let line = fp.bind(py).call_method0("readline")?;
+                if let Ok(text) = line.cast::<PyString>() {
+                    text.to_str()?.to_string()
+                } else if let Ok(bytes) = line.cast::<PyBytes>() {
+                    String::from_utf8(bytes.as_bytes().to_vec()).map_err(|err| {
+                        PyValueError::new_err(format!(
+                            "Line {} is not valid UTF-8: {}",
+                            self.lineno + 1,
+                            err
+                        ))
+                    })?
+                } else {
+                    return Err(PyTypeError::new_err(format!(
+                        "fp.readline() must return str or bytes, not {}",
+                        line.get_type().name()?
+                    )));
+                }
+            }
+            LineSource::Text(text, offset) => {
+                if *offset == text.len() {
+                    return Ok(None);
+                }
+                let end = text[*offset..]
+                    .find('\n')
+                    .map_or(text.len(), |newline| *offset + newline + 1);
+                let line = text[*offset..end].to_string();
+                *offset = end;
+                line
+            }
+        };
+        if line.is_empty() {
+            return Ok(None);
+        }
+        self.lineno += 1;
+        let content = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(content);
+        Ok(Some(line))
+    }
+}
+
+#[pymethods]
+impl JsonLinesIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let options = ParserOptions {
+            preserve_number_text: true,
+            ..ParserOptions::default()
+        };
+        while let Some(line) = self.next_line(py)? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            return match parse_json_with_options(&line, &options) {
+                Ok(value) => json_value_to_py(value, py, &Hooks::default()).map(Some),
+                Err(err) => Err(line_decode_error(py, err, &line, self.lineno)),
+            };
+        }
+        Ok(None)
+    }
+}
+
+/// Utility function to convert an error from parsing line `lineno` of a JSON
+/// Lines document into a [`JSONDecodeError`] whose ``doc`` is that line.
+fn line_decode_error(py: Python<'_>, err: JsonError, line: &str, lineno: usize) -> PyErr {
+    match err.line_column(line) {
+        Some((_, colno)) => new_decode_error(py, err, Some(line), colno - 1, lineno, colno),
+        None => err.into(),
+    }
+}
+
+/// Iterate lazily over the values of a JSON Lines (NDJSON) file.
+///
+/// Every non-blank line must hold one JSON value. Lines are read and parsed in
+/// Rust one at a time as the iterator advances, so files larger than memory can
+/// be processed.
+///
+/// Args:
+///     fp: A path, or a file-like object with a ``readline`` method returning
+///         str or bytes.
+///
+/// Returns:
+///     An iterator yielding one Python object per line.
+///
+/// Raises:
+///     JSONDecodeError: When a line that is not valid JSON is reached. ``lineno``
+///         is its line number, ``doc`` the line itself.
+///     OSError: If the file at a path cannot be opened or read.
+///
+/// Examples:
+///     >>> for event in iter_json_lines("events.jsonl"):
+///     ...     print(event["type"])
+///     login
+///     logout
+#[pyfunction]
+fn iter_json_lines(fp: &Bound<PyAny>) -> PyResult<JsonLinesIterator> {
+    let source = match file_path(fp)? {
+        Some(path) => LineSource::File(BufReader::new(File::open(path).map_err(JsonError::from)?)),
+        None => LineSource::Object(fp.clone().unbind()),
+    };
+    Ok(JsonLinesIterator { source, lineno: 0 })
+}
+
+/// Parse a JSON Lines (NDJSON) string into a list with one value per line.
+///
+/// Blank lines are skipped.
+///
+/// Args:
+///     input: A string with one JSON value per line.
+///
+/// Returns:
+///     A list of Python objects.
+///
+/// Raises:
+///     JSONDecodeError: If a line is not valid JSON, as for ``iter_json_lines``.
+///
+/// Examples:
+///     >>> loads_lines('{"id": 1}\n{"id": 2}\n')
+///     [{'id': 1}, {'id': 2}]
+#[pyfunction]
+fn loads_lines<'py>(py: Python<'py>, input: String) -> PyResult<Bound<'py, PyList>> {
+    let mut lines = JsonLinesIterator {
+        source: LineSource::Text(input, 0),
+        lineno: 0,
+    };
+    let mut values = Vec::new();
+    while let Some(value) = lines.__next__(py)? {
+        values.push(value);
+    }
+    PyList::new(py, values)
+}
+
+/// Check that a string is valid JSON without converting it to Python objects.
+///
+/// The document is parsed in Rust with the GIL released and then discarded, so
+/// this measures raw parsing throughput, and other Python threads keep running
+/// meanwhile.
+///
+/// Args:
+///     input: A string to check.
+///
+/// Raises:
+///     JSONDecodeError: If the input is not valid JSON.
+///
+/// Examples:
+///     >>> parse_validate_only('{"ok": [1, 2, 3]}')
+///
+///     >>> parse_validate_only('{"ok": }')
+///     Traceback (most recent call last):
+///     rust_json_parser.JSONDecodeError: ...
+#[pyfunction]
+fn parse_validate_only(py: Python<'_>, input: &str) -> PyResult<()> {
+    py.detach(|| parse(input))
+        .map_err(|err| decode_error(py, err, input))?;
+    Ok(())
+}
+
+/// Check that a string is valid JSON without building any values.
+///
+/// Unlike ``parse_validate_only``, which parses the whole document and throws
+/// it away, this only checks the syntax, so it allocates almost nothing and is
+/// the fastest way to accept or reject a document. The GIL is released while
+/// checking.
+///
+/// Args:
+///     input: A string to check.
+///
+/// Raises:
+///     JSONDecodeError: If the input is not valid JSON, with the same message
+///         and position ``parse_json`` would report.
+///
+/// Examples:
+///     >>> validate_json('{"ok": [1, 2, 3]}')
+///
+///     >>> validate_json('{"ok": }')
+///     Traceback (most recent call last):
+///     rust_json_parser.JSONDecodeError: ...
+#[pyfunction]
+fn validate_json(py: Python<'_>, input: &str) -> PyResult<()> {
+    py.detach(|| validate_syntax(input))
+        .map_err(|err| decode_error(py, err, input))
+}
+
+/// Parse a JSON string into a ``JsonDocument`` that stays on the Rust side.
+///
+/// Parsing happens with the GIL released. Nothing is converted to Python
+/// objects until a method of the document asks for it, so pipelines that only
+/// need a few values or summary statistics skip most of the conversion cost.
+///
+/// Args:
+///     input: A string containing valid JSON.
+///
+/// Returns:
+///     An opaque ``JsonDocument``.
+///
+/// Raises:
+///     JSONDecodeError: If the input is not valid JSON.
+///
+/// Examples:
+///     >>> doc = parse_to_handle('{"users": [{"name": "Ann"}, {"name": "Bob"}]}')
+///     >>> doc.pointer("/users/1/name")
+///     'Bob'
+///     >>> len(doc.pointer("/users"))
+///     2
+#[pyfunction]
+fn parse_to_handle(py: Python<'_>, input: &str) -> PyResult<JsonDocument> {
+    let value = py
+        .detach(|| parse(input))
+        .map_err(|err| decode_error(py, err, input))?;
+    Ok(JsonDocument { value })
+}
+
+/// A parsed JSON document held in Rust memory, returned by ``parse_to_handle``.
+///
+/// ``len(doc)`` is the number of elements or members of the root array or
+/// object.
+#[pyclass(frozen, module = "rust_json_parser")]
+struct JsonDocument {
+    value: JsonValue,
+}
+
+#[pymethods]
+impl JsonDocument {
+    /// The JSON type of the root value: ``"object"``, ``"array"``,
+    /// ``"string"``, ``"number"``, ``"boolean"`` or ``"null"``.
+    #[getter]
+    fn r#type(&self) -> &'static str {
+        json_type_name(&self.value)
+    }
+
+    /// Convert the whole document to Python objects.
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_value_to_py(self.value.clone(), py, &Hooks::default())
+    }
+
+    /// Select values with a JSONPath expression, like ``query``. Only the
+    /// matching values are converted to Python objects.
+    fn query<'py>(&self, py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyList>> {
+        let matches = self
+            .value
+            .query(path)?
+            .into_iter()
+            .map(|value| json_value_to_py(value.clone(), py, &Hooks::default()))
+            .collect::<PyResult<Vec<_>>>()?;
+        PyList::new(py, matches)
+    }
+
+    /// Look up a single value by JSON Pointer, like ``pointer``.
+    #[pyo3(signature = (pointer, default=None))]
+    fn pointer<'py>(
+        &self,
+        py: Python<'py>,
+        pointer: &str,
+        default: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match self.value.pointer(pointer) {
+            Some(value) => json_value_to_py(value.clone(), py, &Hooks::default()),
+            None => Ok(default.unwrap_or_else(|| py.None().into_bound(py))),
+        }
+    }
+
+    /// Serialize the document back to a JSON string, like ``dumps``.
+    #[pyo3(signature = (
+        indent=None,
+        *,
+        allow_nan=false,
+        sort_keys=false,
+        ensure_ascii=false,
+        separators=None,
+    ))]
+    fn dumps(
+        &self,
+        indent: Option<usize>,
+        allow_nan: bool,
+        sort_keys: bool,
+        ensure_ascii: bool,
+        separators: Option<(String, String)>,
+    ) -> PyResult<String> {
+        let options = serialize_options(indent, allow_nan, sort_keys, ensure_ascii, separators)?;
+        Ok(self.value.to_string_with(&options))
+    }
+
+    /// Count object keys and value types across the document, like
+    /// ``key_histogram``.
+    fn key_histogram<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        histogram_to_py(py, &self.value.key_histogram())
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        match &self.value {
+            JsonValue::Array(items) => Ok(items.len()),
+            JsonValue::Object(members) => Ok(members.len()),
+            other => Err(PyTypeError::new_err(format!(
+                "a JSON {} has no len()",
+                json_type_name(other)
+            ))),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<JsonDocument {}>", json_type_name(&self.value))
+    }
+}
+
+/// One step from an array or object to one of its values.
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// A lazily converted view of a parsed JSON document.
+///
+/// ``LazyJson(input)`` parses a JSON string with the GIL released but converts
+/// nothing to Python objects. Indexing an object or array returns the value
+/// there: another ``LazyJson`` for arrays and objects, a Python object for
+/// everything else. ``materialize()`` converts the viewed value in full. Views
+/// share the parsed document, so indexing never copies it.
+///
+/// Examples:
+///     >>> doc = LazyJson('{"users": [{"name": "Ann"}, {"name": "Bob"}]}')
+///     >>> doc["users"][-1]["name"]
+///     'Bob'
+///     >>> len(doc["users"]), doc.keys()
+///     (2, ['users'])
+///     >>> doc["users"][0].materialize()
+///     {'name': 'Ann'}
+#[pyclass(frozen, module = "rust_json_parser")]
+struct LazyJson {
+    root: Arc<JsonValue>,
+    path: Vec<Step>,
+}
+
+impl LazyJson {
+    /// Returns the value this view refers to.
+    fn value(&self) -> &JsonValue {
+        self.path
+            .iter()
+            .fold(&*self.root, |value, step| match (value, step) {
+                (JsonValue::Object(members), Step::Key(key)) => &members[key.as_str()],
+                (JsonValue::Array(items), Step::Index(index)) => &items[*index],
+                _ => unreachable!("views only step into arrays and objects"),
+            })
+    }
+
+    /// Returns `value`, found one `step` below this view, as a view if it is an
+    /// array or object and as a Python object otherwise.
+    fn child<'py>(
+        &self,
+        py: Python<'py>,
+        value: &JsonValue,
+        step: Step,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match value {
+            JsonValue::Array(_) | JsonValue::Object(_) => {
+                let path = self
+                    .path
+                    .iter()
+                    .map(|step| match step {
+                        Step::Key(key) => Step::Key(key.clone()),
+                        Step::Index(index) => Step::Index(*index),
+                    })
+                    .chain([step])
+                    .collect();
+                let view = LazyJson {
+                    root: Arc::clone(&self.root),
+                    path,
+                };
+                Ok(Bound::new(py, view)?.into_any())
+            }
+            scalar => json_value_to_py(scalar.clone(), py, &Hooks::default()),
+        }
+    }
+}
+
+#[pymethods]
+impl LazyJson {
+    #[new]
+    fn new(py: Python<'_>, input: &str) -> PyResult<Self> {
+        let options = ParserOptions {
+            preserve_number_text: true,
+            ..ParserOptions::default()
+        };
+        let root = py
+            .detach(|| parse_json_with_options(input, &options))
+            .map_err(|err| decode_error(py, err, input))?;
+        Ok(LazyJson {
+            root: Arc::new(root),
+            path: Vec::new(),
+        })
+    }
+
+    fn __getitem__<'py>(
+        &self,
+        py: Python<'py>,
+        key: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        match self.value() {
+            JsonValue::Object(members) => {
+                let Ok(key) = key.cast::<PyString>() else {
+                    return Err(PyTypeError::new_err(format!(
+                        "JSON object keys are str, not {}",
+                        key.get_type().name()?
+                    )));
+                };
+                let key = key.to_str()?;
+                match members.get(key) {
+                    Some(value) => self.child(py, value, Step::Key(key.to_string())),
+                    None => Err(PyKeyError::new_err(key.to_string())),
+                }
+            }
+            JsonValue::Array(items) => {
+                let index: isize = key.extract()?;
+                let resolved = if index < 0 {
+                    index.checked_add_unsigned(items.len())
+                } else {
+                    Some(index)
+                };
+                match resolved.and_then(|index| usize::try_from(index).ok()) {
+                    Some(index) if index < items.len() => {
+                        self.child(py, &items[index], Step::Index(index))
+                    }
+                    _ => Err(PyIndexError::new_err("JSON array index out of range")),
+                }
+            }
+            other => Err(PyTypeError::new_err(format!(
+                "a JSON {} is not subscriptable",
+                json_type_name(other)
+            ))),
+        }
+    }
+
+    /// The keys of the viewed object.
+    fn keys(&self) -> PyResult<Vec<String>> {
+        match self.value() {
+            JsonValue::Object(members) => Ok(members.keys().map(|key| key.to_string()).collect()),
+            other => Err(PyTypeError::new_err(format!(
+                "a JSON {} has no keys()",
+                json_type_name(other)
+            ))),
+        }
+    }
+
+    /// Convert the viewed value to Python objects in full.
+    fn materialize<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_value_to_py(self.value().clone(), py, &Hooks::default())
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        match self.value() {
+            JsonValue::Array(items) => Ok(items.len()),
+            JsonValue::Object(members) => Ok(members.len()),
+            other => Err(PyTypeError::new_err(format!(
+                "a JSON {} has no len()",
+                json_type_name(other)
+            ))),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<LazyJson {}>", json_type_name(self.value()))
+    }
+}
+
+/// Returns the JSON type name reported by [`JsonDocument`] for a value.
+fn json_type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Boolean(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(_) => "custom",
+    }
+}
+
+/// Utility function to obtain a JsonValue from either a JSON string, which is
+/// parsed in Rust, or an already decoded Python object.
+fn document_from_py(doc: &Bound<PyAny>) -> PyResult<JsonValue> {
+    match doc.cast::<PyString>() {
+        Ok(text) => {
+            let text = text.to_str()?;
+            parse(text).map_err(|err| decode_error(doc.py(), err, text))
+        }
+        Err(_) => py_to_json_value(doc, None),
+    }
+}
+
+/// Select values from a JSON document with a JSONPath expression.
+///
+/// The query is evaluated in Rust and only the matching values are converted to
+/// Python objects, so passing the raw JSON string avoids decoding the whole
+/// document.
+///
+/// Args:
+///     doc: A JSON string, or an already decoded Python object.
+///     path: A JSONPath expression such as ``$.store.books[*].title``. Supported
+///         are ``$``, ``.name``, ``['name']``, ``[0]``, ``[-1]``, ``*``, ``[*]``
+///         and ``..`` (recursive descent).
+///
+/// Returns:
+///     A list with every matching value (empty if nothing matches).
+///
+/// Raises:
+///     ValueError: If ``doc`` is not valid JSON or the expression is malformed.
+///
+/// Examples:
+///     >>> query('{"users": [{"name": "Ann"}, {"name": "Bob"}]}', "$.users[*].name")
+///     ['Ann', 'Bob']
+///
+///     >>> query({"a": {"b": 1}}, "$..b")
+///     [1]
+#[pyfunction]
+fn query<'py>(
+    py: Python<'py>,
+    doc: &Bound<'py, PyAny>,
+    path: &str,
+) -> PyResult<Bound<'py, PyList>> {
+    let document = document_from_py(doc)?;
+    let matches = document
+        .query(path)?
+        .into_iter()
+        .map(|value| json_value_to_py(value.clone(), py, &Hooks::default()))
+        .collect::<PyResult<Vec<_>>>()?;
+    PyList::new(py, matches)
+}
+
+/// Look up a single value in a JSON document by JSON Pointer (RFC 6901).
+///
+/// Args:
+///     doc: A JSON string, or an already decoded Python object.
+///     pointer: A JSON Pointer such as ``/users/0/name``; ``""`` is the whole document.
+///     default: Returned when nothing matches, like ``dict.get``. Defaults to None.
+///
+/// Returns:
+///     The matching value converted to a Python object, or ``default``.
+///
+/// Raises:
+///     ValueError: If ``doc`` is not valid JSON.
+///
+/// Examples:
+///     >>> pointer('{"users": [{"name": "Ann"}]}', "/users/0/name")
+///     'Ann'
+///
+///     >>> pointer({"a": 1}, "/b", 0)
+///     0
+#[pyfunction]
+#[pyo3(signature = (doc, pointer, default=None))]
+fn pointer<'py>(
+    py: Python<'py>,
+    doc: &Bound<'py, PyAny>,
+    pointer: &str,
+    default: Option<Bound<'py, PyAny>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let document = document_from_py(doc)?;
+    match document.pointer(pointer) {
+        Some(value) => json_value_to_py(value.clone(), py, &Hooks::default()),
+        None => Ok(default.unwrap_or_else(|| py.None().into_bound(py))),
+    }
+}
+
+/// Count how often every object key occurs in a JSON document, and the types of
+/// the values found under it and in the whole document.
+///
+/// The document is walked in Rust, so passing the raw JSON string avoids
+/// converting it to Python objects at all.
+///
+/// Args:
+///     doc: A JSON string, or an already decoded Python object.
+///
+/// Returns:
+///     A dict with ``keys``, mapping every key to the counts of the types of its
+///     values, and ``types``, the counts for all values including the root.
+///     Counts are dicts with the keys ``null``, ``boolean``, ``number``,
+///     ``string``, ``array`` and ``object``.
+///
+/// Raises:
+///     ValueError: If ``doc`` is not valid JSON.
+///
+/// Examples:
+///     >>> stats = key_histogram('[{"age": 31}, {"age": null}]')
+///     >>> stats["keys"]["age"]
+///     {'null': 1, 'boolean': 0, 'number': 1, 'string': 0, 'array': 0, 'object': 0}
+///     >>> stats["types"]["object"]
+///     2
+#[pyfunction]
+fn key_histogram<'py>(py: Python<'py>, doc: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyDict>> {
+    histogram_to_py(py, &document_from_py(doc)?.key_histogram())
+}
+
+/// Converts a [`KeyHistogram`] to the dict returned by [`key_histogram`].
+fn histogram_to_py<'py>(py: Python<'py>, histogram: &KeyHistogram) -> PyResult<Bound<'py, PyDict>> {
+    let counts_to_py = |counts: &TypeCounts| -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (type_name, count) in counts.iter() {
+            dict.set_item(type_name, count)?;
+        }
+        Ok(dict)
+    };
+    let keys = PyDict::new(py);
+    for (key, counts) in &histogram.keys {
+        keys.set_item(key, counts_to_py(counts)?)?;
+    }
+    let dict = PyDict::new(py);
+    dict.set_item("keys", keys)?;
+    dict.set_item("types", counts_to_py(&histogram.types)?)?;
+    Ok(dict)
+}
+
+/// Validate a JSON document against a JSON Schema.
+///
+/// Supports the common keywords (``type``, ``enum``, ``const``, numeric and
+/// length bounds, ``items``/``prefixItems``, ``properties``/``required``/
+/// ``additionalProperties``, ``allOf``/``anyOf``/``oneOf``/``not``,
+/// ``if``/``then``/``else`` and local ``$ref``). ``pattern`` and ``format`` are
+/// not checked.
+///
+/// Args:
+///     instance: The document to check, as a JSON string or a decoded Python object.
+///     schema: The schema, as a JSON string or a decoded Python object.
+///
+/// Returns:
+///     A list of errors, empty if the document is valid. Each error is a dict with
+///     ``path`` (a JSON Pointer to the offending value), ``message`` and ``keyword``.
+///
+/// Raises:
+///     ValueError: If either argument is a string that is not valid JSON.
+///
+/// Examples:
+///     >>> validate({"age": -1}, {"properties": {"age": {"minimum": 0}}})
+///     [{'path': '/age', 'message': '-1 is less than the minimum of 0', 'keyword': 'minimum'}]
+///
+///     >>> validate('[1, 2]', '{"type": "array"}')
+///     []
 #[pyfunction]
-fn parse_json_file<'py>(py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyAny>> {
-    parse_file(path)?.into_pyobject(py)
+fn validate<'py>(
+    py: Python<'py>,
+    instance: &Bound<'py, PyAny>,
+    schema: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyList>> {
+    let instance = document_from_py(instance)?;
+    let schema = document_from_py(schema)?;
+    let errors = validate_schema(&instance, &schema)
+        .into_iter()
+        .map(|error| {
+            let dict = PyDict::new(py);
+            dict.set_item("path", error.path)?;
+            dict.set_item("message", error.message)?;
+            dict.set_item("keyword", error.keyword)?;
+            Ok(dict)
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    PyList::new(py, errors)
+}
+
+/// Build a tagged value: a dict of the form ``{"$type": type_name, "value": value}``.
+///
+/// Tagged values represent objects that have no JSON counterpart, such as dates
+/// or sets. Return one from a ``default`` hook of ``dumps``, and decode it with
+/// the ``tag_hooks`` of ``parse_json``.
+///
+/// Args:
+///     type_name: The name identifying the type, such as ``"datetime"``.
+///     value: A JSON-serializable representation of the object.
+///
+/// Returns:
+///     The tagged value as a dict.
+///
+/// Examples:
+///     >>> tagged("datetime", "2024-05-01T12:00:00")
+///     {'$type': 'datetime', 'value': '2024-05-01T12:00:00'}
+#[pyfunction]
+fn tagged<'py>(
+    py: Python<'py>,
+    type_name: &str,
+    value: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item(TYPE_KEY, type_name)?;
+    dict.set_item(VALUE_KEY, value)?;
+    Ok(dict)
 }
 
 /// Serialize a Python object to a JSON string.
@@ -165,12 +1417,32 @@ fn parse_json_file<'py>(py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyAn
 /// Args:
 ///     obj: A Python object to serialize (dict, list, str, float, int, bool, or None).
 ///     indent: Optional number of spaces for pretty-printing. If None, output is compact.
+///     allow_nan: Write NaN and infinite floats as ``NaN``, ``Infinity`` and
+///         ``-Infinity``, like ``json.dumps`` does by default. If False (the
+///         default), they are written as ``null``.
+///     sort_keys: Write the members of every dict in key order, so the output
+///         does not depend on insertion order. Defaults to False.
+///     ensure_ascii: Escape every non-ASCII character as ``\uXXXX``, like
+///         ``json.dumps`` does by default. Defaults to False, which writes them
+///         as they are.
+///     separators: An ``(item_separator, key_separator)`` tuple: ``(',', ': ')``
+///         (the default), ``(',', ':')`` for the most compact output, or
+///         ``(', ', ': ')``. When indenting, items always end in a newline.
+///     default: Optional callable invoked with each object that cannot be
+///         serialized otherwise; its result is serialized instead. Returning
+///         ``tagged(name, value)`` lets ``parse_json(tag_hooks=...)`` restore
+///         the object.
 ///
 /// Returns:
 ///     A JSON string representation of the object.
 ///
 /// Raises:
-///     TypeError: If the object contains types that cannot be serialized to JSON.
+///     TypeError: If the object contains types that cannot be serialized to JSON
+///         and ``default`` is not given.
+///     ValueError: If ``separators`` is not one of the supported pairs, or if
+///         a list or dict contains itself.
+///     RecursionError: If the object is nested deeper than the default maximum
+///         parsing depth.
 ///
 /// Examples:
 ///     >>> dumps({"name": "Alice", "age": 30})
@@ -186,28 +1458,249 @@ fn parse_json_file<'py>(py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyAn
 ///
 ///     >>> dumps(None)
 ///     'null'
+///
+///     >>> text = dumps({"ids": {1, 2}}, default=lambda obj: tagged("set", sorted(obj)))
+///     >>> parse_json(text, tag_hooks={"set": set})
+///     {'ids': {1, 2}}
 #[pyfunction]
-#[pyo3(signature = (obj, indent=None))]
-fn dumps(obj: &Bound<PyAny>, indent: Option<usize>) -> PyResult<String> {
-    match indent {
-        Some(indent) => Ok(py_to_json_value(obj)?.pretty_print(indent)),
-        None => Ok(py_to_json_value(obj)?.to_string()),
+#[pyo3(signature = (
+    obj,
+    indent=None,
+    *,
+    allow_nan=false,
+    sort_keys=false,
+    ensure_ascii=false,
+    separators=None,
+    default=None,
+))]
+fn dumps(
+    obj: &Bound<PyAny>,
+    indent: Option<usize>,
+    allow_nan: bool,
+    sort_keys: bool,
+    ensure_ascii: bool,
+    separators: Option<(String, String)>,
+    default: Option<&Bound<PyAny>>,
+) -> PyResult<String> {
+    let options = serialize_options(indent, allow_nan, sort_keys, ensure_ascii, separators)?;
+    Ok(py_to_json_value(obj, default)?.to_string_with(&options))
+}
+
+/// Builds [`SerializeOptions`] from the keyword arguments shared by the
+/// serializing functions.
+fn serialize_options(
+    indent: Option<usize>,
+    allow_nan: bool,
+    sort_keys: bool,
+    ensure_ascii: bool,
+    separators: Option<(String, String)>,
+) -> PyResult<SerializeOptions> {
+    let separators = match separators {
+        None => Separators::Standard,
+        Some((item, key)) => match (item.as_str(), key.as_str()) {
+            (",", ": ") => Separators::Standard,
+            (",", ":") => Separators::Compact,
+            (", ", ": ") => Separators::Spaced,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "separators must be (',', ': '), (',', ':') or (', ', ': '), not ({:?}, {:?})",
+                    item, key
+                )));
+            }
+        },
+    };
+    Ok(SerializeOptions {
+        indent,
+        allow_nan,
+        sort_keys,
+        ensure_ascii,
+        separators,
+        ..SerializeOptions::default()
+    })
+}
+
+/// Adapter that forwards serialized output to a Python file-like object in
+/// chunks of about [`DUMP_CHUNK_SIZE`] bytes.
+struct PyFileWriter<'a, 'py> {
+    fp: &'a Bound<'py, PyAny>,
+    buffer: Vec<u8>,
+    /// The exception raised by `fp.write()`, reported instead of a generic IO error
+    error: Option<PyErr>,
+}
+
+impl<'a, 'py> PyFileWriter<'a, 'py> {
+    fn new(fp: &'a Bound<'py, PyAny>) -> Self {
+        Self {
+            fp,
+            buffer: Vec::with_capacity(DUMP_CHUNK_SIZE),
+            error: None,
+        }
+    }
+
+    /// Writes out the buffered output up to the last complete UTF-8 character.
+    fn write_chunk(&mut self) -> io::Result<()> {
+        let valid = match std::str::from_utf8(&self.buffer) {
+            Ok(text) => text.len(),
+            Err(err) => err.valid_up_to(),
+        };
+        let text = String::from_utf8_lossy(&self.buffer[..valid]);
+        if let Err(err) = self.fp.call_method1("write", (text,)) {
+            self.error = Some(err);
+            return Err(io::Error::other("fp.write() raised an exception"));
+        }
+        self.buffer.drain(..valid);
+        Ok(())
     }
 }
 
-fn median(times: &mut [f64]) -> f64 {
-    times.sort_by(|a, b| a.total_cmp(b));
-    let mid = times.len() / 2;
-    if times.len() % 2 == 1 {
-        times[mid]
-    } else {
-        (times[mid - 1] + times[mid]) / 2.0
+impl Write for PyFileWriter<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= DUMP_CHUNK_SIZE {
+            self.write_chunk()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.write_chunk()
+    }
+}
+
+/// Serialize a Python object as JSON and write it to a file path or a file-like
+/// object.
+///
+/// A path (``str`` or ``os.PathLike``) is written atomically: the file is
+/// replaced only once the whole document has been written, so readers never
+/// see half of it. For a file-like object, the output is passed to
+/// ``fp.write()`` in chunks as it is produced, so no string holding the whole
+/// document is built.
+///
+/// Args:
+///     obj: A Python object to serialize (dict, list, str, float, int, bool, or None).
+///     fp: A path, or a text file-like object with a ``write`` method.
+///     indent: Optional number of spaces for pretty-printing. If None, output is compact.
+///     allow_nan: Write NaN and infinite floats as ``NaN``, ``Infinity`` and
+///         ``-Infinity``, like ``json.dumps`` does by default. If False (the
+///         default), they are written as ``null``.
+///     sort_keys, ensure_ascii, separators, default: Same as for ``dumps``.
+///
+/// Raises:
+///     TypeError: If the object contains types that cannot be serialized to JSON.
+///     ValueError: If ``separators`` is not one of the supported pairs, or if
+///         a list or dict contains itself.
+///     RecursionError: If the object is nested deeper than the default maximum
+///         parsing depth.
+///     OSError: If the file at a path cannot be written.
+///     Any exception raised by ``fp.write()``.
+///
+/// Examples:
+///     >>> dump({"name": "Alice"}, "out.json", indent=2)
+///
+///     >>> with open("out.json", "w") as fp:
+///     ...     dump({"name": "Alice"}, fp, indent=2)
+///
+///     >>> buffer = io.StringIO()
+///     >>> dump([1, 2, 3], buffer)
+///     >>> buffer.getvalue()
+///     '[1,2,3]'
+#[pyfunction]
+#[pyo3(signature = (
+    obj,
+    fp,
+    indent=None,
+    *,
+    allow_nan=false,
+    sort_keys=false,
+    ensure_ascii=false,
+    separators=None,
+    default=None,
+))]
+// One parameter per keyword argument of the Python signature
+#[allow(clippy::too_many_arguments)]
+fn dump(
+    obj: &Bound<PyAny>,
+    fp: &Bound<PyAny>,
+    indent: Option<usize>,
+    allow_nan: bool,
+    sort_keys: bool,
+    ensure_ascii: bool,
+    separators: Option<(String, String)>,
+    default: Option<&Bound<PyAny>>,
+) -> PyResult<()> {
+    let value = py_to_json_value(obj, default)?;
+    let options = serialize_options(indent, allow_nan, sort_keys, ensure_ascii, separators)?;
+    if let Some(path) = file_path(fp)? {
+        return Ok(value.write_to_file_atomic(path, &options)?);
+    }
+    let mut writer = PyFileWriter::new(fp);
+    let result = value
+        .write_to(&mut writer, &options)
+        .and_then(|()| Ok(writer.flush()?));
+    match writer.error.take() {
+        Some(err) => Err(err),
+        None => Ok(result?),
+    }
+}
+
+/// Python modules whose `loads` and `dumps` are benchmarked alongside ours when
+/// they can be imported.
+const BENCHMARK_BASELINES: [&str; 4] = ["json", "simplejson", "orjson", "ujson"];
+
+/// Per-iteration timings of one benchmarked function, in seconds.
+struct BenchStats {
+    mean: f64,
+    median: f64,
+    p95: f64,
+    stddev: f64,
+    rounds: usize,
+}
+
+impl BenchStats {
+    fn from_times(times: &mut [f64]) -> Self {
+        times.sort_by(|a, b| a.total_cmp(b));
+        let rounds = times.len();
+        let mid = rounds / 2;
+        let median = if rounds % 2 == 1 {
+            times[mid]
+        } else {
+            (times[mid - 1] + times[mid]) / 2.0
+        };
+        let mean = times.iter().sum::<f64>() / rounds as f64;
+        let variance = times.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / rounds as f64;
+        // Nearest-rank percentile
+        let p95 = times[(rounds * 95).div_ceil(100) - 1];
+        BenchStats {
+            mean,
+            median,
+            p95,
+            stddev: variance.sqrt(),
+            rounds,
+        }
+    }
+
+    fn to_py<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("mean", self.mean)?;
+        dict.set_item("median", self.median)?;
+        dict.set_item("p95", self.p95)?;
+        dict.set_item("stddev", self.stddev)?;
+        dict.set_item("rounds", self.rounds)?;
+        Ok(dict)
     }
 }
 
 /// Run `f` for `warmup` untimed iterations, then `rounds` timed iterations,
-/// and return the median per-iteration time in seconds.
-fn bench_median<F>(rounds: u32, warmup: u32, mut f: F) -> PyResult<f64>
+/// and return statistics of the per-iteration times as a dict.
+fn bench<'py, F>(
+    py: Python<'py>,
+    rounds: u32,
+    warmup: u32,
+    mut f: F,
+) -> PyResult<Bound<'py, PyDict>>
 where
     F: FnMut() -> PyResult<()>,
 {
@@ -220,80 +1713,144 @@ where
         f()?;
         times.push(start.elapsed().as_secs_f64());
     }
-    Ok(median(&mut times))
+    BenchStats::from_times(&mut times).to_py(py)
 }
 
-fn bench_pure_rust(input: &str, rounds: u32, warmup: u32) -> PyResult<f64> {
-    bench_median(rounds, warmup, || {
-        let _ = parse(input)?;
-        Ok(())
-    })
-}
-
-fn bench_rust_with_bindings(
-    py: Python<'_>,
+/// Measures every parser and serializer on `input`, see [`benchmark_performance`].
+fn run_benchmarks<'py>(
+    py: Python<'py>,
     input: &str,
     rounds: u32,
     warmup: u32,
-) -> PyResult<f64> {
-    bench_median(rounds, warmup, || {
-        let _ = parse_json(py, input)?;
-        Ok(())
-    })
-}
+) -> PyResult<Bound<'py, PyDict>> {
+    let value = parse(input)?;
+    let obj = value.clone().into_pyobject(py)?;
+    let loads = PyDict::new(py);
+    let dumps = PyDict::new(py);
 
-fn bench_python_json(py: Python<'_>, input: &str, rounds: u32, warmup: u32) -> PyResult<f64> {
-    let loads = py.import("json")?.getattr("loads")?;
-    bench_median(rounds, warmup, || {
-        let _ = loads.call1((input,))?;
+    let pure_rust = bench(py, rounds, warmup, || {
+        black_box(parse(input)?);
         Ok(())
-    })
-}
-
-fn bench_simplejson(py: Python<'_>, input: &str, rounds: u32, warmup: u32) -> PyResult<f64> {
-    let loads = py.import("simplejson")?.getattr("loads")?;
-    bench_median(rounds, warmup, || {
-        let _ = loads.call1((input,))?;
+    })?;
+    loads.set_item("pure-rust", pure_rust)?;
+    let rust = bench(py, rounds, warmup, || {
+        black_box(parse(input)?.into_pyobject(py)?);
         Ok(())
-    })
+    })?;
+    loads.set_item("rust", rust)?;
+    let pure_rust = bench(py, rounds, warmup, || {
+        black_box(value.to_string());
+        Ok(())
+    })?;
+    dumps.set_item("pure-rust", pure_rust)?;
+    let rust = bench(py, rounds, warmup, || {
+        black_box(py_to_json_value(&obj, None)?.to_string());
+        Ok(())
+    })?;
+    dumps.set_item("rust", rust)?;
+
+    for name in BENCHMARK_BASELINES {
+        let module = match py.import(name) {
+            Ok(module) => module,
+            Err(err) if err.is_instance_of::<PyImportError>(py) => continue,
+            Err(err) => return Err(err),
+        };
+        let (module_loads, module_dumps) = (module.getattr("loads")?, module.getattr("dumps")?);
+        let stats = bench(py, rounds, warmup, || {
+            module_loads.call1((input,))?;
+            Ok(())
+        })?;
+        loads.set_item(name, stats)?;
+        let stats = bench(py, rounds, warmup, || {
+            module_dumps.call1((&obj,))?;
+            Ok(())
+        })?;
+        dumps.set_item(name, stats)?;
+    }
+
+    let result = PyDict::new(py);
+    result.set_item("loads", loads)?;
+    result.set_item("dumps", dumps)?;
+    Ok(result)
 }
 
-/// Benchmark parse_json against json.loads and simplejson.loads.
+/// Benchmark parse_json and dumps against the standard library and other JSON
+/// packages.
 ///
-/// All three parsers are measured doing identical work: taking a JSON string
-/// and returning Python objects. Each gets the same number of warmup and
-/// timed rounds to ensure a fair comparison. Reports the **median**
-/// per-iteration time to reduce the impact of GC pauses and other outliers.
+/// Parsing is measured from a JSON string to Python objects and serializing
+/// from the Python objects back to a string, so every parser does identical
+/// work. ``"pure-rust"`` is the Rust side alone, without any conversion from
+/// or to Python objects. ``json`` is always compared; ``simplejson``,
+/// ``orjson`` and ``ujson`` are compared when they can be imported. Each gets
+/// the same number of warmup and timed rounds.
 ///
 /// Args:
 ///     input: A JSON string to parse.
 ///     rounds: Number of timed iterations per parser (default: 1000).
 ///     warmup: Number of untimed warmup iterations per parser (default: 10).
+///     disable_gc: Turn off Python's garbage collector while timing, so that
+///         collections do not land in random rounds. Defaults to False.
 ///
 /// Returns:
-///     A dict with median per-iteration times in seconds:
-///     ``{"pure-rust": float, "rust": float, "json": float, "simplejson": float}``.
+///     A dict with ``"loads"`` and ``"dumps"`` entries, each mapping
+///     ``"pure-rust"``, ``"rust"`` and the name of every compared package to
+///     its per-iteration times in seconds:
+///     ``{"mean": float, "median": float, "p95": float, "stddev": float, "rounds": int}``.
+///
+/// Raises:
+///     ValueError: If ``input`` is not valid JSON or ``rounds`` is 0.
+///
+/// Examples:
+///     >>> times = benchmark_performance('{"test": [1, 2, 3]}', rounds=100)
+///     >>> sorted(times["dumps"]["rust"])
+///     ['mean', 'median', 'p95', 'rounds', 'stddev']
+///     >>> times["loads"]["rust"]["rounds"]
+///     100
 #[pyfunction]
-#[pyo3(signature = (input, rounds=1000, warmup=10))]
+#[pyo3(signature = (input, rounds=1000, warmup=10, *, disable_gc=false))]
 fn benchmark_performance<'py>(
     py: Python<'py>,
     input: &str,
     rounds: u32,
     warmup: u32,
+    disable_gc: bool,
 ) -> PyResult<Bound<'py, PyDict>> {
-    let result = PyDict::new(py);
-    result.set_item("pure-rust", bench_pure_rust(input, rounds, warmup)?)?;
-    result.set_item("rust", bench_rust_with_bindings(py, input, rounds, warmup)?)?;
-    result.set_item("json", bench_python_json(py, input, rounds, warmup)?)?;
-    result.set_item("simplejson", bench_simplejson(py, input, rounds, warmup)?)?;
-    Ok(result)
+    if rounds == 0 {
+        return Err(PyValueError::new_err("rounds must be at least 1"));
+    }
+    let gc = py.import("gc")?;
+    let reenable_gc = disable_gc && gc.call_method0("isenabled")?.is_truthy()?;
+    if disable_gc {
+        gc.call_method0("disable")?;
+    }
+    let result = run_benchmarks(py, input, rounds, warmup);
+    if reenable_gc {
+        gc.call_method0("enable")?;
+    }
+    result
 }
 
 #[pymodule]
 fn _rust_json_parser(m: &Bound<PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(parse_json, m)?)?;
     m.add_function(wrap_pyfunction!(parse_json_file, m)?)?;
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+    m.add_function(wrap_pyfunction!(iter_json_lines, m)?)?;
+    m.add_function(wrap_pyfunction!(loads_lines, m)?)?;
+    m.add_class::<JsonLinesIterator>()?;
+    m.add_function(wrap_pyfunction!(parse_validate_only, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_json, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_to_handle, m)?)?;
+    m.add_class::<JsonDocument>()?;
+    m.add_class::<LazyJson>()?;
+    m.add("JSONDecodeError", m.py().get_type::<JSONDecodeError>())?;
+    m.add_function(wrap_pyfunction!(query, m)?)?;
+    m.add_function(wrap_pyfunction!(pointer, m)?)?;
+    m.add_function(wrap_pyfunction!(key_histogram, m)?)?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    m.add_function(wrap_pyfunction!(tagged, m)?)?;
     m.add_function(wrap_pyfunction!(dumps, m)?)?;
+    m.add_function(wrap_pyfunction!(dump, m)?)?;
     m.add_function(wrap_pyfunction!(benchmark_performance, m)?)?;
     Ok(())
 }
@@ -0,0 +1,400 @@
+//! Selecting values inside a document with JSON Pointer (RFC 6901) and JSONPath.
+//!
+//! Both are exposed through [`JsonValue::pointer`], [`JsonValue::pointer_mut`] and
+//! [`JsonValue::query`], alongside the lighter dotted paths of [`JsonValue::path`]
+//! and [`JsonValue::path_mut`]. The JSONPath dialect supported here covers the common
+//! subset: `$` for the root, `.name` and `['name']` for members, `[0]` and
+//! `[-1]` for array elements, `*` and `[*]` wildcards, and `..` for recursive
+//! descent.
+
+use std::borrow::Cow;
+
+use crate::value::JsonValue;
+use crate::{JsonError, JsonResult};
+
+/*
+ * Appends `/segment` to a JSON Pointer, escaping `~` and `/` as RFC 6901 requires.
+ */
+pub(crate) fn push_segment(path: &mut String, segment: &str) {
+    path.push('/');
+    for c in segment.chars() {
+        match c {
+            '~' => path.push_str("~0"),
+            '/' => path.push_str("~1"),
+            _ => path.push(c),
+        }
+    }
+}
+
+/*
+ * Decodes one reference token of a JSON Pointer: `~1` stands for `/` and `~0`
+ * for `~`, in that order.
+ */
+pub(crate) fn unescape_token(token: &str) -> Cow<'_, str> {
+    if token.contains('~') {
+        Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+/*
+ * Parses an array index reference token. RFC 6901 only allows plain decimal
+ * numbers without leading zeros.
+ */
+pub(crate) fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty()
+        || (token.len() > 1 && token.starts_with('0'))
+        || !token.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    token.parse().ok()
+}
+
+pub(crate) fn pointer<'a>(value: &'a JsonValue, pointer: &str) -> Option<&'a JsonValue> {
+    if pointer.is_empty() {
+        return Some(value);
+    }
+    pointer
+        .strip_prefix('/')?
+        .split('/')
+        .try_fold(value, |current, token| match current {
+            JsonValue::Object(members) => members.get(unescape_token(token).as_ref()),
+            JsonValue::Array(items) => parse_index(token).and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
+
+pub(crate) fn pointer_mut<'a>(
+    value: &'a mut JsonValue,
+    pointer: &str,
+) -> Option<&'a mut JsonValue> {
+    if pointer.is_empty() {
+        return Some(value);
+    }
+    pointer
+        .strip_prefix('/')?
+        .split('/')
+        .try_fold(value, |current, token| match current {
+            JsonValue::Object(members) => members.get_mut(unescape_token(token).as_ref()),
+            JsonValue::Array(items) => parse_index(token).and_then(|i| items.get_mut(i)),
+            _ => None,
+        })
+}
+
+/*
+ * One step of a dotted path.
+ */
+enum PathStep<'p> {
+    Member(&'p str),
+    Index(usize),
+}
+
+/*
+ * Splits a dotted path such as `servers[0].tls.cert` into its steps, or returns
+ * `None` if it is malformed. Names run up to the next `.` or `[`, and only the
+ * first step may omit the leading dot.
+ */
+fn path_steps(path: &str) -> Option<Vec<PathStep<'_>>> {
+    let mut steps = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']')?;
+            steps.push(PathStep::Index(parse_index(&after[..end])?));
+            rest = &after[end + 1..];
+        } else {
+            if !steps.is_empty() {
+                rest = rest.strip_prefix('.')?;
+            }
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            steps.push(PathStep::Member(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    Some(steps)
+}
+
+pub(crate) fn path<'a>(value: &'a JsonValue, path: &str) -> Option<&'a JsonValue> {
+    path_steps(path)?
+        .into_iter()
+        .try_fold(value, |current, step| match (current, step) {
+            (JsonValue::Object(members), PathStep::Member(name)) => members.get(name),
+            (JsonValue::Array(items), PathStep::Index(index)) => items.get(index),
+            _ => None,
+        })
+}
+
+pub(crate) fn path_mut<'a>(value: &'a mut JsonValue, path: &str) -> Option<&'a mut JsonValue> {
+    path_steps(path)?
+        .into_iter()
+        .try_fold(value, |current, step| match (current, step) {
+            (JsonValue::Object(members), PathStep::Member(name)) => members.get_mut(name),
+            (JsonValue::Array(items), PathStep::Index(index)) => items.get_mut(index),
+            _ => None,
+        })
+}
+
+/*
+ * One step of a compiled JSONPath expression.
+ */
+#[derive(Debug, PartialEq)]
+enum Selector {
+    Member(String),
+    Index(i64),
+    Wildcard,
+    /// Replaces the current selection with every value nested in it, itself
+    /// included; always followed by another selector
+    Descendants,
+}
+
+fn invalid_query(query: &str, position: usize) -> JsonError {
+    JsonError::InvalidQuery {
+        query: query.to_string(),
+        position,
+    }
+}
+
+/*
+ * Compiles a JSONPath expression into its selectors. Error positions are byte
+ * offsets into the expression.
+ */
+fn compile(query: &str) -> JsonResult<Vec<Selector>> {
+    let bytes = query.as_bytes();
+    if bytes.first() != Some(&b'$') {
+        return Err(invalid_query(query, 0));
+    }
+    let mut selectors = Vec::new();
+    let mut i = 1;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'.' => {
+                i += 1;
+                if bytes.get(i) == Some(&b'.') {
+                    selectors.push(Selector::Descendants);
+                    i += 1;
+                    if bytes.get(i) == Some(&b'[') {
+                        continue;
+                    }
+                }
+                if bytes.get(i) == Some(&b'*') {
+                    selectors.push(Selector::Wildcard);
+                    i += 1;
+                    continue;
+                }
+                let start = i;
+                while i < bytes.len() && bytes[i] != b'.' && bytes[i] != b'[' {
+                    i += 1;
+                }
+                if i == start {
+                    return Err(invalid_query(query, start));
+                }
+                selectors.push(Selector::Member(query[start..i].to_string()));
+            }
+            b'[' => {
+                let start = i + 1;
+                let end = query[start..]
+                    .find(']')
+                    .map(|offset| start + offset)
+                    .ok_or_else(|| invalid_query(query, query.len()))?;
+                let inner = &query[start..end];
+                let selector = if inner == "*" {
+                    Selector::Wildcard
+                } else if let Some(name) = inner
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+                {
+                    Selector::Member(name.to_string())
+                } else {
+                    Selector::Index(inner.parse().map_err(|_| invalid_query(query, start))?)
+                };
+                selectors.push(selector);
+                i = end + 1;
+            }
+            _ => return Err(invalid_query(query, i)),
+        }
+    }
+
+    if selectors.last() == Some(&Selector::Descendants) {
+        return Err(invalid_query(query, query.len()));
+    }
+    Ok(selectors)
+}
+
+/*
+ * Appends `value` and everything nested in it to `out`, parents first.
+ */
+fn collect_descendants<'a>(value: &'a JsonValue, out: &mut Vec<&'a JsonValue>) {
+    let mut pending = vec![value];
+    while let Some(value) = pending.pop() {
+        out.push(value);
+        match value {
+            JsonValue::Array(items) => pending.extend(items.iter().rev()),
+            JsonValue::Object(members) => pending.extend(members.values()),
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn query<'a>(value: &'a JsonValue, query: &str) -> JsonResult<Vec<&'a JsonValue>> {
+    let mut selection = vec![value];
+
+    for selector in compile(query)? {
+        let mut next = Vec::new();
+        for value in selection {
+            match (&selector, value) {
+                (Selector::Descendants, _) => collect_descendants(value, &mut next),
+                (Selector::Member(name), JsonValue::Object(members)) => {
+                    next.extend(members.get(name.as_str()));
+                }
+                (Selector::Index(index), JsonValue::Array(items)) => {
+                    let index = if *index < 0 {
+                        items.len().checked_sub(index.unsigned_abs() as usize)
+                    } else {
+                        Some(*index as usize)
+                    };
+                    next.extend(index.and_then(|i| items.get(i)));
+                }
+                (Selector::Wildcard, JsonValue::Array(items)) => next.extend(items),
+                (Selector::Wildcard, JsonValue::Object(members)) => next.extend(members.values()),
+                _ => {}
+            }
+        }
+        selection = next;
+    }
+
+    Ok(selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    fn doc() -> JsonValue {
+        parse_json(
+            r#"{"store": {"books": [{"title": "A", "price": 8}, {"title": "B", "price": 12}]},
+                "a/b": {"m~n": 1}, "": 0}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_dotted_paths() {
+        let mut value = parse_json(
+            r#"{"servers": [{"tls": {"cert": "a.pem"}}, {"ports": [[80, 443]]}], "a.b": 1}"#,
+        )
+        .unwrap();
+        let cert = JsonValue::String("a.pem".to_string());
+        assert_eq!(path(&value, "servers[0].tls.cert"), Some(&cert));
+        assert_eq!(
+            path(&value, "servers[1].ports[0][1]"),
+            Some(&JsonValue::Number(443.into()))
+        );
+        assert_eq!(path(&value, ""), Some(&value));
+        assert_eq!(
+            path(&path(&value, "servers").unwrap().clone(), "[0].tls.cert"),
+            Some(&cert)
+        );
+        for missing in [
+            "servers[2]",
+            "servers.0",
+            "servers[0]tls",
+            "servers[01]",
+            "servers[0",
+            "servers..x",
+            "servers.",
+            ".servers",
+            "a.b",
+        ] {
+            assert_eq!(path(&value, missing), None, "{}", missing);
+        }
+
+        *path_mut(&mut value, "servers[1].ports[0][0]").unwrap() = JsonValue::Null;
+        assert_eq!(
+            value.pointer("/servers/1/ports/0/0"),
+            Some(&JsonValue::Null)
+        );
+    }
+
+    #[test]
+    fn test_pointer() {
+        let doc = doc();
+        assert_eq!(pointer(&doc, ""), Some(&doc));
+        assert_eq!(
+            pointer(&doc, "/store/books/1/title").and_then(|v| v.as_str()),
+            Some("B")
+        );
+        assert_eq!(
+            pointer(&doc, "/a~1b/m~0n"),
+            Some(&JsonValue::Number(1.into()))
+        );
+        assert_eq!(pointer(&doc, "/"), Some(&JsonValue::Number(0.into())));
+        for missing in [
+            "store",
+            "/nope",
+            "/store/books/2",
+            "/store/books/01",
+            "/store/books/-",
+        ] {
+            assert_eq!(pointer(&doc, missing), None, "{}", missing);
+        }
+    }
+
+    #[test]
+    fn test_pointer_mut() {
+        let mut doc = doc();
+        *pointer_mut(&mut doc, "/store/books/0/price").unwrap() = JsonValue::Null;
+        assert_eq!(
+            pointer(&doc, "/store/books/0/price"),
+            Some(&JsonValue::Null)
+        );
+    }
+
+    #[test]
+    fn test_query() {
+        let doc = doc();
+        let titles = |q| -> Vec<&str> {
+            query(&doc, q)
+                .unwrap()
+                .into_iter()
+                .filter_map(|v| v.as_str())
+                .collect()
+        };
+        assert_eq!(titles("$.store.books[0].title"), ["A"]);
+        assert_eq!(titles("$['store'][\"books\"][-1].title"), ["B"]);
+        assert_eq!(titles("$.store.books[*].title"), ["A", "B"]);
+        assert_eq!(titles("$..title"), ["A", "B"]);
+        assert_eq!(query(&doc, "$").unwrap(), vec![&doc]);
+        assert!(query(&doc, "$.store.books[5]").unwrap().is_empty());
+        assert_eq!(query(&doc, "$..price").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_query() {
+        for (q, position) in [
+            ("store", 0),
+            ("$.", 2),
+            ("$[1", 3),
+            ("$[x]", 2),
+            ("$..", 3),
+            ("$a", 1),
+        ] {
+            assert_eq!(
+                query(&doc(), q),
+                Err(JsonError::InvalidQuery {
+                    query: q.to_string(),
+                    position
+                }),
+                "{}",
+                q
+            );
+        }
+    }
+}
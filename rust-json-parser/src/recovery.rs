@@ -0,0 +1,503 @@
+//! Parsing that reports every problem in a document instead of stopping at the
+//! first one, for editors and linters.
+//!
+//! [`parse_json_lenient`] repairs what it can and carries on:
+//!
+//! - a missing comma between values or members is assumed,
+//! - stray tokens are skipped,
+//! - a closing bracket or brace that belongs to an enclosing container closes
+//!   the ones still open inside it,
+//! - a token that fails to scan (an invalid character, a bad escape sequence)
+//!   is skipped,
+//! - members without a key or a value are dropped.
+//!
+//! Errors that follow directly from one already reported are not repeated:
+//! after an error, further structural errors are only recorded once a value has
+//! been parsed successfully again.
+
+use crate::JsonError;
+use crate::error::{unexpected_end_of_input, unexpected_token_error};
+use crate::options::DEFAULT_MAX_DEPTH;
+use crate::tokenizer::{RawToken, Tokenizer};
+use crate::value::{JsonKey, JsonMap, JsonValue};
+
+/*
+ * Recursive descent parser with one token of lookahead that records errors
+ * instead of returning them.
+ */
+struct RecoveringParser<'input> {
+    tokenizer: Tokenizer<'input>,
+    peeked: Option<RawToken<'input>>,
+    // Closing characters of the containers currently open, innermost last
+    open: Vec<u8>,
+    errors: Vec<JsonError>,
+    // Set after an error until a value parses, to avoid cascades
+    recovering: bool,
+}
+
+impl<'input> RecoveringParser<'input> {
+    /*
+     * Records a structural error, unless it is likely a consequence of the
+     * previous one.
+     */
+    fn error(&mut self, err: JsonError) {
+        if !self.recovering {
+            self.errors.push(err);
+            self.recovering = true;
+        }
+    }
+
+    fn unexpected(&mut self, expected: &str) {
+        let found = match &self.peeked {
+            Some(token) => format!("{:?}", token),
+            None => return self.end_of_input(expected),
+        };
+        let err = unexpected_token_error(expected, &found, self.tokenizer.token_start());
+        self.error(err);
+    }
+
+    fn end_of_input(&mut self, expected: &str) {
+        let err = unexpected_end_of_input(expected, self.tokenizer.offset());
+        self.error(err);
+    }
+
+    /*
+     * Returns the next token without consuming it. Tokens that fail to scan are
+     * recorded and skipped.
+     */
+    fn peek(&mut self) -> Option<&RawToken<'input>> {
+        while self.peeked.is_none() {
+            match self.tokenizer.next_raw_token() {
+                Ok(None) => return None,
+                Ok(token) => self.peeked = token,
+                Err(err) => {
+                    self.errors.push(err);
+                    self.recovering = true;
+                    self.tokenizer.skip_invalid_token();
+                }
+            }
+        }
+        self.peeked.as_ref()
+    }
+
+    fn bump(&mut self) -> Option<RawToken<'input>> {
+        self.peek();
+        self.peeked.take()
+    }
+
+    /*
+     * Returns `true` if the next token closes a container enclosing the
+     * innermost one, which is then closed without it.
+     */
+    fn closes_outer(&mut self) -> bool {
+        let closing = match self.peek() {
+            Some(RawToken::RightBracket) => b']',
+            Some(RawToken::RightBrace) => b'}',
+            _ => return false,
+        };
+        let outer = self.open.len().saturating_sub(1);
+        self.open[..outer].contains(&closing)
+    }
+
+    /*
+     * Parses the value at the next token. Returns `None` if no value starts
+     * there, after consuming the offending token or too deeply nested container.
+     */
+    fn parse_value(&mut self) -> Option<JsonValue> {
+        let value = match self.peek() {
+            None => {
+                self.end_of_input("valid JSON value");
+                return None;
+            }
+            Some(RawToken::LeftBracket | RawToken::LeftBrace) => {
+                if self.open.len() >= DEFAULT_MAX_DEPTH {
+                    let position = self.tokenizer.token_start();
+                    self.error(JsonError::DepthLimitExceeded {
+                        max_depth: DEFAULT_MAX_DEPTH,
+                        position,
+                    });
+                    self.skip_container();
+                    return None;
+                }
+                self.recovering = false;
+                if self.bump() == Some(RawToken::LeftBracket) {
+                    self.parse_array()
+                } else {
+                    self.parse_object()
+                }
+            }
+            Some(
+                RawToken::String(_) | RawToken::Number(_) | RawToken::Boolean(_) | RawToken::Null,
+            ) => {
+                self.recovering = false;
+                match self.bump() {
+                    Some(RawToken::String(s)) => JsonValue::String(s.into_owned()),
+                    Some(RawToken::Number(n)) => JsonValue::Number(n),
+                    Some(RawToken::Boolean(b)) => JsonValue::Boolean(b),
+                    _ => JsonValue::Null,
+                }
+            }
+            Some(_) => {
+                self.unexpected("valid JSON value");
+                self.bump();
+                return None;
+            }
+        };
+        Some(value)
+    }
+
+    /*
+     * Skips the container at the next token, which is nested too deeply to be
+     * parsed.
+     */
+    fn skip_container(&mut self) {
+        let mut depth = 0usize;
+        while let Some(token) = self.bump() {
+            match token {
+                RawToken::LeftBracket | RawToken::LeftBrace => depth += 1,
+                RawToken::RightBracket | RawToken::RightBrace => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+
+    /*
+     * Parses the elements of an array whose opening bracket has been consumed.
+     */
+    fn parse_array(&mut self) -> JsonValue {
+        self.open.push(b']');
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            // A value, or the end of the array
+            let expected = if first {
+                "valid JSON value or closing bracket"
+            } else {
+                "valid JSON value"
+            };
+            let closes_outer = self.closes_outer();
+            match self.peek() {
+                None => {
+                    self.end_of_input(expected);
+                    break;
+                }
+                Some(RawToken::RightBracket) => {
+                    if !first {
+                        // A trailing comma
+                        self.unexpected(expected);
+                    }
+                    self.bump();
+                    break;
+                }
+                _ if closes_outer => {
+                    self.unexpected(expected);
+                    break;
+                }
+                _ => match self.parse_value() {
+                    Some(value) => items.push(value),
+                    None => continue,
+                },
+            }
+
+            // A comma, or the end of the array
+            let closes_outer = self.closes_outer();
+            match self.peek() {
+                None => {
+                    self.end_of_input(", or closing bracket");
+                    break;
+                }
+                Some(RawToken::Comma) => {
+                    self.bump();
+                    first = false;
+                }
+                Some(RawToken::RightBracket) => {
+                    self.bump();
+                    break;
+                }
+                Some(token) if starts_value(token) => {
+                    // A missing comma
+                    self.unexpected(", or closing bracket");
+                    first = false;
+                }
+                _ if closes_outer => {
+                    self.unexpected(", or closing bracket");
+                    break;
+                }
+                Some(_) => {
+                    self.unexpected(", or closing bracket");
+                    self.bump();
+                    first = false;
+                }
+            }
+        }
+        self.open.pop();
+        JsonValue::Array(items)
+    }
+
+    /*
+     * Parses the members of an object whose opening brace has been consumed.
+     * Repeated keys keep their last value.
+     */
+    fn parse_object(&mut self) -> JsonValue {
+        self.open.push(b'}');
+        let mut members = JsonMap::default();
+        let mut first = true;
+        loop {
+            // A key, or the end of the object
+            let closes_outer = self.closes_outer();
+            let key = match self.peek() {
+                None => {
+                    self.end_of_input(if first {
+                        "string or closing brace"
+                    } else {
+                        "string"
+                    });
+                    break;
+                }
+                Some(RawToken::RightBrace) => {
+                    if !first {
+                        // A trailing comma
+                        self.unexpected("string");
+                    }
+                    self.bump();
+                    break;
+                }
+                Some(RawToken::String(_)) => match self.bump() {
+                    Some(RawToken::String(key)) => Some(JsonKey::from(&*key)),
+                    _ => unreachable!("the key was peeked"),
+                },
+                _ if closes_outer => {
+                    self.unexpected("string");
+                    break;
+                }
+                Some(_) => {
+                    self.unexpected("string");
+                    None
+                }
+            };
+            first = false;
+
+            // The colon, and the value
+            match self.peek() {
+                Some(RawToken::Colon) => {
+                    self.bump();
+                }
+                // A missing colon
+                Some(token) if key.is_some() && starts_value(token) => self.unexpected(":"),
+                None => {
+                    self.end_of_input(":");
+                    break;
+                }
+                // A stray token where the key should be
+                Some(_) if key.is_none() => {
+                    self.bump();
+                    if self.peek() == Some(&RawToken::Colon) {
+                        self.bump();
+                    }
+                }
+                Some(_) => self.unexpected(":"),
+            }
+            if self.peek().is_some_and(starts_value) {
+                let value = self.parse_value();
+                if let (Some(key), Some(value)) = (key, value) {
+                    members.insert(key, value);
+                }
+            } else {
+                self.unexpected("valid JSON value");
+            }
+
+            // A comma, or the end of the object
+            let closes_outer = self.closes_outer();
+            match self.peek() {
+                None => {
+                    self.end_of_input(", or closing brace");
+                    break;
+                }
+                Some(RawToken::Comma) => {
+                    self.bump();
+                }
+                Some(RawToken::RightBrace) => {
+                    self.bump();
+                    break;
+                }
+                // A missing comma before the next key
+                Some(RawToken::String(_)) => self.unexpected(", or closing brace"),
+                _ if closes_outer => {
+                    self.unexpected(", or closing brace");
+                    break;
+                }
+                Some(_) => {
+                    self.unexpected(", or closing brace");
+                    self.bump();
+                }
+            }
+        }
+        self.open.pop();
+        JsonValue::Object(members)
+    }
+}
+
+fn starts_value(token: &RawToken<'_>) -> bool {
+    matches!(
+        token,
+        RawToken::LeftBracket
+            | RawToken::LeftBrace
+            | RawToken::String(_)
+            | RawToken::Number(_)
+            | RawToken::Boolean(_)
+            | RawToken::Null
+    )
+}
+
+/// Parses `input` like [`parse_json`](crate::parse_json), but instead of
+/// stopping at the first error, repairs the document where it can and keeps
+/// going, so that every problem is reported in one pass.
+///
+/// Returns the (possibly repaired) value, or `None` if no value could be read
+/// at all, together with the errors in input order. For valid JSON the value is
+/// the same as from `parse_json` and there are no errors; otherwise the first
+/// error is the one `parse_json` would have returned. See the
+/// [module documentation](self) for how errors are recovered from.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, parse_json_lenient};
+///
+/// let (value, errors) = parse_json_lenient(r#"{"a": 1 "b": [1, 2,], "c": @}"#);
+/// assert_eq!(value, Some(parse_json(r#"{"a": 1, "b": [1, 2]}"#).unwrap()));
+/// let positions: Vec<_> = errors.iter().filter_map(|e| e.input_offset()).collect();
+/// assert_eq!(positions, [8, 19, 27]);
+/// ```
+pub fn parse_json_lenient(input: &str) -> (Option<JsonValue>, Vec<JsonError>) {
+    let mut tokenizer = Tokenizer::new(input);
+    tokenizer.skip_bom();
+    let mut parser = RecoveringParser {
+        tokenizer,
+        peeked: None,
+        open: Vec::new(),
+        errors: Vec::new(),
+        recovering: false,
+    };
+    let value = parser.parse_value();
+    // Anything but whitespace after the root value
+    parser.recovering = false;
+    if parser.peeked.is_some() || !matches!(parser.tokenizer.next_raw_token(), Ok(None)) {
+        let position = parser.tokenizer.token_start();
+        if value.is_some() {
+            parser.error(JsonError::TrailingCharacters { position });
+        }
+    }
+    (value, parser.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn test_valid_input_matches_parse_json() {
+        for input in [
+            "null",
+            r#""text""#,
+            "[]",
+            "{}",
+            r#"{"a": [1, {"b": null}], "c": "d", "e": [[], {}]}"#,
+        ] {
+            assert_eq!(
+                parse_json_lenient(input),
+                (Some(parse_json(input).unwrap()), Vec::new()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_first_error_matches_parse_json() {
+        for input in [
+            "",
+            "[1 2]",
+            "[1,]",
+            r#"{"a" 1}"#,
+            r#"{"a": 1,}"#,
+            r#"{"a": }"#,
+            r#"{1: 2}"#,
+            "[1, 2}",
+            "[1, @]",
+            r#"["a\qb"]"#,
+            "[1",
+            "1 2",
+            "]",
+        ] {
+            let (_, errors) = parse_json_lenient(input);
+            assert_eq!(
+                errors.first(),
+                Some(&parse_json(input).unwrap_err()),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_recovers_and_reports_every_problem() {
+        let input = r#"{
+            "a": [1 2, 3,],
+            "b": {"c": tru, "d": 4}
+            "e": "\q",
+            "f": [5, 6}
+        "#;
+        let (value, errors) = parse_json_lenient(input);
+        let expected = r#"{"a": [1, 2, 3], "b": {"d": 4}, "f": [5, 6]}"#;
+        assert_eq!(value, Some(parse_json(expected).unwrap()));
+        let names: Vec<&str> = errors.iter().map(JsonError::name).collect();
+        assert_eq!(
+            names,
+            [
+                "UnexpectedToken", // missing comma after 1
+                "UnexpectedToken", // trailing comma
+                "UnexpectedToken", // `tru`
+                "UnexpectedToken", // missing comma after the "b" object
+                "InvalidEscape",   // `\q`
+                "UnexpectedToken", // `}` closing the "f" array
+            ]
+        );
+    }
+
+    #[test]
+    fn test_byte_order_mark_is_skipped() {
+        let (value, errors) = parse_json_lenient("\u{FEFF}{\"a\": 1}");
+        assert_eq!(value, Some(parse_json(r#"{"a": 1}"#).unwrap()));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_garbage_yields_no_value() {
+        let (value, errors) = parse_json_lenient("@@ ]");
+        assert_eq!(value, None);
+        assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn test_deep_nesting_is_skipped() {
+        let input = format!(
+            "[{}{}, 1]",
+            "[".repeat(DEFAULT_MAX_DEPTH),
+            "]".repeat(DEFAULT_MAX_DEPTH)
+        );
+        let (value, errors) = parse_json_lenient(&input);
+        // Only the container that crosses the limit is dropped
+        let expected = format!(
+            "[{}{}, 1]",
+            "[".repeat(DEFAULT_MAX_DEPTH - 1),
+            "]".repeat(DEFAULT_MAX_DEPTH - 1)
+        );
+        assert_eq!(value, Some(parse_json(&expected).unwrap()));
+        assert!(matches!(errors[..], [JsonError::DepthLimitExceeded { .. }]));
+    }
+}
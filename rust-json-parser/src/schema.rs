@@ -0,0 +1,577 @@
+//! Validation of documents against a JSON Schema.
+//!
+//! [`validate`] checks an instance against a schema and reports every violation
+//! with the location of the offending value. The supported vocabulary covers
+//! what request validation typically needs:
+//!
+//! - `type`, `enum`, `const`
+//! - `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `multipleOf`
+//! - `minLength`, `maxLength`
+//! - `items`, `prefixItems`, `minItems`, `maxItems`, `uniqueItems`, `contains`
+//! - `properties`, `required`, `additionalProperties`, `propertyNames`,
+//!   `minProperties`, `maxProperties`
+//! - `allOf`, `anyOf`, `oneOf`, `not`, `if`/`then`/`else`
+//! - `$ref` to locations within the same schema (e.g. `#/$defs/address`)
+//!
+//! Other keywords, including `pattern` and `format`, are ignored.
+
+use std::fmt;
+
+use crate::query::push_segment;
+use crate::value::{JsonMap, JsonValue};
+
+/*
+ * Bound on nested `$ref` resolution, which stops self-referencing schemas such
+ * as `{"$ref": "#"}` from looping forever.
+ */
+const MAX_REF_DEPTH: usize = 64;
+
+/// A single way in which an instance fails to match a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// JSON Pointer to the offending value within the instance (`""` for the root).
+    pub path: String,
+    /// The schema keyword that failed, e.g. `"required"`.
+    pub keyword: String,
+    /// A human-readable description of the failure.
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {} ({})", path, self.message, self.keyword)
+    }
+}
+
+/// Validates `instance` against `schema` and returns every violation found, or
+/// an empty `Vec` if the instance is valid.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, validate};
+///
+/// let schema = parse_json(r#"{
+///     "type": "object",
+///     "properties": {"age": {"type": "integer", "minimum": 0}},
+///     "required": ["name"]
+/// }"#)?;
+/// let errors = validate(&parse_json(r#"{"age": -1}"#)?, &schema);
+/// assert_eq!(errors.len(), 2);
+/// assert!(errors.iter().any(|e| e.keyword == "required" && e.path.is_empty()));
+/// assert!(errors.iter().any(|e| e.keyword == "minimum" && e.path == "/age"));
+///
+/// assert!(validate(&parse_json(r#"{"name": "Ann", "age": 30}"#)?, &schema).is_empty());
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+pub fn validate(instance: &JsonValue, schema: &JsonValue) -> Vec<ValidationError> {
+    let mut validator = Validator {
+        root: schema,
+        errors: Vec::new(),
+        ref_depth: 0,
+    };
+    validator.check(instance, schema, &mut String::new());
+    validator.errors
+}
+
+/*
+ * Returns the JSON Schema type name of a value. Integral numbers report
+ * "integer", which also satisfies "number".
+ */
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Boolean(_) => "boolean",
+        JsonValue::Number(n) if n.as_f64().fract() == 0.0 => "integer",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(_) => unreachable!("custom values are checked as their JSON"),
+    }
+}
+
+fn has_type(value: &JsonValue, expected: &str) -> bool {
+    let actual = type_name(value);
+    actual == expected || (expected == "number" && actual == "integer")
+}
+
+struct Validator<'s> {
+    root: &'s JsonValue,
+    errors: Vec<ValidationError>,
+    ref_depth: usize,
+}
+
+impl<'s> Validator<'s> {
+    fn fail(&mut self, path: &str, keyword: &str, message: String) {
+        self.errors.push(ValidationError {
+            path: path.to_string(),
+            keyword: keyword.to_string(),
+            message,
+        });
+    }
+
+    /*
+     * Returns whether `instance` matches `schema`, without recording errors.
+     */
+    fn matches(&mut self, instance: &JsonValue, schema: &'s JsonValue, path: &mut String) -> bool {
+        let errors = std::mem::take(&mut self.errors);
+        self.check(instance, schema, path);
+        let matched = self.errors.is_empty();
+        self.errors = errors;
+        matched
+    }
+
+    fn check(&mut self, instance: &JsonValue, schema: &'s JsonValue, path: &mut String) {
+        #[cfg(feature = "custom-values")]
+        if let JsonValue::Custom(custom) = instance {
+            return self.check(&custom.to_json(), schema, path);
+        }
+        let members = match schema {
+            JsonValue::Boolean(true) => return,
+            JsonValue::Boolean(false) => {
+                return self.fail(path, "false", "no value is allowed here".to_string());
+            }
+            JsonValue::Object(members) => members,
+            // Not a schema; treat it like the empty schema
+            _ => return,
+        };
+        let keyword = |name: &str| members.get(name);
+
+        if let Some(reference) = keyword("$ref") {
+            self.check_ref(instance, reference, path);
+        }
+        if let Some(expected) = keyword("type") {
+            self.check_type(instance, expected, path);
+        }
+        if let Some(JsonValue::Array(allowed)) = keyword("enum")
+            && !allowed.contains(instance)
+        {
+            self.fail(
+                path,
+                "enum",
+                format!("{} is not one of the allowed values", instance),
+            );
+        }
+        if let Some(expected) = keyword("const")
+            && instance != expected
+        {
+            self.fail(
+                path,
+                "const",
+                format!("expected {}, found {}", expected, instance),
+            );
+        }
+
+        match instance {
+            JsonValue::Number(n) => self.check_number(n.as_f64(), members, path),
+            JsonValue::String(s) => self.check_string(s, members, path),
+            JsonValue::Array(items) => self.check_array(items, members, path),
+            JsonValue::Object(object) => self.check_object(object, members, path),
+            JsonValue::Boolean(_) | JsonValue::Null => {}
+            #[cfg(feature = "custom-values")]
+            JsonValue::Custom(_) => unreachable!("custom values are checked as their JSON"),
+        }
+
+        self.check_combinators(instance, members, path);
+    }
+
+    fn check_ref(&mut self, instance: &JsonValue, reference: &JsonValue, path: &mut String) {
+        let target = reference
+            .as_str()
+            .and_then(|r| r.strip_prefix('#'))
+            .and_then(|pointer| self.root.pointer(pointer));
+        let Some(target) = target else {
+            return self.fail(
+                path,
+                "$ref",
+                format!("cannot resolve reference {}", reference),
+            );
+        };
+        if self.ref_depth >= MAX_REF_DEPTH {
+            return self.fail(path, "$ref", "references are nested too deeply".to_string());
+        }
+        self.ref_depth += 1;
+        self.check(instance, target, path);
+        self.ref_depth -= 1;
+    }
+
+    fn check_type(&mut self, instance: &JsonValue, expected: &JsonValue, path: &str) {
+        let matches = match expected {
+            JsonValue::String(name) => has_type(instance, name),
+            JsonValue::Array(names) => names
+                .iter()
+                .any(|name| name.as_str().is_some_and(|name| has_type(instance, name))),
+            _ => true,
+        };
+        if !matches {
+            self.fail(
+                path,
+                "type",
+                format!("expected type {}, found {}", expected, type_name(instance)),
+            );
+        }
+    }
+
+    fn check_number(&mut self, n: f64, members: &JsonMap, path: &str) {
+        let bound = |name: &str| members.get(name).and_then(|v| v.as_f64());
+        if let Some(min) = bound("minimum")
+            && n < min
+        {
+            self.fail(
+                path,
+                "minimum",
+                format!("{} is less than the minimum of {}", n, min),
+            );
+        }
+        if let Some(max) = bound("maximum")
+            && n > max
+        {
+            self.fail(
+                path,
+                "maximum",
+                format!("{} is greater than the maximum of {}", n, max),
+            );
+        }
+        if let Some(min) = bound("exclusiveMinimum")
+            && n <= min
+        {
+            self.fail(
+                path,
+                "exclusiveMinimum",
+                format!("{} is not greater than {}", n, min),
+            );
+        }
+        if let Some(max) = bound("exclusiveMaximum")
+            && n >= max
+        {
+            self.fail(
+                path,
+                "exclusiveMaximum",
+                format!("{} is not less than {}", n, max),
+            );
+        }
+        if let Some(divisor) = bound("multipleOf")
+            && divisor > 0.0
+            && (n / divisor).fract() != 0.0
+        {
+            self.fail(
+                path,
+                "multipleOf",
+                format!("{} is not a multiple of {}", n, divisor),
+            );
+        }
+    }
+
+    fn check_string(&mut self, s: &str, members: &JsonMap, path: &str) {
+        let length = s.chars().count();
+        if let Some(min) = members.get("minLength").and_then(|v| v.as_usize())
+            && length < min
+        {
+            self.fail(
+                path,
+                "minLength",
+                format!("string is shorter than {} characters", min),
+            );
+        }
+        if let Some(max) = members.get("maxLength").and_then(|v| v.as_usize())
+            && length > max
+        {
+            self.fail(
+                path,
+                "maxLength",
+                format!("string is longer than {} characters", max),
+            );
+        }
+    }
+
+    fn check_array(&mut self, items: &[JsonValue], members: &'s JsonMap, path: &mut String) {
+        let base_len = path.len();
+        let prefix: &[JsonValue] = match members.get("prefixItems") {
+            Some(JsonValue::Array(schemas)) => schemas,
+            _ => &[],
+        };
+        for (index, item) in items.iter().enumerate() {
+            let schema = match prefix.get(index) {
+                Some(schema) => Some(schema),
+                None => members.get("items"),
+            };
+            if let Some(schema) = schema {
+                push_segment(path, &index.to_string());
+                self.check(item, schema, path);
+                path.truncate(base_len);
+            }
+        }
+
+        if let Some(min) = members.get("minItems").and_then(|v| v.as_usize())
+            && items.len() < min
+        {
+            self.fail(
+                path,
+                "minItems",
+                format!("array has fewer than {} items", min),
+            );
+        }
+        if let Some(max) = members.get("maxItems").and_then(|v| v.as_usize())
+            && items.len() > max
+        {
+            self.fail(
+                path,
+                "maxItems",
+                format!("array has more than {} items", max),
+            );
+        }
+        if members.get("uniqueItems") == Some(&JsonValue::Boolean(true))
+            && let Some(index) = (1..items.len()).find(|&i| items[..i].contains(&items[i]))
+        {
+            self.fail(
+                path,
+                "uniqueItems",
+                format!("item {} is a duplicate of an earlier item", index),
+            );
+        }
+        if let Some(schema) = members.get("contains")
+            && !items.iter().any(|item| self.matches(item, schema, path))
+        {
+            self.fail(
+                path,
+                "contains",
+                "no item matches the \"contains\" schema".to_string(),
+            );
+        }
+    }
+
+    fn check_object(&mut self, object: &JsonMap, members: &'s JsonMap, path: &mut String) {
+        let base_len = path.len();
+        let properties = members.get("properties").and_then(|p| p.as_object());
+
+        if let Some(JsonValue::Array(required)) = members.get("required") {
+            for name in required.iter().filter_map(|name| name.as_str()) {
+                if !object.contains_key(name) {
+                    self.fail(
+                        path,
+                        "required",
+                        format!("missing required property \"{}\"", name),
+                    );
+                }
+            }
+        }
+
+        // Sorted so that errors are reported in a stable order
+        let mut keys: Vec<&str> = object.keys().map(|key| &**key).collect();
+        keys.sort();
+        for key in keys {
+            let value = &object[key];
+            let schema = match properties.and_then(|p| p.get(key)) {
+                Some(schema) => Some(schema),
+                None => members.get("additionalProperties"),
+            };
+            push_segment(path, key);
+            if let Some(schema) = schema {
+                if schema == &JsonValue::Boolean(false) {
+                    self.fail(
+                        path,
+                        "additionalProperties",
+                        format!("property \"{}\" is not allowed", key),
+                    );
+                } else {
+                    self.check(value, schema, path);
+                }
+            }
+            if let Some(schema) = members.get("propertyNames") {
+                self.check(&JsonValue::String(key.to_string()), schema, path);
+            }
+            path.truncate(base_len);
+        }
+
+        if let Some(min) = members.get("minProperties").and_then(|v| v.as_usize())
+            && object.len() < min
+        {
+            self.fail(
+                path,
+                "minProperties",
+                format!("object has fewer than {} properties", min),
+            );
+        }
+        if let Some(max) = members.get("maxProperties").and_then(|v| v.as_usize())
+            && object.len() > max
+        {
+            self.fail(
+                path,
+                "maxProperties",
+                format!("object has more than {} properties", max),
+            );
+        }
+    }
+
+    fn check_combinators(&mut self, instance: &JsonValue, members: &'s JsonMap, path: &mut String) {
+        if let Some(JsonValue::Array(schemas)) = members.get("allOf") {
+            for schema in schemas {
+                self.check(instance, schema, path);
+            }
+        }
+        if let Some(JsonValue::Array(schemas)) = members.get("anyOf")
+            && !schemas
+                .iter()
+                .any(|schema| self.matches(instance, schema, path))
+        {
+            self.fail(
+                path,
+                "anyOf",
+                "value does not match any of the schemas".to_string(),
+            );
+        }
+        if let Some(JsonValue::Array(schemas)) = members.get("oneOf") {
+            let matched = schemas
+                .iter()
+                .filter(|schema| self.matches(instance, schema, path))
+                .count();
+            if matched != 1 {
+                self.fail(
+                    path,
+                    "oneOf",
+                    format!(
+                        "value matches {} of the schemas instead of exactly one",
+                        matched
+                    ),
+                );
+            }
+        }
+        if let Some(schema) = members.get("not")
+            && self.matches(instance, schema, path)
+        {
+            self.fail(
+                path,
+                "not",
+                "value matches a schema it must not match".to_string(),
+            );
+        }
+        if let Some(condition) = members.get("if") {
+            let branch = if self.matches(instance, condition, path) {
+                members.get("then")
+            } else {
+                members.get("else")
+            };
+            if let Some(schema) = branch {
+                self.check(instance, schema, path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    fn errors(instance: &str, schema: &str) -> Vec<(String, String)> {
+        validate(&parse_json(instance).unwrap(), &parse_json(schema).unwrap())
+            .into_iter()
+            .map(|e| (e.path, e.keyword))
+            .collect()
+    }
+
+    fn error(path: &str, keyword: &str) -> (String, String) {
+        (path.to_string(), keyword.to_string())
+    }
+
+    #[test]
+    fn test_types() {
+        assert!(errors("3", r#"{"type": "integer"}"#).is_empty());
+        assert!(errors("3.0", r#"{"type": "integer"}"#).is_empty());
+        assert!(errors("3", r#"{"type": "number"}"#).is_empty());
+        assert_eq!(errors("3.5", r#"{"type": "integer"}"#), [error("", "type")]);
+        assert!(errors("null", r#"{"type": ["string", "null"]}"#).is_empty());
+        assert_eq!(errors("[]", "false"), [error("", "false")]);
+        assert!(errors("[]", "true").is_empty());
+    }
+
+    #[test]
+    fn test_nested_paths() {
+        let schema = r#"{
+            "type": "object",
+            "required": ["id"],
+            "additionalProperties": false,
+            "properties": {
+                "id": {"type": "integer"},
+                "tags": {"type": "array", "items": {"type": "string", "maxLength": 3}, "uniqueItems": true}
+            }
+        }"#;
+        assert_eq!(
+            errors(r#"{"tags": ["ok", "long", 1, "ok"], "x/y": 1}"#, schema),
+            [
+                error("", "required"),
+                error("/tags/1", "maxLength"),
+                error("/tags/2", "type"),
+                error("/tags", "uniqueItems"),
+                error("/x~1y", "additionalProperties"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_numbers_and_enums() {
+        let schema = r#"{"minimum": 1, "exclusiveMaximum": 10, "multipleOf": 2}"#;
+        assert!(errors("4", schema).is_empty());
+        assert_eq!(errors("0", schema), [error("", "minimum")]);
+        assert_eq!(
+            errors("11", schema),
+            [error("", "exclusiveMaximum"), error("", "multipleOf")]
+        );
+        assert_eq!(
+            errors(r#""c""#, r#"{"enum": ["a", "b"]}"#),
+            [error("", "enum")]
+        );
+        assert!(errors("1.0", r#"{"const": 1}"#).is_empty());
+    }
+
+    #[test]
+    fn test_combinators() {
+        let schema = r#"{"oneOf": [{"type": "integer"}, {"minimum": 0}]}"#;
+        assert!(errors("-1", schema).is_empty());
+        assert_eq!(errors("1", schema), [error("", "oneOf")]);
+        assert_eq!(
+            errors(
+                r#""x""#,
+                r#"{"anyOf": [{"type": "integer"}, {"type": "null"}]}"#
+            ),
+            [error("", "anyOf")]
+        );
+        assert_eq!(
+            errors("1", r#"{"not": {"type": "integer"}}"#),
+            [error("", "not")]
+        );
+        let conditional =
+            r#"{"if": {"type": "string"}, "then": {"minLength": 2}, "else": {"type": "null"}}"#;
+        assert_eq!(errors(r#""a""#, conditional), [error("", "minLength")]);
+        assert_eq!(errors("1", conditional), [error("", "type")]);
+        assert_eq!(
+            errors("[1, 2]", r#"{"contains": {"type": "string"}}"#),
+            [error("", "contains")]
+        );
+    }
+
+    #[test]
+    fn test_refs() {
+        let schema = r##"{
+            "$defs": {"node": {"type": "object", "properties": {"next": {"$ref": "#/$defs/node"}}}},
+            "$ref": "#/$defs/node"
+        }"##;
+        assert!(errors(r#"{"next": {"next": {}}}"#, schema).is_empty());
+        assert_eq!(
+            errors(r#"{"next": {"next": 1}}"#, schema),
+            [error("/next/next", "type")]
+        );
+        assert_eq!(
+            errors("1", r##"{"$ref": "#/missing"}"##),
+            [error("", "$ref")]
+        );
+        assert_eq!(errors("1", r##"{"$ref": "#"}"##), [error("", "$ref")]);
+    }
+}
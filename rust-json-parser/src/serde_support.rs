@@ -0,0 +1,168 @@
+//! `serde` support for [`JsonValue`], enabled by the `serde` feature.
+//!
+//! [`JsonValue`] implements [`Serialize`] and [`Deserialize`], so it can be
+//! embedded in structs handled by any serde data format. Integers keep their
+//! exact value: they are serialized as `u64` or `i64` and only other numbers as
+//! `f64`.
+
+use crate::{JsonArray, JsonMap, JsonNumber, JsonValue};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::fmt;
+
+impl Serialize for JsonNumber {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(u) = self.as_u64() {
+            serializer.serialize_u64(u)
+        } else if let Some(i) = self.as_i64() {
+            serializer.serialize_i64(i)
+        } else {
+            serializer.serialize_f64(self.as_f64())
+        }
+    }
+}
+
+impl Serialize for JsonValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Boolean(b) => serializer.serialize_bool(*b),
+            JsonValue::Number(n) => n.serialize(serializer),
+            JsonValue::String(s) => serializer.serialize_str(s),
+            JsonValue::Array(arr) => {
+                let mut seq = serializer.serialize_seq(Some(arr.len()))?;
+                for item in arr {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            JsonValue::Object(obj) => {
+                let mut map = serializer.serialize_map(Some(obj.len()))?;
+                for (key, value) in obj {
+                    map.serialize_entry(&**key, value)?;
+                }
+                map.end()
+            }
+            #[cfg(feature = "custom-values")]
+            JsonValue::Custom(custom) => custom.to_json().serialize(serializer),
+        }
+    }
+}
+
+/*
+ * Builds a `JsonValue` from whatever the data format presents.
+ */
+struct JsonValueVisitor;
+
+impl<'de> Visitor<'de> for JsonValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::Boolean(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, i: i64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(i.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, u: u64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(u.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, f: f64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(f.into()))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(s.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(s))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        JsonValue::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut arr = JsonArray::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(item) = seq.next_element()? {
+            arr.push(item);
+        }
+        Ok(JsonValue::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut obj = JsonMap::default();
+        while let Some((key, value)) = map.next_entry::<String, JsonValue>()? {
+            obj.insert(key.as_str().into(), value);
+        }
+        Ok(JsonValue::Object(obj))
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+    use serde_test::{Token, assert_de_tokens, assert_ser_tokens, assert_tokens};
+
+    #[test]
+    fn test_scalars_round_trip() {
+        assert_tokens(&JsonValue::Null, &[Token::Unit]);
+        assert_tokens(&JsonValue::Boolean(true), &[Token::Bool(true)]);
+        assert_tokens(&JsonValue::Number(7.into()), &[Token::U64(7)]);
+        assert_tokens(&JsonValue::Number((-7).into()), &[Token::I64(-7)]);
+        assert_tokens(&JsonValue::Number(2.5.into()), &[Token::F64(2.5)]);
+        assert_tokens(&JsonValue::String("hi".to_string()), &[Token::String("hi")]);
+    }
+
+    #[test]
+    fn test_containers_round_trip() {
+        let value = parse_json(r#"{"list": [1, null]}"#).unwrap();
+        assert_tokens(
+            &value,
+            &[
+                Token::Map { len: Some(1) },
+                Token::String("list"),
+                Token::Seq { len: Some(2) },
+                Token::U64(1),
+                Token::Unit,
+                Token::SeqEnd,
+                Token::MapEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_integers_stay_exact() {
+        let value = parse_json("9007199254740993").unwrap();
+        assert_ser_tokens(&value, &[Token::U64(9007199254740993)]);
+    }
+
+    #[test]
+    fn test_deserialize_from_other_shapes() {
+        assert_de_tokens(&JsonValue::Null, &[Token::None]);
+        assert_de_tokens(&JsonValue::Number(3.into()), &[Token::Some, Token::I8(3)]);
+        assert_de_tokens(&JsonValue::String("x".to_string()), &[Token::Char('x')]);
+    }
+}
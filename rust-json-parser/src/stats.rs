@@ -0,0 +1,284 @@
+//! Statistics about the shape of a document, for data-quality checks.
+//!
+//! [`JsonValue::key_histogram`] walks a whole tree once and counts how often
+//! each object key occurs and which types of values it holds.
+//! [`JsonValue::stats`] sums up the size and shape of a tree, such as its depth
+//! and approximate memory use.
+
+use std::collections::BTreeMap;
+use std::mem::size_of;
+
+use crate::query::push_segment;
+use crate::value::{JsonKey, JsonValue};
+
+/// Counts of values by JSON type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeCounts {
+    /// Number of `null` values.
+    pub null: usize,
+    /// Number of booleans.
+    pub boolean: usize,
+    /// Number of numbers.
+    pub number: usize,
+    /// Number of strings.
+    pub string: usize,
+    /// Number of arrays.
+    pub array: usize,
+    /// Number of objects.
+    pub object: usize,
+}
+
+impl TypeCounts {
+    /// Returns the number of values of any type.
+    pub fn total(&self) -> usize {
+        self.null + self.boolean + self.number + self.string + self.array + self.object
+    }
+
+    /// Returns the counts as `(type name, count)` pairs, using the type names of
+    /// JSON Schema without `"integer"`.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, usize)> {
+        [
+            ("null", self.null),
+            ("boolean", self.boolean),
+            ("number", self.number),
+            ("string", self.string),
+            ("array", self.array),
+            ("object", self.object),
+        ]
+        .into_iter()
+    }
+
+    fn count(&mut self, value: &JsonValue) {
+        match value {
+            JsonValue::Null => self.null += 1,
+            JsonValue::Boolean(_) => self.boolean += 1,
+            JsonValue::Number(_) => self.number += 1,
+            JsonValue::String(_) => self.string += 1,
+            JsonValue::Array(_) => self.array += 1,
+            JsonValue::Object(_) => self.object += 1,
+            #[cfg(feature = "custom-values")]
+            JsonValue::Custom(custom) => self.count(&custom.to_json()),
+        }
+    }
+}
+
+/// How often keys and value types occur in a document, returned by
+/// [`JsonValue::key_histogram`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyHistogram {
+    /// For every object key, the types of the values stored under it. The
+    /// [`total`](TypeCounts::total) is the number of objects with that key.
+    pub keys: BTreeMap<String, TypeCounts>,
+    /// The types of all values in the document, the root included.
+    pub types: TypeCounts,
+}
+
+impl KeyHistogram {
+    fn add(&mut self, value: &JsonValue) {
+        #[cfg(feature = "custom-values")]
+        if let JsonValue::Custom(custom) = value {
+            return self.add(&custom.to_json());
+        }
+        self.types.count(value);
+        match value {
+            JsonValue::Array(items) => items.iter().for_each(|item| self.add(item)),
+            JsonValue::Object(members) => {
+                for (key, member) in members {
+                    match self.keys.get_mut(&**key) {
+                        Some(counts) => counts.count(member),
+                        None => {
+                            let mut counts = TypeCounts::default();
+                            counts.count(member);
+                            self.keys.insert(key.to_string(), counts);
+                        }
+                    }
+                    self.add(member);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Figures about the size and shape of a document, returned by
+/// [`JsonValue::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentStats {
+    /// The types of all values in the document, the root included.
+    pub types: TypeCounts,
+    /// The number of arrays and objects on the most deeply nested path: `0`
+    /// for a scalar, `1` for `[1, 2]`, `2` for `[[1]]`.
+    pub max_depth: usize,
+    /// The number of members of all objects together.
+    pub object_keys: usize,
+    /// The length in bytes of the longest string value (keys not included).
+    pub longest_string: usize,
+    /// JSON Pointer to the longest string value, or `None` if there are no
+    /// strings.
+    pub longest_string_path: Option<String>,
+    /// An estimate of the memory the parsed tree takes, in bytes. It counts the
+    /// values, the string and array buffers and the map entries, but not the
+    /// spare capacity of maps or the bookkeeping of the allocator.
+    pub approx_memory: usize,
+}
+
+impl DocumentStats {
+    fn add(&mut self, value: &JsonValue, depth: usize, path: &mut String) {
+        #[cfg(feature = "custom-values")]
+        if let JsonValue::Custom(custom) = value {
+            return self.add(&custom.to_json(), depth, path);
+        }
+        self.types.count(value);
+        match value {
+            JsonValue::Number(number) => {
+                self.approx_memory += number.raw().map_or(0, str::len);
+            }
+            JsonValue::String(s) => {
+                self.approx_memory += s.capacity();
+                if self.longest_string_path.is_none() || s.len() > self.longest_string {
+                    self.longest_string = s.len();
+                    self.longest_string_path = Some(path.clone());
+                }
+            }
+            JsonValue::Array(items) => {
+                self.max_depth = self.max_depth.max(depth + 1);
+                self.approx_memory += items.capacity() * size_of::<JsonValue>();
+                for (index, item) in items.iter().enumerate() {
+                    let len = path.len();
+                    push_segment(path, &index.to_string());
+                    self.add(item, depth + 1, path);
+                    path.truncate(len);
+                }
+            }
+            JsonValue::Object(members) => {
+                self.max_depth = self.max_depth.max(depth + 1);
+                self.object_keys += members.len();
+                for (key, member) in members {
+                    self.approx_memory += size_of::<(JsonKey, JsonValue)>() + key.len();
+                    let len = path.len();
+                    push_segment(path, key);
+                    self.add(member, depth + 1, path);
+                    path.truncate(len);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl JsonValue {
+    /// Sums up the size and shape of the tree: the number of values of each
+    /// type, the nesting depth, the number of object members, the longest
+    /// string and an estimate of the memory it takes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"users": [{"name": "Ann"}, {"name": "Robert"}]}"#)?;
+    /// let stats = value.stats();
+    ///
+    /// assert_eq!(stats.types.total(), 6);
+    /// assert_eq!(stats.max_depth, 3);
+    /// assert_eq!(stats.object_keys, 3);
+    /// assert_eq!(stats.longest_string, 6);
+    /// assert_eq!(stats.longest_string_path.as_deref(), Some("/users/1/name"));
+    /// assert!(stats.approx_memory > 0);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn stats(&self) -> DocumentStats {
+        let mut stats = DocumentStats {
+            approx_memory: size_of::<JsonValue>(),
+            ..DocumentStats::default()
+        };
+        stats.add(self, 0, &mut String::new());
+        stats
+    }
+
+    /// Counts how often every object key occurs across the whole tree, with the
+    /// types of the values stored under it, along with the types of all values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let users = parse_json(r#"[{"name": "Ann", "age": 31}, {"name": "Bob", "age": null}]"#)?;
+    /// let histogram = users.key_histogram();
+    ///
+    /// assert_eq!(histogram.keys["name"].string, 2);
+    /// assert_eq!(histogram.keys["age"].total(), 2);
+    /// assert_eq!(histogram.keys["age"].null, 1);
+    /// assert_eq!(histogram.types.object, 2);
+    /// assert_eq!(histogram.types.total(), 7);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn key_histogram(&self) -> KeyHistogram {
+        let mut histogram = KeyHistogram::default();
+        histogram.add(self);
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn test_key_histogram_counts_nested_keys() {
+        let value = parse_json(
+            r#"{"id": 1, "items": [{"id": "a", "tags": []}, {"id": 2, "meta": {"id": null}}]}"#,
+        )
+        .unwrap();
+        let histogram = value.key_histogram();
+        assert_eq!(
+            histogram.keys.keys().collect::<Vec<_>>(),
+            ["id", "items", "meta", "tags"]
+        );
+        assert_eq!(
+            histogram.keys["id"],
+            TypeCounts {
+                null: 1,
+                number: 2,
+                string: 1,
+                ..TypeCounts::default()
+            }
+        );
+        assert_eq!(histogram.keys["items"].array, 1);
+        assert_eq!(
+            histogram.types.iter().collect::<Vec<_>>(),
+            [
+                ("null", 1),
+                ("boolean", 0),
+                ("number", 2),
+                ("string", 1),
+                ("array", 2),
+                ("object", 4),
+            ]
+        );
+        assert_eq!(
+            parse_json("[true]").unwrap().key_histogram().keys,
+            BTreeMap::new()
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let value = parse_json(r#"[1, "ab", {"k": ["abc", "xyz", {}]}, null]"#).unwrap();
+        let stats = value.stats();
+        assert_eq!(stats.types.total(), 9);
+        assert_eq!(stats.types.string, 3);
+        assert_eq!(stats.max_depth, 4);
+        assert_eq!(stats.object_keys, 1);
+        assert_eq!(stats.longest_string, 3);
+        assert_eq!(stats.longest_string_path.as_deref(), Some("/2/k/0"));
+        assert!(stats.approx_memory >= 8 * size_of::<JsonValue>() + 7);
+
+        let scalar = parse_json("true").unwrap().stats();
+        assert_eq!(scalar.max_depth, 0);
+        assert_eq!(scalar.longest_string_path, None);
+        assert_eq!(scalar.approx_memory, size_of::<JsonValue>());
+    }
+}
@@ -0,0 +1,385 @@
+//! Event-driven (SAX-style) parsing.
+//!
+//! Instead of building a [`JsonValue`](crate::JsonValue) tree, the
+//! [`StreamingParser`] reports every structural element of the document to a
+//! [`JsonEventHandler`] as soon as it is read. This lets callers aggregate or
+//! filter huge documents without materializing them in memory.
+
+use crate::error::{unexpected_end_of_input, unexpected_token_error};
+use crate::number::JsonNumber;
+use crate::tokenizer::{RawToken, Tokenizer};
+use crate::{JsonError, JsonResult};
+
+/// Receives parsing events from a [`StreamingParser`].
+///
+/// Every method has an empty default implementation, so handlers only need to
+/// implement the events they care about.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{JsonEventHandler, JsonNumber, StreamingParser};
+///
+/// #[derive(Default)]
+/// struct NumberSum(f64);
+///
+/// impl JsonEventHandler for NumberSum {
+///     fn on_number(&mut self, value: &JsonNumber) {
+///         self.0 += value.as_f64();
+///     }
+/// }
+///
+/// let mut sum = NumberSum::default();
+/// StreamingParser::new(r#"{"a": 1, "b": [2, 3]}"#).parse_with(&mut sum)?;
+/// assert_eq!(sum.0, 6.0);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+pub trait JsonEventHandler {
+    /// Called when an opening brace `{` is read.
+    fn on_object_start(&mut self) {}
+    /// Called when a closing brace `}` is read.
+    fn on_object_end(&mut self) {}
+    /// Called when an opening bracket `[` is read.
+    fn on_array_start(&mut self) {}
+    /// Called when a closing bracket `]` is read.
+    fn on_array_end(&mut self) {}
+    /// Called with each object key, before the events of its value.
+    fn on_key(&mut self, _key: &str) {}
+    /// Called with each string value.
+    fn on_string(&mut self, _value: &str) {}
+    /// Called with each numeric value.
+    fn on_number(&mut self, _value: &JsonNumber) {}
+    /// Called with each boolean value.
+    fn on_boolean(&mut self, _value: bool) {}
+    /// Called for each `null` value.
+    fn on_null(&mut self) {}
+}
+
+/*
+ * The kind of container the parser is currently inside of.
+ */
+enum Container {
+    Array,
+    Object,
+}
+
+/*
+ * What the parser accepts as the next token.
+ */
+#[derive(Clone, Copy)]
+enum Expect {
+    /// Any JSON value (at the root, after a colon or after an array comma).
+    Value,
+    /// A value or `]`, right after an opening bracket.
+    ArrayValueOrEnd,
+    /// A `,` or `]`, after an array element.
+    ArrayCommaOrEnd,
+    /// A key or `}`, right after an opening brace.
+    ObjectKeyOrEnd,
+    /// A key, after an object comma.
+    ObjectKey,
+    /// The `:` following a key.
+    Colon,
+    /// A `,` or `}`, after an object member.
+    ObjectCommaOrEnd,
+}
+
+impl Expect {
+    fn description(self) -> &'static str {
+        match self {
+            Expect::Value => "valid JSON value",
+            Expect::ArrayValueOrEnd => "valid JSON value or closing bracket",
+            Expect::ArrayCommaOrEnd => ", or closing bracket",
+            Expect::ObjectKeyOrEnd => "string or closing brace",
+            Expect::ObjectKey => "string",
+            Expect::Colon => ":",
+            Expect::ObjectCommaOrEnd => ", or closing brace",
+        }
+    }
+}
+
+/// A parser that reports JSON structure as a sequence of events rather than
+/// building a tree.
+///
+/// It shares the [`Tokenizer`] with [`JsonParser`](crate::JsonParser) and performs
+/// the same structural validation, but uses an explicit stack instead of
+/// recursion, never stores more than one token at a time and only allocates
+/// for strings that contain escape sequences.
+pub struct StreamingParser<'input> {
+    tokenizer: Tokenizer<'input>,
+    position: usize,
+}
+
+impl<'input> StreamingParser<'input> {
+    /// Creates a new `StreamingParser` over the given JSON input string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::StreamingParser;
+    ///
+    /// let parser = StreamingParser::new("[1, 2, 3]");
+    /// ```
+    pub fn new(input: &'input str) -> Self {
+        let mut tokenizer = Tokenizer::new(input);
+        tokenizer.skip_bom();
+        Self {
+            tokenizer,
+            position: 0,
+        }
+    }
+
+    /// Parses one complete JSON value, reporting each element to `handler`.
+    ///
+    /// Events already delivered before an error is found are not retracted, so
+    /// handlers that must only act on valid documents should buffer their results
+    /// until this method returns `Ok`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{JsonEventHandler, StreamingParser};
+    ///
+    /// struct KeyCollector(Vec<String>);
+    ///
+    /// impl JsonEventHandler for KeyCollector {
+    ///     fn on_key(&mut self, key: &str) {
+    ///         self.0.push(key.to_string());
+    ///     }
+    /// }
+    ///
+    /// let mut keys = KeyCollector(Vec::new());
+    /// StreamingParser::new(r#"{"id": 1, "tags": {"x": null}}"#).parse_with(&mut keys)?;
+    /// assert_eq!(keys.0, vec!["id", "tags", "x"]);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`JsonParser::parse`](crate::JsonParser::parse):
+    /// tokenization errors, [`JsonError::UnexpectedToken`] for structurally
+    /// invalid JSON, [`JsonError::UnexpectedEndOfInput`] if the document is
+    /// incomplete and [`JsonError::TrailingCharacters`] if anything but
+    /// whitespace follows it.
+    pub fn parse_with<H: JsonEventHandler>(&mut self, handler: &mut H) -> JsonResult<()> {
+        let mut stack: Vec<Container> = Vec::new();
+        let mut expect = Expect::Value;
+
+        loop {
+            let token = match self.tokenizer.next_raw_token()? {
+                Some(token) => token,
+                None => {
+                    return Err(unexpected_end_of_input(expect.description(), self.position));
+                }
+            };
+
+            let closed_value = match (expect, &token) {
+                (Expect::ArrayValueOrEnd | Expect::ArrayCommaOrEnd, RawToken::RightBracket) => {
+                    stack.pop();
+                    handler.on_array_end();
+                    true
+                }
+                (Expect::ObjectKeyOrEnd | Expect::ObjectCommaOrEnd, RawToken::RightBrace) => {
+                    stack.pop();
+                    handler.on_object_end();
+                    true
+                }
+                (Expect::ArrayCommaOrEnd, RawToken::Comma) => {
+                    expect = Expect::Value;
+                    false
+                }
+                (Expect::ObjectCommaOrEnd, RawToken::Comma) => {
+                    expect = Expect::ObjectKey;
+                    false
+                }
+                (Expect::ObjectKeyOrEnd | Expect::ObjectKey, RawToken::String(key)) => {
+                    handler.on_key(key);
+                    expect = Expect::Colon;
+                    false
+                }
+                (Expect::Colon, RawToken::Colon) => {
+                    expect = Expect::Value;
+                    false
+                }
+                (Expect::Value | Expect::ArrayValueOrEnd, RawToken::LeftBracket) => {
+                    stack.push(Container::Array);
+                    handler.on_array_start();
+                    expect = Expect::ArrayValueOrEnd;
+                    false
+                }
+                (Expect::Value | Expect::ArrayValueOrEnd, RawToken::LeftBrace) => {
+                    stack.push(Container::Object);
+                    handler.on_object_start();
+                    expect = Expect::ObjectKeyOrEnd;
+                    false
+                }
+                (Expect::Value | Expect::ArrayValueOrEnd, RawToken::String(s)) => {
+                    handler.on_string(s);
+                    true
+                }
+                (Expect::Value | Expect::ArrayValueOrEnd, RawToken::Number(n)) => {
+                    handler.on_number(n);
+                    true
+                }
+                (Expect::Value | Expect::ArrayValueOrEnd, RawToken::Boolean(b)) => {
+                    handler.on_boolean(*b);
+                    true
+                }
+                (Expect::Value | Expect::ArrayValueOrEnd, RawToken::Null) => {
+                    handler.on_null();
+                    true
+                }
+                (_, token) => {
+                    return Err(unexpected_token_error(
+                        expect.description(),
+                        &format!("{:?}", token),
+                        self.position,
+                    ));
+                }
+            };
+            self.position += 1;
+
+            if closed_value {
+                expect = match stack.last() {
+                    Some(Container::Array) => Expect::ArrayCommaOrEnd,
+                    Some(Container::Object) => Expect::ObjectCommaOrEnd,
+                    None => break,
+                };
+            }
+        }
+        match self.tokenizer.next_raw_token() {
+            Ok(None) => Ok(()),
+            _ => Err(JsonError::TrailingCharacters {
+                position: self.tokenizer.token_start(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /*
+     * Records every event as a short string so tests can assert on the order.
+     */
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl JsonEventHandler for Recorder {
+        fn on_object_start(&mut self) {
+            self.0.push("{".to_string());
+        }
+        fn on_object_end(&mut self) {
+            self.0.push("}".to_string());
+        }
+        fn on_array_start(&mut self) {
+            self.0.push("[".to_string());
+        }
+        fn on_array_end(&mut self) {
+            self.0.push("]".to_string());
+        }
+        fn on_key(&mut self, key: &str) {
+            self.0.push(format!("key:{}", key));
+        }
+        fn on_string(&mut self, value: &str) {
+            self.0.push(format!("str:{}", value));
+        }
+        fn on_number(&mut self, value: &JsonNumber) {
+            self.0.push(format!("num:{}", value));
+        }
+        fn on_boolean(&mut self, value: bool) {
+            self.0.push(format!("bool:{}", value));
+        }
+        fn on_null(&mut self) {
+            self.0.push("null".to_string());
+        }
+    }
+
+    fn record(input: &str) -> JsonResult<Vec<String>> {
+        let mut recorder = Recorder::default();
+        StreamingParser::new(input).parse_with(&mut recorder)?;
+        Ok(recorder.0)
+    }
+
+    #[test]
+    fn test_primitive_events() {
+        assert_eq!(record("42").unwrap(), vec!["num:42"]);
+        assert_eq!(record("true").unwrap(), vec!["bool:true"]);
+        assert_eq!(record("null").unwrap(), vec!["null"]);
+        assert_eq!(record(r#""hi""#).unwrap(), vec!["str:hi"]);
+    }
+
+    #[test]
+    fn test_nested_events_in_order() {
+        let events = record(r#"{"a": [1, {"b": null}], "c": "d"}"#).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                "{", "key:a", "[", "num:1", "{", "key:b", "null", "}", "]", "key:c", "str:d", "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_containers() {
+        assert_eq!(record("[]").unwrap(), vec!["[", "]"]);
+        assert_eq!(record("{}").unwrap(), vec!["{", "}"]);
+        assert_eq!(
+            record("[[], {}]").unwrap(),
+            vec!["[", "[", "]", "{", "}", "]"]
+        );
+    }
+
+    #[test]
+    fn test_default_handler_methods() {
+        struct Nothing;
+        impl JsonEventHandler for Nothing {}
+        assert!(
+            StreamingParser::new(r#"[1, "a", {"k": false}]"#)
+                .parse_with(&mut Nothing)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_structural_errors() {
+        assert!(matches!(
+            record("[1 2]"),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            record("[1,]"),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            record(r#"{"a" 1}"#),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            record(r#"{1: 2}"#),
+            Err(JsonError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(
+            record("[1, 2"),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+        assert!(matches!(
+            record(""),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+    }
+
+    #[test]
+    fn test_byte_order_mark_is_skipped() {
+        assert_eq!(record("\u{FEFF}[1]").unwrap(), record("[1]").unwrap());
+    }
+
+    #[test]
+    fn test_tokenizer_errors_propagate() {
+        assert!(matches!(
+            record(r#"["\q"]"#),
+            Err(JsonError::InvalidEscape { .. })
+        ));
+    }
+}
@@ -0,0 +1,279 @@
+//! Escaping and unescaping the contents of JSON strings.
+//!
+//! These are the routines the parser and the serializer use, exposed for tools
+//! that build or take apart JSON text by hand. They work on the contents between
+//! the quotes: [`escape`] never adds quotes and [`unescape`] expects none.
+//!
+//! Escapes follow RFC 8259: `\"`, `\\`, `\/`, `\b`, `\f`, `\n`, `\r`, `\t` and
+//! `\uXXXX`, where characters outside the Basic Multilingual Plane are written
+//! as a UTF-16 surrogate pair such as `\ud83d\ude00`.
+
+use crate::{JsonError, JsonResult};
+
+/*
+ * Returns the character a single-character escape sequence (the character after
+ * the backslash) stands for, or `None` if it is not one.
+ */
+pub(crate) fn resolve_escape_sequence(char: char) -> Option<char> {
+    match char {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '\\' => Some('\\'),
+        '"' => Some('"'),
+        '/' => Some('/'),
+        'b' => Some('\u{0008}'), // backspace
+        'f' => Some('\u{000C}'), // form feed
+        _ => None,
+    }
+}
+
+/*
+ * Reads the four hex digits of a `\uXXXX` escape starting at `start`.
+ */
+fn hex_unit(input: &str, start: usize) -> Option<u16> {
+    let hex = input.get(start..start + 4)?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u16::from_str_radix(hex, 16).ok()
+}
+
+/*
+ * Decodes the `\uXXXX` escape whose hex digits start at `start` (right after the
+ * `u`), combining a high surrogate with the `\uXXXX` low surrogate that must
+ * follow it. Returns the character and the offset just past the escape.
+ */
+pub(crate) fn decode_unicode_escape(input: &str, start: usize) -> JsonResult<(char, usize)> {
+    let invalid = |end: usize| JsonError::InvalidUnicode {
+        sequence: format!(
+            "\\u{}",
+            input.get(start..end.min(input.len())).unwrap_or_default()
+        ),
+        position: start,
+    };
+    let unit = hex_unit(input, start).ok_or_else(|| invalid(start + 4))?;
+    match unit {
+        0xD800..=0xDBFF => {
+            let low = input[start + 4..]
+                .strip_prefix("\\u")
+                .and_then(|_| hex_unit(input, start + 6))
+                .filter(|low| (0xDC00..=0xDFFF).contains(low))
+                .ok_or_else(|| invalid(start + 4))?;
+            let code = 0x10000 + ((u32::from(unit) - 0xD800) << 10) + (u32::from(low) - 0xDC00);
+            let ch = char::from_u32(code).expect("surrogate pairs encode valid characters");
+            Ok((ch, start + 10))
+        }
+        0xDC00..=0xDFFF => Err(invalid(start + 4)),
+        unit => Ok((
+            char::from_u32(u32::from(unit)).expect("not a surrogate"),
+            start + 4,
+        )),
+    }
+}
+
+/// Escapes `s` for use between the quotes of a JSON string.
+///
+/// Quotes, backslashes and control characters are escaped, using the short forms
+/// (`\n`, `\t`, ...) where they exist and `\u00XX` otherwise. Everything else,
+/// including `/` and non-ASCII characters, is kept as it is; see
+/// [`escape_ascii`] for ASCII-only output.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::strings::escape;
+///
+/// assert_eq!(escape("say \"hi\"\n"), r#"say \"hi\"\n"#);
+/// assert_eq!(escape("tab\there\u{1}"), r"tab\there\u0001");
+/// assert_eq!(escape("café"), "café");
+/// ```
+pub fn escape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '\u{0008}' => result.push_str("\\b"),
+            '\u{000C}' => result.push_str("\\f"),
+            c if c < ' ' => result.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Escapes `s` like [`escape`], and also writes every non-ASCII character as
+/// `\uXXXX`, using a surrogate pair for characters outside the Basic
+/// Multilingual Plane. The result is plain ASCII.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::strings::escape_ascii;
+///
+/// assert_eq!(escape_ascii("café 😀"), r"caf\u00e9 \ud83d\ude00");
+/// ```
+pub fn escape_ascii(s: &str) -> String {
+    let escaped = escape(s);
+    if escaped.is_ascii() {
+        return escaped;
+    }
+    let mut result = String::with_capacity(escaped.len() * 2);
+    let mut units = [0; 2];
+    for c in escaped.chars() {
+        if c.is_ascii() {
+            result.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut units) {
+                result.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    result
+}
+
+/// Decodes the escape sequences in the contents of a JSON string, as found
+/// between its quotes.
+///
+/// `\uXXXX` escapes may use upper- or lowercase hex digits, and a surrogate pair
+/// decodes to a single character.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::strings::unescape;
+///
+/// assert_eq!(unescape(r"caf\u00e9 \ud83d\ude00\n")?, "café 😀\n");
+/// assert_eq!(unescape(r"a\/b")?, "a/b");
+/// assert!(unescape(r"\ud83d").is_err());
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// # Errors
+///
+/// The error positions are byte offsets into `s`:
+///
+/// - [`JsonError::InvalidEscape`] for a backslash followed by a character that
+///   does not start an escape sequence.
+/// - [`JsonError::InvalidUnicode`] for a `\u` not followed by four hex digits,
+///   or for a surrogate that is not part of a pair.
+/// - [`JsonError::UnexpectedEndOfInput`] for a trailing backslash.
+/// - [`JsonError::ControlCharacter`] for a raw control character (U+0000 to
+///   U+001F), and [`JsonError::UnexpectedToken`] for an unescaped `"`, since
+///   neither can appear in a JSON string.
+pub fn unescape(s: &str) -> JsonResult<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut position = 0;
+    while let Some(c) = s[position..].chars().next() {
+        match c {
+            '\\' => {
+                let Some(special) = s[position + 1..].chars().next() else {
+                    return Err(JsonError::UnexpectedEndOfInput {
+                        expected: "Special meaning char for escape sequence".to_string(),
+                        position: s.len(),
+                    });
+                };
+                if special == 'u' {
+                    let (ch, end) = decode_unicode_escape(s, position + 2)?;
+                    result.push(ch);
+                    position = end;
+                    continue;
+                }
+                let ch = resolve_escape_sequence(special).ok_or(JsonError::InvalidEscape {
+                    char: special,
+                    position: position + 1,
+                })?;
+                result.push(ch);
+                position += 1 + special.len_utf8();
+                continue;
+            }
+            '"' => {
+                return Err(JsonError::UnexpectedToken {
+                    expected: "escaped quote".to_string(),
+                    found: "\"".to_string(),
+                    position,
+                });
+            }
+            c if c < ' ' => {
+                return Err(JsonError::ControlCharacter { char: c, position });
+            }
+            c => result.push(c),
+        }
+        position += c.len_utf8();
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_round_trips_every_kind_of_character() {
+        let text = "q\" b\\ s/ \u{8}\u{c}\n\r\t \u{0}\u{1f} é 😀";
+        assert_eq!(escape(text), r#"q\" b\\ s/ \b\f\n\r\t \u0000\u001f é 😀"#);
+        assert_eq!(unescape(&escape(text)).unwrap(), text);
+        assert!(escape_ascii(text).is_ascii());
+        assert_eq!(unescape(&escape_ascii(text)).unwrap(), text);
+        assert_eq!(escape_ascii("😀"), r"\ud83d\ude00");
+    }
+
+    #[test]
+    fn test_unescape_surrogate_pairs() {
+        assert_eq!(unescape(r"\uD83D\uDE00!").unwrap(), "😀!");
+        assert_eq!(unescape(r"\u00e9\u00C9\u0041").unwrap(), "éÉA");
+        for (input, position) in [
+            (r"\ud83d", 2),
+            (r"\ud83dx", 2),
+            (r"\ud83d\u0041", 2),
+            (r"a\ude00", 3),
+            (r"\u12", 2),
+            (r"\u+123", 2),
+            (r"\u00é", 2),
+        ] {
+            assert!(
+                matches!(
+                    unescape(input),
+                    Err(JsonError::InvalidUnicode { position: p, .. }) if p == position
+                ),
+                "{}: {:?}",
+                input,
+                unescape(input)
+            );
+        }
+    }
+
+    #[test]
+    fn test_unescape_errors() {
+        assert!(matches!(
+            unescape(r"ab\q"),
+            Err(JsonError::InvalidEscape {
+                char: 'q',
+                position: 3
+            })
+        ));
+        assert!(matches!(
+            unescape(r"é\é"),
+            Err(JsonError::InvalidEscape { char: 'é', .. })
+        ));
+        assert!(matches!(
+            unescape("ab\\"),
+            Err(JsonError::UnexpectedEndOfInput { position: 3, .. })
+        ));
+        assert!(matches!(
+            unescape("a\nb"),
+            Err(JsonError::ControlCharacter {
+                char: '\n',
+                position: 1
+            })
+        ));
+        assert!(matches!(
+            unescape("a\"b"),
+            Err(JsonError::UnexpectedToken { position: 1, .. })
+        ));
+    }
+}
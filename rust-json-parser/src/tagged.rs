@@ -0,0 +1,235 @@
+//! A convention for values of richer types, such as dates or decimals, that
+//! have no JSON counterpart.
+//!
+//! A tagged value is an object with exactly two members: [`TYPE_KEY`] naming
+//! the type and [`VALUE_KEY`] holding its JSON representation, such as
+//! `{"$type": "datetime", "value": "2024-05-01T12:00:00Z"}`. Tagged values are
+//! built with [`JsonValue::tagged`], recognized with [`JsonValue::as_tagged`]
+//! and turned back into domain values by [`decode_tagged`]. The Python bindings
+//! use the same convention, so such values round-trip between both sides.
+
+use crate::value::{JsonMap, JsonValue};
+
+/// The member of a tagged value that names its type.
+pub const TYPE_KEY: &str = "$type";
+
+/// The member of a tagged value that holds its JSON representation.
+pub const VALUE_KEY: &str = "value";
+
+impl JsonValue {
+    /// Builds a tagged value: `{"$type": type_name, "value": value}`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::JsonValue;
+    ///
+    /// let when = JsonValue::tagged("datetime", JsonValue::String("2024-05-01".to_string()));
+    /// assert_eq!(when.as_tagged(), Some(("datetime", &JsonValue::String("2024-05-01".to_string()))));
+    /// ```
+    pub fn tagged(type_name: &str, value: JsonValue) -> JsonValue {
+        let mut members = JsonMap::default();
+        members.insert(TYPE_KEY.into(), JsonValue::String(type_name.to_string()));
+        members.insert(VALUE_KEY.into(), value);
+        JsonValue::Object(members)
+    }
+
+    /// Returns the type name and representation if this is a tagged value: an
+    /// object with a string `"$type"` member, a `"value"` member and nothing
+    /// else. Returns `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"$type": "decimal", "value": "19.99"}"#)?;
+    /// assert_eq!(value.as_tagged().map(|(name, _)| name), Some("decimal"));
+    ///
+    /// let value = parse_json(r#"{"$type": "decimal", "value": "19.99", "note": 1}"#)?;
+    /// assert_eq!(value.as_tagged(), None);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn as_tagged(&self) -> Option<(&str, &JsonValue)> {
+        let members = self.as_object()?;
+        if members.len() != 2 {
+            return None;
+        }
+        let type_name = members.get(TYPE_KEY)?.as_str()?;
+        Some((type_name, members.get(VALUE_KEY)?))
+    }
+}
+
+/// Replaces tagged values in `value` with what `decode` returns for them.
+///
+/// `decode` is called with the type name and representation of every tagged
+/// value, innermost first, so a representation may itself contain values that
+/// were already decoded. Returning `Ok(None)` leaves a tagged value as it is,
+/// which is how types the caller does not know are passed through.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{JsonValue, parse_json, tagged::decode_tagged};
+///
+/// let mut value = parse_json(r#"{"total": {"$type": "cents", "value": 1999}}"#)?;
+/// decode_tagged(&mut value, |type_name: &str, repr: &JsonValue| {
+///     Ok::<_, String>(match (type_name, repr.as_f64()) {
+///         ("cents", Some(cents)) => Some(JsonValue::Number((cents / 100.0).into())),
+///         ("cents", None) => return Err(format!("bad cents: {}", repr)),
+///         _ => None,
+///     })
+/// })?;
+/// assert_eq!(value, parse_json(r#"{"total": 19.99}"#)?);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Stops at and returns the first error returned by `decode`; values decoded
+/// before it keep their new form.
+pub fn decode_tagged<E, F>(value: &mut JsonValue, mut decode: F) -> Result<(), E>
+where
+    F: FnMut(&str, &JsonValue) -> Result<Option<JsonValue>, E>,
+{
+    decode_into(value, &mut decode)
+}
+
+fn decode_into<E, F>(value: &mut JsonValue, decode: &mut F) -> Result<(), E>
+where
+    F: FnMut(&str, &JsonValue) -> Result<Option<JsonValue>, E>,
+{
+    match value {
+        JsonValue::Array(items) => {
+            for item in items {
+                decode_into(item, decode)?;
+            }
+        }
+        JsonValue::Object(members) => {
+            for member in members.values_mut() {
+                decode_into(member, decode)?;
+            }
+        }
+        _ => return Ok(()),
+    }
+    if let Some((type_name, repr)) = value.as_tagged()
+        && let Some(decoded) = decode(type_name, repr)?
+    {
+        *value = decoded;
+    }
+    Ok(())
+}
+
+/// Replaces every [`JsonValue::Custom`] in `value` with a tagged value named
+/// after its [`type_name`](crate::CustomValue::type_name), so that
+/// [`decode_tagged`] can restore it after the document was serialized and
+/// parsed again. Requires the `custom-values` feature.
+#[cfg(feature = "custom-values")]
+pub fn encode_custom_values(value: &mut JsonValue) {
+    match value {
+        JsonValue::Array(items) => items.iter_mut().for_each(encode_custom_values),
+        JsonValue::Object(members) => members.values_mut().for_each(encode_custom_values),
+        JsonValue::Custom(custom) => {
+            *value = JsonValue::tagged(custom.type_name(), custom.to_json());
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn test_as_tagged_requires_exact_shape() {
+        let tagged = JsonValue::tagged("set", parse_json("[1, 2]").unwrap());
+        assert_eq!(
+            tagged.as_tagged(),
+            Some(("set", &parse_json("[1, 2]").unwrap()))
+        );
+        for not_tagged in [
+            r#"{"$type": 1, "value": 2}"#,
+            r#"{"$type": "set"}"#,
+            r#"{"type": "set", "value": 2}"#,
+            r#"["set", 2]"#,
+        ] {
+            assert_eq!(parse_json(not_tagged).unwrap().as_tagged(), None);
+        }
+    }
+
+    #[test]
+    fn test_decode_tagged_innermost_first() {
+        let mut value = parse_json(
+            r#"[{"$type": "pair", "value": [{"$type": "neg", "value": 1}, 2]},
+                {"$type": "unknown", "value": 3}]"#,
+        )
+        .unwrap();
+        let mut seen = Vec::new();
+        decode_tagged(&mut value, |type_name: &str, repr: &JsonValue| {
+            seen.push(format!("{} {}", type_name, repr));
+            Ok::<_, ()>(match type_name {
+                "neg" => Some(JsonValue::Number((-repr.as_f64().unwrap()).into())),
+                "pair" => Some(repr.clone()),
+                _ => None,
+            })
+        })
+        .unwrap();
+        assert_eq!(
+            value,
+            parse_json(r#"[[-1, 2], {"$type": "unknown", "value": 3}]"#).unwrap()
+        );
+        assert_eq!(seen, ["neg 1", "pair [-1,2]", "unknown 3"]);
+
+        let result = decode_tagged(&mut value.clone(), |_: &str, _: &JsonValue| Err("no"));
+        assert_eq!(result, Err("no"));
+    }
+
+    #[cfg(feature = "custom-values")]
+    #[test]
+    fn test_custom_values_round_trip_through_tags() {
+        use crate::CustomValue;
+
+        #[derive(Debug, Clone)]
+        struct Upper(String);
+
+        impl CustomValue for Upper {
+            fn type_name(&self) -> &str {
+                "upper"
+            }
+
+            fn to_json(&self) -> JsonValue {
+                JsonValue::String(self.0.to_lowercase())
+            }
+
+            fn clone_box(&self) -> Box<dyn CustomValue> {
+                Box::new(self.clone())
+            }
+        }
+
+        let mut value = JsonValue::Array(vec![JsonValue::Custom(Box::new(Upper("AB".into())))]);
+        encode_custom_values(&mut value);
+        let mut parsed = parse_json(&value.to_string()).unwrap();
+        assert_eq!(
+            parsed,
+            parse_json(r#"[{"$type": "upper", "value": "ab"}]"#).unwrap()
+        );
+
+        decode_tagged(&mut parsed, |type_name: &str, repr: &JsonValue| {
+            Ok::<_, ()>(
+                (type_name == "upper").then(|| {
+                    JsonValue::Custom(Box::new(Upper(repr.as_str().unwrap().to_uppercase())))
+                }),
+            )
+        })
+        .unwrap();
+        assert_eq!(
+            parsed
+                .get_index(0)
+                .unwrap()
+                .as_custom::<Upper>()
+                .map(|u| u.0.as_str()),
+            Some("AB")
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Assertion macros for tests that compare JSON documents, enabled by the
+//! `testing` feature.
+//!
+//! [`assert_json_eq!`](crate::assert_json_eq) and
+//! [`assert_json_includes!`](crate::assert_json_includes) compare [`JsonValue`]s
+//! and, on failure, panic with one line per difference (as a JSON Pointer)
+//! followed by both documents pretty-printed:
+//!
+//! ```text
+//! assert_json_eq!(left, right) failed:
+//!   ~ /replicas: 3 != 5
+//!   + /debug: true (only in right)
+//! left: ...
+//! ```
+
+use crate::hash::{HashedJson, JsonDiff};
+use crate::query::push_segment;
+use crate::value::JsonValue;
+
+/// Asserts that two [`JsonValue`]s are equal, printing their differences if not.
+///
+/// Values compare as with `==`: member order does not matter and `1` equals `1.0`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{assert_json_eq, parse_json};
+///
+/// let actual = parse_json(r#"{"b": [1, 2], "a": null}"#)?;
+/// assert_json_eq!(actual, parse_json(r#"{"a": null, "b": [1.0, 2]}"#)?);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+///
+/// ```should_panic
+/// use rust_json_parser::{assert_json_eq, parse_json};
+///
+/// // Panics with "~ /b/1: 2 != 3"
+/// assert_json_eq!(parse_json(r#"{"b": [1, 2]}"#)?, parse_json(r#"{"b": [1, 3]}"#)?);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        if let Some(report) = $crate::testing::json_eq_report(&$left, &$right) {
+            panic!(
+                "assert_json_eq!({}, {}) failed:\n{}",
+                stringify!($left),
+                stringify!($right),
+                report
+            );
+        }
+    };
+}
+
+/// Asserts that `actual` contains everything in `expected`, printing what is
+/// missing or different if not.
+///
+/// Objects in `actual` may have members that `expected` lacks; the members
+/// `expected` has must be included in the matching members of `actual`. Arrays
+/// must have the same length, with each element of `actual` including the
+/// matching element of `expected`. Other values must be equal.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{assert_json_includes, parse_json};
+///
+/// let response = parse_json(r#"{"id": 7, "user": {"name": "Ann", "roles": ["admin"]}}"#)?;
+/// assert_json_includes!(response, parse_json(r#"{"user": {"roles": ["admin"]}}"#)?);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[macro_export]
+macro_rules! assert_json_includes {
+    ($actual:expr, $expected:expr $(,)?) => {
+        if let Some(report) = $crate::testing::json_includes_report(&$actual, &$expected) {
+            panic!(
+                "assert_json_includes!({}, {}) failed:\n{}",
+                stringify!($actual),
+                stringify!($expected),
+                report
+            );
+        }
+    };
+}
+
+/*
+ * Formats a JSON Pointer for a report line; the root pointer is empty.
+ */
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "(root)" } else { path }
+}
+
+/*
+ * Joins the difference lines and both documents into a failure report.
+ */
+fn report(
+    lines: &[String],
+    left_name: &str,
+    left: &JsonValue,
+    right_name: &str,
+    right: &JsonValue,
+) -> String {
+    let mut report = String::new();
+    for line in lines {
+        report.push_str("  ");
+        report.push_str(line);
+        report.push('\n');
+    }
+    report.push_str(&format!(
+        "{}:\n{}\n{}:\n{}",
+        left_name,
+        left.pretty_print(2),
+        right_name,
+        right.pretty_print(2)
+    ));
+    report
+}
+
+/// Returns a description of how `left` and `right` differ, or `None` if they are
+/// equal. Used by [`assert_json_eq!`](crate::assert_json_eq).
+pub fn json_eq_report(left: &JsonValue, right: &JsonValue) -> Option<String> {
+    if left == right {
+        return None;
+    }
+    let mut lines: Vec<String> = HashedJson::new(left)
+        .diff(&HashedJson::new(right))
+        .into_iter()
+        .map(|diff| match diff {
+            JsonDiff::Added { path, value } => {
+                format!("+ {}: {} (only in right)", display_path(&path), value)
+            }
+            JsonDiff::Removed { path, value } => {
+                format!("- {}: {} (only in left)", display_path(&path), value)
+            }
+            JsonDiff::Changed { path, old, new } => {
+                format!("~ {}: {} != {}", display_path(&path), old, new)
+            }
+        })
+        .collect();
+    if lines.is_empty() {
+        // Values such as NaN are unequal without differing structurally
+        lines.push(format!("~ (root): {} != {}", left, right));
+    }
+    Some(report(&lines, "left", left, "right", right))
+}
+
+fn includes_into(
+    actual: &JsonValue,
+    expected: &JsonValue,
+    path: &mut String,
+    lines: &mut Vec<String>,
+) {
+    let length = path.len();
+    match (actual, expected) {
+        (JsonValue::Object(actual_members), JsonValue::Object(expected_members)) => {
+            let mut keys: Vec<&str> = expected_members.keys().map(|key| &**key).collect();
+            keys.sort_unstable();
+            for key in keys {
+                push_segment(path, key);
+                match actual_members.get(key) {
+                    Some(value) => includes_into(value, &expected_members[key], path, lines),
+                    None => lines.push(format!(
+                        "- {}: {} (missing from actual)",
+                        display_path(path),
+                        expected_members[key]
+                    )),
+                }
+                path.truncate(length);
+            }
+        }
+        (JsonValue::Array(actual_items), JsonValue::Array(expected_items))
+            if actual_items.len() == expected_items.len() =>
+        {
+            for (index, (value, expected)) in actual_items.iter().zip(expected_items).enumerate() {
+                push_segment(path, &index.to_string());
+                includes_into(value, expected, path, lines);
+                path.truncate(length);
+            }
+        }
+        _ if actual == expected => {}
+        _ => lines.push(format!(
+            "~ {}: {} != {} (expected)",
+            display_path(path),
+            actual,
+            expected
+        )),
+    }
+}
+
+/// Returns a description of what `expected` has that `actual` lacks, or `None`
+/// if `actual` includes all of `expected`. Used by
+/// [`assert_json_includes!`](crate::assert_json_includes).
+pub fn json_includes_report(actual: &JsonValue, expected: &JsonValue) -> Option<String> {
+    let mut lines = Vec::new();
+    includes_into(actual, expected, &mut String::new(), &mut lines);
+    if lines.is_empty() {
+        return None;
+    }
+    Some(report(&lines, "actual", actual, "expected", expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn test_eq_report_lists_differences() {
+        let left = parse_json(r#"{"a": 1, "b": [1, 2], "c": true}"#).unwrap();
+        let right = parse_json(r#"{"a": 1.0, "b": [1, 3], "d": null}"#).unwrap();
+        let report = json_eq_report(&left, &right).unwrap();
+        assert!(report.starts_with(
+            "  ~ /b/1: 2 != 3\n  - /c: true (only in left)\n  + /d: null (only in right)\nleft:\n"
+        ));
+        assert_eq!(json_eq_report(&left, &left.clone()), None);
+    }
+
+    #[test]
+    fn test_includes_report() {
+        let actual =
+            parse_json(r#"{"id": 1, "tags": ["a", "b"], "owner": {"name": "x"}}"#).unwrap();
+        assert_eq!(
+            json_includes_report(&actual, &parse_json(r#"{"owner": {}}"#).unwrap()),
+            None
+        );
+        let report = json_includes_report(
+            &actual,
+            &parse_json(r#"{"tags": ["a"], "owner": {"name": "y", "id": 2}}"#).unwrap(),
+        )
+        .unwrap();
+        assert!(report.starts_with(
+            "  - /owner/id: 2 (missing from actual)\n  ~ /owner/name: \"x\" != \"y\" (expected)\n  ~ /tags: [\"a\",\"b\"] != [\"a\"] (expected)\n"
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "~ (root): 1 != 2")]
+    fn test_assert_json_eq_panics() {
+        assert_json_eq!(JsonValue::Number(1.into()), JsonValue::Number(2.into()));
+    }
+
+    #[test]
+    fn test_assert_json_includes_passes() {
+        let actual = parse_json(r#"[{"a": 1, "b": 2}]"#).unwrap();
+        assert_json_includes!(actual, parse_json(r#"[{"b": 2}]"#).unwrap());
+    }
+}
@@ -1,19 +1,12 @@
-use crate::error::unexpected_token_error;
-use crate::{JsonError, JsonResult};
+use std::borrow::Cow;
 
-fn resolve_escape_sequence(char: char) -> Option<char> {
-    match char {
-        'n' => Some('\n'),
-        't' => Some('\t'),
-        'r' => Some('\r'),
-        '\\' => Some('\\'),
-        '"' => Some('"'),
-        '/' => Some('/'),
-        'b' => Some('\u{0008}'), // backspace
-        'f' => Some('\u{000C}'), // form feed
-        _ => None,
-    }
-}
+use crate::error::{unexpected_end_of_input, unexpected_token_error};
+use crate::number::JsonNumber;
+use crate::options::{
+    DEFAULT_MAX_EXPONENT, DEFAULT_MAX_NUMBER_LENGTH, ParserOptions, UnknownEscapePolicy,
+};
+use crate::strings::{decode_unicode_escape, resolve_escape_sequence};
+use crate::{JsonError, JsonResult};
 
 /// Represents a Token result of tokenization
 #[derive(Debug, Clone, PartialEq)]
@@ -21,7 +14,7 @@ pub enum Token {
     /// A quoted string value.
     String(String),
     /// A numeric literal.
-    Number(f64),
+    Number(JsonNumber),
     /// A `true` or `false` literal.
     Boolean(bool),
     /// The `null` literal.
@@ -39,6 +32,10 @@ pub enum Token {
     Colon,
     /// Comma `,` separating elements.
     Comma,
+    /// An unquoted object key such as `name` in `{name: 1}`, only produced when
+    /// unquoted keys are allowed (see
+    /// [`ParserOptions::allow_unquoted_keys`](crate::ParserOptions::allow_unquoted_keys)).
+    Identifier(String),
 }
 
 impl Token {
@@ -53,7 +50,7 @@ impl Token {
     /// let b = Token::String("world".to_string());
     /// assert!(a.is_variant(&b));
     ///
-    /// let c = Token::Number(42.0);
+    /// let c = Token::Number(42.into());
     /// assert!(!a.is_variant(&c));
     /// ```
     pub fn is_variant(&self, other: &Self) -> bool {
@@ -61,17 +58,287 @@ impl Token {
     }
 }
 
-fn parse_unicode_hex(s: &str) -> Option<char> {
-    if s.len() != 4 {
+/// A [`Token`] together with the byte offsets in the input where it starts and
+/// ends, as produced by [`Tokenizer::tokenize_spanned`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    /// The token itself.
+    pub token: Token,
+    /// Byte offset of the first byte of the token.
+    pub start: usize,
+    /// Byte offset just past the last byte of the token, so that
+    /// `&input[start..end]` is its source text.
+    pub end: usize,
+}
+
+/*
+ * The tokenizer's internal token representation. Strings without escape
+ * sequences borrow directly from the input; `Token` is the owned, public
+ * counterpart produced by `Tokenizer::tokenize`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum RawToken<'input> {
+    String(Cow<'input, str>),
+    Number(JsonNumber),
+    Boolean(bool),
+    Null,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    Colon,
+    Comma,
+    Identifier(&'input str),
+}
+
+impl From<RawToken<'_>> for Token {
+    fn from(token: RawToken<'_>) -> Self {
+        match token {
+            RawToken::String(s) => Token::String(s.into_owned()),
+            RawToken::Number(n) => Token::Number(n),
+            RawToken::Boolean(b) => Token::Boolean(b),
+            RawToken::Null => Token::Null,
+            RawToken::LeftBracket => Token::LeftBracket,
+            RawToken::RightBracket => Token::RightBracket,
+            RawToken::LeftBrace => Token::LeftBrace,
+            RawToken::RightBrace => Token::RightBrace,
+            RawToken::Colon => Token::Colon,
+            RawToken::Comma => Token::Comma,
+            RawToken::Identifier(name) => Token::Identifier(name.to_string()),
+        }
+    }
+}
+
+/*
+ * Decodes the escape sequences in string contents scanned with unescaping
+ * deferred. The contents were validated when scanned, so this cannot fail.
+ */
+pub(crate) fn unescape(raw: &str) -> Cow<'_, str> {
+    if !raw.contains('\\') {
+        return Cow::Borrowed(raw);
+    }
+    let mut tokenizer = Tokenizer::new(raw);
+    // `\'` only passed validation if single quotes were allowed
+    tokenizer.allow_single_quotes(true);
+    let mut out = String::with_capacity(raw.len());
+    let mut start = 0;
+    while let Some(offset) = raw[start..].find('\\') {
+        out.push_str(&raw[start..start + offset]);
+        tokenizer.current = start + offset;
+        tokenizer
+            .consume_escape(Some(&mut out))
+            .expect("escape sequences are validated when scanned");
+        start = tokenizer.current;
+    }
+    out.push_str(&raw[start..]);
+    Cow::Owned(out)
+}
+
+/*
+ * Returns the offset of the exponent of a number literal (after `e` or `E`) and
+ * its absolute value, saturating at `u64::MAX`. Literals without exponent digits
+ * return `None`; malformed ones are reported by the number parsing itself.
+ */
+fn exponent_of(literal: &[u8]) -> Option<(usize, u64)> {
+    let marker = literal.iter().position(|b| matches!(b, b'e' | b'E'))?;
+    let mut digits = &literal[marker + 1..];
+    if let Some((b'+' | b'-', rest)) = digits.split_first() {
+        digits = rest;
+    }
+    let digits = &digits[..digits.iter().take_while(|b| b.is_ascii_digit()).count()];
+    if digits.is_empty() {
         return None;
     }
-    u32::from_str_radix(s, 16).ok().and_then(char::from_u32)
+    let value = digits.iter().fold(0u64, |value, digit| {
+        value
+            .saturating_mul(10)
+            .saturating_add(u64::from(digit - b'0'))
+    });
+    Some((marker + 1, value))
+}
+
+/*
+ * Opening and closing delimiter of multi-line strings besides the backtick.
+ */
+const TRIPLE_QUOTE: &str = "\"\"\"";
+
+/*
+ * The byte `b` repeated in every byte of a word, for scanning eight bytes at a
+ * time.
+ */
+const fn repeated(b: u8) -> u64 {
+    u64::from_ne_bytes([b; 8])
+}
+
+/*
+ * Sets the high bit of every byte of `word` that is below `n` (at most 0x80).
+ * Borrows can also set it in bytes above the first match, so only the lowest
+ * set bit is reliable.
+ */
+const fn bytes_below(word: u64, n: u8) -> u64 {
+    word.wrapping_sub(repeated(n)) & !word & repeated(0x80)
+}
+
+/*
+ * Returns the offset of the first byte in `bytes` that is `quote`, a backslash
+ * or, if `control` is set, below 0x20, or `bytes.len()` if there is none. Whole
+ * words are checked at once, since string contents are mostly ordinary bytes.
+ */
+fn find_string_special(bytes: &[u8], quote: u8, control: bool) -> usize {
+    let is_special = |b: u8| b == quote || b == b'\\' || (control && b < 0x20);
+    let mut chunks = bytes.chunks_exact(8);
+    let mut offset = 0;
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().expect("chunks are 8 bytes"));
+        let mut found =
+            bytes_below(word ^ repeated(quote), 1) | bytes_below(word ^ repeated(b'\\'), 1);
+        if control {
+            found |= bytes_below(word, 0x20);
+        }
+        if found != 0 {
+            // Little-endian, so the lowest set bit belongs to the first match
+            return offset + (found.trailing_zeros() / 8) as usize;
+        }
+        offset += 8;
+    }
+    let rest = chunks.remainder();
+    offset
+        + rest
+            .iter()
+            .position(|&b| is_special(b))
+            .unwrap_or(rest.len())
+}
+
+/*
+ * Returns the length of the run of JSON whitespace at the start of `bytes`.
+ * Indentation is skipped eight spaces at a time.
+ */
+fn whitespace_run(bytes: &[u8]) -> usize {
+    let mut offset = 0;
+    loop {
+        while let Some(chunk) = bytes.get(offset..offset + 8)
+            && u64::from_ne_bytes(chunk.try_into().expect("chunks are 8 bytes")) == repeated(b' ')
+        {
+            offset += 8;
+        }
+        match bytes.get(offset) {
+            Some(b' ' | b'\n' | b'\t' | b'\r') => offset += 1,
+            _ => return offset,
+        }
+    }
+}
+
+/*
+ * Returns the radix of a literal starting with `0x` or `0b` (after an optional
+ * `-`), or `None` for ordinary decimal literals.
+ */
+fn radix_prefix(literal: &[u8]) -> Option<u32> {
+    let unsigned = literal.strip_prefix(b"-").unwrap_or(literal);
+    match unsigned {
+        [b'0', b'x' | b'X', ..] => Some(16),
+        [b'0', b'b' | b'B', ..] => Some(2),
+        _ => None,
+    }
+}
+
+/*
+ * Builds the error for a number literal exceeding the configured limits, quoting
+ * only the start of very long literals.
+ */
+fn number_out_of_range(literal: &str, position: usize) -> JsonError {
+    const QUOTED_LENGTH: usize = 32;
+    let value = match literal.get(..QUOTED_LENGTH) {
+        Some(start) if literal.len() > QUOTED_LENGTH => format!("{}...", start),
+        _ => literal.to_string(),
+    };
+    JsonError::NumberOutOfRange { value, position }
+}
+
+/*
+ * Checks a number literal against the RFC 8259 grammar
+ * `-? (0 | [1-9][0-9]*) (.[0-9]+)? ([eE][+-]?[0-9]+)?` and returns the offset of
+ * the first byte that does not fit, or the literal length if it ends too early.
+ */
+fn invalid_number_offset(literal: &[u8]) -> Option<usize> {
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while literal.get(*i).is_some_and(u8::is_ascii_digit) {
+            *i += 1;
+        }
+        *i > start
+    };
+    let mut i = usize::from(literal.first() == Some(&b'-'));
+    match literal.get(i) {
+        Some(b'0') => i += 1,
+        Some(b'1'..=b'9') => {
+            digits(&mut i);
+        }
+        _ => return Some(i),
+    }
+    if literal.get(i) == Some(&b'.') {
+        i += 1;
+        if !digits(&mut i) {
+            return Some(i);
+        }
+    }
+    if matches!(literal.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        if matches!(literal.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        if !digits(&mut i) {
+            return Some(i);
+        }
+    }
+    (i < literal.len()).then_some(i)
 }
 
 /// A lexer that converts a JSON input string into a sequence of [`Token`]s.
+///
+/// Tokens can be collected all at once with [`tokenize`](Tokenizer::tokenize), or
+/// produced lazily by using the tokenizer as an [`Iterator`]. The iterator yields
+/// `JsonResult<Token>` items and stops after the first error; wrap it in
+/// [`Peekable`](std::iter::Peekable) for lookahead.
+///
+/// Input is scanned according to RFC 8259: malformed numbers such as `0123`,
+/// characters that cannot start a token and unescaped control characters inside
+/// strings are rejected.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{Token, Tokenizer};
+///
+/// let mut tokens = Tokenizer::new("[true]").peekable();
+/// assert_eq!(tokens.peek(), Some(&Ok(Token::LeftBracket)));
+/// assert_eq!(tokens.next(), Some(Ok(Token::LeftBracket)));
+/// assert_eq!(tokens.next(), Some(Ok(Token::Boolean(true))));
+/// assert_eq!(tokens.next(), Some(Ok(Token::RightBracket)));
+/// assert_eq!(tokens.next(), None);
+/// ```
 pub struct Tokenizer<'input> {
     input: &'input str,
     current: usize,
+    token_start: usize,
+    keep_number_text: bool,
+    allow_overflow: bool,
+    max_number_length: usize,
+    max_exponent: u32,
+    max_string_length: usize,
+    max_input_size: usize,
+    max_values: usize,
+    values: usize,
+    strict: bool,
+    allow_comments: bool,
+    allow_single_quotes: bool,
+    allow_unquoted_keys: bool,
+    allow_nan: bool,
+    allow_hex_and_binary: bool,
+    allow_multiline_strings: bool,
+    unknown_escapes: UnknownEscapePolicy,
+    passed_escapes: Vec<usize>,
+    defer_unescape: bool,
+    failed: bool,
 }
 
 impl<'input> Tokenizer<'input> {
@@ -85,7 +352,30 @@ impl<'input> Tokenizer<'input> {
     /// let tokenizer = Tokenizer::new(r#"{"key": 42}"#);
     /// ```
     pub fn new(input: &'input str) -> Self {
-        Self { current: 0, input }
+        Self {
+            current: 0,
+            input,
+            token_start: 0,
+            keep_number_text: false,
+            allow_overflow: false,
+            max_number_length: DEFAULT_MAX_NUMBER_LENGTH,
+            max_exponent: DEFAULT_MAX_EXPONENT,
+            max_string_length: usize::MAX,
+            max_input_size: usize::MAX,
+            max_values: usize::MAX,
+            values: 0,
+            strict: true,
+            allow_comments: false,
+            allow_single_quotes: false,
+            allow_unquoted_keys: false,
+            allow_nan: false,
+            allow_hex_and_binary: false,
+            allow_multiline_strings: false,
+            unknown_escapes: UnknownEscapePolicy::Reject,
+            passed_escapes: Vec::new(),
+            defer_unescape: false,
+            failed: false,
+        }
     }
 
     /*
@@ -104,6 +394,226 @@ impl<'input> Tokenizer<'input> {
         Some(b)
     }
 
+    /*
+     * Makes number tokens retain their source literal
+     */
+    pub(crate) fn keep_number_text(&mut self, keep: bool) {
+        self.keep_number_text = keep;
+    }
+
+    /*
+     * Accepts finite literals too large for `f64`, which are read as infinity
+     */
+    pub(crate) fn allow_overflow(&mut self, allow: bool) {
+        self.allow_overflow = allow;
+    }
+
+    /*
+     * Switches between strict RFC 8259 scanning (the default) and the lenient
+     * mode, which accepts any literal `f64::from_str` understands and skips
+     * unrecognized non-punctuation bytes
+     */
+    pub(crate) fn strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /*
+     * Makes `//` and `/* */` comments count as whitespace
+     */
+    pub(crate) fn allow_comments(&mut self, allow: bool) {
+        self.allow_comments = allow;
+    }
+
+    /*
+     * Accepts strings delimited by `'`, in which `\'` is a valid escape
+     */
+    pub(crate) fn allow_single_quotes(&mut self, allow: bool) {
+        self.allow_single_quotes = allow;
+    }
+
+    /*
+     * Turns bare words other than `true`, `false` and `null` into identifier
+     * tokens instead of rejecting them
+     */
+    pub(crate) fn allow_unquoted_keys(&mut self, allow: bool) {
+        self.allow_unquoted_keys = allow;
+    }
+
+    /*
+     * Turns the bare words `NaN`, `Infinity` and `-Infinity` into number tokens
+     * holding the matching `f64` instead of rejecting them
+     */
+    pub(crate) fn allow_nan(&mut self, allow: bool) {
+        self.allow_nan = allow;
+    }
+
+    /*
+     * Accepts integer literals with a `0x` or `0b` prefix
+     */
+    pub(crate) fn allow_hex_and_binary(&mut self, allow: bool) {
+        self.allow_hex_and_binary = allow;
+    }
+
+    /*
+     * Accepts raw strings delimited by a backtick or by triple double quotes
+     */
+    pub(crate) fn allow_multiline_strings(&mut self, allow: bool) {
+        self.allow_multiline_strings = allow;
+    }
+
+    /*
+     * Chooses whether escape sequences JSON does not define are rejected or let
+     * through
+     */
+    pub(crate) fn unknown_escapes(&mut self, policy: UnknownEscapePolicy) {
+        self.unknown_escapes = policy;
+    }
+
+    /*
+     * Byte offsets of the backslashes of the unknown escape sequences let
+     * through so far
+     */
+    pub(crate) fn passed_escapes(&self) -> &[usize] {
+        &self.passed_escapes
+    }
+
+    /*
+     * Makes string tokens carry their raw contents, with escape sequences still
+     * encoded (but validated), so that decoding can be done later with `unescape`
+     */
+    pub(crate) fn defer_unescape(&mut self, defer: bool) {
+        self.defer_unescape = defer;
+    }
+
+    /*
+     * Returns whether string tokens carry their raw contents (see
+     * `defer_unescape`)
+     */
+    pub(crate) fn unescape_deferred(&self) -> bool {
+        self.defer_unescape
+    }
+
+    /*
+     * Applies the scanning related settings of `options`
+     */
+    pub(crate) fn configure(&mut self, options: &ParserOptions) {
+        self.keep_number_text(options.preserve_number_text);
+        self.allow_overflow(options.preserve_number_text || options.allow_nan);
+        self.max_number_length = options.max_number_length;
+        self.max_exponent = options.max_exponent;
+        self.max_string_length = options.max_string_length;
+        self.max_input_size = options.max_input_size;
+        self.max_values = options.max_values;
+        self.strict(options.strict);
+        self.allow_comments(options.allow_comments);
+        self.allow_single_quotes(options.allow_single_quotes);
+        self.allow_unquoted_keys(options.allow_unquoted_keys);
+        self.allow_nan(options.allow_nan);
+        self.allow_hex_and_binary(options.allow_hex_and_binary);
+        self.allow_multiline_strings(options.allow_multiline_strings);
+        self.unknown_escapes(options.unknown_escapes);
+    }
+
+    /*
+     * Accounts for one more value of the document being parsed, enforcing
+     * `ParserOptions::max_values`. Parsers call this as each value starts, since
+     * only they can tell values from object keys.
+     */
+    pub(crate) fn count_value(&mut self, position: usize) -> JsonResult<()> {
+        self.values += 1;
+        if self.values > self.max_values {
+            return Err(JsonError::ValueCountExceeded {
+                max_values: self.max_values,
+                position,
+            });
+        }
+        Ok(())
+    }
+
+    /*
+     * Starts counting values afresh, for inputs holding several documents.
+     */
+    pub(crate) fn reset_value_count(&mut self) {
+        self.values = 0;
+    }
+
+    /*
+     * Fails if the contents of the string token that just ended, which started
+     * at byte `start`, are longer than `ParserOptions::max_string_length`.
+     */
+    fn check_string_length(&self, start: usize, end: usize) -> JsonResult<()> {
+        if end - start > self.max_string_length {
+            return Err(JsonError::StringLengthExceeded {
+                max_length: self.max_string_length,
+                position: self.token_start,
+            });
+        }
+        Ok(())
+    }
+
+    /*
+     * Moves past the token that just failed to scan, so that scanning can resume
+     * after the error. A string is skipped up to its closing quote, or the end of
+     * its line if it has none; anything else up to the next whitespace or
+     * punctuation. At least one character is always skipped.
+     */
+    pub(crate) fn skip_invalid_token(&mut self) {
+        let bytes = self.input.as_bytes();
+        let start = self.token_start;
+        let Some(&first) = bytes.get(start) else {
+            self.current = self.input.len();
+            return;
+        };
+        let mut end = start + 1;
+        if first == b'"' || first == b'\'' {
+            while let Some(&b) = bytes.get(end) {
+                end += 1;
+                match b {
+                    b'\\' => end += 1,
+                    b'\n' => break,
+                    _ if b == first => break,
+                    _ => {}
+                }
+            }
+        } else {
+            while let Some(&b) = bytes.get(end) {
+                if b.is_ascii_whitespace() || b"{}[],:\"'".contains(&b) {
+                    break;
+                }
+                end += 1;
+            }
+        }
+        end = end.min(self.input.len());
+        while !self.input.is_char_boundary(end) {
+            end += 1;
+        }
+        self.current = end;
+    }
+
+    /*
+     * Byte offset of the first byte of the most recently scanned token
+     */
+    pub(crate) fn token_start(&self) -> usize {
+        self.token_start
+    }
+
+    /*
+     * Byte offset of the next unread byte
+     */
+    pub(crate) fn offset(&self) -> usize {
+        self.current
+    }
+
+    /*
+     * Skips a byte order mark at the start of the input. RFC 8259 lets parsers
+     * ignore one, and Windows tools often write it.
+     */
+    pub(crate) fn skip_bom(&mut self) {
+        if self.current == 0 && self.input.starts_with('\u{FEFF}') {
+            self.current = '\u{FEFF}'.len_utf8();
+        }
+    }
+
     /*
      * Check if the input has been consumed
      */
@@ -111,8 +621,13 @@ impl<'input> Tokenizer<'input> {
         self.peek().is_none()
     }
 
-    fn consume_number(&mut self) -> JsonResult<f64> {
+    fn consume_number(&mut self) -> JsonResult<JsonNumber> {
         let start = self.current;
+        if self.allow_hex_and_binary
+            && let Some(radix) = radix_prefix(&self.input.as_bytes()[start..])
+        {
+            return self.consume_radix_integer(radix);
+        }
 
         while let Some(c) = self.peek() {
             if !(c.is_ascii_digit()
@@ -127,38 +642,116 @@ impl<'input> Tokenizer<'input> {
             self.advance();
         }
         let slice = &self.input[start..self.current];
-        let number = slice.parse::<f64>().map_err(|_| JsonError::InvalidNumber {
-            value: slice.to_string(),
-            position: self.current,
+        if slice.len() > self.max_number_length {
+            return Err(number_out_of_range(slice, start));
+        }
+        if let Some((offset, exponent)) = exponent_of(slice.as_bytes())
+            && exponent > u64::from(self.max_exponent)
+        {
+            return Err(number_out_of_range(slice, start + offset));
+        }
+        if self.strict
+            && let Some(offset) = invalid_number_offset(slice.as_bytes())
+        {
+            return Err(JsonError::InvalidNumber {
+                value: slice.to_string(),
+                position: start + offset,
+            });
+        }
+        let number = JsonNumber::parse_literal(slice, self.keep_number_text).ok_or_else(|| {
+            JsonError::InvalidNumber {
+                value: slice.to_string(),
+                position: self.current,
+            }
         })?;
+        if !self.allow_overflow && !number.as_f64().is_finite() {
+            return Err(number_out_of_range(slice, start));
+        }
         Ok(number)
     }
 
-    fn consume_string(&mut self) -> JsonResult<String> {
+    /*
+     * Consumes an integer literal such as `0x1F` or `-0b1010` whose prefix has
+     * been recognized by `radix_prefix`
+     */
+    fn consume_radix_integer(&mut self, radix: u32) -> JsonResult<JsonNumber> {
+        let start = self.current;
+        if self.peek() == Some(&b'-') {
+            self.advance();
+        }
+        while self.peek().is_some_and(u8::is_ascii_alphanumeric) {
+            self.advance();
+        }
+        let slice = &self.input[start..self.current];
+        if slice.len() > self.max_number_length {
+            return Err(number_out_of_range(slice, start));
+        }
+        let (negative, unsigned) = match slice.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, slice),
+        };
+        let digits = &unsigned[2..];
+        let digits_start = self.current - digits.len();
+        if let Some(offset) = digits.find(|c: char| !c.is_digit(radix)) {
+            return Err(JsonError::InvalidNumber {
+                value: slice.to_string(),
+                position: digits_start + offset,
+            });
+        }
+        if digits.is_empty() {
+            return Err(JsonError::InvalidNumber {
+                value: slice.to_string(),
+                position: self.current,
+            });
+        }
+        let magnitude =
+            u64::from_str_radix(digits, radix).map_err(|_| number_out_of_range(slice, start))?;
+        if !negative {
+            return Ok(magnitude.into());
+        }
+        0i64.checked_sub_unsigned(magnitude)
+            .map(JsonNumber::from)
+            .ok_or_else(|| number_out_of_range(slice, start))
+    }
+
+    fn consume_string(&mut self, quote: u8) -> JsonResult<Cow<'input, str>> {
+        let opening = self.current;
         let mut start = self.current;
         let mut buffer: Option<String> = None;
 
         loop {
             match self.peek() {
-                Some(&b'"') => {
+                Some(&c) if c == quote => {
+                    self.check_string_length(opening, self.current)?;
                     let tail = &self.input[start..self.current];
                     self.advance();
                     return Ok(match buffer {
-                        None => tail.to_string(),
+                        None => Cow::Borrowed(tail),
                         Some(mut s) => {
                             s.push_str(tail);
-                            s
+                            Cow::Owned(s)
                         }
                     });
                 }
+                Some(&b'\\') if self.defer_unescape => {
+                    self.consume_escape(None)?;
+                }
                 Some(&b'\\') => {
                     let s = buffer.get_or_insert_with(String::new);
                     s.push_str(&self.input[start..self.current]);
-                    self.consume_escape(s)?;
+                    self.consume_escape(Some(s))?;
                     start = self.current;
                 }
+                Some(&c) if c < 0x20 && self.strict => {
+                    return Err(JsonError::ControlCharacter {
+                        char: c as char,
+                        position: self.current,
+                    });
+                }
                 Some(_) => {
-                    self.advance();
+                    // Ordinary bytes up to the next one that needs attention
+                    let rest = &self.input.as_bytes()[self.current + 1..];
+                    self.current += 1 + find_string_special(rest, quote, self.strict);
                 }
                 None => {
                     return Err(JsonError::UnexpectedEndOfInput {
@@ -170,7 +763,29 @@ impl<'input> Tokenizer<'input> {
         }
     }
 
-    fn consume_escape(&mut self, s: &mut String) -> JsonResult<()> {
+    /*
+     * Consumes the contents of a raw string up to and including the closing
+     * `delimiter`. The contents are returned as written, without unescaping.
+     */
+    fn consume_raw_string(&mut self, delimiter: &str) -> JsonResult<Cow<'input, str>> {
+        let start = self.current;
+        let Some(length) = self.input[start..].find(delimiter) else {
+            return Err(JsonError::UnexpectedEndOfInput {
+                expected: format!("Closing {}", delimiter),
+                position: self.input.len(),
+            });
+        };
+        self.check_string_length(start, start + length)?;
+        self.current = start + length + delimiter.len();
+        Ok(Cow::Borrowed(&self.input[start..start + length]))
+    }
+
+    /*
+     * Consumes the escape sequence at the current `\\` and appends the character
+     * it stands for to `out`, or only validates it if `out` is `None`.
+     */
+    fn consume_escape(&mut self, out: Option<&mut String>) -> JsonResult<()> {
+        let backslash = self.current;
         self.advance();
         let special = self
             .peek()
@@ -180,36 +795,78 @@ impl<'input> Tokenizer<'input> {
                 position: self.current,
             })?;
         self.advance();
-        if special == b'u' {
-            let hex_start = self.current;
-            if self.current + 4 > self.input.len() {
-                return Err(JsonError::InvalidUnicode {
-                    sequence: format!("\\u{}", &self.input[hex_start..]),
+        let ch = if special == b'\'' && self.allow_single_quotes {
+            '\''
+        } else if special == b'u' {
+            let (ch, end) = decode_unicode_escape(self.input, self.current)?;
+            self.current = end;
+            ch
+        } else if let Some(ch) = resolve_escape_sequence(special as char) {
+            ch
+        } else {
+            // The escaped character may take several bytes
+            let ch = self.input[backslash + 1..]
+                .chars()
+                .next()
+                .expect("a byte follows the backslash");
+            if self.unknown_escapes == UnknownEscapePolicy::Reject {
+                return Err(JsonError::InvalidEscape {
+                    char: ch,
                     position: self.current,
                 });
             }
-            let hex_str = &self.input[hex_start..hex_start + 4];
-            let ch = parse_unicode_hex(hex_str).ok_or(JsonError::InvalidUnicode {
-                sequence: format!("\\u{}", hex_str),
-                position: self.current,
-            })?;
-            s.push(ch);
-            self.current += 4;
-        } else {
-            let ch = resolve_escape_sequence(special as char).ok_or(JsonError::InvalidEscape {
-                char: special as char,
-                position: self.current,
-            })?;
-            s.push(ch);
+            self.current = backslash + 1 + ch.len_utf8();
+            self.passed_escapes.push(backslash);
+            if let Some(out) = out {
+                if self.unknown_escapes == UnknownEscapePolicy::Keep {
+                    out.push('\\');
+                }
+                out.push(ch);
+            }
+            return Ok(());
+        };
+        if let Some(out) = out {
+            out.push(ch);
         }
         Ok(())
     }
 
-    fn consume_keyword(&mut self) -> JsonResult<Token> {
+    /*
+     * Skips a `// line` or `/* block */` comment starting at the current `/`
+     */
+    fn skip_comment(&mut self) -> JsonResult<()> {
         let start = self.current;
+        self.advance(); // consume the first /
+        match self.advance() {
+            Some(b'/') => {
+                while let Some(&c) = self.peek() {
+                    if c == b'\n' {
+                        break;
+                    }
+                    self.advance();
+                }
+                Ok(())
+            }
+            Some(b'*') => match self.input[self.current..].find("*/") {
+                Some(end) => {
+                    self.current += end + 2;
+                    Ok(())
+                }
+                None => {
+                    self.current = self.input.len();
+                    Err(unexpected_end_of_input("*/", self.current))
+                }
+            },
+            _ => Err(unexpected_token_error("Valid JSON value", "/", start)),
+        }
+    }
 
-        while let Some(c) = self.peek() {
-            if !c.is_ascii_alphabetic() {
+    fn consume_keyword(&mut self) -> JsonResult<RawToken<'input>> {
+        let start = self.current;
+
+        while let Some(&c) = self.peek() {
+            let identifier_char = c.is_ascii_alphanumeric() || c == b'_' || c == b'$';
+            if !(c.is_ascii_alphabetic() || self.allow_unquoted_keys && identifier_char) {
                 break;
             }
             self.advance();
@@ -217,15 +874,18 @@ impl<'input> Tokenizer<'input> {
 
         let slice = &self.input[start..self.current];
         match slice {
-            "true" => Ok(Token::Boolean(true)),
-            "false" => Ok(Token::Boolean(false)),
-            "null" => Ok(Token::Null),
+            "true" => Ok(RawToken::Boolean(true)),
+            "false" => Ok(RawToken::Boolean(false)),
+            "null" => Ok(RawToken::Null),
+            "NaN" if self.allow_nan => Ok(RawToken::Number(f64::NAN.into())),
+            "Infinity" if self.allow_nan => Ok(RawToken::Number(f64::INFINITY.into())),
+            _ if self.allow_unquoted_keys => Ok(RawToken::Identifier(slice)),
             _ => {
                 let found = match slice.chars().next() {
                     Some(first) => first.to_string(),
                     None => "unknown".to_string(),
                 };
-                Err(unexpected_token_error("Valid JSON value", &found, 0))
+                Err(unexpected_token_error("Valid JSON value", &found, start))
             }
         }
     }
@@ -241,7 +901,7 @@ impl<'input> Tokenizer<'input> {
     /// let tokens = tokenizer.tokenize()?;
     /// assert_eq!(tokens, vec![
     ///     Token::LeftBracket,
-    ///     Token::Number(1.0),
+    ///     Token::Number(1.into()),
     ///     Token::Comma,
     ///     Token::Boolean(true),
     ///     Token::RightBracket,
@@ -254,70 +914,196 @@ impl<'input> Tokenizer<'input> {
     /// Returns [`JsonError::UnexpectedToken`] if an invalid character is encountered,
     /// [`JsonError::InvalidNumber`] if a numeric literal cannot be parsed,
     /// [`JsonError::InvalidEscape`] if a string contains an unrecognized escape sequence,
-    /// [`JsonError::InvalidUnicode`] if a `\uXXXX` sequence is malformed, or
+    /// [`JsonError::InvalidUnicode`] if a `\uXXXX` sequence is malformed or a
+    /// surrogate is not part of a pair,
+    /// [`JsonError::ControlCharacter`] if a string contains a raw control character, or
     /// [`JsonError::UnexpectedEndOfInput`] if a string is unterminated.
     pub fn tokenize(&mut self) -> JsonResult<Vec<Token>> {
         let mut tokens: Vec<Token> = Vec::new();
 
-        while let Some(c) = self.peek() {
-            match c {
+        while let Some(token) = self.next_token()? {
+            tokens.push(token);
+        }
+
+        Ok(tokens)
+    }
+
+    /// Like [`tokenize`](Self::tokenize), but records where each token is found
+    /// in the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{Token, Tokenizer};
+    ///
+    /// let input = r#"{"id": 7}"#;
+    /// let tokens = Tokenizer::new(input).tokenize_spanned()?;
+    /// assert_eq!(tokens[1].token, Token::String("id".to_string()));
+    /// assert_eq!(&input[tokens[1].start..tokens[1].end], r#""id""#);
+    /// assert_eq!((tokens[3].start, tokens[3].end), (7, 8));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`tokenize`](Self::tokenize).
+    pub fn tokenize_spanned(&mut self) -> JsonResult<Vec<SpannedToken>> {
+        let mut tokens = Vec::new();
+
+        while let Some(token) = self.next_spanned_token()? {
+            tokens.push(token);
+        }
+
+        Ok(tokens)
+    }
+
+    /*
+     * Same as `next_token`, but also returns the offsets of the token.
+     */
+    pub(crate) fn next_spanned_token(&mut self) -> JsonResult<Option<SpannedToken>> {
+        Ok(self.next_token()?.map(|token| SpannedToken {
+            token,
+            start: self.token_start,
+            end: self.current,
+        }))
+    }
+
+    /*
+     * Scans and returns the next token, skipping any whitespace before it.
+     * Returns `Ok(None)` once the input has been consumed.
+     */
+    pub(crate) fn next_token(&mut self) -> JsonResult<Option<Token>> {
+        Ok(self.next_raw_token()?.map(Token::from))
+    }
+
+    /*
+     * Same as `next_token`, but string contents borrow from the input whenever
+     * they contain no escape sequences.
+     */
+    pub(crate) fn next_raw_token(&mut self) -> JsonResult<Option<RawToken<'input>>> {
+        if self.current == 0 && self.input.len() > self.max_input_size {
+            return Err(JsonError::InputSizeExceeded {
+                max_size: self.max_input_size,
+                size: self.input.len(),
+            });
+        }
+        while let Some(&c) = self.peek() {
+            self.token_start = self.current;
+            let token = match c {
                 b' ' | b'\n' | b'\t' | b'\r' => {
-                    self.advance(); // explicitly skip whitespace
+                    self.current += whitespace_run(&self.input.as_bytes()[self.current..]);
+                    continue;
+                }
+                b'"' if self.allow_multiline_strings
+                    && self.input[self.current..].starts_with(TRIPLE_QUOTE) =>
+                {
+                    self.current += TRIPLE_QUOTE.len();
+                    RawToken::String(self.consume_raw_string(TRIPLE_QUOTE)?)
+                }
+                b'`' if self.allow_multiline_strings => {
+                    self.advance();
+                    RawToken::String(self.consume_raw_string("`")?)
                 }
                 b'"' => {
                     self.advance(); // consume opening quote
-                    let consumed_string = self.consume_string()?;
-                    tokens.push(Token::String(consumed_string));
+                    RawToken::String(self.consume_string(b'"')?)
                 }
-                b'0'..=b'9' | b'-' => {
-                    let consumed_number = self.consume_number()?;
-                    tokens.push(Token::Number(consumed_number));
+                b'\'' if self.allow_single_quotes => {
+                    self.advance();
+                    RawToken::String(self.consume_string(b'\'')?)
+                }
+                b'-' if self.allow_nan
+                    && self.input[self.current + 1..].starts_with("Infinity") =>
+                {
+                    self.advance();
+                    match self.consume_keyword()? {
+                        RawToken::Number(n) => RawToken::Number((-n.as_f64()).into()),
+                        _ => {
+                            return Err(unexpected_token_error(
+                                "Valid JSON value",
+                                "-",
+                                self.token_start,
+                            ));
+                        }
+                    }
                 }
+                b'0'..=b'9' | b'-' => RawToken::Number(self.consume_number()?),
                 b'{' => {
                     self.advance();
-                    tokens.push(Token::LeftBrace);
+                    RawToken::LeftBrace
                 }
                 b'}' => {
                     self.advance();
-                    tokens.push(Token::RightBrace);
+                    RawToken::RightBrace
                 }
                 b'[' => {
                     self.advance();
-                    tokens.push(Token::LeftBracket);
+                    RawToken::LeftBracket
                 }
                 b']' => {
                     self.advance();
-                    tokens.push(Token::RightBracket);
+                    RawToken::RightBracket
                 }
                 b',' => {
                     self.advance();
-                    tokens.push(Token::Comma);
+                    RawToken::Comma
                 }
                 b':' => {
                     self.advance();
-                    tokens.push(Token::Colon);
+                    RawToken::Colon
+                }
+                b'/' if self.allow_comments => {
+                    self.skip_comment()?;
+                    continue;
                 }
-                _ if c.is_ascii_alphabetic() => {
-                    let keyword_token = self.consume_keyword()?;
-                    tokens.push(keyword_token);
+                _ if c.is_ascii_alphabetic() => self.consume_keyword()?,
+                b'_' | b'$' if self.allow_unquoted_keys => self.consume_keyword()?,
+                _ if self.strict => {
+                    let found = self.input[self.current..].chars().next().unwrap_or('?');
+                    return Err(unexpected_token_error(
+                        "Valid JSON value",
+                        &found.to_string(),
+                        self.current,
+                    ));
                 }
                 _ => {
                     if c.is_ascii_punctuation() {
                         return Err(unexpected_token_error(
                             "Valid JSON value",
-                            &(*c as char).to_string(),
-                            0,
+                            &(c as char).to_string(),
+                            self.current,
                         ));
                     }
                     self.advance();
+                    continue;
                 }
-            }
+            };
+            return Ok(Some(token));
         }
 
-        Ok(tokens)
+        Ok(None)
     }
 }
 
+impl Iterator for Tokenizer<'_> {
+    type Item = JsonResult<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.next_token() {
+            Ok(token) => token.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for Tokenizer<'_> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -354,20 +1140,51 @@ mod tests {
         assert_eq!(tokens.len(), 2);
     }
 
+    #[test]
+    fn test_tokenizer_iterator() {
+        let tokens: JsonResult<Vec<Token>> = Tokenizer::new(r#"{"a": [1]}"#).collect();
+        assert_eq!(
+            tokens.unwrap(),
+            vec![
+                Token::LeftBrace,
+                Token::String("a".to_string()),
+                Token::Colon,
+                Token::LeftBracket,
+                Token::Number(1.0.into()),
+                Token::RightBracket,
+                Token::RightBrace,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_iterator_stops_after_error() {
+        let mut tokenizer = Tokenizer::new(r#"[1, "\q", 2]"#);
+        assert_eq!(tokenizer.next(), Some(Ok(Token::LeftBracket)));
+        assert_eq!(tokenizer.next(), Some(Ok(Token::Number(1.0.into()))));
+        assert_eq!(tokenizer.next(), Some(Ok(Token::Comma)));
+        assert!(matches!(
+            tokenizer.next(),
+            Some(Err(JsonError::InvalidEscape { .. }))
+        ));
+        assert_eq!(tokenizer.next(), None);
+    }
+
     // === Basic Token Tests (from Week 1 - ensure they still pass) ===
 
     #[test]
     fn test_tokenize_number() {
         let mut tokenizer = Tokenizer::new("42");
         let tokens = tokenizer.tokenize().unwrap();
-        assert_eq!(tokens, vec![Token::Number(42.0)]);
+        assert_eq!(tokens, vec![Token::Number(42.0.into())]);
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_tokenize_negative_number() {
         let mut tokenizer = Tokenizer::new("-3.14");
         let tokens = tokenizer.tokenize().unwrap();
-        assert_eq!(tokens, vec![Token::Number(-3.14)]);
+        assert_eq!(tokens, vec![Token::Number((-3.14).into())]);
     }
 
     #[test]
@@ -375,7 +1192,7 @@ mod tests {
         let mut tokenizer = Tokenizer::new("0.5");
         let tokens = tokenizer.tokenize().unwrap();
         assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0], Token::Number(0.5));
+        assert_eq!(tokens[0], Token::Number(0.5.into()));
     }
 
     #[test]
@@ -387,6 +1204,297 @@ mod tests {
         assert!(matches!(result, Err(JsonError::UnexpectedToken { .. })));
     }
 
+    #[test]
+    fn test_malformed_numbers_rejected() {
+        for input in ["01", "-", "1.", "1e+", "1.2.3"] {
+            let mut tokenizer = Tokenizer::new(input);
+            assert!(
+                matches!(tokenizer.tokenize(), Err(JsonError::InvalidNumber { .. })),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_number_limits() {
+        let long = "1".repeat(10 * 1024);
+        match Tokenizer::new(&long).tokenize() {
+            Err(JsonError::NumberOutOfRange { value, position }) => {
+                assert_eq!(position, 0);
+                assert_eq!(value, format!("{}...", &long[..32]));
+            }
+            other => panic!("expected NumberOutOfRange, got {:?}", other),
+        }
+        assert_eq!(
+            Tokenizer::new("[1e999999]").tokenize(),
+            Err(JsonError::NumberOutOfRange {
+                value: "1e999999".to_string(),
+                position: 3
+            })
+        );
+        assert!(matches!(
+            Tokenizer::new("-2.5E-99999999999999999999999").tokenize(),
+            Err(JsonError::NumberOutOfRange { position: 5, .. })
+        ));
+        assert!(Tokenizer::new("1e308 1e-1000").tokenize().is_ok());
+
+        let options = ParserOptions {
+            max_number_length: 4,
+            max_exponent: 99,
+            ..ParserOptions::default()
+        };
+        for (input, ok) in [
+            ("1234", true),
+            ("12345", false),
+            ("1e99", true),
+            ("1e100", false),
+        ] {
+            let mut tokenizer = Tokenizer::new(input);
+            tokenizer.configure(&options);
+            assert_eq!(tokenizer.tokenize().is_ok(), ok, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_overflowing_numbers_rejected() {
+        let many_digits = format!("1{}", "0".repeat(400));
+        for input in ["1e999", "-1e500", "1.8e308", many_digits.as_str()] {
+            assert!(
+                matches!(
+                    Tokenizer::new(input).tokenize(),
+                    Err(JsonError::NumberOutOfRange { position: 0, .. })
+                ),
+                "{}",
+                input
+            );
+            for options in [
+                ParserOptions {
+                    preserve_number_text: true,
+                    ..ParserOptions::default()
+                },
+                ParserOptions {
+                    allow_nan: true,
+                    ..ParserOptions::default()
+                },
+            ] {
+                let mut tokenizer = Tokenizer::new(input);
+                tokenizer.configure(&options);
+                match tokenizer.next_token() {
+                    Ok(Some(Token::Number(number))) => assert!(number.as_f64().is_infinite()),
+                    other => panic!("{}: expected a number, got {:?}", input, other),
+                }
+            }
+        }
+        assert!(Tokenizer::new("1.7e308 -1e-400 0e999").tokenize().is_ok());
+    }
+
+    #[test]
+    fn test_unescaped_control_character_rejected() {
+        let mut tokenizer = Tokenizer::new("\"line\nbreak\"");
+        assert_eq!(
+            tokenizer.tokenize(),
+            Err(JsonError::ControlCharacter {
+                char: '\n',
+                position: 5
+            })
+        );
+        let mut tokenizer = Tokenizer::new("\"nul\u{0}\"");
+        assert!(matches!(
+            tokenizer.tokenize(),
+            Err(JsonError::ControlCharacter { char: '\0', .. })
+        ));
+        // DEL is not a control character for JSON's purposes
+        let mut tokenizer = Tokenizer::new("\"\u{7f}\"");
+        assert!(tokenizer.tokenize().is_ok());
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "[1, // one\n /* two */ 2 /**/]// end";
+        let mut tokenizer = Tokenizer::new(input);
+        assert!(tokenizer.tokenize().is_err());
+
+        let mut tokenizer = Tokenizer::new(input);
+        tokenizer.allow_comments(true);
+        assert_eq!(
+            tokenizer.tokenize().unwrap(),
+            vec![
+                Token::LeftBracket,
+                Token::Number(1.into()),
+                Token::Comma,
+                Token::Number(2.into()),
+                Token::RightBracket,
+            ]
+        );
+
+        let mut tokenizer = Tokenizer::new("[1 /* open");
+        tokenizer.allow_comments(true);
+        assert!(matches!(
+            tokenizer.tokenize(),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+        let mut tokenizer = Tokenizer::new("/x");
+        tokenizer.allow_comments(true);
+        assert!(matches!(
+            tokenizer.tokenize(),
+            Err(JsonError::UnexpectedToken { position: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_nan_and_infinity() {
+        let input = "[NaN, Infinity, -Infinity]";
+        assert!(Tokenizer::new(input).tokenize().is_err());
+
+        let mut tokenizer = Tokenizer::new(input);
+        tokenizer.allow_nan(true);
+        let numbers: Vec<f64> = tokenizer
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Number(n) => Some(n.as_f64()),
+                _ => None,
+            })
+            .collect();
+        assert!(numbers[0].is_nan());
+        assert_eq!(numbers[1..], [f64::INFINITY, f64::NEG_INFINITY]);
+
+        for input in ["-NaN", "-Infinityx", "Inf", "nan"] {
+            let mut tokenizer = Tokenizer::new(input);
+            tokenizer.allow_nan(true);
+            assert!(tokenizer.tokenize().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_hex_and_binary_literals() {
+        let tokenize = |input: &str| {
+            let mut tokenizer = Tokenizer::new(input);
+            tokenizer.allow_hex_and_binary(true);
+            tokenizer.tokenize()
+        };
+        assert!(Tokenizer::new("0x1F").tokenize().is_err());
+        assert_eq!(
+            tokenize("[0x1F, 0Xff, 0b1010, -0x10, 0xFFFFFFFFFFFFFFFF, -0x8000000000000000]")
+                .unwrap(),
+            vec![
+                Token::LeftBracket,
+                Token::Number(31.into()),
+                Token::Comma,
+                Token::Number(255.into()),
+                Token::Comma,
+                Token::Number(10.into()),
+                Token::Comma,
+                Token::Number((-16).into()),
+                Token::Comma,
+                Token::Number(u64::MAX.into()),
+                Token::Comma,
+                Token::Number(i64::MIN.into()),
+                Token::RightBracket,
+            ]
+        );
+        assert!(matches!(
+            tokenize("0b102"),
+            Err(JsonError::InvalidNumber { position: 4, .. })
+        ));
+        assert!(matches!(
+            tokenize("[0x]"),
+            Err(JsonError::InvalidNumber { position: 3, .. })
+        ));
+        assert!(matches!(
+            tokenize("0x10000000000000000"),
+            Err(JsonError::NumberOutOfRange { position: 0, .. })
+        ));
+        assert!(matches!(
+            tokenize("-0x8000000000000001"),
+            Err(JsonError::NumberOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_word_scans_match_bytewise_scans() {
+        let text = "plain ascii text, then caf\u{e9} \u{1f389}\"\\\t\u{1}\n    \r\n\t  x";
+        let bytes = text.as_bytes();
+        for start in 0..bytes.len() {
+            let rest = &bytes[start..];
+            for (quote, control) in [(b'"', true), (b'"', false), (b'\'', true)] {
+                let expected = rest
+                    .iter()
+                    .position(|&b| b == quote || b == b'\\' || (control && b < 0x20))
+                    .unwrap_or(rest.len());
+                assert_eq!(
+                    find_string_special(rest, quote, control),
+                    expected,
+                    "{:?}",
+                    rest
+                );
+            }
+            let expected = rest
+                .iter()
+                .position(|b| !b" \n\t\r".contains(b))
+                .unwrap_or(rest.len());
+            assert_eq!(whitespace_run(rest), expected);
+        }
+        assert_eq!(whitespace_run(&[b' '; 20]), 20);
+    }
+
+    #[test]
+    fn test_multiline_raw_strings() {
+        let input = "[`a\\b\n\t\"c\"`, \"\"\"SELECT *\n  FROM t -- `x`\"\"\", \"\", \"d\"]";
+        assert!(Tokenizer::new(input).tokenize().is_err());
+
+        let mut tokenizer = Tokenizer::new(input);
+        tokenizer.allow_multiline_strings(true);
+        assert_eq!(
+            tokenizer.tokenize().unwrap(),
+            vec![
+                Token::LeftBracket,
+                Token::String("a\\b\n\t\"c\"".to_string()),
+                Token::Comma,
+                Token::String("SELECT *\n  FROM t -- `x`".to_string()),
+                Token::Comma,
+                Token::String(String::new()),
+                Token::Comma,
+                Token::String("d".to_string()),
+                Token::RightBracket,
+            ]
+        );
+
+        let mut tokenizer = Tokenizer::new("[\"\"\"never closed\"\"]");
+        tokenizer.allow_multiline_strings(true);
+        assert!(matches!(
+            tokenizer.tokenize(),
+            Err(JsonError::UnexpectedEndOfInput { .. })
+        ));
+    }
+
+    #[test]
+    fn test_single_quotes_and_identifiers() {
+        let input = r#"{name: 'it\'s "ok"', _id$2: true}"#;
+        let mut tokenizer = Tokenizer::new(input);
+        assert!(tokenizer.tokenize().is_err());
+
+        let mut tokenizer = Tokenizer::new(input);
+        tokenizer.allow_single_quotes(true);
+        tokenizer.allow_unquoted_keys(true);
+        assert_eq!(
+            tokenizer.tokenize().unwrap(),
+            vec![
+                Token::LeftBrace,
+                Token::Identifier("name".to_string()),
+                Token::Colon,
+                Token::String(r#"it's "ok""#.to_string()),
+                Token::Comma,
+                Token::Identifier("_id$2".to_string()),
+                Token::Colon,
+                Token::Boolean(true),
+                Token::RightBrace,
+            ]
+        );
+    }
+
     #[test]
     fn test_tokenize_literals() {
         let mut t1 = Tokenizer::new("true");
@@ -431,7 +1539,7 @@ mod tests {
         // Verify we have the right tokens
         assert_eq!(tokens[0], Token::LeftBrace);
         assert!(tokens.contains(&Token::String("age".to_string())));
-        assert!(tokens.contains(&Token::Number(30.0)));
+        assert!(tokens.contains(&Token::Number(30.0.into())));
         assert!(tokens.contains(&Token::Comma));
         assert!(tokens.contains(&Token::String("active".to_string())));
         assert!(tokens.contains(&Token::Boolean(true)));
@@ -567,6 +1675,28 @@ mod tests {
         assert_eq!(tokens, vec![Token::String("Hello World".to_string())]);
     }
 
+    #[test]
+    fn test_multibyte_utf8_around_escapes() {
+        // Non-ASCII text before, between and after escape sequences must be
+        // copied as whole characters, not byte by byte
+        let cases = [
+            (r#""\né""#, "\né"),
+            (r#""ñ\tü\"ö""#, "ñ\tü\"ö"),
+            (r#""日本\u0041語""#, "日本A語"),
+            (r#""\\🦀\/""#, "\\🦀/"),
+            (r#""é\u00e9e\u0301""#, "éée\u{301}"),
+        ];
+        for (input, expected) in cases {
+            let mut tokenizer = Tokenizer::new(input);
+            assert_eq!(
+                tokenizer.tokenize().unwrap(),
+                vec![Token::String(expected.to_string())],
+                "{}",
+                input
+            );
+        }
+    }
+
     #[test]
     fn test_unicode_escape_lowercase() {
         // Lowercase hex digits should work too
@@ -591,6 +1721,23 @@ mod tests {
         assert!(matches!(result, Err(JsonError::InvalidUnicode { .. })));
     }
 
+    #[test]
+    fn test_unicode_surrogate_pairs() {
+        let mut tokenizer = Tokenizer::new(r#""\ud83d\uDE00 \u00e9""#);
+        assert_eq!(
+            tokenizer.tokenize().unwrap(),
+            vec![Token::String("😀 é".to_string())]
+        );
+        for lone in [r#""\ud83d""#, r#""\ud83d\n""#, r#""\ude00""#] {
+            let result = Tokenizer::new(lone).tokenize();
+            assert!(
+                matches!(result, Err(JsonError::InvalidUnicode { position: 3, .. })),
+                "{}",
+                lone
+            );
+        }
+    }
+
     #[test]
     fn test_invalid_unicode_bad_hex() {
         let mut tokenizer = Tokenizer::new(r#""\u00GG""#);
@@ -604,4 +1751,16 @@ mod tests {
         let result = tokenizer.tokenize();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_tokenize_spanned() {
+        let input = " [\"a\\u0021\", -1.5e3,\ntrue] ";
+        let tokens = Tokenizer::new(input).tokenize_spanned().unwrap();
+        let spans: Vec<&str> = tokens.iter().map(|t| &input[t.start..t.end]).collect();
+        assert_eq!(
+            spans,
+            ["[", r#""a\u0021""#, ",", "-1.5e3", ",", "true", "]"]
+        );
+        assert_eq!(tokens[1].token, Token::String("a!".to_string()));
+    }
 }
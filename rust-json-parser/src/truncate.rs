@@ -0,0 +1,235 @@
+//! Serialization with a hard cap on the output size, for log lines and other
+//! sinks that cut off long records.
+//!
+//! Cutting serialized JSON at an arbitrary byte leaves invalid text that breaks
+//! whatever parses the log next. [`JsonValue::to_string_max_bytes`] instead stops
+//! at a value boundary, marks the place where values were left out and closes
+//! every open array and object, so the output is always valid JSON.
+
+use crate::strings::escape;
+use crate::value::{JsonValue, Separators, SerializeOptions};
+
+/// The key marking where [`JsonValue::to_string_max_bytes`] left values out.
+///
+/// In an object, the marker is the member `"$truncated":true`; in an array, it
+/// is the element `{"$truncated":true}`.
+pub const TRUNCATED_KEY: &str = "$truncated";
+
+/// The shortest output of [`JsonValue::to_string_max_bytes`]: the marker
+/// object `{"$truncated":true}`, which is 19 bytes long.
+pub const MIN_TRUNCATED_LEN: usize = ARRAY_MARKER.len();
+
+const ARRAY_MARKER: &str = r#"{"$truncated":true}"#;
+const OBJECT_MARKER: &str = r#""$truncated":true"#;
+
+/*
+ * Serializes `value` with the compact separators used throughout this module.
+ */
+fn compact(value: &JsonValue) -> String {
+    value.to_string_with(&SerializeOptions {
+        separators: Separators::Compact,
+        ..SerializeOptions::default()
+    })
+}
+
+/*
+ * How much of a member `write_member` wrote.
+ */
+enum Written {
+    Whole,
+    Partial,
+    Nothing,
+}
+
+/*
+ * Appends as much of the array or object `value` as fits in `limit` bytes,
+ * leaving room for `closers` bytes that the enclosing containers still need,
+ * and for a marker after it unless it is the `last` member of its parent.
+ * If anything is left out, a marker is written in its place. The caller checks
+ * beforehand that the container itself fits with a marker inside it.
+ */
+fn write_truncated(value: &JsonValue, last: bool, out: &mut String, limit: usize, closers: usize) {
+    match value {
+        JsonValue::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                let separator = if index > 0 { "," } else { "" };
+                match write_member(
+                    item,
+                    separator,
+                    "",
+                    last && index + 1 == items.len(),
+                    out,
+                    limit,
+                    closers + 1,
+                ) {
+                    Written::Whole => {}
+                    Written::Partial => break,
+                    Written::Nothing => {
+                        out.push_str(separator);
+                        out.push_str(ARRAY_MARKER);
+                        break;
+                    }
+                }
+            }
+            out.push(']');
+        }
+        JsonValue::Object(members) => {
+            out.push('{');
+            for (index, (key, member)) in members.iter().enumerate() {
+                let separator = if index > 0 { "," } else { "" };
+                let prefix = format!("\"{}\":", escape(key));
+                match write_member(
+                    member,
+                    separator,
+                    &prefix,
+                    last && index + 1 == members.len(),
+                    out,
+                    limit,
+                    closers + 1,
+                ) {
+                    Written::Whole => {}
+                    Written::Partial => break,
+                    Written::Nothing => {
+                        out.push_str(separator);
+                        out.push_str(OBJECT_MARKER);
+                        break;
+                    }
+                }
+            }
+            out.push('}');
+        }
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(custom) => write_truncated(&custom.to_json(), last, out, limit, closers),
+        _ => unreachable!("only containers are truncated"),
+    }
+}
+
+/*
+ * Appends `separator`, `prefix` and `member` if they fit, or as much of the
+ * member as fits if it is a container, with the marker inside it. After a
+ * partial member the caller stops; if nothing was written, it writes the marker.
+ *
+ * A member that is not the last one is only written whole if the marker still
+ * fits after it, since later members may not.
+ */
+fn write_member(
+    member: &JsonValue,
+    separator: &str,
+    prefix: &str,
+    last: bool,
+    out: &mut String,
+    limit: usize,
+    closers: usize,
+) -> Written {
+    let head = out.len() + separator.len() + prefix.len();
+    let marker = if last {
+        0
+    } else {
+        1 + OBJECT_MARKER.len().max(ARRAY_MARKER.len())
+    };
+    let serialized = compact(member);
+    if head + serialized.len() + marker + closers <= limit {
+        out.push_str(separator);
+        out.push_str(prefix);
+        out.push_str(&serialized);
+        return Written::Whole;
+    }
+    // An opened container holds at least its brackets and the marker
+    let inner_marker = match member {
+        JsonValue::Array(items) if !items.is_empty() => ARRAY_MARKER.len(),
+        JsonValue::Object(members) if !members.is_empty() => OBJECT_MARKER.len(),
+        _ => return Written::Nothing,
+    };
+    // The marker the caller would write instead is never longer than this
+    if head + 2 + inner_marker + closers > limit {
+        return Written::Nothing;
+    }
+    out.push_str(separator);
+    out.push_str(prefix);
+    write_truncated(member, last, out, limit, closers);
+    Written::Partial
+}
+
+impl JsonValue {
+    /// Serializes this value compactly (as with
+    /// [`Separators::Compact`]) in at most `limit` bytes.
+    ///
+    /// If the whole value does not fit, values are left out from the point where
+    /// the limit would be exceeded: the output keeps the members and elements
+    /// that fit, marks the place of the first one left out with a
+    /// [`TRUNCATED_KEY`] marker and closes the open arrays and objects, so it
+    /// stays valid JSON. Strings and numbers are never cut. A value that does
+    /// not fit even partially becomes the bare marker `{"$truncated":true}`, so
+    /// limits below [`MIN_TRUNCATED_LEN`] are raised to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let event = parse_json(r#"[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14]"#)?;
+    /// assert_eq!(event.to_string_max_bytes(100), "[1,2,3,4,5,6,7,8,9,10,11,12,13,14]");
+    ///
+    /// let cut = event.to_string_max_bytes(30);
+    /// assert_eq!(cut, r#"[1,2,3,4,{"$truncated":true}]"#);
+    /// assert!(parse_json(&cut).is_ok());
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn to_string_max_bytes(&self, limit: usize) -> String {
+        let serialized = compact(self);
+        if serialized.len() <= limit {
+            return serialized;
+        }
+        let mut out = String::with_capacity(limit);
+        match write_member(self, "", "", true, &mut out, limit, 0) {
+            Written::Nothing => ARRAY_MARKER.to_string(),
+            _ => out,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_json;
+
+    #[test]
+    fn test_to_string_max_bytes_stays_valid_and_within_limit() {
+        let value = parse_json(
+            r#"{"id": 7, "tags": ["alpha", "beta", "gamma"], "nested": [[1, 2, 3], {"deep": "value"}],
+                "message": "a fairly long message that will not fit in small limits"}"#,
+        )
+        .unwrap();
+        let full = compact(&value);
+        for limit in 0..=full.len() + 1 {
+            let out = value.to_string_max_bytes(limit);
+            assert!(
+                out.len() <= limit.max(MIN_TRUNCATED_LEN),
+                "{} > {}: {}",
+                out.len(),
+                limit,
+                out
+            );
+            let parsed = parse_json(&out).unwrap_or_else(|err| panic!("{}: {}", out, err));
+            if limit >= full.len() {
+                assert_eq!(parsed, value);
+            } else {
+                assert!(out.contains(TRUNCATED_KEY), "{}", out);
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_string_max_bytes_marks_innermost_container() {
+        let value =
+            parse_json(r#"[[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17]]"#).unwrap();
+        assert_eq!(
+            value.to_string_max_bytes(34),
+            r#"[[1,2,3,4,5,{"$truncated":true}]]"#
+        );
+        let text = JsonValue::String("x".repeat(50));
+        assert_eq!(text.to_string_max_bytes(40), r#"{"$truncated":true}"#);
+        assert_eq!(text.to_string_max_bytes(52).len(), 52);
+    }
+}
@@ -1,60 +1,251 @@
-use std::{collections::HashMap, fmt};
+use std::collections::HashMap;
+use std::{fmt, io, path::Path};
 
-fn escape_json_string(s: &str) -> String {
-    let mut result = String::new();
-    for c in s.chars() {
-        match c {
-            '"' => result.push_str("\\\""),
-            '\\' => result.push_str("\\\\"),
-            '\n' => result.push_str("\\n"),
-            '\t' => result.push_str("\\t"),
-            '\r' => result.push_str("\\r"),
-            '\u{0008}' => result.push_str("\\b"),
-            '\u{000C}' => result.push_str("\\f"),
-            _ => result.push(c),
+use crate::JsonResult;
+#[cfg(feature = "custom-values")]
+use crate::custom::CustomValue;
+use crate::files::write_atomic;
+use crate::number::JsonNumber;
+use crate::query;
+use crate::strings::{escape, escape_ascii};
+
+/* 2^63 and 2^64, the exclusive upper bounds of `i64` and `u64` as floats */
+const I64_BOUND: f64 = 9_223_372_036_854_775_808.0;
+const U64_BOUND: f64 = 18_446_744_073_709_551_616.0;
+
+/*
+ * Returns the value of a number stored as a float if it has no fractional part.
+ * Infinities and NaN are rejected too, since their fractional part is NaN.
+ */
+fn integral_float(n: &JsonNumber) -> Option<f64> {
+    let f = n.as_f64();
+    (n.is_f64() && f.fract() == 0.0).then_some(f)
+}
+
+/// Controls how a [`JsonValue`] is rendered by [`JsonValue::to_string_with`],
+/// [`JsonValue::write_to`] and the file writing helpers.
+///
+/// The default options produce the compact form used by `Display`. Combined
+/// with `sort_keys`, the output is deterministic, which makes it suitable for
+/// diffs.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, Separators, SerializeOptions};
+///
+/// let value = parse_json(r#"{"b": [1, 2], "a": "é"}"#)?;
+/// assert_eq!(value.to_string_with(&SerializeOptions::pretty(2).sorted()), "{\n  \"a\": \"é\",\n  \"b\": [\n    1,\n    2\n  ]\n}");
+///
+/// let options = SerializeOptions {
+///     sort_keys: true,
+///     ensure_ascii: true,
+///     separators: Separators::Compact,
+///     trailing_newline: true,
+///     ..SerializeOptions::default()
+/// };
+/// assert_eq!(value.to_string_with(&options), "{\"a\":\"\\u00e9\",\"b\":[1,2]}\n");
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerializeOptions {
+    /// Number of `indent_char`s per indentation level, or `None` for output on a
+    /// single line.
+    pub indent: Option<usize>,
+    /// The character repeated for indentation, a space by default. Use `'\t'`
+    /// (with an `indent` of 1) to indent with tabs.
+    pub indent_char: char,
+    /// Write object members sorted by key rather than in map iteration order.
+    pub sort_keys: bool,
+    /// Escape every non-ASCII character as `\uXXXX` (using surrogate pairs
+    /// outside the Basic Multilingual Plane), so the output is pure ASCII.
+    pub ensure_ascii: bool,
+    /// The punctuation written between members and after object keys.
+    pub separators: Separators,
+    /// End the output with a newline.
+    pub trailing_newline: bool,
+    /// Write NaN and infinite numbers as the bare words `NaN`, `Infinity` and
+    /// `-Infinity`, which Python's `json` module understands and
+    /// [`ParserOptions::allow_nan`](crate::ParserOptions::allow_nan) accepts.
+    /// Otherwise they are written as `null`, keeping the output valid JSON.
+    pub allow_nan: bool,
+    /// Delimit strings (and quoted keys) with single quotes, as in `'it\'s'`.
+    /// Double quotes inside are then written as they are. The output is not
+    /// JSON; [`ParserOptions::allow_single_quotes`](crate::ParserOptions::allow_single_quotes)
+    /// reads it back.
+    pub single_quotes: bool,
+    /// Write object keys that are identifiers (letters, digits, `_` and `$`,
+    /// not starting with a digit) without quotes, as JSON5 and JavaScript allow.
+    /// The words `true`, `false`, `null`, `NaN` and `Infinity` stay quoted. The
+    /// output is not JSON;
+    /// [`ParserOptions::allow_unquoted_keys`](crate::ParserOptions::allow_unquoted_keys)
+    /// reads it back.
+    pub unquoted_keys: bool,
+}
+
+/// The separators written by the serializer. See [`SerializeOptions::separators`].
+///
+/// When pretty-printing, members are always separated by a comma followed by a
+/// newline, so only the key separator differs between the variants.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Separators {
+    /// `,` between members and `: ` after keys, as written by `Display`.
+    #[default]
+    Standard,
+    /// `,` and `:` without any spaces, for the smallest output.
+    Compact,
+    /// `, ` between members and `: ` after keys.
+    Spaced,
+}
+
+impl Separators {
+    fn item(self) -> &'static str {
+        match self {
+            Separators::Standard | Separators::Compact => ",",
+            Separators::Spaced => ", ",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Separators::Standard | Separators::Spaced => ": ",
+            Separators::Compact => ":",
+        }
+    }
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self {
+            indent: None,
+            indent_char: ' ',
+            sort_keys: false,
+            ensure_ascii: false,
+            separators: Separators::Standard,
+            trailing_newline: false,
+            allow_nan: false,
+            single_quotes: false,
+            unquoted_keys: false,
+        }
+    }
+}
+
+impl SerializeOptions {
+    /// Returns options for pretty-printed output with `indent` spaces per level.
+    pub fn pretty(indent: usize) -> Self {
+        Self {
+            indent: Some(indent),
+            ..Self::default()
+        }
+    }
+
+    /// Returns these options with `sort_keys` enabled.
+    pub fn sorted(self) -> Self {
+        Self {
+            sort_keys: true,
+            ..self
         }
     }
-    result
 }
 
+/// The [`BuildHasher`](std::hash::BuildHasher) used by [`JsonMap`].
+///
+/// This is the standard library's SipHash-based `RandomState` unless one of the
+/// `fxhash` or `ahash` Cargo features is enabled, which trade its resistance to
+/// hash flooding for faster hashing of keys. Prefer the alias over naming a hasher
+/// directly so that code keeps compiling whichever feature is selected.
+#[cfg(not(any(feature = "fxhash", feature = "ahash")))]
+pub type JsonHasher = std::hash::RandomState;
+/// The [`BuildHasher`](std::hash::BuildHasher) used by [`JsonMap`] (FxHash).
+#[cfg(feature = "fxhash")]
+pub type JsonHasher = rustc_hash::FxBuildHasher;
+/// The [`BuildHasher`](std::hash::BuildHasher) used by [`JsonMap`] (aHash).
+#[cfg(all(feature = "ahash", not(feature = "fxhash")))]
+pub type JsonHasher = ahash::RandomState;
+
+/// The type of object keys in a [`JsonMap`].
+///
+/// This is a `String`, or an `Arc<str>` when the `shared-keys` Cargo feature is
+/// enabled, so that documents parsed with `ParserOptions::intern_keys` share
+/// one allocation per distinct key. Build keys with `.into()` (from a `&str` or a
+/// `String`) to support both.
+#[cfg(not(feature = "shared-keys"))]
+pub type JsonKey = String;
+/// The type of object keys in a [`JsonMap`], shared between equal keys.
+#[cfg(feature = "shared-keys")]
+pub type JsonKey = std::sync::Arc<str>;
+
+/// The map type holding the members of a [`JsonValue::Object`].
+///
+/// This is a `HashMap` using [`JsonHasher`], or a `BTreeMap` when the `btreemap`
+/// Cargo feature is enabled, in which case members iterate (and are serialized)
+/// sorted by key. Create one with `JsonMap::default()` rather than
+/// `HashMap::new()`, which is only available for the default configuration.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{JsonMap, JsonValue};
+///
+/// let mut members = JsonMap::default();
+/// members.insert("id".into(), JsonValue::Number(7.into()));
+/// assert_eq!(JsonValue::Object(members).to_string(), r#"{"id": 7}"#);
+/// ```
+#[cfg(not(feature = "btreemap"))]
+pub type JsonMap = std::collections::HashMap<JsonKey, JsonValue, JsonHasher>;
+/// The map type holding the members of a [`JsonValue::Object`], sorted by key.
+#[cfg(feature = "btreemap")]
+pub type JsonMap = std::collections::BTreeMap<JsonKey, JsonValue>;
+
+/// A view into a single member of a [`JsonMap`], which may be vacant or
+/// occupied, as returned by [`JsonValue::entry`].
+#[cfg(not(feature = "btreemap"))]
+pub type JsonEntry<'a> = std::collections::hash_map::Entry<'a, JsonKey, JsonValue>;
+/// A view into a single member of a [`JsonMap`], as returned by [`JsonValue::entry`].
+#[cfg(feature = "btreemap")]
+pub type JsonEntry<'a> = std::collections::btree_map::Entry<'a, JsonKey, JsonValue>;
+
+/// The sequence type holding the elements of a [`JsonValue::Array`].
+///
+/// This is always a `Vec`. A small-size-optimized vector cannot be used here:
+/// storing elements inline would make `JsonValue` contain itself, giving it an
+/// infinite size. To avoid an allocation per array when processing many
+/// documents, reuse buffers through a [`JsonValuePool`](crate::JsonValuePool)
+/// instead.
+pub type JsonArray = Vec<JsonValue>;
+
 /// Represents a parsed JSON value.
 #[derive(Debug, Clone, PartialEq)]
 pub enum JsonValue {
     /// A JSON string (e.g. `"hello"`).
     String(String),
-    /// A JSON number, stored as `f64` (e.g. `42`, `3.14`).
-    Number(f64),
+    /// A JSON number (e.g. `42`, `3.14`); integers are kept exact (see [`JsonNumber`]).
+    Number(JsonNumber),
     /// A JSON boolean (`true` or `false`).
     Boolean(bool),
     /// The JSON `null` literal.
     Null,
     /// An ordered JSON array of values (e.g. `[1, "two", true]`).
-    Array(Vec<JsonValue>),
+    Array(JsonArray),
     /// A JSON object mapping string keys to values (e.g. `{"key": "value"}`).
-    Object(HashMap<String, JsonValue>),
+    Object(JsonMap),
+    /// A domain value inserted by the embedder, serialized through its
+    /// [`CustomValue::to_json`] hook. Requires the `custom-values` feature.
+    #[cfg(feature = "custom-values")]
+    Custom(Box<dyn CustomValue>),
 }
 
 trait JsonFormat {
     fn to_json_string(&self) -> String;
 }
 
-impl JsonFormat for f64 {
-    fn to_json_string(&self) -> String {
-        if self.trunc() == *self {
-            format!("{}", self.trunc())
-        } else {
-            format!("{}", self)
-        }
-    }
-}
-
-impl JsonFormat for String {
+impl JsonFormat for str {
     fn to_json_string(&self) -> String {
-        format!("\"{}\"", escape_json_string(self))
+        format!("\"{}\"", escape(self))
     }
 }
 
-impl JsonFormat for HashMap<String, JsonValue> {
+impl JsonFormat for JsonMap {
     fn to_json_string(&self) -> String {
         let mut array_as_string = r#"{"#.to_string();
 
@@ -66,12 +257,14 @@ impl JsonFormat for HashMap<String, JsonValue> {
             let value_as_string = match value {
                 JsonValue::Null => "null".to_string(),
                 JsonValue::Boolean(b) => b.to_string(),
-                JsonValue::Number(n) => n.to_json_string(),
+                JsonValue::Number(n) => n.to_string(),
                 JsonValue::String(s) => s.to_json_string(),
                 JsonValue::Array(inner_array) => inner_array.to_json_string(),
                 JsonValue::Object(inner_object) => inner_object.to_json_string(),
+                #[cfg(feature = "custom-values")]
+                JsonValue::Custom(custom) => custom.to_json().to_string(),
             };
-            let item_as_string = format!("\"{}\": {}", key, value_as_string);
+            let item_as_string = format!("\"{}\": {}", escape(key), value_as_string);
             array_as_string.push_str(&item_as_string);
         }
         array_as_string.push('}');
@@ -91,10 +284,12 @@ impl JsonFormat for [JsonValue] {
             let item_as_string = match item {
                 JsonValue::Null => "null".to_string(),
                 JsonValue::Boolean(b) => b.to_string(),
-                JsonValue::Number(n) => n.to_json_string(),
+                JsonValue::Number(n) => n.to_string(),
                 JsonValue::String(s) => s.to_json_string(),
                 JsonValue::Array(inner_array) => inner_array.to_json_string(),
                 JsonValue::Object(inner_object) => inner_object.to_json_string(),
+                #[cfg(feature = "custom-values")]
+                JsonValue::Custom(custom) => custom.to_json().to_string(),
             };
             array_as_string.push_str(&item_as_string);
         }
@@ -143,7 +338,9 @@ impl JsonValue {
         }
     }
 
-    /// Returns the inner `f64` if this is a `JsonValue::Number`, or `None` otherwise.
+    /// Returns the number as an `f64` if this is a `JsonValue::Number`, or `None`
+    /// otherwise. Integers beyond 2^53 may lose precision; see
+    /// [`as_number`](Self::as_number) for exact access.
     ///
     /// # Examples
     ///
@@ -161,7 +358,88 @@ impl JsonValue {
         let JsonValue::Number(n) = self else {
             return None;
         };
-        Some(*n)
+        Some(n.as_f64())
+    }
+
+    /// Returns the number as an `i64` if this is a `JsonValue::Number` holding an
+    /// integral value within range, or `None` otherwise. Integral floats such as
+    /// `1e3` are accepted; fractional or out of range values are never truncated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// assert_eq!(parse_json("-42")?.as_i64(), Some(-42));
+    /// assert_eq!(parse_json("1e3")?.as_i64(), Some(1000));
+    /// assert_eq!(parse_json("2.5")?.as_i64(), None);
+    /// assert_eq!(parse_json("9223372036854775808")?.as_i64(), None);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn as_i64(&self) -> Option<i64> {
+        let n = self.as_number()?;
+        n.as_i64().or_else(|| {
+            integral_float(n)
+                .filter(|f| (-I64_BOUND..I64_BOUND).contains(f))
+                .map(|f| f as i64)
+        })
+    }
+
+    /// Returns the number as a `u64` if this is a `JsonValue::Number` holding a
+    /// non-negative integral value within range, or `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// assert_eq!(parse_json("18446744073709551615")?.as_u64(), Some(u64::MAX));
+    /// assert_eq!(parse_json("-1")?.as_u64(), None);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn as_u64(&self) -> Option<u64> {
+        let n = self.as_number()?;
+        n.as_u64().or_else(|| {
+            integral_float(n)
+                .filter(|f| (0.0..U64_BOUND).contains(f))
+                .map(|f| f as u64)
+        })
+    }
+
+    /// Returns the number as a `usize` if this is a `JsonValue::Number` holding a
+    /// non-negative integral value that fits, or `None` otherwise. Handy for
+    /// indices and lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"count": 3}"#)?;
+    /// assert_eq!(value.get("count").and_then(|v| v.as_usize()), Some(3));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_u64().and_then(|u| usize::try_from(u).ok())
+    }
+
+    /// Returns a reference to the inner [`JsonNumber`] if this is a
+    /// `JsonValue::Number`, or `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json("9007199254740993")?;
+    /// assert_eq!(value.as_number().and_then(|n| n.as_u64()), Some(9007199254740993));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn as_number(&self) -> Option<&JsonNumber> {
+        match self {
+            JsonValue::Number(n) => Some(n),
+            _ => None,
+        }
     }
 
     /// Returns the inner `bool` if this is a `JsonValue::Boolean`, or `None` otherwise.
@@ -185,7 +463,7 @@ impl JsonValue {
         Some(*b)
     }
 
-    /// Returns a reference to the inner `Vec` if this is a `JsonValue::Array`, or `None` otherwise.
+    /// Returns a reference to the inner [`JsonArray`] if this is a `JsonValue::Array`, or `None` otherwise.
     ///
     /// # Examples
     ///
@@ -199,14 +477,14 @@ impl JsonValue {
     /// assert_eq!(value.as_array(), None);
     /// # Ok::<(), rust_json_parser::JsonError>(())
     /// ```
-    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+    pub fn as_array(&self) -> Option<&JsonArray> {
         match self {
             JsonValue::Array(a) => Some(a),
             _ => None,
         }
     }
 
-    /// Returns a reference to the inner `HashMap` if this is a `JsonValue::Object`, or `None` otherwise.
+    /// Returns a reference to the inner [`JsonMap`] if this is a `JsonValue::Object`, or `None` otherwise.
     ///
     /// # Examples
     ///
@@ -220,13 +498,131 @@ impl JsonValue {
     /// assert_eq!(value.as_object(), None);
     /// # Ok::<(), rust_json_parser::JsonError>(())
     /// ```
-    pub fn as_object(&self) -> Option<&HashMap<String, JsonValue>> {
+    pub fn as_object(&self) -> Option<&JsonMap> {
+        match self {
+            JsonValue::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the inner `T` if this is a `JsonValue::Custom` holding a
+    /// `T`, or `None` otherwise. See [`CustomValue`] for an example.
+    #[cfg(feature = "custom-values")]
+    pub fn as_custom<T: CustomValue>(&self) -> Option<&T> {
+        match self {
+            JsonValue::Custom(c) => c.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the inner `T` if this is a `JsonValue::Custom`
+    /// holding a `T`, or `None` otherwise.
+    #[cfg(feature = "custom-values")]
+    pub fn as_custom_mut<T: CustomValue>(&mut self) -> Option<&mut T> {
+        match self {
+            JsonValue::Custom(c) => c.downcast_mut(),
+            _ => None,
+        }
+    }
+
+    /// Moves this value out, leaving `JsonValue::Null` in its place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, JsonValue};
+    ///
+    /// let mut value = parse_json(r#"{"items": [1, 2]}"#)?;
+    /// let items = value.pointer_mut("/items").map(JsonValue::take);
+    /// assert_eq!(items.and_then(JsonValue::into_array).map(|a| a.len()), Some(2));
+    /// assert_eq!(value.to_string(), r#"{"items": null}"#);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn take(&mut self) -> JsonValue {
+        std::mem::replace(self, JsonValue::Null)
+    }
+
+    /// Moves the value of the member named `key` out of a `JsonValue::Object`,
+    /// leaving `JsonValue::Null` in its place (use [`remove`](Self::remove) to
+    /// drop the member instead). Returns `None` if the key is missing or if this
+    /// value is not an object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, JsonValue};
+    ///
+    /// let mut value = parse_json(r#"{"name": "Ann"}"#)?;
+    /// let name = value.take_key("name").and_then(JsonValue::into_string);
+    /// assert_eq!(name.as_deref(), Some("Ann"));
+    /// assert_eq!(value.get("name"), Some(&JsonValue::Null));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn take_key(&mut self, key: &str) -> Option<JsonValue> {
+        match self {
+            JsonValue::Object(o) => o.get_mut(key).map(JsonValue::take),
+            _ => None,
+        }
+    }
+
+    /// Converts this value into its inner [`JsonArray`] if it is a
+    /// `JsonValue::Array`, or returns `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// assert_eq!(parse_json("[1, 2]")?.into_array().map(|a| a.len()), Some(2));
+    /// assert_eq!(parse_json("{}")?.into_array(), None);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn into_array(self) -> Option<JsonArray> {
+        match self {
+            JsonValue::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Converts this value into its inner [`JsonMap`] if it is a
+    /// `JsonValue::Object`, or returns `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let members = parse_json(r#"{"a": 1}"#)?.into_object();
+    /// assert!(members.is_some_and(|o| o.contains_key("a")));
+    /// assert_eq!(parse_json("[]")?.into_object(), None);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn into_object(self) -> Option<JsonMap> {
         match self {
             JsonValue::Object(o) => Some(o),
             _ => None,
         }
     }
 
+    /// Converts this value into its inner `String` if it is a
+    /// `JsonValue::String`, or returns `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// assert_eq!(parse_json(r#""hi""#)?.into_string(), Some("hi".to_string()));
+    /// assert_eq!(parse_json("1")?.into_string(), None);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn into_string(self) -> Option<String> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
     /// Looks up a value by key if this is a `JsonValue::Object`. Returns `None` if the
     /// key is missing or if this value is not an object.
     ///
@@ -257,7 +653,7 @@ impl JsonValue {
     /// use rust_json_parser::{parse_json, JsonValue};
     ///
     /// let value = parse_json("[10, 20, 30]")?;
-    /// assert_eq!(value.get_index(1), Some(&JsonValue::Number(20.0)));
+    /// assert_eq!(value.get_index(1), Some(&JsonValue::Number(20.0.into())));
     /// assert_eq!(value.get_index(5), None);
     /// # Ok::<(), rust_json_parser::JsonError>(())
     /// ```
@@ -269,100 +665,1300 @@ impl JsonValue {
         }
     }
 
-    /// Serializes this value to a pretty-printed JSON string with the given number
-    /// of spaces per indentation level.
+    /// Returns an iterator over the elements of a `JsonValue::Array`. The iterator
+    /// is empty for any other value.
     ///
     /// # Examples
     ///
     /// ```
     /// use rust_json_parser::parse_json;
     ///
-    /// let value = parse_json(r#"{"key": [1, 2]}"#)?;
-    /// let pretty = value.pretty_print(2);
-    /// assert!(pretty.contains("\"key\""));
-    /// assert!(pretty.contains('\n'));
+    /// let value = parse_json("[1, 2, 3]")?;
+    /// let doubled: Vec<i64> = value.members().filter_map(|v| v.as_i64()).map(|n| n * 2).collect();
+    /// assert_eq!(doubled, [2, 4, 6]);
+    /// assert_eq!(parse_json(r#"{"a": 1}"#)?.members().count(), 0);
     /// # Ok::<(), rust_json_parser::JsonError>(())
     /// ```
-    pub fn pretty_print(&self, indent: usize) -> String {
-        self.pretty_print_recursive(0, indent)
+    pub fn members(&self) -> std::slice::Iter<'_, JsonValue> {
+        self.into_iter()
     }
 
-    /// Recursive helper for [`pretty_print`](Self::pretty_print) that tracks the current
-    /// nesting depth.
-    fn pretty_print_recursive(&self, depth: usize, indent: usize) -> String {
-        let pad = " ".repeat(depth * indent);
-        let inner_pad = " ".repeat((depth + 1) * indent);
-
-        match self {
-            JsonValue::Null => "null".to_string(),
-            JsonValue::Boolean(b) => b.to_string(),
-            JsonValue::Number(n) => n.to_json_string(),
-            JsonValue::String(s) => s.to_json_string(),
-            JsonValue::Array(arr) => {
-                if arr.is_empty() {
-                    return "[]".to_string();
-                }
-                let items: Vec<String> = arr
-                    .iter()
-                    .map(|v| {
-                        format!(
-                            "{}{}",
-                            inner_pad,
-                            v.pretty_print_recursive(depth + 1, indent)
-                        )
-                    })
-                    .collect();
-                format!("[\n{}\n{}]", items.join(",\n"), pad)
-            }
-            JsonValue::Object(obj) => {
-                if obj.is_empty() {
-                    return "{}".to_string();
-                }
-                let entries: Vec<String> = obj
-                    .iter()
-                    .map(|(k, v)| {
-                        format!(
-                            "{}\"{}\": {}",
-                            inner_pad,
-                            escape_json_string(k),
-                            v.pretty_print_recursive(depth + 1, indent)
-                        )
-                    })
-                    .collect();
-                format!("{{\n{}\n{}}}", entries.join(",\n"), pad)
-            }
-        }
+    /// Returns an iterator over the `(name, value)` members of a
+    /// `JsonValue::Object`, in [`JsonMap`] order. The iterator is empty for any
+    /// other value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"port": 8080}"#)?;
+    /// for (name, value) in value.entries() {
+    ///     assert_eq!((name, value.as_i64()), ("port", Some(8080)));
+    /// }
+    /// assert_eq!(parse_json("[1]")?.entries().count(), 0);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &JsonValue)> {
+        self.as_object()
+            .into_iter()
+            .flat_map(|o| o.iter().map(|(key, value)| (&**key, value)))
     }
-}
 
-impl fmt::Display for JsonValue {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            JsonValue::Null => write!(f, "null"),
-            JsonValue::Boolean(b) => write!(f, "{}", b),
-            JsonValue::Number(n) => write!(f, "{}", n.to_json_string()),
-            JsonValue::String(s) => write!(f, "{}", s.to_json_string()),
-            JsonValue::Array(array) => write!(f, "{}", array.to_json_string()),
-            JsonValue::Object(object) => write!(f, "{}", object.to_json_string()),
-        }
+    /// Returns `true` if this is a `JsonValue::Object` with a member named `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"debug": false}"#)?;
+    /// assert!(value.contains_key("debug"));
+    /// assert!(!value.contains_key("verbose"));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.as_object().is_some_and(|o| o.contains_key(key))
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn test_json_value_creation() {
-        let null_val = JsonValue::Null;
-        let bool_val = JsonValue::Boolean(true);
-        let num_val = JsonValue::Number(42.5);
-        let str_val = JsonValue::String("hello".to_string());
+    /// Returns an iterator over the member names of a `JsonValue::Object`, in
+    /// [`JsonMap`] order. The iterator is empty for any other value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"host": "localhost", "port": 8080}"#)?;
+    /// let mut keys: Vec<&str> = value.keys().collect();
+    /// keys.sort();
+    /// assert_eq!(keys, ["host", "port"]);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.as_object()
+            .into_iter()
+            .flat_map(|o| o.keys().map(|key| &**key))
+    }
 
-        assert!(null_val.is_null());
-        assert_eq!(bool_val.as_bool(), Some(true));
-        assert_eq!(num_val.as_f64(), Some(42.5));
-        assert_eq!(str_val.as_str(), Some("hello"));
+    /// Returns an iterator over the member values of a `JsonValue::Object`, in
+    /// [`JsonMap`] order. The iterator is empty for any other value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"a": 1, "b": 2}"#)?;
+    /// let total: i64 = value.values().filter_map(|v| v.as_i64()).sum();
+    /// assert_eq!(total, 3);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn values(&self) -> impl Iterator<Item = &JsonValue> {
+        self.as_object().into_iter().flat_map(|o| o.values())
+    }
+
+    /// Returns the number of members of a `JsonValue::Object` or elements of a
+    /// `JsonValue::Array`, or 0 for any other value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// assert_eq!(parse_json(r#"{"a": 1, "b": 2}"#)?.len(), 2);
+    /// assert_eq!(parse_json("[1, 2, 3]")?.len(), 3);
+    /// assert_eq!(parse_json("\"abc\"")?.len(), 0);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn len(&self) -> usize {
+        match self {
+            JsonValue::Object(o) => o.len(),
+            JsonValue::Array(a) => a.len(),
+            _ => 0,
+        }
+    }
+
+    /// Returns `true` if [`len`](Self::len) is 0, i.e. this is an empty object or
+    /// array, or not a container at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// assert!(parse_json("{}")?.is_empty());
+    /// assert!(!parse_json("[null]")?.is_empty());
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes the member named `key` from a `JsonValue::Object` and returns its
+    /// value. Returns `None` if the key is missing or if this value is not an
+    /// object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, JsonValue};
+    ///
+    /// let mut value = parse_json(r#"{"keep": 1, "drop": 2}"#)?;
+    /// assert_eq!(value.remove("drop"), Some(JsonValue::Number(2.into())));
+    /// assert_eq!(value.remove("drop"), None);
+    /// assert_eq!(value.to_string(), r#"{"keep": 1}"#);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue> {
+        match self {
+            JsonValue::Object(o) => o.remove(key),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`JsonEntry`] for the member named `key` if this is a
+    /// `JsonValue::Object`, for in-place insertion or update, or `None` for any
+    /// other value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, JsonValue};
+    ///
+    /// let mut config = parse_json(r#"{"retries": 3}"#)?;
+    /// if let Some(entry) = config.entry("timeout") {
+    ///     entry.or_insert(JsonValue::Number(30.into()));
+    /// }
+    /// if let Some(entry) = config.entry("retries") {
+    ///     entry.or_insert(JsonValue::Number(5.into()));
+    /// }
+    /// assert_eq!(config.get("timeout"), Some(&JsonValue::Number(30.into())));
+    /// assert_eq!(config.get("retries"), Some(&JsonValue::Number(3.into())));
+    ///
+    /// let mut array = parse_json("[]")?;
+    /// assert!(array.entry("timeout").is_none());
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn entry(&mut self, key: impl Into<JsonKey>) -> Option<JsonEntry<'_>> {
+        match self {
+            JsonValue::Object(o) => Some(o.entry(key.into())),
+            _ => None,
+        }
+    }
+
+    /// Looks up a value by [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901),
+    /// e.g. `"/users/0/name"`. The empty pointer refers to the value itself.
+    /// Returns `None` if nothing matches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, JsonValue};
+    ///
+    /// let value = parse_json(r#"{"users": [{"name": "Ann"}], "a/b": 1}"#)?;
+    /// assert_eq!(value.pointer("/users/0/name").and_then(|v| v.as_str()), Some("Ann"));
+    /// assert_eq!(value.pointer("/a~1b"), Some(&JsonValue::Number(1.into())));
+    /// assert_eq!(value.pointer("/users/1"), None);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+        query::pointer(self, pointer)
+    }
+
+    /// Like [`pointer`](Self::pointer), but returns a mutable reference.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, JsonValue};
+    ///
+    /// let mut value = parse_json(r#"{"limits": {"rps": 10}}"#)?;
+    /// if let Some(rps) = value.pointer_mut("/limits/rps") {
+    ///     *rps = JsonValue::Number(20.into());
+    /// }
+    /// assert_eq!(value.to_string(), r#"{"limits": {"rps": 20}}"#);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue> {
+        query::pointer_mut(self, pointer)
+    }
+
+    /// Looks up a value by a dotted path such as `"servers[0].tls.cert"`: member
+    /// names separated by dots, each optionally followed by array indices in
+    /// brackets. The empty path is the value itself.
+    ///
+    /// Returns `None` if nothing matches or the path is malformed. Member names
+    /// cannot contain `.` or `[`; use [`pointer`](Self::pointer) for such keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"servers": [{"tls": {"cert": "a.pem"}}]}"#)?;
+    /// assert_eq!(value.path("servers[0].tls.cert").and_then(|v| v.as_str()), Some("a.pem"));
+    /// assert_eq!(value.path("servers[1].tls"), None);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn path(&self, path: &str) -> Option<&JsonValue> {
+        query::path(self, path)
+    }
+
+    /// Like [`path`](Self::path), but returns a mutable reference.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, JsonValue};
+    ///
+    /// let mut value = parse_json(r#"{"servers": [{"port": 80}]}"#)?;
+    /// if let Some(port) = value.path_mut("servers[0].port") {
+    ///     *port = JsonValue::Number(443.into());
+    /// }
+    /// assert_eq!(value.to_string(), r#"{"servers": [{"port": 443}]}"#);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn path_mut(&mut self, path: &str) -> Option<&mut JsonValue> {
+        query::path_mut(self, path)
+    }
+
+    /// Returns the string at `path`, a dotted path as accepted by
+    /// [`path`](Self::path).
+    ///
+    /// Unlike the `as_*` accessors, the typed `try_get_*` getters return an error
+    /// naming the path and both types, ready to report to a user.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::TypeMismatch`](crate::JsonError::TypeMismatch) if nothing is at `path` or the value
+    /// there is not a string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let config = parse_json(r#"{"servers": [{"host": "a", "port": "80"}]}"#)?;
+    /// assert_eq!(config.try_get_str("servers[0].host")?, "a");
+    ///
+    /// let err = config.try_get_f64("servers[0].port").unwrap_err();
+    /// assert_eq!(err.to_string(), "Type mismatch: expected number at servers[0].port, found string");
+    /// let err = config.try_get_str("servers[1].host").unwrap_err();
+    /// assert_eq!(err.to_string(), "Type mismatch: expected string at servers[1].host, found nothing");
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn try_get_str(&self, path: &str) -> JsonResult<&str> {
+        self.try_get_as(path, "string", JsonValue::as_str)
+    }
+
+    /// Returns the boolean at `path`, like [`try_get_str`](Self::try_get_str).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::TypeMismatch`](crate::JsonError::TypeMismatch) if nothing is at `path` or the value
+    /// there is not a boolean.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let config = parse_json(r#"{"debug": true}"#)?;
+    /// assert!(config.try_get_bool("debug")?);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn try_get_bool(&self, path: &str) -> JsonResult<bool> {
+        self.try_get_as(path, "boolean", JsonValue::as_bool)
+    }
+
+    /// Returns the number as an `f64` at `path`, like [`try_get_str`](Self::try_get_str).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::TypeMismatch`](crate::JsonError::TypeMismatch) if nothing is at `path` or the value
+    /// there is not a number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let config = parse_json(r#"{"ratio": 0.5}"#)?;
+    /// assert_eq!(config.try_get_f64("ratio")?, 0.5);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn try_get_f64(&self, path: &str) -> JsonResult<f64> {
+        self.try_get_as(path, "number", JsonValue::as_f64)
+    }
+
+    /// Returns the integer as an `i64` at `path`, like [`try_get_str`](Self::try_get_str).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::TypeMismatch`](crate::JsonError::TypeMismatch) if nothing is at `path` or the value
+    /// there is not an integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let config = parse_json(r#"{"offset": -3, "ratio": 0.5}"#)?;
+    /// assert_eq!(config.try_get_i64("offset")?, -3);
+    /// assert!(config.try_get_i64("ratio").is_err());
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn try_get_i64(&self, path: &str) -> JsonResult<i64> {
+        self.try_get_as(path, "integer", JsonValue::as_i64)
+    }
+
+    /// Returns the non-negative integer as a `u64` at `path`, like [`try_get_str`](Self::try_get_str).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::TypeMismatch`](crate::JsonError::TypeMismatch) if nothing is at `path` or the value
+    /// there is not a non-negative integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let config = parse_json(r#"{"port": 8080, "offset": -3}"#)?;
+    /// assert_eq!(config.try_get_u64("port")?, 8080);
+    /// assert!(config.try_get_u64("offset").is_err());
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn try_get_u64(&self, path: &str) -> JsonResult<u64> {
+        self.try_get_as(path, "non-negative integer", JsonValue::as_u64)
+    }
+
+    /// Returns the array at `path`, like [`try_get_str`](Self::try_get_str).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::TypeMismatch`](crate::JsonError::TypeMismatch) if nothing is at `path` or the value
+    /// there is not an array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let config = parse_json(r#"{"tags": ["a", "b"]}"#)?;
+    /// assert_eq!(config.try_get_array("tags")?.len(), 2);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn try_get_array(&self, path: &str) -> JsonResult<&JsonArray> {
+        self.try_get_as(path, "array", JsonValue::as_array)
+    }
+
+    /// Returns the object at `path`, like [`try_get_str`](Self::try_get_str).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::TypeMismatch`](crate::JsonError::TypeMismatch) if nothing is at `path` or the value
+    /// there is not an object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let config = parse_json(r#"{"limits": {"rps": 10}}"#)?;
+    /// assert!(config.try_get_object("limits")?.contains_key("rps"));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn try_get_object(&self, path: &str) -> JsonResult<&JsonMap> {
+        self.try_get_as(path, "object", JsonValue::as_object)
+    }
+
+    /// Applies a JSON Patch (RFC 6902) document: an array of `add`, `remove`,
+    /// `replace`, `move`, `copy` and `test` operations whose paths are JSON
+    /// Pointers.
+    ///
+    /// The patch is applied atomically: if any operation fails, `self` is left
+    /// unchanged. Use [`json_diff`](crate::json_diff) to generate a patch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let mut value = parse_json(r#"{"replicas": 1, "tags": ["a"]}"#)?;
+    /// let patch = parse_json(r#"[
+    ///     {"op": "test", "path": "/replicas", "value": 1},
+    ///     {"op": "replace", "path": "/replicas", "value": 3},
+    ///     {"op": "add", "path": "/tags/-", "value": "b"}
+    /// ]"#)?;
+    /// value.apply_patch(&patch)?;
+    /// assert_eq!(value, parse_json(r#"{"replicas": 3, "tags": ["a", "b"]}"#)?);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::InvalidPatch`](crate::JsonError::InvalidPatch) with
+    /// the index of the first operation that is malformed, refers to a missing
+    /// location or whose test fails.
+    pub fn apply_patch(&mut self, patch: &JsonValue) -> JsonResult<()> {
+        let mut patched = self.clone();
+        crate::patch::apply(&mut patched, patch)?;
+        *self = patched;
+        Ok(())
+    }
+
+    /// Applies a JSON Merge Patch (RFC 7386) document.
+    ///
+    /// Members of an object patch are merged into `self` recursively, and a
+    /// member whose value is `null` is removed. Any patch that is not an object,
+    /// including an array, replaces `self` entirely.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let mut config = parse_json(r#"{"replicas": 1, "debug": true, "limits": {"cpu": 1}}"#)?;
+    /// config.merge_patch(&parse_json(r#"{"replicas": 3, "debug": null, "limits": {"memory": 512}}"#)?);
+    /// assert_eq!(
+    ///     config,
+    ///     parse_json(r#"{"replicas": 3, "limits": {"cpu": 1, "memory": 512}}"#)?
+    /// );
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn merge_patch(&mut self, patch: &JsonValue) {
+        crate::patch::merge(self, patch);
+    }
+
+    /// Merges `other` into `self`, recursively combining objects member by
+    /// member.
+    ///
+    /// Members only present in `other` are added. Where both sides have a value
+    /// and they are not both objects, `strategy` decides the result: see
+    /// [`MergeStrategy`](crate::MergeStrategy). Unlike
+    /// [`merge_patch`](Self::merge_patch), `null` in `other` is an ordinary value
+    /// and does not remove anything.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, MergeStrategy};
+    ///
+    /// let mut config = parse_json(r#"{"port": 80, "tls": {"enabled": false, "cert": null}}"#)?;
+    /// let overrides = parse_json(r#"{"tls": {"enabled": true}, "debug": true}"#)?;
+    /// config.merge(overrides, MergeStrategy::default());
+    /// assert_eq!(
+    ///     config,
+    ///     parse_json(r#"{"port": 80, "tls": {"enabled": true, "cert": null}, "debug": true}"#)?
+    /// );
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn merge(&mut self, other: JsonValue, strategy: crate::MergeStrategy) {
+        crate::merge::deep_merge(self, other, strategy);
+    }
+
+    /// Evaluates a JSONPath expression such as `"$.store.books[*].title"` and
+    /// returns every matching value.
+    ///
+    /// The supported subset is `$` for the root, `.name` and `['name']` for object
+    /// members, `[0]` and `[-1]` for array elements, `*` and `[*]` wildcards, and
+    /// `..` for recursive descent (e.g. `$..price`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"books": [{"title": "A"}, {"title": "B"}]}"#)?;
+    /// let titles: Vec<_> = value
+    ///     .query("$.books[*].title")?
+    ///     .into_iter()
+    ///     .filter_map(|v| v.as_str())
+    ///     .collect();
+    /// assert_eq!(titles, ["A", "B"]);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::InvalidQuery`](crate::JsonError::InvalidQuery) if the
+    /// expression is malformed.
+    pub fn query(&self, path: &str) -> JsonResult<Vec<&JsonValue>> {
+        query::query(self, path)
+    }
+
+    /// Returns a hash of this value's content that ignores the order of object
+    /// members, so values that compare equal always have the same hash.
+    ///
+    /// To hash a document once and compare or diff it repeatedly, use
+    /// [`HashedJson`](crate::hash::HashedJson), which also keeps subtree hashes.
+    pub fn structural_hash(&self) -> u64 {
+        crate::hash::structural_hash(self)
+    }
+
+    /// Compares two values, using their [`structural_hash`](Self::structural_hash)
+    /// to reject different values early.
+    ///
+    /// Unequal hashes prove the values differ. Equal hashes are confirmed with a
+    /// full `==` comparison, so a hash collision never reports different values
+    /// as equal. The result is always the same as `==`; this pays off when most
+    /// compared values differ.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let a = parse_json(r#"{"x": 1, "y": [true]}"#)?;
+    /// let b = parse_json(r#"{"y": [true], "x": 1}"#)?;
+    /// assert!(a.structural_hash_eq(&b));
+    /// assert!(!a.structural_hash_eq(&parse_json("[]")?));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn structural_hash_eq(&self, other: &JsonValue) -> bool {
+        self.structural_hash() == other.structural_hash() && self == other
+    }
+
+    /// Removes repeated elements from an array, keeping the first occurrence of
+    /// each and the order of the rest, and returns how many were removed.
+    ///
+    /// Elements are compared with `==`, so `1` and `1.0` are duplicates, as are
+    /// objects with the same members in a different order. Values other than
+    /// arrays are left unchanged. Nested arrays are not deduplicated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let mut hosts = parse_json(r#"["a", {"x": 1, "y": 2}, "b", "a", {"y": 2, "x": 1.0}]"#)?;
+    /// assert_eq!(hosts.dedup_array_in_place(), 2);
+    /// assert_eq!(hosts, parse_json(r#"["a", {"x": 1, "y": 2}, "b"]"#)?);
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn dedup_array_in_place(&mut self) -> usize {
+        let JsonValue::Array(items) = self else {
+            return 0;
+        };
+        // Structural hashes narrow the comparisons down to likely duplicates
+        let mut kept_by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut keep = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let kept = kept_by_hash.entry(item.structural_hash()).or_default();
+            let duplicate = kept.iter().any(|&earlier| items[earlier] == *item);
+            if !duplicate {
+                kept.push(index);
+            }
+            keep.push(!duplicate);
+        }
+        let before = items.len();
+        let mut keep = keep.into_iter();
+        items.retain(|_| keep.next().unwrap_or(true));
+        before - items.len()
+    }
+
+    /// Serializes this value to a pretty-printed JSON string with the given number
+    /// of spaces per indentation level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"key": [1, 2]}"#)?;
+    /// let pretty = value.pretty_print(2);
+    /// assert!(pretty.contains("\"key\""));
+    /// assert!(pretty.contains('\n'));
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn pretty_print(&self, indent: usize) -> String {
+        self.pretty_print_recursive(0, indent, false)
+    }
+
+    /// Like [`pretty_print`](Self::pretty_print), but writes the members of every
+    /// object in key order, so equal values always produce the same output.
+    ///
+    /// Keys are compared by their UTF-8 bytes, as with
+    /// [`SerializeOptions::sort_keys`], the general option that works with every
+    /// output setting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"b": 1, "a": {"d": [], "c": null}}"#)?;
+    /// assert_eq!(
+    ///     value.pretty_print_sorted(2),
+    ///     "{\n  \"a\": {\n    \"c\": null,\n    \"d\": []\n  },\n  \"b\": 1\n}"
+    /// );
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn pretty_print_sorted(&self, indent: usize) -> String {
+        self.pretty_print_recursive(0, indent, true)
+    }
+
+    /// Serializes this value in the canonical form defined by the JSON
+    /// Canonicalization Scheme (RFC 8785), suitable for hashing and signing.
+    ///
+    /// The output has no whitespace, object members sorted by the UTF-16 code
+    /// units of their keys, numbers formatted as ECMAScript would format the
+    /// equivalent `f64`, and only the escapes JSON requires in strings. As in
+    /// ECMAScript, integers beyond 2^53 are rounded to the nearest `f64`.
+    ///
+    /// Returns `None` if the value contains a NaN or infinite number, which
+    /// cannot be canonicalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"b": [1.0, 1e21, 0.000001], "a": "é\u000f"}"#)?;
+    /// assert_eq!(
+    ///     value.to_canonical_string().as_deref(),
+    ///     Some(r#"{"a":"é\u000f","b":[1,1e+21,0.000001]}"#)
+    /// );
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn to_canonical_string(&self) -> Option<String> {
+        let mut output = String::new();
+        self.write_canonical(&mut output)?;
+        Some(output)
+    }
+
+    fn write_canonical(&self, out: &mut String) -> Option<()> {
+        match self {
+            JsonValue::Null => out.push_str("null"),
+            JsonValue::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+            JsonValue::Number(n) => out.push_str(&n.to_ecmascript_string()?),
+            JsonValue::String(s) => out.push_str(&s.to_json_string()),
+            JsonValue::Array(arr) => {
+                out.push('[');
+                for (index, item) in arr.iter().enumerate() {
+                    if index > 0 {
+                        out.push(',');
+                    }
+                    item.write_canonical(out)?;
+                }
+                out.push(']');
+            }
+            JsonValue::Object(obj) => {
+                let mut members: Vec<(&JsonKey, &JsonValue)> = obj.iter().collect();
+                members.sort_unstable_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+                out.push('{');
+                for (index, (key, value)) in members.into_iter().enumerate() {
+                    if index > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(&key.to_json_string());
+                    out.push(':');
+                    value.write_canonical(out)?;
+                }
+                out.push('}');
+            }
+            #[cfg(feature = "custom-values")]
+            JsonValue::Custom(custom) => custom.to_json().write_canonical(out)?,
+        }
+        Some(())
+    }
+
+    /// Serializes this value according to the given [`SerializeOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, SerializeOptions};
+    ///
+    /// let value = parse_json(r#"{"key": true}"#)?;
+    /// assert_eq!(value.to_string_with(&SerializeOptions::pretty(2)), "{\n  \"key\": true\n}");
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    pub fn to_string_with(&self, options: &SerializeOptions) -> String {
+        let mut output = Vec::new();
+        self.write_recursive(&mut output, 0, options)
+            .and_then(|()| write_trailer(&mut output, options))
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(output).expect("serialized JSON is valid UTF-8")
+    }
+
+    /// Serializes this value according to the given [`SerializeOptions`] and writes
+    /// it to `writer` piece by piece, without building the whole output in memory.
+    ///
+    /// The output matches [`to_string_with`](Self::to_string_with). Wrap unbuffered
+    /// writers such as files in a [`BufWriter`](std::io::BufWriter).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::{parse_json, SerializeOptions};
+    ///
+    /// let value = parse_json(r#"{"key": [1, 2]}"#)?;
+    /// let mut output = Vec::new();
+    /// value.write_to(&mut output, &SerializeOptions::pretty(2))?;
+    /// assert_eq!(output, value.to_string_with(&SerializeOptions::pretty(2)).as_bytes());
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::Io`](crate::JsonError::Io) if writing fails.
+    pub fn write_to<W: io::Write>(
+        &self,
+        writer: &mut W,
+        options: &SerializeOptions,
+    ) -> JsonResult<()> {
+        self.write_recursive(writer, 0, options)?;
+        write_trailer(writer, options)?;
+        Ok(())
+    }
+
+    /// Writes the compact serialization of this value (as produced by `Display`)
+    /// to `writer` without building it in memory first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json("[1, true, null]")?;
+    /// let mut output = Vec::new();
+    /// value.to_writer(&mut output)?;
+    /// assert_eq!(output, b"[1,true,null]");
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::Io`](crate::JsonError::Io) if writing fails.
+    pub fn to_writer<W: io::Write>(&self, writer: &mut W) -> JsonResult<()> {
+        self.write_to(writer, &SerializeOptions::default())
+    }
+
+    /// Writes the serialization of this value pretty-printed with `indent` spaces
+    /// per level (as produced by [`pretty_print`](Self::pretty_print)) to `writer`
+    /// without building it in memory first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_json_parser::parse_json;
+    ///
+    /// let value = parse_json(r#"{"tags": ["a"]}"#)?;
+    /// let mut output = Vec::new();
+    /// value.to_writer_pretty(&mut output, 2)?;
+    /// assert_eq!(output, value.pretty_print(2).as_bytes());
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::Io`](crate::JsonError::Io) if writing fails.
+    pub fn to_writer_pretty<W: io::Write>(&self, writer: &mut W, indent: usize) -> JsonResult<()> {
+        self.write_to(writer, &SerializeOptions::pretty(indent))
+    }
+
+    /// Serializes this value and atomically writes it to the file at `path`.
+    ///
+    /// The output is written to a temporary file, synced to disk and renamed over
+    /// the target, so a crash never leaves a truncated or half-written document
+    /// behind (see [`crate::files::write_atomic`]).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_json_parser::{parse_json, SerializeOptions};
+    ///
+    /// let config = parse_json(r#"{"debug": false}"#)?;
+    /// config.write_to_file_atomic("config.json", &SerializeOptions::pretty(2))?;
+    /// # Ok::<(), rust_json_parser::JsonError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JsonError::Io`](crate::JsonError::Io) if the file cannot be written.
+    pub fn write_to_file_atomic(
+        &self,
+        path: impl AsRef<Path>,
+        format_options: &SerializeOptions,
+    ) -> JsonResult<()> {
+        write_atomic(path, self.to_string_with(format_options).as_bytes())
+    }
+
+    /// Recursive helper for [`write_to`](Self::write_to) that tracks the current
+    /// nesting depth.
+    fn write_recursive<W: io::Write>(
+        &self,
+        writer: &mut W,
+        depth: usize,
+        options: &SerializeOptions,
+    ) -> io::Result<()> {
+        match self {
+            JsonValue::Null => writer.write_all(b"null"),
+            JsonValue::Boolean(b) => write!(writer, "{}", b),
+            JsonValue::Number(n) => match n.non_finite_text() {
+                Some(text) if options.allow_nan => writer.write_all(text.as_bytes()),
+                _ => write!(writer, "{}", n),
+            },
+            JsonValue::String(s) => write_string(writer, s, options),
+            JsonValue::Array(arr) if arr.is_empty() => writer.write_all(b"[]"),
+            JsonValue::Object(obj) if obj.is_empty() => writer.write_all(b"{}"),
+            JsonValue::Array(arr) => {
+                writer.write_all(b"[")?;
+                for (index, item) in arr.iter().enumerate() {
+                    write_separator(writer, index, depth + 1, options)?;
+                    item.write_recursive(writer, depth + 1, options)?;
+                }
+                write_separator(writer, usize::MAX, depth, options)?;
+                writer.write_all(b"]")
+            }
+            JsonValue::Object(obj) => {
+                let mut members: Vec<(&JsonKey, &JsonValue)> = obj.iter().collect();
+                if options.sort_keys {
+                    members.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                }
+                writer.write_all(b"{")?;
+                for (index, (key, value)) in members.into_iter().enumerate() {
+                    write_separator(writer, index, depth + 1, options)?;
+                    write_key(writer, key, options)?;
+                    writer.write_all(options.separators.key().as_bytes())?;
+                    value.write_recursive(writer, depth + 1, options)?;
+                }
+                write_separator(writer, usize::MAX, depth, options)?;
+                writer.write_all(b"}")
+            }
+            #[cfg(feature = "custom-values")]
+            JsonValue::Custom(custom) => custom.to_json().write_recursive(writer, depth, options),
+        }
+    }
+
+    /// Recursive helper for [`pretty_print`](Self::pretty_print) that tracks the current
+    /// nesting depth.
+    /*
+     * Looks up `path` and converts the value there with `convert`, reporting a
+     * type mismatch if either step fails.
+     */
+    fn try_get_as<'a, T>(
+        &'a self,
+        path: &str,
+        expected: &str,
+        convert: impl FnOnce(&'a JsonValue) -> Option<T>,
+    ) -> JsonResult<T> {
+        let value = self.path(path);
+        value
+            .and_then(convert)
+            .ok_or_else(|| crate::JsonError::TypeMismatch {
+                path: path.to_string(),
+                expected: expected.to_string(),
+                found: value.map_or("nothing", type_name).to_string(),
+            })
+    }
+
+    fn pretty_print_recursive(&self, depth: usize, indent: usize, sort_keys: bool) -> String {
+        let pad = " ".repeat(depth * indent);
+        let inner_pad = " ".repeat((depth + 1) * indent);
+
+        match self {
+            JsonValue::Null => "null".to_string(),
+            JsonValue::Boolean(b) => b.to_string(),
+            JsonValue::Number(n) => n.to_string(),
+            JsonValue::String(s) => s.to_json_string(),
+            JsonValue::Array(arr) => {
+                if arr.is_empty() {
+                    return "[]".to_string();
+                }
+                let items: Vec<String> = arr
+                    .iter()
+                    .map(|v| {
+                        format!(
+                            "{}{}",
+                            inner_pad,
+                            v.pretty_print_recursive(depth + 1, indent, sort_keys)
+                        )
+                    })
+                    .collect();
+                format!("[\n{}\n{}]", items.join(",\n"), pad)
+            }
+            JsonValue::Object(obj) => {
+                if obj.is_empty() {
+                    return "{}".to_string();
+                }
+                let mut members: Vec<(&JsonKey, &JsonValue)> = obj.iter().collect();
+                if sort_keys {
+                    members.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                }
+                let entries: Vec<String> = members
+                    .into_iter()
+                    .map(|(k, v)| {
+                        format!(
+                            "{}\"{}\": {}",
+                            inner_pad,
+                            escape(k),
+                            v.pretty_print_recursive(depth + 1, indent, sort_keys)
+                        )
+                    })
+                    .collect();
+                format!("{{\n{}\n{}}}", entries.join(",\n"), pad)
+            }
+            #[cfg(feature = "custom-values")]
+            JsonValue::Custom(custom) => custom
+                .to_json()
+                .pretty_print_recursive(depth, indent, sort_keys),
+        }
+    }
+}
+
+/*
+ * Writes what precedes the member at `index` of a container, or its closing
+ * bracket when `index` is `usize::MAX`: a separator between members, or a comma,
+ * a newline and `depth` levels of indentation when pretty-printing.
+ */
+fn write_separator<W: io::Write>(
+    writer: &mut W,
+    index: usize,
+    depth: usize,
+    options: &SerializeOptions,
+) -> io::Result<()> {
+    let between_members = index > 0 && index != usize::MAX;
+    match options.indent {
+        Some(indent) => {
+            if between_members {
+                writer.write_all(b",")?;
+            }
+            writer.write_all(b"\n")?;
+            let mut unit = [0; 4];
+            let unit = options.indent_char.encode_utf8(&mut unit).as_bytes();
+            for _ in 0..depth * indent {
+                writer.write_all(unit)?;
+            }
+            Ok(())
+        }
+        None if between_members => writer.write_all(options.separators.item().as_bytes()),
+        None => Ok(()),
+    }
+}
+
+/*
+ * Writes `s` as a quoted JSON string, escaping non-ASCII characters as UTF-16
+ * `\uXXXX` units if `ensure_ascii` is set.
+ */
+fn write_string<W: io::Write>(
+    writer: &mut W,
+    s: &str,
+    options: &SerializeOptions,
+) -> io::Result<()> {
+    let escaped = if options.ensure_ascii {
+        escape_ascii(s)
+    } else {
+        escape(s)
+    };
+    if options.single_quotes {
+        // A backslash right before a quote is always the one escaping it
+        let escaped = escaped.replace("\\\"", "\"").replace('\'', "\\'");
+        writer.write_all(b"'")?;
+        writer.write_all(escaped.as_bytes())?;
+        return writer.write_all(b"'");
+    }
+    writer.write_all(b"\"")?;
+    writer.write_all(escaped.as_bytes())?;
+    writer.write_all(b"\"")
+}
+
+/*
+ * Writes an object key, without quotes if `unquoted_keys` is set and the key is
+ * an identifier that the parser does not read as a literal.
+ */
+fn write_key<W: io::Write>(
+    writer: &mut W,
+    key: &str,
+    options: &SerializeOptions,
+) -> io::Result<()> {
+    let is_identifier = key
+        .bytes()
+        .next()
+        .is_some_and(|b| b.is_ascii_alphabetic() || b == b'_' || b == b'$')
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'$')
+        && !matches!(key, "true" | "false" | "null" | "NaN" | "Infinity");
+    if options.unquoted_keys && is_identifier {
+        writer.write_all(key.as_bytes())
+    } else {
+        write_string(writer, key, options)
+    }
+}
+
+/*
+ * Writes what follows the serialized value.
+ */
+fn write_trailer<W: io::Write>(writer: &mut W, options: &SerializeOptions) -> io::Result<()> {
+    if options.trailing_newline {
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+impl From<JsonNumber> for JsonValue {
+    fn from(number: JsonNumber) -> Self {
+        JsonValue::Number(number)
+    }
+}
+
+macro_rules! impl_from_number {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for JsonValue {
+                fn from(value: $t) -> Self {
+                    JsonValue::Number(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {
+        JsonValue::Boolean(value)
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {
+        JsonValue::String(value)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {
+        JsonValue::String(value.to_string())
+    }
+}
+
+impl From<JsonArray> for JsonValue {
+    fn from(elements: JsonArray) -> Self {
+        JsonValue::Array(elements)
+    }
+}
+
+impl From<JsonMap> for JsonValue {
+    fn from(members: JsonMap) -> Self {
+        JsonValue::Object(members)
+    }
+}
+
+/// Collects values into a `JsonValue::Array`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::JsonValue;
+///
+/// let value: JsonValue = (0..3).map(JsonValue::from).collect();
+/// assert_eq!(value.to_string(), "[0,1,2]");
+/// ```
+impl<T: Into<JsonValue>> FromIterator<T> for JsonValue {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        JsonValue::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Collects `(name, value)` pairs into a `JsonValue::Object`. A later pair
+/// replaces an earlier one with the same name.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::JsonValue;
+///
+/// let value: JsonValue = [("name", "Ann")].into_iter().collect();
+/// assert_eq!(value.to_string(), r#"{"name": "Ann"}"#);
+/// ```
+impl<K: Into<JsonKey>, V: Into<JsonValue>> FromIterator<(K, V)> for JsonValue {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        JsonValue::Object(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Appends values to a `JsonValue::Array`.
+///
+/// # Panics
+///
+/// Panics if this value is not an array.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, JsonValue};
+///
+/// let mut value = parse_json("[1]")?;
+/// value.extend([2, 3]);
+/// assert_eq!(value.to_string(), "[1,2,3]");
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+impl<T: Into<JsonValue>> Extend<T> for JsonValue {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        match self {
+            JsonValue::Array(a) => a.extend(iter.into_iter().map(Into::into)),
+            _ => panic!("only a JSON array can be extended with elements"),
+        }
+    }
+}
+
+/// Inserts `(name, value)` pairs into a `JsonValue::Object`, replacing existing
+/// members with the same name.
+///
+/// # Panics
+///
+/// Panics if this value is not an object.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, JsonValue};
+///
+/// let mut value = parse_json(r#"{"a": 1}"#)?;
+/// value.extend([("a", 2), ("b", 3)]);
+/// assert_eq!(value.get("a"), Some(&JsonValue::from(2)));
+/// assert_eq!(value.len(), 2);
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+impl<K: Into<JsonKey>, V: Into<JsonValue>> Extend<(K, V)> for JsonValue {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        match self {
+            JsonValue::Object(o) => o.extend(
+                iter.into_iter()
+                    .map(|(key, value)| (key.into(), value.into())),
+            ),
+            _ => panic!("only a JSON object can be extended with members"),
+        }
+    }
+}
+
+/// Iterates over the elements of a `JsonValue::Array`, yielding nothing for any
+/// other value. Use [`JsonValue::entries`] for the members of an object.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::{parse_json, JsonValue};
+///
+/// let mut value = parse_json("[1, 2]")?;
+/// for element in &mut value {
+///     *element = JsonValue::Null;
+/// }
+/// assert_eq!(value.to_string(), "[null,null]");
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+impl IntoIterator for JsonValue {
+    type Item = JsonValue;
+    type IntoIter = std::vec::IntoIter<JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            JsonValue::Array(a) => a.into_iter(),
+            _ => Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a JsonValue {
+    type Item = &'a JsonValue;
+    type IntoIter = std::slice::Iter<'a, JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            JsonValue::Array(a) => a.iter(),
+            _ => [].iter(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a mut JsonValue {
+    type Item = &'a mut JsonValue;
+    type IntoIter = std::slice::IterMut<'a, JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            JsonValue::Array(a) => a.iter_mut(),
+            _ => [].iter_mut(),
+        }
+    }
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Boolean(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(_) => "custom",
+    }
+}
+
+/// The default indentation of the alternate (`{:#}`) format.
+const ALTERNATE_INDENT: usize = 2;
+
+/// Formats this value as compact JSON. The alternate flag (`{:#}`) pretty-prints
+/// it as [`JsonValue::pretty_print`] does, indenting by the width if one is given
+/// (`{:#4}`) and by two spaces otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use rust_json_parser::parse_json;
+///
+/// let value = parse_json(r#"{"ids": [1, 2]}"#)?;
+/// assert_eq!(format!("{value}"), r#"{"ids": [1,2]}"#);
+/// assert_eq!(format!("{value:#}"), "{\n  \"ids\": [\n    1,\n    2\n  ]\n}");
+/// assert_eq!(format!("{value:#1}"), "{\n \"ids\": [\n  1,\n  2\n ]\n}");
+/// # Ok::<(), rust_json_parser::JsonError>(())
+/// ```
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let indent = f.width().unwrap_or(ALTERNATE_INDENT);
+            return f.write_str(&self.pretty_print(indent));
+        }
+        match self {
+            JsonValue::Null => write!(f, "null"),
+            JsonValue::Boolean(b) => write!(f, "{}", b),
+            JsonValue::Number(n) => write!(f, "{}", n),
+            JsonValue::String(s) => write!(f, "{}", s.to_json_string()),
+            JsonValue::Array(array) => write!(f, "{}", array.to_json_string()),
+            JsonValue::Object(object) => write!(f, "{}", object.to_json_string()),
+            #[cfg(feature = "custom-values")]
+            JsonValue::Custom(custom) => write!(f, "{}", custom.to_json()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JsonError, parse_json};
+
+    #[test]
+    fn test_json_value_creation() {
+        let null_val = JsonValue::Null;
+        let bool_val = JsonValue::Boolean(true);
+        let num_val = JsonValue::Number(42.5.into());
+        let str_val = JsonValue::String("hello".to_string());
+
+        assert!(null_val.is_null());
+        assert_eq!(bool_val.as_bool(), Some(true));
+        assert_eq!(num_val.as_f64(), Some(42.5));
+        assert_eq!(str_val.as_str(), Some("hello"));
     }
 
     #[test]
@@ -373,7 +1969,7 @@ mod tests {
         assert_eq!(value.as_bool(), None);
         assert!(!value.is_null());
 
-        let value = JsonValue::Number(42.0);
+        let value = JsonValue::Number(42.0.into());
         assert_eq!(value.as_f64(), Some(42.0));
         assert_eq!(value.as_str(), None);
 
@@ -384,27 +1980,211 @@ mod tests {
         assert!(value.is_null());
     }
 
+    #[test]
+    fn test_integer_accessors() {
+        let value = JsonValue::Number(42.0.into());
+        assert_eq!(value.as_i64(), Some(42));
+        assert_eq!(value.as_u64(), Some(42));
+        assert_eq!(value.as_usize(), Some(42));
+
+        let value = JsonValue::Number((-7).into());
+        assert_eq!(value.as_i64(), Some(-7));
+        assert_eq!(value.as_u64(), None);
+        assert_eq!(value.as_usize(), None);
+
+        let value = JsonValue::Number(u64::MAX.into());
+        assert_eq!(value.as_i64(), None);
+        assert_eq!(value.as_u64(), Some(u64::MAX));
+
+        for f in [2.5, 1e19, 1e20, -1e19, f64::INFINITY, f64::NAN] {
+            assert_eq!(JsonValue::Number(f.into()).as_i64(), None, "{}", f);
+        }
+        assert_eq!(JsonValue::Number(1e19.into()).as_u64(), Some(10u64.pow(19)));
+        assert_eq!(JsonValue::Number((-0.0).into()).as_u64(), Some(0));
+        assert_eq!(JsonValue::Number((-1.0).into()).as_u64(), None);
+        assert_eq!(JsonValue::String("1".to_string()).as_i64(), None);
+    }
+
+    #[test]
+    fn test_object_helpers() {
+        let mut value = parse_json(r#"{"a": 1, "b": [true]}"#).unwrap();
+        assert!(value.contains_key("a"));
+        assert_eq!(value.len(), 2);
+        let mut keys: Vec<&str> = value.keys().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["a", "b"]);
+        assert_eq!(value.values().filter(|v| v.as_array().is_some()).count(), 1);
+
+        *value.entry("a").unwrap().or_insert(JsonValue::Null) = JsonValue::Boolean(false);
+        value
+            .entry("c")
+            .unwrap()
+            .and_modify(|_| panic!("\"c\" is vacant"))
+            .or_insert_with(|| JsonValue::Number(3.into()));
+        assert_eq!(value.get("a"), Some(&JsonValue::Boolean(false)));
+        assert_eq!(value.get("c"), Some(&JsonValue::Number(3.into())));
+
+        assert_eq!(value.remove("b"), Some(parse_json("[true]").unwrap()));
+        assert!(!value.contains_key("b"));
+        assert_eq!(value.len(), 2);
+
+        // Non-objects have no members
+        let mut array = parse_json("[1, 2]").unwrap();
+        assert_eq!(array.len(), 2);
+        assert_eq!(array.keys().count(), 0);
+        assert_eq!(array.values().count(), 0);
+        assert!(!array.contains_key("0"));
+        assert_eq!(array.remove("0"), None);
+        assert!(array.entry("0").is_none());
+        assert!(JsonValue::Null.is_empty());
+    }
+
+    #[test]
+    fn test_iteration() {
+        let value = parse_json(r#"[1, "two", null]"#).unwrap();
+        assert_eq!(value.members().len(), 3);
+        assert_eq!(
+            (&value).into_iter().next(),
+            Some(&JsonValue::Number(1.into()))
+        );
+        let owned: Vec<JsonValue> = value.into_iter().collect();
+        assert_eq!(owned[1], JsonValue::String("two".to_string()));
+
+        let value = parse_json(r#"{"a": 1, "b": 2}"#).unwrap();
+        let mut entries: Vec<(&str, &JsonValue)> = value.entries().collect();
+        entries.sort_unstable_by_key(|(name, _)| *name);
+        assert_eq!(entries[1], ("b", &JsonValue::Number(2.into())));
+
+        // Objects and scalars have no elements
+        assert_eq!(value.members().count(), 0);
+        assert_eq!(value.clone().into_iter().count(), 0);
+        assert_eq!(JsonValue::Null.entries().count(), 0);
+        let mut scalar = JsonValue::Boolean(true);
+        assert_eq!((&mut scalar).into_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_collect_and_extend() {
+        let mut array: JsonValue = ["a", "b"].into_iter().collect();
+        array.extend([JsonValue::Null]);
+        array.extend(vec![true]);
+        assert_eq!(array, parse_json(r#"["a", "b", null, true]"#).unwrap());
+
+        let mut object: JsonValue = (1..=2).map(|n| (format!("k{n}"), n)).collect();
+        object.extend([("k2", JsonValue::from(20)), ("k3", JsonValue::from(3.5))]);
+        assert_eq!(
+            object,
+            parse_json(r#"{"k1": 1, "k2": 20, "k3": 3.5}"#).unwrap()
+        );
+
+        let empty: JsonValue = std::iter::empty::<(String, JsonValue)>().collect();
+        assert_eq!(empty, JsonValue::Object(JsonMap::default()));
+    }
+
+    #[test]
+    #[should_panic(expected = "only a JSON array")]
+    fn test_extend_non_array_panics() {
+        JsonValue::Null.extend([1]);
+    }
+
+    #[test]
+    fn test_alternate_display_pretty_prints() {
+        let value = parse_json(r#"{"a": [true, {}]}"#).unwrap();
+        assert_eq!(format!("{value:#}"), value.pretty_print(2));
+        assert_eq!(format!("{value:#4}"), value.pretty_print(4));
+        assert_eq!(format!("{value}"), value.to_string());
+        assert!(!format!("{value:4}").contains('\n'));
+        assert_eq!(format!("{:#}", JsonValue::Null), "null");
+    }
+
+    #[test]
+    fn test_take_and_into() {
+        let mut value = parse_json(r#"{"list": [1, 2], "name": "x", "n": 1}"#).unwrap();
+        let list = value.take_key("list").unwrap();
+        assert_eq!(list.into_array().map(|a| a.len()), Some(2));
+        assert_eq!(value.get("list"), Some(&JsonValue::Null));
+        assert_eq!(value.take_key("missing"), None);
+        assert_eq!(
+            value
+                .take_key("name")
+                .and_then(JsonValue::into_string)
+                .as_deref(),
+            Some("x")
+        );
+
+        let members = value.take().into_object().unwrap();
+        assert_eq!(members.len(), 3);
+        assert!(value.is_null());
+        assert_eq!(value.take_key("n"), None);
+        assert_eq!(JsonValue::Boolean(true).into_string(), None);
+        assert_eq!(JsonValue::Null.into_object(), None);
+    }
+
+    #[test]
+    fn test_typed_getters() {
+        let config = parse_json(
+            r#"{"servers": [{"host": "a", "port": 80, "tls": false, "tags": [], "env": {}}], "ratio": 0.5}"#,
+        )
+        .unwrap();
+        assert_eq!(config.try_get_str("servers[0].host"), Ok("a"));
+        assert_eq!(config.try_get_u64("servers[0].port"), Ok(80));
+        assert_eq!(config.try_get_i64("servers[0].port"), Ok(80));
+        assert_eq!(config.try_get_f64("ratio"), Ok(0.5));
+        assert_eq!(config.try_get_bool("servers[0].tls"), Ok(false));
+        assert!(config.try_get_array("servers[0].tags").unwrap().is_empty());
+        assert!(config.try_get_object("servers[0].env").unwrap().is_empty());
+        assert!(config.try_get_object("").is_ok());
+
+        assert_eq!(
+            config.try_get_str("servers[0].port"),
+            Err(JsonError::TypeMismatch {
+                path: "servers[0].port".to_string(),
+                expected: "string".to_string(),
+                found: "number".to_string(),
+            })
+        );
+        let err = config.try_get_i64("ratio").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type mismatch: expected integer at ratio, found number"
+        );
+        let err = config.try_get_array("missing").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type mismatch: expected array at missing, found nothing"
+        );
+        let err = config.try_get_bool("").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type mismatch: expected boolean at (root), found object"
+        );
+    }
+
     #[test]
     fn test_json_value_equality() {
         assert_eq!(JsonValue::Null, JsonValue::Null);
         assert_eq!(JsonValue::Boolean(true), JsonValue::Boolean(true));
-        assert_eq!(JsonValue::Number(42.0), JsonValue::Number(42.0));
+        assert_eq!(
+            JsonValue::Number(42.0.into()),
+            JsonValue::Number(42.0.into())
+        );
         assert_eq!(
             JsonValue::String("test".to_string()),
             JsonValue::String("test".to_string())
         );
 
         assert_ne!(JsonValue::Null, JsonValue::Boolean(false));
-        assert_ne!(JsonValue::Number(1.0), JsonValue::Number(2.0));
+        assert_ne!(JsonValue::Number(1.0.into()), JsonValue::Number(2.0.into()));
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_display_primitives() {
         assert_eq!(JsonValue::Null.to_string(), "null");
         assert_eq!(JsonValue::Boolean(true).to_string(), "true");
         assert_eq!(JsonValue::Boolean(false).to_string(), "false");
-        assert_eq!(JsonValue::Number(42.0).to_string(), "42");
-        assert_eq!(JsonValue::Number(3.14).to_string(), "3.14");
+        assert_eq!(JsonValue::Number(42.0.into()).to_string(), "42");
+        assert_eq!(JsonValue::Number(3.14.into()).to_string(), "3.14");
         assert_eq!(
             JsonValue::String("hello".to_string()).to_string(),
             "\"hello\""
@@ -413,20 +2193,74 @@ mod tests {
 
     #[test]
     fn test_display_array() {
-        let value = JsonValue::Array(vec![JsonValue::Number(1.0), JsonValue::Number(2.0)]);
+        let value = JsonValue::Array(vec![
+            JsonValue::Number(1.0.into()),
+            JsonValue::Number(2.0.into()),
+        ]);
         assert_eq!(value.to_string(), "[1,2]");
     }
 
     #[test]
     fn test_display_empty_containers() {
         assert_eq!(JsonValue::Array(vec![]).to_string(), "[]");
-        assert_eq!(JsonValue::Object(HashMap::new()).to_string(), "{}");
+        assert_eq!(JsonValue::Object(JsonMap::default()).to_string(), "{}");
     }
 
     #[test]
     fn test_display_escape_string() {
         let value = JsonValue::String("hello\nworld".to_string());
         assert_eq!(value.to_string(), "\"hello\\nworld\"");
+
+        let value = JsonValue::String("\u{0}\u{1f}\u{7f}".to_string());
+        assert_eq!(value.to_string(), "\"\\u0000\\u001f\u{7f}\"");
+    }
+
+    #[test]
+    fn test_canonical_string() {
+        // Example from RFC 8785, section 3.2.2
+        let value = crate::parse_json(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value.to_canonical_string().unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn test_canonical_key_order_uses_utf16() {
+        // U+FB01 sorts after U+1F600 by code point but before it in UTF-16
+        let value = JsonValue::Object(JsonMap::from_iter([
+            ("\u{1f600}".into(), JsonValue::Null),
+            ("\u{fb01}".into(), JsonValue::Null),
+            ("a".into(), JsonValue::Null),
+        ]));
+        assert_eq!(
+            value.to_canonical_string().unwrap(),
+            "{\"a\":null,\"\u{1f600}\":null,\"\u{fb01}\":null}"
+        );
+        let nan = JsonValue::Array(vec![JsonValue::Number(f64::NAN.into())]);
+        assert_eq!(nan.to_canonical_string(), None);
+    }
+
+    #[cfg(feature = "btreemap")]
+    #[test]
+    fn test_btreemap_objects_serialize_in_key_order() {
+        let value = crate::parse_json(r#"{"b": 1, "c": {"z": 0, "y": 0}, "a": 2}"#).unwrap();
+        assert_eq!(value.to_string(), r#"{"a": 2,"b": 1,"c": {"y": 0,"z": 0}}"#);
+    }
+
+    #[test]
+    fn test_display_escapes_object_keys() {
+        let mut object = JsonMap::default();
+        object.insert("a\"b\n".into(), JsonValue::Null);
+        let value = JsonValue::Object(object);
+        assert_eq!(value.to_string(), r#"{"a\"b\n": null}"#);
     }
 
     #[test]
@@ -434,4 +2268,214 @@ mod tests {
         let value = JsonValue::String("say \"hi\"".to_string());
         assert_eq!(value.to_string(), "\"say \\\"hi\\\"\"");
     }
+
+    #[test]
+    fn test_to_string_with_options() {
+        let value = JsonValue::Array(vec![JsonValue::Null]);
+        assert_eq!(value.to_string_with(&SerializeOptions::default()), "[null]");
+        assert_eq!(
+            value.to_string_with(&SerializeOptions::pretty(4)),
+            "[\n    null\n]"
+        );
+    }
+
+    #[test]
+    fn test_dedup_array_in_place() {
+        let mut value = crate::parse_json(r#"[1, [1, 1], 1.0, null, [1, 1], "1", null]"#).unwrap();
+        assert_eq!(value.dedup_array_in_place(), 3);
+        assert_eq!(
+            value,
+            crate::parse_json(r#"[1, [1, 1], null, "1"]"#).unwrap()
+        );
+        assert_eq!(value.dedup_array_in_place(), 0);
+
+        let mut object = crate::parse_json(r#"{"a": [1, 1]}"#).unwrap();
+        assert_eq!(object.dedup_array_in_place(), 0);
+        assert_eq!(object, crate::parse_json(r#"{"a": [1, 1]}"#).unwrap());
+    }
+
+    #[test]
+    fn test_serialize_options_sort_keys_and_separators() {
+        let value = crate::parse_json(r#"{"b": [1, 2], "a": null}"#).unwrap();
+        let sorted = SerializeOptions::default().sorted();
+        assert_eq!(value.to_string_with(&sorted), r#"{"a": null,"b": [1,2]}"#);
+
+        let compact = SerializeOptions {
+            separators: Separators::Compact,
+            ..sorted.clone()
+        };
+        assert_eq!(value.to_string_with(&compact), r#"{"a":null,"b":[1,2]}"#);
+
+        let spaced = SerializeOptions {
+            separators: Separators::Spaced,
+            ..sorted
+        };
+        assert_eq!(value.to_string_with(&spaced), r#"{"a": null, "b": [1, 2]}"#);
+    }
+
+    #[test]
+    fn test_pretty_print_sorted_is_independent_of_insertion_order() {
+        let keys = ["delta", "alpha", "charlie", "bravo", "echo"];
+        let mut forward = JsonMap::default();
+        let mut backward = JsonMap::default();
+        for (index, key) in keys.iter().enumerate() {
+            forward.insert((*key).into(), JsonValue::Number(index.into()));
+        }
+        for (index, key) in keys.iter().enumerate().rev() {
+            backward.insert((*key).into(), JsonValue::Number(index.into()));
+        }
+        let forward = JsonValue::Array(vec![JsonValue::Object(forward)]);
+        let backward = JsonValue::Array(vec![JsonValue::Object(backward)]);
+
+        let pretty = forward.pretty_print_sorted(4);
+        assert_eq!(pretty, backward.pretty_print_sorted(4));
+        assert_eq!(
+            pretty,
+            forward.to_string_with(&SerializeOptions::pretty(4).sorted())
+        );
+        assert!(pretty.find("alpha") < pretty.find("bravo"));
+    }
+
+    #[test]
+    fn test_non_finite_numbers_round_trip_with_allow_nan() {
+        let input = "[NaN,Infinity,-Infinity,1.5]";
+        let parse_options = crate::ParserOptions {
+            allow_nan: true,
+            ..crate::ParserOptions::default()
+        };
+        let value = crate::parse_json_with_options(input, &parse_options).unwrap();
+        let options = SerializeOptions {
+            allow_nan: true,
+            ..SerializeOptions::default()
+        };
+        assert_eq!(value.to_string_with(&options), input);
+        assert_eq!(
+            value.to_string_with(&SerializeOptions::default()),
+            "[null,null,null,1.5]"
+        );
+        assert_eq!(value.to_string(), "[null,null,null,1.5]");
+        assert_eq!(value.pretty_print(0), "[\nnull,\nnull,\nnull,\n1.5\n]");
+        assert_eq!(
+            format!("{:#}", value),
+            value.to_string_with(&SerializeOptions::pretty(2))
+        );
+    }
+
+    #[test]
+    fn test_serialize_options_tabs_and_trailing_newline() {
+        let value = JsonValue::Array(vec![JsonValue::Array(vec![JsonValue::Null])]);
+        let options = SerializeOptions {
+            indent: Some(1),
+            indent_char: '\t',
+            trailing_newline: true,
+            ..SerializeOptions::default()
+        };
+        assert_eq!(value.to_string_with(&options), "[\n\t[\n\t\tnull\n\t]\n]\n");
+    }
+
+    #[test]
+    fn test_serialize_options_ensure_ascii() {
+        let value = JsonValue::Object(JsonMap::from_iter([(
+            "clé".into(),
+            JsonValue::String("a\"é😀".to_string()),
+        )]));
+        let options = SerializeOptions {
+            ensure_ascii: true,
+            ..SerializeOptions::default()
+        };
+        let output = value.to_string_with(&options);
+        assert_eq!(output, r#"{"cl\u00e9": "a\"\u00e9\ud83d\ude00"}"#);
+
+        let value = JsonValue::String("ñ€".to_string());
+        let output = value.to_string_with(&options);
+        assert_eq!(output, r#""\u00f1\u20ac""#);
+        assert_eq!(crate::parse_json(&output).unwrap(), value);
+    }
+
+    #[test]
+    fn test_serialize_options_single_quotes_and_unquoted_keys() {
+        let value = crate::parse_json(
+            r#"{"name": "it's \"ok\" \\", "$id": 1, "_x9": [], "9a": 2, "a-b": 3, "null": 4}"#,
+        )
+        .unwrap();
+        let options = SerializeOptions {
+            sort_keys: true,
+            single_quotes: true,
+            unquoted_keys: true,
+            ..SerializeOptions::default()
+        };
+        let output = value.to_string_with(&options);
+        assert_eq!(
+            output,
+            r#"{$id: 1,'9a': 2,_x9: [],'a-b': 3,name: 'it\'s "ok" \\','null': 4}"#
+        );
+        let lenient = crate::ParserOptions {
+            allow_single_quotes: true,
+            allow_unquoted_keys: true,
+            ..crate::ParserOptions::default()
+        };
+        assert_eq!(
+            crate::parse_json_with_options(&output, &lenient).unwrap(),
+            value
+        );
+
+        let options = SerializeOptions {
+            unquoted_keys: true,
+            ..SerializeOptions::default()
+        };
+        assert_eq!(
+            value.get("name").unwrap().to_string_with(&options),
+            r#""it's \"ok\" \\""#
+        );
+    }
+
+    #[test]
+    fn test_write_to_matches_to_string_with() {
+        let value = crate::parse_json(
+            r#"{"list": [1, "a\nb", {"x": null}], "empty": [], "obj": {}, "t": true}"#,
+        )
+        .unwrap();
+        for options in [SerializeOptions::default(), SerializeOptions::pretty(3)] {
+            let mut output = Vec::new();
+            value.write_to(&mut output, &options).unwrap();
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                value.to_string_with(&options)
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_writer_reports_write_failures() {
+        struct Full;
+        impl io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let value = JsonValue::Array(vec![JsonValue::Null]);
+        assert!(matches!(
+            value.to_writer(&mut Full),
+            Err(crate::JsonError::Io { .. })
+        ));
+        assert!(value.to_writer_pretty(&mut Full, 2).is_err());
+    }
+
+    #[test]
+    fn test_write_to_file_atomic() {
+        let dir = crate::files::tests::test_dir("value_atomic");
+        let path = dir.join("value.json");
+        let value = JsonValue::Array(vec![JsonValue::Boolean(true)]);
+
+        value
+            .write_to_file_atomic(&path, &SerializeOptions::pretty(2))
+            .unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written, "[\n  true\n]");
+    }
 }
@@ -0,0 +1,149 @@
+use crate::options::DEFAULT_MAX_DEPTH;
+use crate::{
+    JsonError, JsonMap, JsonValue, ParserOptions, SerializeOptions, parse_json,
+    parse_json_with_options,
+};
+use js_sys::{Array, Object, Reflect, SyntaxError, TypeError};
+use wasm_bindgen::prelude::*;
+
+/// Utility function to convert a JsonValue into a JavaScript value. Numbers
+/// become JS numbers, so integers beyond 2^53 lose precision as with
+/// `JSON.parse`.
+fn json_value_to_js(value: &JsonValue) -> JsValue {
+    match value {
+        JsonValue::Null => JsValue::NULL,
+        JsonValue::Boolean(b) => JsValue::from_bool(*b),
+        JsonValue::Number(n) => JsValue::from_f64(n.as_f64()),
+        JsonValue::String(s) => JsValue::from_str(s),
+        JsonValue::Array(items) => items.iter().map(json_value_to_js).collect::<Array>().into(),
+        JsonValue::Object(members) => {
+            // `Object.fromEntries` defines own properties, so a `"__proto__"` key
+            // is kept as a member as `JSON.parse` does
+            let entries = members
+                .iter()
+                .map(|(key, member)| Array::of2(&JsValue::from_str(key), &json_value_to_js(member)))
+                .collect::<Array>();
+            Object::from_entries(&entries)
+                .expect("entries are [key, value] pairs")
+                .into()
+        }
+        #[cfg(feature = "custom-values")]
+        JsonValue::Custom(custom) => json_value_to_js(&custom.to_json()),
+    }
+}
+
+/// Utility function to convert a JavaScript value into a JsonValue, as
+/// `JSON.stringify` would serialize it. `undefined` becomes `null`; functions,
+/// symbols and bigints are rejected with a `TypeError`.
+fn js_to_json_value(value: &JsValue, depth: usize) -> Result<JsonValue, JsValue> {
+    if depth > DEFAULT_MAX_DEPTH {
+        return Err(TypeError::new(&format!(
+            "value is nested more than {} levels deep (is it cyclic?)",
+            DEFAULT_MAX_DEPTH
+        ))
+        .into());
+    }
+    if value.is_null() || value.is_undefined() {
+        Ok(JsonValue::Null)
+    } else if let Some(b) = value.as_bool() {
+        Ok(JsonValue::Boolean(b))
+    } else if let Some(n) = value.as_f64() {
+        // JS has no integers, so whole numbers in the exact range are written as such
+        if n.fract() == 0.0 && n.abs() < 2f64.powi(53) {
+            Ok(JsonValue::Number((n as i64).into()))
+        } else {
+            Ok(JsonValue::Number(n.into()))
+        }
+    } else if let Some(s) = value.as_string() {
+        Ok(JsonValue::String(s))
+    } else if Array::is_array(value) {
+        Array::from(value)
+            .iter()
+            .map(|item| js_to_json_value(&item, depth + 1))
+            .collect::<Result<Vec<_>, _>>()
+            .map(JsonValue::Array)
+    } else if value.is_object() && !value.is_function() {
+        let mut members = JsonMap::default();
+        for entry in Object::entries(value.unchecked_ref()).iter() {
+            let entry = Array::from(&entry);
+            let key = entry.get(0).as_string().unwrap_or_default();
+            members.insert(
+                key.as_str().into(),
+                js_to_json_value(&entry.get(1), depth + 1)?,
+            );
+        }
+        Ok(JsonValue::Object(members))
+    } else {
+        Err(TypeError::new(&format!(
+            "cannot serialize a {} to JSON",
+            value.js_typeof().as_string().unwrap_or_default()
+        ))
+        .into())
+    }
+}
+
+/// Utility function to convert a parse error into a JavaScript `SyntaxError`,
+/// like the one thrown by `JSON.parse`, with `position`, `line` and `column`
+/// properties when the error refers to a place in `input`.
+fn syntax_error(err: &JsonError, input: &str) -> JsValue {
+    let error = SyntaxError::new(&err.to_string());
+    if let (Some(position), Some((line, column))) = (err.input_offset(), err.line_column(input)) {
+        for (name, value) in [("position", position), ("line", line), ("column", column)] {
+            // Setting a property on a fresh error object cannot fail
+            let _ = Reflect::set(&error, &name.into(), &JsValue::from_f64(value as f64));
+        }
+    }
+    error.into()
+}
+
+/// Parse a JSON string into a JavaScript value.
+///
+/// Throws a `SyntaxError` with `position`, `line` and `column` properties if
+/// the input is not valid JSON.
+#[wasm_bindgen(js_name = parseJson)]
+pub fn parse_json_js(input: &str) -> Result<JsValue, JsValue> {
+    parse_json(input)
+        .map(|value| json_value_to_js(&value))
+        .map_err(|err| syntax_error(&err, input))
+}
+
+/// Serialize a JavaScript value to a JSON string, indented by `indent` spaces
+/// per level if given and on one line otherwise.
+///
+/// Throws a `TypeError` for values JSON cannot represent, such as functions
+/// and bigints.
+#[wasm_bindgen]
+pub fn dumps(value: JsValue, indent: Option<u32>) -> Result<String, JsValue> {
+    let options = SerializeOptions {
+        indent: indent.map(|indent| indent as usize),
+        ..SerializeOptions::default()
+    };
+    Ok(js_to_json_value(&value, 0)?.to_string_with(&options))
+}
+
+/// Reformat a JSON string with `indent` spaces per level (2 by default).
+///
+/// The document never becomes a JavaScript value, so numbers are written
+/// exactly as they appear in the input.
+#[wasm_bindgen(js_name = prettyPrint)]
+pub fn pretty_print(input: &str, indent: Option<u32>) -> Result<String, JsValue> {
+    let options = ParserOptions {
+        preserve_number_text: true,
+        ..ParserOptions::default()
+    };
+    let value =
+        parse_json_with_options(input, &options).map_err(|err| syntax_error(&err, input))?;
+    Ok(value.to_string_with(&SerializeOptions::pretty(indent.unwrap_or(2) as usize)))
+}
+
+/// Check that a string is valid JSON without converting it.
+///
+/// Returns `null` if it is, and otherwise the `SyntaxError` that `parseJson`
+/// would throw, for showing diagnostics next to an editor.
+#[wasm_bindgen(js_name = validateJson)]
+pub fn validate_json(input: &str) -> JsValue {
+    match parse_json(input) {
+        Ok(_) => JsValue::NULL,
+        Err(err) => syntax_error(&err, input),
+    }
+}
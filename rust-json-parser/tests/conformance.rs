@@ -0,0 +1,445 @@
+//! Cross-checks this crate against serde_json on generated documents and a
+//! corpus of edge cases. For every input, both parsers must accept or reject it
+//! alike and read it as the same value, and each must read the other's
+//! serialized output back unchanged.
+//!
+//! Run with `cargo test --features conformance`. To also check an external
+//! corpus, point `JSON_CONFORMANCE_CORPUS` at a directory of `*.json` files,
+//! such as `test_parsing` from JSONTestSuite; files whose names start with `i_`
+//! (implementation-defined behaviour) are skipped.
+//!
+//! One difference is by design: numbers beyond the range of `f64`. Both reject
+//! overflowing literals, but this crate also rejects exponents above
+//! `ParserOptions::max_exponent`, while serde_json reads such underflow as
+//! zero. Such inputs are counted and printed, but do not fail.
+#![cfg(feature = "conformance")]
+
+use std::env;
+use std::fs;
+
+use rust_json_parser::{
+    JsonError, JsonValue, Separators, SerializeOptions, parse_json, parse_json_bytes,
+};
+use serde_json::Value;
+
+/// Number of random documents checked, each with `MUTATIONS` corrupted copies.
+const DOCUMENTS: usize = 2000;
+const MUTATIONS: usize = 4;
+
+/// Inputs known to trip up parsers: number edge cases, escapes, whitespace
+/// and truncated or malformed structure.
+const CORPUS: &[&str] = &[
+    "0",
+    "-0",
+    "-0.0",
+    "1E2",
+    "1e-2",
+    "1.5e+3",
+    "01",
+    "-01",
+    "1.",
+    ".1",
+    "+1",
+    "1e",
+    "1e+",
+    "0x10",
+    "-",
+    "--1",
+    "1.0e",
+    "Infinity",
+    "NaN",
+    "-Infinity",
+    "1e400",
+    "-1e400",
+    "1e-400",
+    "123456789012345678901234567890",
+    "18446744073709551615",
+    "18446744073709551616",
+    "-9223372036854775808",
+    "-9223372036854775809",
+    "0.1e1",
+    "1E-0",
+    "[1,]",
+    "[,1]",
+    "[1 2]",
+    "{\"a\":1,}",
+    "{\"a\" 1}",
+    "{\"a\":}",
+    "{1:1}",
+    "{\"a\":1,\"a\":2}",
+    "[",
+    "]",
+    "{",
+    "}",
+    "[[]",
+    "[]]",
+    "",
+    " ",
+    "nul",
+    "null ",
+    " true",
+    "tru",
+    "falsey",
+    "[true false]",
+    "\"",
+    "\"abc",
+    "\"\\\"",
+    "\"\\x41\"",
+    "\"\\u00\"",
+    "\"\\u00e9\"",
+    "\"\\uD83D\\uDE00\"",
+    "\"\\ud800\"",
+    "\"\\udc00\"",
+    "\"\\ud800\\u0041\"",
+    "\"a\tb\"",
+    "\"a\nb\"",
+    "\"\\/\"",
+    "\"\u{7f}\"",
+    "\"\u{feff}\"",
+    "\u{feff}1",
+    "1 2",
+    "[1] [2]",
+    "\t[1]\r\n",
+    "[1]\u{a0}",
+    "\"\\U0041\"",
+    "'a'",
+    "[\"a\",]",
+    "{\"a\":{\"b\":[{}]}}",
+    "[-]",
+    "[1e1.0]",
+    "[0e]",
+    "[.5]",
+    "[5.]",
+    "[00]",
+    "[-00]",
+    "[1,,2]",
+    "[\"\\u0000\"]",
+    "{\"\":\"\"}",
+];
+
+/// A small xorshift generator, so failures reproduce without a seed argument.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<T: Clone>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())].clone()
+    }
+}
+
+fn random_string(rng: &mut Rng) -> String {
+    const CHARS: &[char] = &[
+        'a', 'b', 'z', 'A', '0', ' ', '"', '\\', '/', '\n', '\t', '\r', '\u{8}', '\u{c}', '\u{0}',
+        '\u{1f}', '\u{7f}', 'é', 'ß', '€', '\u{2028}', '\u{feff}', '😀', '𝄞',
+    ];
+    (0..rng.below(12)).map(|_| rng.pick(CHARS)).collect()
+}
+
+fn random_number(rng: &mut Rng) -> Value {
+    match rng.below(6) {
+        0 => Value::from(rng.below(1000) as i64 - 500),
+        1 => Value::from(rng.next() as i64),
+        2 => Value::from(rng.next()),
+        3 => Value::from(rng.below(100_000) as f64 / 100.0),
+        4 => Value::from(rng.pick(&[0.1, 1e-7, 1.5e300, -2.5e-300, 1e21, 123.456e10])),
+        _ => {
+            let float = f64::from_bits(rng.next());
+            if float.is_finite() {
+                Value::from(float)
+            } else {
+                Value::from(0.5)
+            }
+        }
+    }
+}
+
+fn random_value(rng: &mut Rng, depth: usize) -> Value {
+    let kinds = if depth == 0 { 4 } else { 6 };
+    match rng.below(kinds) {
+        0 => rng.pick(&[Value::Null, Value::Bool(true), Value::Bool(false)]),
+        1 | 2 => random_number(rng),
+        3 => Value::String(random_string(rng)),
+        4 => Value::Array(
+            (0..rng.below(5))
+                .map(|_| random_value(rng, depth - 1))
+                .collect(),
+        ),
+        _ => Value::Object(
+            (0..rng.below(5))
+                .map(|_| (random_string(rng), random_value(rng, depth - 1)))
+                .collect(),
+        ),
+    }
+}
+
+/// Returns `text` with one character deleted, inserted or replaced.
+fn mutate(rng: &mut Rng, text: &str) -> String {
+    const INSERTED: &[char] = &[
+        '[', ']', '{', '}', '"', ',', ':', '0', '1', 'e', '.', '-', '+', ' ', '\\', 'u', 'n', '\n',
+        '\u{0}',
+    ];
+    let mut chars: Vec<char> = text.chars().collect();
+    let at = rng.below(chars.len() + 1);
+    match rng.below(3) {
+        0 if at < chars.len() => {
+            chars.remove(at);
+        }
+        1 if at < chars.len() => chars[at] = rng.pick(INSERTED),
+        _ => chars.insert(at, rng.pick(INSERTED)),
+    }
+    chars.into_iter().collect()
+}
+
+/// Returns whether two numbers are equal: integers exactly, others as `f64`.
+fn same_number(ours: &rust_json_parser::JsonNumber, theirs: &serde_json::Number) -> bool {
+    match (
+        ours.as_i64(),
+        ours.as_u64(),
+        theirs.as_i64(),
+        theirs.as_u64(),
+    ) {
+        (Some(a), _, Some(b), _) => a == b,
+        (_, Some(a), _, Some(b)) => a == b,
+        _ => theirs.as_f64() == Some(ours.as_f64()),
+    }
+}
+
+fn same(ours: &JsonValue, theirs: &Value) -> bool {
+    match (ours, theirs) {
+        (JsonValue::Null, Value::Null) => true,
+        (JsonValue::Boolean(a), Value::Bool(b)) => a == b,
+        (JsonValue::Number(a), Value::Number(b)) => same_number(a, b),
+        (JsonValue::String(a), Value::String(b)) => a == b,
+        (JsonValue::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+        }
+        (JsonValue::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(&**key).is_some_and(|b| same(a, b)))
+        }
+        _ => false,
+    }
+}
+
+/// Returns whether the document contains a non-integer number, whose textual
+/// form legitimately differs between serializers (e.g. `1e300` or `1000...`).
+fn has_float(value: &Value) -> bool {
+    match value {
+        Value::Number(n) => n.is_f64(),
+        Value::Array(items) => items.iter().any(has_float),
+        Value::Object(map) => map.values().any(has_float),
+        _ => false,
+    }
+}
+
+fn has_non_finite(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Number(n) => !n.as_f64().is_finite(),
+        JsonValue::Array(items) => items.iter().any(has_non_finite),
+        JsonValue::Object(map) => map.values().any(has_non_finite),
+        _ => false,
+    }
+}
+
+/// Returns whether the parsers disagree only about a number outside the range
+/// of `f64` (see the module documentation).
+fn is_range_difference(
+    ours: &Result<JsonValue, JsonError>,
+    theirs: &Result<Value, serde_json::Error>,
+) -> bool {
+    let ours_out_of_range = match ours {
+        Ok(value) => has_non_finite(value),
+        Err(err) => matches!(err, JsonError::NumberOutOfRange { .. }),
+    };
+    let theirs_out_of_range = match theirs {
+        Ok(_) => true,
+        Err(err) => err.to_string().starts_with("number out of range"),
+    };
+    ours_out_of_range && theirs_out_of_range
+}
+
+/// Divergences found so far, reported all at once at the end of a test.
+#[derive(Default)]
+struct Report {
+    checked: usize,
+    divergences: Vec<String>,
+    range_differences: Vec<String>,
+}
+
+impl Report {
+    fn diverge(&mut self, input: &[u8], what: String) {
+        self.divergences
+            .push(format!("{}: {:?}", what, String::from_utf8_lossy(input)));
+    }
+
+    /// Checks that both parsers treat `input` the same way, and returns the
+    /// value if both accept it.
+    fn check_input(&mut self, input: &[u8]) -> Option<(JsonValue, Value)> {
+        self.checked += 1;
+        let ours = parse_json_bytes(input);
+        // RFC 8259 lets parsers ignore a leading byte order mark, which
+        // `parse_json_bytes` does and serde_json does not
+        let unmarked = input.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(input);
+        let theirs = serde_json::from_slice::<Value>(unmarked);
+        if ours.is_ok() != theirs.is_ok() && is_range_difference(&ours, &theirs) {
+            self.range_differences
+                .push(String::from_utf8_lossy(input).into_owned());
+            return None;
+        }
+        match (ours, theirs) {
+            (Ok(ours), Ok(theirs)) => {
+                if !same(&ours, &theirs) {
+                    self.diverge(
+                        input,
+                        format!("read as {} but serde_json reads {}", ours, theirs),
+                    );
+                    return None;
+                }
+                Some((ours, theirs))
+            }
+            (Err(_), Err(_)) => None,
+            (Ok(ours), Err(err)) => {
+                self.diverge(
+                    input,
+                    format!("accepted as {} but serde_json fails: {}", ours, err),
+                );
+                None
+            }
+            (Err(err), Ok(theirs)) => {
+                self.diverge(
+                    input,
+                    format!("rejected ({}) but serde_json reads {}", err, theirs),
+                );
+                None
+            }
+        }
+    }
+
+    /// Checks that each parser reads the other's serialization of a value back
+    /// unchanged, and that the texts match when they can.
+    fn check_output(&mut self, ours: &JsonValue, theirs: &Value) {
+        let compact = SerializeOptions {
+            separators: Separators::Compact,
+            sort_keys: true,
+            ..SerializeOptions::default()
+        };
+        let our_text = ours.to_string_with(&compact);
+        let their_text = serde_json::to_string(theirs).unwrap();
+
+        match serde_json::from_str::<Value>(&our_text) {
+            Ok(read) if same(ours, &read) => {}
+            Ok(read) => self.diverge(
+                our_text.as_bytes(),
+                format!("our output is read by serde_json as {}", read),
+            ),
+            Err(err) => self.diverge(
+                our_text.as_bytes(),
+                format!("our output is rejected by serde_json: {}", err),
+            ),
+        }
+        match parse_json(&their_text) {
+            Ok(read) if same(&read, theirs) => {}
+            Ok(read) => self.diverge(
+                their_text.as_bytes(),
+                format!("serde_json output is read as {}", read),
+            ),
+            Err(err) => self.diverge(
+                their_text.as_bytes(),
+                format!("serde_json output is rejected: {}", err),
+            ),
+        }
+        if !has_float(theirs) && our_text != their_text {
+            self.diverge(
+                our_text.as_bytes(),
+                format!("serialized differently from serde_json's {:?}", their_text),
+            );
+        }
+    }
+
+    fn assert_clean(&self) {
+        if !self.range_differences.is_empty() {
+            eprintln!(
+                "{} inputs with numbers outside the f64 range, read differently by design: {:?}",
+                self.range_differences.len(),
+                self.range_differences
+            );
+        }
+        assert!(
+            self.divergences.is_empty(),
+            "{} of {} inputs diverge from serde_json:\n{}",
+            self.divergences.len(),
+            self.checked,
+            self.divergences
+                .iter()
+                .take(25)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+}
+
+#[test]
+fn test_generated_documents_match_serde_json() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    let mut report = Report::default();
+    for _ in 0..DOCUMENTS {
+        let document = random_value(&mut rng, 4);
+        let compact = serde_json::to_string(&document).unwrap();
+        let pretty = serde_json::to_string_pretty(&document).unwrap();
+        for text in [&compact, &pretty] {
+            if let Some((ours, theirs)) = report.check_input(text.as_bytes()) {
+                report.check_output(&ours, &theirs);
+            }
+        }
+        for _ in 0..MUTATIONS {
+            let mutated = mutate(&mut rng, &compact);
+            report.check_input(mutated.as_bytes());
+        }
+    }
+    report.assert_clean();
+}
+
+#[test]
+fn test_corpus_matches_serde_json() {
+    let mut report = Report::default();
+    for input in CORPUS {
+        if let Some((ours, theirs)) = report.check_input(input.as_bytes()) {
+            report.check_output(&ours, &theirs);
+        }
+    }
+
+    if let Ok(dir) = env::var("JSON_CONFORMANCE_CORPUS") {
+        let mut paths: Vec<_> = fs::read_dir(&dir)
+            .unwrap_or_else(|err| panic!("{}: {}", dir, err))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter(|path| {
+                !path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("i_")
+            })
+            .collect();
+        paths.sort();
+        for path in paths {
+            let input = fs::read(&path).unwrap();
+            if let Some((ours, theirs)) = report.check_input(&input) {
+                report.check_output(&ours, &theirs);
+            }
+        }
+    }
+    report.assert_clean();
+}
@@ -1,8 +1,24 @@
+import gzip
+import io
+
 import pytest
 from rust_json_parser import (
+    JSONDecodeError,
+    LazyJson,
+    dump,
     dumps,
+    iter_json_lines,
+    key_histogram,
+    load,
+    loads_lines,
     parse_json,
     parse_json_file,
+    parse_to_handle,
+    parse_validate_only,
+    pointer,
+    query,
+    validate,
+    validate_json,
 )
 
 
@@ -39,10 +55,255 @@ class TestTypeConversions:
         assert result["f"] is False
         assert isinstance(result["t"], bool)
 
-    def test_numbers_are_float(self):
+    def test_integers_are_int_and_floats_are_float(self):
         result = parse_json('{"int": 42, "float": 3.14}')
-        assert result["int"] == 42.0
+        assert result["int"] == 42
+        assert isinstance(result["int"], int)
         assert result["float"] == 3.14
+        assert isinstance(result["float"], float)
+
+    def test_large_integers_are_exact(self):
+        result = parse_json("[9007199254740993, -9223372036854775808]")
+        assert result == [9007199254740993, -9223372036854775808]
+        assert dumps(result) == "[9007199254740993,-9223372036854775808]"
+
+    def test_integers_beyond_64_bits_are_exact(self):
+        result = parse_json("[123456789012345678901234567890, -18446744073709551617, 1e2]")
+        assert result == [123456789012345678901234567890, -18446744073709551617, 100.0]
+        assert isinstance(result[2], float)
+
+    def test_parse_int_and_parse_float_hooks(self):
+        import decimal
+
+        result = parse_json(
+            '{"price": 1.10, "qty": 3, "big": 1E400, "nan": NaN}',
+            parse_int=str,
+            parse_float=decimal.Decimal,
+            allow_nan=True,
+        )
+        assert result["price"] == decimal.Decimal("1.10")
+        assert str(result["price"]) == "1.10"
+        assert result["qty"] == "3"
+        assert result["big"] == decimal.Decimal("1E400")
+        assert result["nan"] != result["nan"]
+
+    def test_decode_error_reports_position(self):
+        doc = '{\n  "café": tru\n}'
+        with pytest.raises(JSONDecodeError) as info:
+            parse_json(doc)
+        err = info.value
+        assert isinstance(err, ValueError)
+        assert err.doc == doc
+        assert (err.lineno, err.colno) == (2, 11)
+        assert doc[err.pos :].startswith("tru")
+        assert str(err) == f"{err.msg}: line 2 column 11 (char {err.pos})"
+
+    def test_decode_error_from_stream_counts_bytes(self):
+        with pytest.raises(JSONDecodeError) as info:
+            load(io.BytesIO(b'[1,\n 2,\n x]'))
+        assert info.value.doc is None
+        assert (info.value.pos, info.value.lineno, info.value.colno) == (9, 3, 2)
+
+    def test_parse_from_many_threads(self):
+        from concurrent.futures import ThreadPoolExecutor
+
+        docs = [f'{{"id": {i}, "items": {list(range(i))}}}' for i in range(200)]
+        with ThreadPoolExecutor(max_workers=8) as pool:
+            results = list(pool.map(parse_json, docs))
+        assert [result["id"] for result in results] == list(range(200))
+        assert all(len(result["items"]) == result["id"] for result in results)
+
+    def test_trailing_characters_rejected(self):
+        with pytest.raises(ValueError, match="Trailing characters"):
+            parse_json("[1, 2] xyz")
+
+
+class TestParserOptions:
+    def test_allow_comments(self):
+        source = '{"a": 1 /* one */} // trailing'
+        with pytest.raises(ValueError):
+            parse_json(source)
+        assert parse_json(source, allow_comments=True) == {"a": 1}
+
+    def test_allow_trailing_commas(self):
+        with pytest.raises(ValueError):
+            parse_json("[1, 2,]")
+        assert parse_json("[1, 2,]", allow_trailing_commas=True) == [1, 2]
+
+    def test_max_depth(self):
+        assert parse_json("[[[1]]]", max_depth=3) == [[[1]]]
+        with pytest.raises(ValueError):
+            parse_json("[[[1]]]", max_depth=2)
+
+    def test_duplicate_keys(self):
+        source = '{"a": 1, "a": 2}'
+        assert parse_json(source) == {"a": 2}
+        assert parse_json(source, duplicate_keys="first") == {"a": 1}
+        with pytest.raises(ValueError):
+            parse_json(source, duplicate_keys="error")
+        with pytest.raises(ValueError):
+            parse_json(source, duplicate_keys="sometimes")
+
+    def test_parse_json_file_options(self, tmp_path):
+        path = tmp_path / "config.jsonc"
+        path.write_text('// config\n{"debug": true,}')
+        assert parse_json_file(
+            str(path), allow_comments=True, allow_trailing_commas=True
+        ) == {"debug": True}
+
+    def test_parse_json_file_encodings(self, tmp_path):
+        path = tmp_path / "export.json"
+        for encoding in ("utf-8-sig", "utf-16", "utf-16-le", "utf-16-be"):
+            path.write_text('{"name": "Zoë"}', encoding=encoding)
+            assert parse_json_file(str(path)) == {"name": "Zoë"}
+        path.write_text("[1]", encoding="utf-32")
+        with pytest.raises(ValueError, match="UTF-32"):
+            parse_json_file(str(path))
+
+
+class TestQueries:
+    DOC = '{"users": [{"name": "Ann", "tags": ["a"]}, {"name": "Bob", "tags": []}]}'
+
+    def test_query_on_json_string(self):
+        assert query(self.DOC, "$.users[*].name") == ["Ann", "Bob"]
+        assert query(self.DOC, "$.users[-1].name") == ["Bob"]
+        assert query(self.DOC, "$.missing") == []
+
+    def test_query_on_python_object(self):
+        assert query({"a": {"b": 1}, "c": [{"b": 2}]}, "$..b") in ([1, 2], [2, 1])
+
+    def test_query_invalid_expression(self):
+        with pytest.raises(ValueError):
+            query(self.DOC, "users")
+
+    def test_pointer(self):
+        assert pointer(self.DOC, "/users/0/name") == "Ann"
+        assert pointer({"a/b": [1, 2]}, "/a~1b/1") == 2
+        assert pointer(self.DOC, "/users/5") is None
+        assert pointer(self.DOC, "/users/5", "n/a") == "n/a"
+
+
+
+class TestValidate:
+    SCHEMA = {
+        "type": "object",
+        "required": ["name"],
+        "properties": {"age": {"type": "integer", "minimum": 0}},
+    }
+
+    def test_valid_document(self):
+        assert validate({"name": "Ann", "age": 3}, self.SCHEMA) == []
+        assert validate("[1, 2]", '{"type": "array"}') == []
+
+    def test_errors_have_path_message_and_keyword(self):
+        errors = validate('{"age": -1}', self.SCHEMA)
+        assert sorted((e["path"], e["keyword"]) for e in errors) == [
+            ("", "required"),
+            ("/age", "minimum"),
+        ]
+        assert all(isinstance(e["message"], str) and e["message"] for e in errors)
+
+    def test_invalid_json_string(self):
+        with pytest.raises(ValueError):
+            validate("{", self.SCHEMA)
+
+class TestLoad:
+    def test_load_text_file_object(self):
+        assert load(io.StringIO('{"name": "Alice", "tags": ["ñandú"]}')) == {
+            "name": "Alice",
+            "tags": ["ñandú"],
+        }
+
+    def test_load_gzip_file_object(self):
+        compressed = io.BytesIO()
+        with gzip.GzipFile(fileobj=compressed, mode="wb") as fp:
+            fp.write(("[" + ",".join(["1"] * 100000) + "]").encode())
+        compressed.seek(0)
+        with gzip.GzipFile(fileobj=compressed, mode="rb") as fp:
+            assert load(fp) == [1] * 100000
+
+    def test_load_invalid_json_raises_value_error(self):
+        with pytest.raises(ValueError):
+            load(io.BytesIO(b'{"bad": }'))
+
+    def test_load_and_dump_paths(self, tmp_path):
+        import pathlib
+
+        path = tmp_path / "data.json"
+        dump({"tags": ["ñandú"], "name": "Alice"}, path, indent=2, sort_keys=True)
+        assert path.read_text(encoding="utf-8") == (
+            '{\n  "name": "Alice",\n  "tags": [\n    "ñandú"\n  ]\n}'
+        )
+        assert load(path) == {"name": "Alice", "tags": ["ñandú"]}
+        assert load(str(path)) == load(pathlib.PurePath(path))
+
+        dump([1, 2], str(path))
+        assert path.read_text() == "[1,2]"
+
+        with pytest.raises(OSError):
+            load(tmp_path / "missing.json")
+        with pytest.raises(OSError):
+            dump([1], tmp_path / "missing" / "out.json")
+        path.write_text("[1,")
+        with pytest.raises(ValueError):
+            load(path)
+
+    def test_load_rejects_non_file_objects(self):
+        class NotAFile:
+            def read(self, size):
+                return 42
+
+        with pytest.raises(TypeError):
+            load(NotAFile())
+
+
+class TestHandles:
+    def test_parse_validate_only(self):
+        assert parse_validate_only('{"a": [1, 2, {"b": null}]}') is None
+        with pytest.raises(ValueError):
+            parse_validate_only('{"a": [1, 2}')
+
+    def test_validate_json(self):
+        assert validate_json('{"a": [1, 2, {"b": "\\u00e9"}]}') is None
+        with pytest.raises(JSONDecodeError) as info:
+            validate_json('{"a": [1, 2}')
+        assert info.value.pos == 11
+
+    def test_parse_to_handle(self):
+        doc = parse_to_handle('{"users": [{"name": "Ann"}, {"name": "Bob"}], "n": 2}')
+        assert doc.type == "object"
+        assert len(doc) == 2
+        assert doc.query("$.users[*].name") == ["Ann", "Bob"]
+        assert doc.pointer("/users/1/name") == "Bob"
+        assert doc.pointer("/missing", 0) == 0
+        assert doc.to_python() == parse_json(doc.dumps())
+        assert repr(doc) == "<JsonDocument object>"
+
+    def test_key_histogram(self):
+        text = '[{"id": 1, "tags": ["a"]}, {"id": "2", "tags": null}]'
+        stats = key_histogram(text)
+        assert stats == key_histogram(parse_json(text))
+        assert stats == parse_to_handle(text).key_histogram()
+        assert sorted(stats["keys"]) == ["id", "tags"]
+        assert stats["keys"]["id"]["number"] == 1
+        assert stats["keys"]["id"]["string"] == 1
+        assert stats["keys"]["tags"]["null"] == 1
+        assert stats["types"] == {
+            "null": 1,
+            "boolean": 0,
+            "number": 1,
+            "string": 2,
+            "array": 2,
+            "object": 2,
+        }
+
+    def test_handle_of_scalar_has_no_len(self):
+        doc = parse_to_handle("3")
+        assert doc.type == "number"
+        with pytest.raises(TypeError):
+            len(doc)
+        with pytest.raises(ValueError):
+            parse_to_handle("[")
 
 
 class TestErrorHandling:
@@ -71,14 +332,281 @@ class TestSerialization:
         result = dumps({"key": "value"}, indent=2)
         assert '{\n  "key": "value"\n}' == result
 
+    def test_dumps_sort_keys(self):
+        assert dumps({"b": 1, "a": {"d": 2, "c": 3}}, sort_keys=True) == (
+            '{"a": {"c": 3,"d": 2},"b": 1}'
+        )
+        buffer = io.StringIO()
+        dump({"b": 1, "a": 2}, buffer, indent=2, sort_keys=True)
+        assert buffer.getvalue() == '{\n  "a": 2,\n  "b": 1\n}'
+
+    def test_dumps_ensure_ascii_and_separators_match_json(self):
+        import json
+
+        value = {"name": "café 🎉", "items": [1, 2]}
+        for separators in [(",", ":"), (", ", ": ")]:
+            options = {"sort_keys": True, "separators": separators}
+            assert dumps(value, ensure_ascii=True, **options) == json.dumps(value, **options)
+        assert dumps({"name": "café"}, separators=(",", ":")) == '{"name":"café"}'
+        with pytest.raises(ValueError, match="separators"):
+            dumps(value, separators=(";", "="))
+
+    def test_dump_writes_to_file_like_object(self):
+        buffer = io.StringIO()
+        dump({"key": "value"}, buffer, indent=2)
+        assert buffer.getvalue() == dumps({"key": "value"}, indent=2)
+
+    def test_dump_large_object_in_chunks(self):
+        class Recorder:
+            def __init__(self):
+                self.chunks = []
+
+            def write(self, text):
+                self.chunks.append(text)
+
+        data = ["ñandú"] * 50000
+        fp = Recorder()
+        dump(data, fp)
+        assert len(fp.chunks) > 1
+        assert parse_json("".join(fp.chunks)) == data
+
+    def test_dump_propagates_write_errors(self):
+        class Broken:
+            def write(self, text):
+                raise RuntimeError("disk full")
+
+        with pytest.raises(RuntimeError):
+            dump({"key": "value"}, Broken())
+
+    def test_nan_and_infinity_round_trip_with_allow_nan(self):
+        import json
+        import math
+
+        text = json.dumps([float("nan"), float("inf"), float("-inf")])
+        with pytest.raises(ValueError):
+            parse_json(text)
+        values = parse_json(text, allow_nan=True)
+        assert math.isnan(values[0])
+        assert values[1:] == [math.inf, -math.inf]
+        assert dumps(values, allow_nan=True) == "[NaN,Infinity,-Infinity]"
+        assert dumps(values) == "[null,null,null]"
+
+    def test_tagged_values_round_trip_through_hooks(self):
+        from datetime import date
+
+        from rust_json_parser import tagged
+
+        def encode(obj):
+            if isinstance(obj, date):
+                return tagged("date", obj.isoformat())
+            if isinstance(obj, set):
+                return tagged("set", sorted(obj))
+            raise TypeError(f"cannot serialize {obj!r}")
+
+        data = {"when": date(2024, 5, 1), "tags": {"b", "a"}, "n": 1}
+        text = dumps(data, default=encode)
+        assert parse_json(text)["when"] == {"$type": "date", "value": "2024-05-01"}
+        decoded = parse_json(text, tag_hooks={"date": date.fromisoformat, "set": set})
+        assert decoded == data
+
+        buffer = io.StringIO()
+        dump([date(2024, 5, 1)], buffer, default=encode)
+        assert parse_json(buffer.getvalue(), tag_hooks={"date": date.fromisoformat}) == [
+            date(2024, 5, 1)
+        ]
+
+    def test_default_errors_propagate(self):
+        with pytest.raises(TypeError):
+            dumps({"x": object()})
+        with pytest.raises(TypeError):
+            dumps(object(), default=lambda obj: obj)
+        with pytest.raises(ValueError):
+            dumps(object(), default=lambda obj: (_ for _ in ()).throw(ValueError("no")))
+
+    def test_dumps_big_integers_round_trip(self):
+        for n in [2**70, -(2**70), 2**64, -(2**63) - 1, 10**400]:
+            text = dumps([n])
+            assert text == f"[{n}]"
+            assert parse_json(text) == [n]
+
+    def test_dumps_rejects_cycles_and_runaway_nesting(self):
+        cyclic = []
+        cyclic.append(cyclic)
+        with pytest.raises(ValueError, match="Circular reference detected"):
+            dumps(cyclic)
+        mapping = {}
+        mapping["self"] = [mapping]
+        with pytest.raises(ValueError, match="Circular reference detected"):
+            dumps(mapping)
+        with pytest.raises(RecursionError):
+            dumps(object(), default=lambda obj: [object()])
+        with pytest.raises(RecursionError):
+            dumps(object(), default=lambda obj: object())
+        # Shared (acyclic) references are fine
+        shared = [1]
+        assert parse_json(dumps([shared, shared])) == [[1], [1]]
+
+
+class TestConformance:
+    """Cross-checks parse_json and dumps against the stdlib json module on
+    generated documents and corrupted copies of them (see also
+    tests/conformance.rs, which compares against serde_json). Literals that
+    overflow a float and exponents above ParserOptions::max_exponent are
+    rejected by design, where json reads them as infinity or zero."""
+
+    CHARS = 'abz0 "\\/\n\t\r\b\f\x00\x1f\x7féß€\u2028\ufeff😀𝄞'
+    INSERTED = '[]{}",:01e.-+ \\un\n\x00'
+
+    def random_value(self, rng, depth):
+        kind = rng.randrange(4 if depth == 0 else 6)
+        if kind == 0:
+            return rng.choice([None, True, False])
+        if kind == 1:
+            return rng.choice([rng.randint(-500, 500), rng.randint(-(2**63), 2**64)])
+        if kind == 2:
+            return rng.choice([rng.uniform(-1e6, 1e6), 1.5e300, -2.5e-300, 0.1, 1e21])
+        if kind == 3:
+            return self.random_string(rng)
+        if kind == 4:
+            return [self.random_value(rng, depth - 1) for _ in range(rng.randrange(5))]
+        return {
+            self.random_string(rng): self.random_value(rng, depth - 1)
+            for _ in range(rng.randrange(5))
+        }
+
+    def random_string(self, rng):
+        return "".join(rng.choice(self.CHARS) for _ in range(rng.randrange(12)))
+
+    def same(self, a, b):
+        """Compares documents, treating an integer and a float as equal when they
+        hold the same number: JSON does not tell them apart, and large floats
+        are serialized without a decimal point."""
+        number = (int, float)
+        if isinstance(a, bool) or isinstance(b, bool):
+            return a is b
+        if isinstance(a, number) and isinstance(b, number):
+            return a == b if isinstance(a, int) and isinstance(b, int) else float(a) == float(b)
+        if isinstance(a, list) and isinstance(b, list):
+            return len(a) == len(b) and all(self.same(x, y) for x, y in zip(a, b))
+        if isinstance(a, dict) and isinstance(b, dict):
+            return a.keys() == b.keys() and all(self.same(a[k], b[k]) for k in a)
+        return type(a) is type(b) and a == b
+
+    def mutate(self, rng, text):
+        at = rng.randrange(len(text) + 1)
+        op = rng.randrange(3)
+        if op == 0 and at < len(text):
+            return text[:at] + text[at + 1 :]
+        if op == 1 and at < len(text):
+            return text[:at] + rng.choice(self.INSERTED) + text[at + 1 :]
+        return text[:at] + rng.choice(self.INSERTED) + text[at:]
+
+    def test_generated_documents_match_stdlib_json(self):
+        import json
+        import random
+
+        rng = random.Random(1234)
+        divergences = []
+
+        def check(text):
+            try:
+                expected = json.loads(text)
+            except ValueError:
+                expected = ValueError
+            try:
+                actual = parse_json(text)
+            except ValueError as err:
+                if "out of range" in str(err):
+                    return ValueError
+                actual = ValueError
+            if not self.same(actual, expected):
+                divergences.append(f"{text!r}: {actual!r} != {expected!r}")
+            return actual
+
+        for _ in range(500):
+            document = self.random_value(rng, 4)
+            compact = json.dumps(document, ensure_ascii=False)
+            for text in [compact, json.dumps(document, indent=2)]:
+                value = check(text)
+                if value is not ValueError and not self.same(json.loads(dumps(value)), value):
+                    divergences.append(f"dumps does not round-trip {value!r}")
+            for _ in range(4):
+                check(self.mutate(rng, compact))
+
+        assert not divergences, "\n".join(divergences[:25])
+
+
+class TestLazyJson:
+    def test_indexing_converts_only_scalars(self):
+        text = '{"users": [{"name": "Ann", "id": 18446744073709551616}, {"name": "Bob"}]}'
+        doc = LazyJson(text)
+        users = doc["users"]
+        assert isinstance(users, LazyJson)
+        assert len(users) == 2
+        assert users[0]["id"] == 18446744073709551616
+        assert users[-1]["name"] == "Bob"
+        assert doc.keys() == ["users"]
+        assert users[1].materialize() == {"name": "Bob"}
+        assert doc.materialize() == parse_json(text)
+
+    def test_lookup_errors(self):
+        doc = LazyJson('{"a": [1, 2]}')
+        with pytest.raises(KeyError):
+            doc["missing"]
+        with pytest.raises(IndexError):
+            doc["a"][2]
+        with pytest.raises(IndexError):
+            doc["a"][-3]
+        with pytest.raises(TypeError):
+            doc[0]
+        with pytest.raises(TypeError):
+            doc["a"].keys()
+        with pytest.raises(JSONDecodeError):
+            LazyJson("[1,")
+
+
+class TestJsonLines:
+    def test_iter_json_lines_reads_paths_and_files(self, tmp_path):
+        path = tmp_path / "events.jsonl"
+        path.write_bytes(b'{"id": 1}\r\n\n[2, 3]\n"four"')
+        assert list(iter_json_lines(path)) == [{"id": 1}, [2, 3], "four"]
+        with open(path, "rb") as fp:
+            assert list(iter_json_lines(fp)) == [{"id": 1}, [2, 3], "four"]
+        assert list(iter_json_lines(io.StringIO(""))) == []
+
+    def test_iter_json_lines_is_lazy(self):
+        lines = iter_json_lines(io.StringIO('{"ok": true}\nnot json\n'))
+        assert next(lines) == {"ok": True}
+        with pytest.raises(JSONDecodeError) as info:
+            next(lines)
+        assert (info.value.lineno, info.value.colno, info.value.doc) == (2, 1, "not json")
+
+    def test_loads_lines(self):
+        assert loads_lines('{"id": 1}\n{"id": 2}\n') == [{"id": 1}, {"id": 2}]
+        with pytest.raises(JSONDecodeError, match="line 3"):
+            loads_lines("1\n2\n[\n")
+
 
 class TestBenchmark:
-    def test_benchmark_returns_dict(self):
-        """Verify benchmark_performance returns timing dict with all four values."""
+    def test_benchmark_returns_stats_per_operation(self):
+        """Verify benchmark_performance returns timing stats for loads and dumps."""
+        from rust_json_parser import benchmark_performance
+
+        result = benchmark_performance('{"test": 1}', rounds=20, disable_gc=True)
+        assert set(result) == {"loads", "dumps"}
+        for operation, times in result.items():
+            for key in ("pure-rust", "rust", "json"):
+                assert key in times, f"missing {operation} key: {key}"
+            for key, stats in times.items():
+                assert stats["rounds"] == 20
+                assert 0 < stats["median"] <= stats["p95"], f"{operation} {key}: {stats}"
+                assert stats["mean"] > 0 and stats["stddev"] >= 0
+        import gc
+
+        assert gc.isenabled()
+
+    def test_benchmark_rejects_zero_rounds(self):
         from rust_json_parser import benchmark_performance
 
-        result = benchmark_performance('{"test": 1}')
-        for key in ("pure-rust", "rust", "json", "simplejson"):
-            assert key in result, f"missing key: {key}"
-            assert isinstance(result[key], float), f"{key} is not float"
-            assert result[key] > 0, f"{key} is not positive"
+        with pytest.raises(ValueError, match="rounds"):
+            benchmark_performance("[]", rounds=0)